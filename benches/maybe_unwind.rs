@@ -0,0 +1,85 @@
+//! A simple benchmark of the non-panicking path.
+//!
+//! Run with `cargo bench --bench maybe_unwind`.
+
+use maybe_unwind::maybe_unwind;
+use std::{panic, ptr, time::Instant};
+
+const ITERATIONS: u32 = 10_000_000;
+
+// `std::hint::black_box` is not available on the MSRV.
+fn black_box(x: u32) -> u32 {
+    unsafe { ptr::read_volatile(&x) }
+}
+
+fn bench<F>(name: &str, mut f: F)
+where
+    F: FnMut(u32) -> u32,
+{
+    // warm up
+    for i in 0..ITERATIONS / 10 {
+        black_box(f(black_box(i)));
+    }
+
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        black_box(f(black_box(i)));
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<28} {:>8.2} ns/iter",
+        name,
+        elapsed.as_secs_f64() * 1e9 / f64::from(ITERATIONS)
+    );
+}
+
+fn main() {
+    maybe_unwind::set_hook();
+
+    bench("catch_unwind", |i| {
+        panic::catch_unwind(|| i.wrapping_mul(3)).unwrap()
+    });
+    bench("maybe_unwind", |i| {
+        maybe_unwind(|| i.wrapping_mul(3)).unwrap()
+    });
+    bench("maybe_unwind (nested)", |i| {
+        maybe_unwind(|| maybe_unwind(|| i.wrapping_mul(3)).unwrap()).unwrap()
+    });
+
+    maybe_unwind::disable_for_current_thread();
+    bench("maybe_unwind (disabled)", |i| {
+        maybe_unwind(|| i.wrapping_mul(3)).unwrap()
+    });
+    maybe_unwind::enable_for_current_thread();
+
+    #[cfg(feature = "futures")]
+    bench_poll();
+}
+
+#[cfg(feature = "futures")]
+fn bench_poll() {
+    use futures_task::noop_waker_ref;
+    use maybe_unwind::FutureMaybeUnwindExt as _;
+    use std::{
+        future::{pending, Future},
+        panic::AssertUnwindSafe,
+        task::{Context, Poll},
+    };
+
+    let mut cx = Context::from_waker(noop_waker_ref());
+    let mut fut = Box::pin(pending::<u32>().maybe_unwind());
+    bench("MaybeUnwind::poll", |i| match fut.as_mut().poll(&mut cx) {
+        Poll::Pending => i,
+        Poll::Ready(..) => unreachable!(),
+    });
+
+    let mut fut = Box::pin(pending::<u32>().maybe_unwind());
+    bench("MaybeUnwind::poll (nested)", |i| {
+        maybe_unwind(AssertUnwindSafe(|| match fut.as_mut().poll(&mut cx) {
+            Poll::Pending => i,
+            Poll::Ready(..) => unreachable!(),
+        }))
+        .unwrap()
+    });
+}
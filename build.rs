@@ -1,59 +1,115 @@
-use std::{
-    env, fs,
-    path::Path,
-    process::{Command, ExitStatus},
-};
+#[path = "build/probe.rs"]
+mod probe;
+
+use crate::probe::{Mode, Prober, BACKTRACE_ENV};
+use std::env;
 
 fn main() {
-    if probe_backtrace().map_or(false, |st| st.success()) {
-        println!("cargo:rustc-cfg=backtrace");
+    println!("cargo:rustc-check-cfg=cfg(backtrace)");
+    println!("cargo:rustc-check-cfg=cfg(backtrace_feature)");
+    println!("cargo:rustc-check-cfg=cfg(docs)");
+    println!("cargo:rustc-check-cfg=cfg(panic_hook_info)");
+    println!("cargo:rustc-check-cfg=cfg(panic_update_hook)");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=build/probe.rs");
+    println!("cargo:rerun-if-env-changed={}", BACKTRACE_ENV);
+
+    let mode = Mode::from_env();
+    println!(
+        "cargo:rustc-env=MAYBE_UNWIND_BACKTRACE_MODE={}",
+        mode.as_str()
+    );
+
+    match mode {
+        // Nothing is probed with the explicit override, so that the compiler
+        // is never spawned. The other capabilities fall back to the ones
+        // available on every supported compiler.
+        Mode::On => println!("cargo:rustc-cfg=backtrace"),
+        Mode::Off => (),
+        Mode::Auto => detect(&Prober::new()),
     }
 }
 
-// copied from anyhow/build.rs
-fn probe_backtrace() -> Option<ExitStatus> {
-    let rustc = env::var_os("RUSTC")?;
-    let out_dir = env::var_os("OUT_DIR")?;
+fn detect(prober: &Prober) {
+    let probe = |name: &str, source: &str| match prober.probe(name, source) {
+        Ok(accepted) => accepted,
+        Err(err) => {
+            println!(
+                "cargo:warning=could not probe the compiler for `{}`: {} \
+                 (set {} to `on` or `off` to skip the detection)",
+                name, err, BACKTRACE_ENV
+            );
+            false
+        }
+    };
+
+    // `PanicInfo` was renamed to `PanicHookInfo` in Rust 1.81.
+    if probe("panic_hook_info", PANIC_HOOK_INFO) {
+        println!("cargo:rustc-cfg=panic_hook_info");
+    }
+
+    // `std::panic::update_hook` is accepted only by nightly compilers.
+    if probe("panic_update_hook", PANIC_UPDATE_HOOK) {
+        println!("cargo:rustc-cfg=panic_update_hook");
+    }
+
+    // `std::backtrace` is available without the feature gate since Rust 1.65.
+    let stable = probe("backtrace", &backtrace_source(false));
+    // The feature gate is accepted only by nightly compilers.
+    let unstable = probe("backtrace_feature", &backtrace_source(true));
+
+    if env::var_os("CARGO_FEATURE_BACKTRACE").is_none() && !unstable {
+        return;
+    }
+
+    if stable {
+        println!("cargo:rustc-cfg=backtrace");
+    } else if unstable {
+        println!("cargo:rustc-cfg=backtrace");
+        println!("cargo:rustc-cfg=backtrace_feature");
+    } else {
+        println!(
+            "cargo:warning=the feature `backtrace` is enabled, \
+             but std::backtrace is not supported by this compiler"
+        );
+    }
+}
 
-    let probefile = Path::new(&out_dir).join("probe_backtrace.rs");
-    fs::write(
-        &probefile,
+// based on anyhow/build.rs
+fn backtrace_source(feature: bool) -> String {
+    format!(
         r#"
-            #![feature(backtrace)]
+            {}
             #![allow(dead_code)]
-            use std::{
-                backtrace::{Backtrace, BacktraceStatus},
-                error,
-                fmt,
-            };
-            #[derive(Debug)]
-            struct E;
-            impl fmt::Display for E {
-                fn fmt(&self, _formatter: &mut fmt::Formatter) -> fmt::Result {
-                    unimplemented!()
-                }
-            }
-            impl error::Error for E {
-                fn backtrace(&self) -> Option<&Backtrace> {
-                    let backtrace = Backtrace::capture();
-                    match backtrace.status() {
-                        BacktraceStatus::Captured | BacktraceStatus::Disabled | _ => {}
-                    }
-                    unimplemented!()
-                }
-            }
+            use std::backtrace::{{Backtrace, BacktraceStatus}};
+            fn probe() -> Option<String> {{
+                let backtrace = Backtrace::force_capture();
+                match backtrace.status() {{
+                    BacktraceStatus::Captured => Some(backtrace.to_string()),
+                    BacktraceStatus::Disabled | BacktraceStatus::Unsupported | _ => None,
+                }}
+            }}
         "#,
+        if feature {
+            "#![feature(backtrace)]"
+        } else {
+            ""
+        }
     )
-    .ok()?;
-
-    Command::new(rustc)
-        .arg("--edition=2018")
-        .arg("--crate-name=maybe_unwind_probe_backtrace")
-        .arg("--crate-type=lib")
-        .arg("--emit=metadata")
-        .arg("--out-dir")
-        .arg(out_dir)
-        .arg(probefile)
-        .status()
-        .ok()
 }
+
+const PANIC_HOOK_INFO: &str = r#"
+    #![allow(dead_code)]
+    fn probe(info: &std::panic::PanicHookInfo<'_>) {
+        let _ = info.payload();
+        let _ = info.location();
+    }
+"#;
+
+const PANIC_UPDATE_HOOK: &str = r#"
+    #![feature(panic_update_hook)]
+    #![allow(dead_code)]
+    fn probe() {
+        std::panic::update_hook(|prev, info| prev(info));
+    }
+"#;
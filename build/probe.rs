@@ -0,0 +1,115 @@
+//! The probes of the compiler capabilities used by the build script.
+//!
+//! Each probe compiles a small source file with the compiler running the
+//! build, in a scratch directory under `OUT_DIR` that is removed afterwards.
+
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+/// The name of the environment variable that overrides the detection of `std::backtrace`.
+pub const BACKTRACE_ENV: &str = "MAYBE_UNWIND_BACKTRACE";
+
+/// How the availability of `std::backtrace` is determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Probe the compiler.
+    Auto,
+    /// Assume that `std::backtrace` is available, without probing.
+    On,
+    /// Disable the backtraces, without probing.
+    Off,
+}
+
+impl Mode {
+    /// Read the mode from `MAYBE_UNWIND_BACKTRACE`.
+    ///
+    /// The unknown values are reported as a warning and fall back to `Auto`.
+    pub fn from_env() -> Self {
+        let value = match env::var(BACKTRACE_ENV) {
+            Ok(value) => value,
+            Err(env::VarError::NotPresent) => return Mode::Auto,
+            Err(env::VarError::NotUnicode(value)) => value.to_string_lossy().into_owned(),
+        };
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "auto" => Mode::Auto,
+            "on" => Mode::On,
+            "off" => Mode::Off,
+            _ => {
+                println!(
+                    "cargo:warning=unknown value of {}: {:?} (expected `on`, `off` or `auto`)",
+                    BACKTRACE_ENV, value
+                );
+                Mode::Auto
+            }
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mode::Auto => "auto",
+            Mode::On => "on",
+            Mode::Off => "off",
+        }
+    }
+}
+
+/// The runner of the probes, which removes its scratch directory on drop.
+pub struct Prober {
+    rustc: Option<OsString>,
+    dir: Option<PathBuf>,
+}
+
+impl Prober {
+    pub fn new() -> Self {
+        let dir = env::var_os("OUT_DIR").map(|out_dir| PathBuf::from(out_dir).join("probe"));
+        if let Some(dir) = &dir {
+            // The artifacts of an interrupted build must not affect the results.
+            let _ = fs::remove_dir_all(dir);
+        }
+        Self {
+            rustc: env::var_os("RUSTC"),
+            dir,
+        }
+    }
+
+    /// Compile `source`, and return whether it is accepted by the compiler.
+    ///
+    /// An `Err` is returned if the compiler could not be run at all,
+    /// in which case the result of the detection is unreliable.
+    pub fn probe(&self, name: &str, source: &str) -> Result<bool, String> {
+        let rustc = self.rustc.as_ref().ok_or("RUSTC is not set")?;
+        let dir = self.dir.as_ref().ok_or("OUT_DIR is not set")?;
+
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        let probefile = dir.join(format!("probe_{}.rs", name));
+        fs::write(&probefile, source).map_err(|err| err.to_string())?;
+
+        let status = Command::new(rustc)
+            .arg("--edition=2018")
+            .arg(format!("--crate-name=maybe_unwind_probe_{}", name))
+            .arg("--crate-type=lib")
+            .arg("--emit=metadata")
+            .arg("--cap-lints=allow")
+            .arg("--out-dir")
+            .arg(dir)
+            .arg(probefile)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|err| format!("failed to run {:?}: {}", rustc, err))?;
+        Ok(status.success())
+    }
+}
+
+impl Drop for Prober {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
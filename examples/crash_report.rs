@@ -0,0 +1,15 @@
+use maybe_unwind::{with_context, HookOptions};
+use std::env;
+
+fn main() {
+    let dir = env::args_os().nth(1).expect("missing the directory");
+    maybe_unwind::set_hook_with(
+        HookOptions::new()
+            .write_crash_report_to(dir)
+            .max_crash_reports(2),
+    );
+
+    with_context("while soaking", || {
+        panic!("soak failed");
+    });
+}
@@ -0,0 +1,24 @@
+use maybe_unwind::{maybe_unwind, with_context};
+
+struct PanicOnDrop;
+
+impl Drop for PanicOnDrop {
+    fn drop(&mut self) {
+        panic!("second");
+    }
+}
+
+fn main() {
+    maybe_unwind::set_hook();
+    maybe_unwind::install_abort_reporter();
+    if let Some(path) = std::env::args_os().nth(1) {
+        maybe_unwind::persist_stats_on_panic(path);
+    }
+
+    let _ = maybe_unwind(|| {
+        with_context("while running the example", || {
+            let _guard = PanicOnDrop;
+            panic!("first");
+        })
+    });
+}
@@ -0,0 +1,15 @@
+use maybe_unwind::{maybe_unwind, maybe_unwind_quiet, HookOptions};
+use std::env;
+
+fn main() {
+    let mode = env::args().nth(1).unwrap_or_default();
+    let forward = mode != "capture-only";
+    maybe_unwind::set_hook_with(HookOptions::new().also_forward_captured(forward));
+
+    let unwind = if mode == "quiet" {
+        maybe_unwind_quiet(|| panic!("captured in the quiet scope")).unwrap_err()
+    } else {
+        maybe_unwind(|| panic!("captured")).unwrap_err()
+    };
+    eprintln!("harness: {}", unwind);
+}
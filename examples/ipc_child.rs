@@ -0,0 +1,16 @@
+use maybe_unwind::{maybe_unwind_named, with_context};
+use std::io;
+
+fn main() {
+    maybe_unwind::set_hook();
+
+    let unwind = maybe_unwind_named("ipc::child", || {
+        with_context("while running in the child process", || {
+            panic!("panicked in the child");
+        })
+    })
+    .unwrap_err();
+
+    let stdout = io::stdout();
+    unwind.to_snapshot().write_to(stdout.lock()).unwrap();
+}
@@ -0,0 +1,37 @@
+use maybe_unwind::{maybe_unwind, testing::with_recorded_hook};
+use std::{panic, thread};
+
+struct CatchOnDrop;
+
+impl Drop for CatchOnDrop {
+    fn drop(&mut self) {
+        let _ = panic::catch_unwind(|| panic!("second"));
+    }
+}
+
+fn main() {
+    maybe_unwind::install_abort_reporter();
+
+    let ((), events) = with_recorded_hook(|| {
+        let _ = maybe_unwind(|| panic!("captured"));
+        let _ = panic::catch_unwind(|| panic!("not captured"));
+        let _ = thread::Builder::new()
+            .name("worker".into())
+            .spawn(|| panic!("on the worker"))
+            .unwrap()
+            .join();
+        let _ = maybe_unwind(|| {
+            let _guard = CatchOnDrop;
+            panic!("first");
+        });
+    });
+
+    for event in events {
+        println!(
+            "captured={} thread={}\n{}--",
+            event.captured(),
+            event.thread_name().unwrap_or("<unnamed>"),
+            event.output(),
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use maybe_unwind::{maybe_unwind, with_context, UnwindReport};
+use std::{env, process};
+
+fn main() -> Result<(), UnwindReport> {
+    maybe_unwind::set_hook();
+    let mode = env::args().nth(1).unwrap_or_default();
+
+    let res = maybe_unwind(|| {
+        if mode != "ok" {
+            with_context("while running the example", || panic!("oops"));
+        }
+    });
+    if mode == "exit" {
+        if let Err(unwind) = res {
+            eprint!("{:#}", unwind);
+            process::exit(i32::from(unwind.exit_code()));
+        }
+        return Ok(());
+    }
+    res?;
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use maybe_unwind::{with_context, RunOptions};
+use std::{env, process::ExitCode};
+
+fn main() -> ExitCode {
+    let mode = env::args().nth(1).unwrap_or_default();
+    let options = match env::args().nth(2) {
+        Some(code) => RunOptions::new().exit_code(code.parse().unwrap()),
+        None => RunOptions::new(),
+    };
+
+    maybe_unwind::run_with(options, || match mode.as_str() {
+        "panic" => with_context("while running the example", || panic!("oops")),
+        "fail" => Err("failed"),
+        _ => Ok(()),
+    })
+}
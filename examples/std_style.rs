@@ -0,0 +1,23 @@
+use maybe_unwind::{maybe_unwind_with, CaptureOptions, DisplayOptions, HookOptions};
+use std::thread;
+
+fn main() {
+    // The captured panics are also reported by the default hook to stderr.
+    maybe_unwind::set_hook_with(HookOptions::new().also_forward_captured(true));
+
+    let options = CaptureOptions::new().hook_message(true);
+    let display = DisplayOptions::new().std_style(true);
+
+    let unwind = maybe_unwind_with(options.clone(), || panic!("oops")).unwrap_err();
+    print!("{}", unwind.display_with(display));
+
+    thread::Builder::new()
+        .name("worker".into())
+        .spawn(move || {
+            let unwind = maybe_unwind_with(options, || panic!("multi\nline")).unwrap_err();
+            print!("{}", unwind.display_with(display));
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -0,0 +1,195 @@
+//! Procedural macros for [`maybe-unwind`].
+//!
+//! The macros are re-exported by `maybe-unwind` with the `macros` feature,
+//! and this crate is not intended to be used directly.
+//!
+//! [`maybe-unwind`]: https://docs.rs/maybe-unwind
+
+#![forbid(unsafe_code)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse::Parser as _, parse_macro_input, parse_quote, Block, ImplItem, Item, LitStr, Path,
+    ReturnType, Signature, Type,
+};
+
+/// Runs the body of each method in a capture scope, converting the captured
+/// panic into the error of the method.
+///
+/// The attribute can be applied to an `impl` block, to wrap all the methods
+/// in the block, or to an individual function or method. Every wrapped
+/// function must return a `Result<T, E>` (any type named `Result`), and the
+/// body is executed as if by:
+///
+/// ```text
+/// match maybe_unwind(AssertUnwindSafe(move || body)) {
+///     Ok(output) => output,
+///     Err(unwind) => Err(error(unwind)),
+/// }
+/// ```
+///
+/// where `error` is the path specified by the `error` argument, e.g.
+/// `#[guard(error = "MyError::Panicked")]`, or `From::from` if omitted.
+/// The body of an `async fn` is wrapped by `FutureMaybeUnwindExt::maybe_unwind`
+/// instead, which requires the `futures` feature of `maybe-unwind`.
+///
+/// As with `AssertUnwindSafe`, the wrapped body is assumed to be unwind safe,
+/// so the state borrowed by the method may be left broken after a panic.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{guard, Unwind};
+///
+/// #[derive(Debug)]
+/// enum PluginError {
+///     Invalid,
+///     Panicked(Unwind),
+/// }
+///
+/// struct Plugin;
+///
+/// #[guard(error = "PluginError::Panicked")]
+/// impl Plugin {
+///     fn on_load(&self, config: &str) -> Result<(), PluginError> {
+///         if config.is_empty() {
+///             return Err(PluginError::Invalid);
+///         }
+///         panic!("bug in the plugin");
+///     }
+/// }
+///
+/// maybe_unwind::set_hook();
+///
+/// assert!(matches!(Plugin.on_load(""), Err(PluginError::Invalid)));
+/// match Plugin.on_load("config") {
+///     Err(PluginError::Panicked(unwind)) => assert_eq!(unwind.message(), "bug in the plugin"),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn guard(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut error = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("error") {
+            let value = meta.value()?;
+            error = Some(if value.peek(LitStr) {
+                let lit = value.parse::<LitStr>()?;
+                lit.parse::<Path>().map_err(|_| {
+                    syn::Error::new(
+                        lit.span(),
+                        "expected a path to the constructor, e.g. `error = \"MyError::Panicked\"`",
+                    )
+                })?
+            } else {
+                value.parse::<Path>()?
+            });
+            Ok(())
+        } else {
+            Err(meta.error("unsupported argument, expected `error = \"path::to::constructor\"`"))
+        }
+    });
+    let parsed = parser.parse(args);
+
+    let item = parse_macro_input!(item as Item);
+    match parsed.and_then(|()| expand(item.clone(), error.as_ref())) {
+        Ok(expanded) => expanded.into(),
+        Err(err) => {
+            // The original item is kept to avoid the spurious errors in the rest of the code.
+            let err = err.into_compile_error();
+            quote!(#err #item).into()
+        }
+    }
+}
+
+fn expand(item: Item, error: Option<&Path>) -> syn::Result<TokenStream2> {
+    match item {
+        Item::Fn(mut item) => {
+            *item.block = wrap(&item.sig, &item.block, error)?;
+            Ok(quote!(#item))
+        }
+        Item::Impl(mut item) => {
+            // Report the errors of all methods at once.
+            let mut errors: Option<syn::Error> = None;
+            for impl_item in &mut item.items {
+                if let ImplItem::Fn(method) = impl_item {
+                    match wrap(&method.sig, &method.block, error) {
+                        Ok(block) => method.block = block,
+                        Err(err) => match &mut errors {
+                            Some(errors) => errors.combine(err),
+                            None => errors = Some(err),
+                        },
+                    }
+                }
+            }
+            match errors {
+                Some(errors) => Err(errors),
+                None => Ok(quote!(#item)),
+            }
+        }
+        item => Err(syn::Error::new_spanned(
+            item,
+            "#[guard] can be applied only to an `impl` block or a function",
+        )),
+    }
+}
+
+fn wrap(sig: &Signature, block: &Block, error: Option<&Path>) -> syn::Result<Block> {
+    let output = match &sig.output {
+        ReturnType::Type(_, ty) if is_result(ty) => ty,
+        ReturnType::Type(_, ty) => {
+            return Err(syn::Error::new_spanned(
+                ty,
+                format!("#[guard] requires `{}` to return `Result<T, E>`", sig.ident),
+            ))
+        }
+        ReturnType::Default => {
+            return Err(syn::Error::new_spanned(
+                sig,
+                format!("#[guard] requires `{}` to return `Result<T, E>`", sig.ident),
+            ))
+        }
+    };
+
+    let error = match error {
+        Some(path) => quote!(#path(unwind)),
+        None => quote!(::core::convert::From::from(unwind)),
+    };
+    let caught = if sig.asyncness.is_some() {
+        quote! {
+            ::maybe_unwind::FutureMaybeUnwindExt::maybe_unwind(
+                ::std::panic::AssertUnwindSafe(async move #block)
+            )
+            .await
+        }
+    } else {
+        quote! {
+            ::maybe_unwind::maybe_unwind(
+                ::std::panic::AssertUnwindSafe(move || -> #output #block)
+            )
+        }
+    };
+    Ok(parse_quote!({
+        match #caught {
+            ::core::result::Result::Ok(output) => output,
+            ::core::result::Result::Err(unwind) => ::core::result::Result::Err(#error),
+        }
+    }))
+}
+
+fn is_result(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) => ty
+            .path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == "Result"),
+        Type::Group(ty) => is_result(&ty.elem),
+        Type::Paren(ty) => is_result(&ty.elem),
+        _ => false,
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{captured::CapturedInfo, context, hook::PanicHookInfo, sink};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+#[cfg(all(feature = "deny-abort", not(panic = "unwind")))]
+compile_error!(
+    "the `deny-abort` feature of maybe-unwind is enabled, \
+     but the panic strategy is not `unwind`"
+);
+
+static ABORTED: Mutex<Option<CapturedInfo>> = Mutex::new(None);
+
+fn lock_aborted() -> MutexGuard<'static, Option<CapturedInfo>> {
+    ABORTED.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg_attr(panic = "unwind", allow(dead_code))]
+pub(crate) fn store(captured: CapturedInfo) {
+    *lock_aborted() = Some(captured);
+}
+
+/// Return whether the panics can be caught by `maybe_unwind`.
+///
+/// If the crate is built with `panic = "abort"`, or for targets that do not
+/// support unwinding (e.g. `wasm32-unknown-unknown`), `maybe_unwind` never
+/// returns an `Err` and the process is aborted on panic. Enable the
+/// `deny-abort` feature to reject such builds at compile time.
+#[inline]
+pub const fn unwinding_supported() -> bool {
+    cfg!(panic = "unwind")
+}
+
+/// Take the panic information captured immediately before the abort.
+///
+/// If unwinding is not supported (see [`unwinding_supported`]), the panic
+/// information captured inside of `maybe_unwind` is stored into a
+/// process-global slot rather than being returned to the caller, so that
+/// it can be retrieved post-mortem, e.g. after a WebAssembly trap.
+/// Only the last captured panic is kept.
+///
+/// [`unwinding_supported`]: ./fn.unwinding_supported.html
+pub fn take_aborted_panic() -> Option<CapturedInfo> {
+    lock_aborted().take()
+}
+
+static ABORT_REPORTER: AtomicBool = AtomicBool::new(false);
+
+/// Enable printing the captured panic information before a double panic aborts the process.
+///
+/// If a panic occurs while another panic captured by `maybe_unwind` is still
+/// unwinding, e.g. in a destructor, the process is usually aborted, and the
+/// information captured for the first panic is lost. Once the reporter is
+/// enabled, the panic hook writes the location, message and context frames
+/// of the panic in flight to stderr before the abort.
+///
+/// The report is written by [`capture_panic_info`], so the panic hook must be
+/// installed by [`set_hook`] or a custom hook calling `capture_panic_info`.
+/// The report is also written if the second panic is caught inside the
+/// destructor by `catch_unwind` without a nested `maybe_unwind` scope, since
+/// it cannot be distinguished from a double panic in the panic hook.
+///
+/// # Example
+///
+/// ```
+/// maybe_unwind::set_hook();
+/// maybe_unwind::install_abort_reporter();
+/// ```
+///
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+/// [`set_hook`]: ./fn.set_hook.html
+pub fn install_abort_reporter() {
+    ABORT_REPORTER.store(true, Ordering::Relaxed);
+}
+
+/// Writes the panic in flight in the current scope, if the reporter is enabled.
+pub(crate) fn report_double_panic(info: &PanicHookInfo<'_>) {
+    if !ABORT_REPORTER.load(Ordering::Relaxed) {
+        return;
+    }
+    context::with_in_flight(|captured| {
+        // The errors are ignored since there is nothing to do in this state.
+        let _ = sink::write_stderr(|w| write_report(w, info, captured));
+    });
+}
+
+// Written directly into stderr without building the report string,
+// to avoid allocation as much as possible.
+fn write_report(
+    w: &mut dyn io::Write,
+    info: &PanicHookInfo<'_>,
+    captured: &CapturedInfo,
+) -> io::Result<()> {
+    match info.location() {
+        Some(loc) => writeln!(
+            w,
+            "note: panicked at {} while processing the panic below, \
+             which is likely to abort the process",
+            loc
+        )?,
+        None => writeln!(
+            w,
+            "note: panicked while processing the panic below, \
+             which is likely to abort the process"
+        )?,
+    }
+    match &captured.location {
+        Some(loc) => writeln!(w, "panicked at {}: {}", loc, captured.message())?,
+        None => writeln!(w, "panicked: {}", captured.message())?,
+    }
+    if !captured.context_frames.is_empty() {
+        writeln!(w, "context:")?;
+        for frame in &captured.context_frames {
+            writeln!(w, "    {}", crate::redact::redact(frame))?;
+        }
+    }
+    w.flush()
+}
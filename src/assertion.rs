@@ -0,0 +1,170 @@
+use crate::unwind::Unwind;
+
+/// The kind of assertion that caused the panic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AssertionKind {
+    /// `assert_eq!` or `debug_assert_eq!`.
+    Eq,
+    /// `assert_ne!` or `debug_assert_ne!`.
+    Ne,
+    /// `assert!` or `debug_assert!` without a custom message.
+    Bool,
+}
+
+/// The structured information about a failed assertion.
+///
+/// See [`Unwind::assertion_info`] for details.
+///
+/// [`Unwind::assertion_info`]: ./struct.Unwind.html#method.assertion_info
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssertionInfo {
+    kind: AssertionKind,
+    expression: Option<String>,
+    left: Option<String>,
+    right: Option<String>,
+    message: Option<String>,
+}
+
+impl AssertionInfo {
+    /// Return the kind of the assertion.
+    #[inline]
+    pub fn kind(&self) -> AssertionKind {
+        self.kind
+    }
+
+    /// Return the stringified condition of an `assert!`.
+    #[inline]
+    pub fn expression(&self) -> Option<&str> {
+        self.expression.as_deref()
+    }
+
+    /// Return the `Debug` representation of the left operand of `assert_eq!`/`assert_ne!`.
+    #[inline]
+    pub fn left(&self) -> Option<&str> {
+        self.left.as_deref()
+    }
+
+    /// Return the `Debug` representation of the right operand of `assert_eq!`/`assert_ne!`.
+    #[inline]
+    pub fn right(&self) -> Option<&str> {
+        self.right.as_deref()
+    }
+
+    /// Return the custom message passed to the assertion macro.
+    #[inline]
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    pub(crate) fn parse(payload: &str) -> Option<Self> {
+        parse_binary(payload)
+            .or_else(|| parse_binary_legacy(payload))
+            .or_else(|| parse_bool(payload))
+    }
+}
+
+fn binary_kind(op: &str) -> Option<AssertionKind> {
+    match op {
+        "==" => Some(AssertionKind::Eq),
+        "!=" => Some(AssertionKind::Ne),
+        _ => None,
+    }
+}
+
+// assertion `left == right` failed: <message>
+//   left: <left>
+//  right: <right>
+fn parse_binary(payload: &str) -> Option<AssertionInfo> {
+    let rest = payload.strip_prefix("assertion `left ")?;
+    let (op, rest) = rest.split_at(rest.find(' ')?);
+    let kind = binary_kind(op)?;
+    let rest = rest.strip_prefix(" right` failed")?;
+
+    let left_pos = rest.find("\n  left: ")?;
+    let (header, rest) = rest.split_at(left_pos);
+    let message = match header {
+        "" => None,
+        header => Some(header.strip_prefix(": ")?.to_owned()),
+    };
+
+    let rest = &rest["\n  left: ".len()..];
+    let right_pos = rest.rfind("\n right: ")?;
+    let left = &rest[..right_pos];
+    let right = &rest[right_pos + "\n right: ".len()..];
+
+    Some(AssertionInfo {
+        kind,
+        expression: None,
+        left: Some(left.to_owned()),
+        right: Some(right.to_owned()),
+        message,
+    })
+}
+
+// assertion failed: `(left == right)`
+//   left: `<left>`,
+//  right: `<right>`: <message>
+fn parse_binary_legacy(payload: &str) -> Option<AssertionInfo> {
+    let rest = payload.strip_prefix("assertion failed: `(left ")?;
+    let (op, rest) = rest.split_at(rest.find(' ')?);
+    let kind = binary_kind(op)?;
+    let rest = rest.strip_prefix(" right)`\n  left: `")?;
+
+    let right_pos = rest.rfind("`,\n right: `")?;
+    let left = &rest[..right_pos];
+    let rest = &rest[right_pos + "`,\n right: `".len()..];
+
+    let (right, message) = match rest.rfind("`: ") {
+        Some(pos) if !rest[pos + 3..].contains('`') => (&rest[..pos], Some(&rest[pos + 3..])),
+        _ => (rest.strip_suffix('`')?, None),
+    };
+
+    Some(AssertionInfo {
+        kind,
+        expression: None,
+        left: Some(left.to_owned()),
+        right: Some(right.to_owned()),
+        message: message.map(ToOwned::to_owned),
+    })
+}
+
+// assertion failed: <expression>
+fn parse_bool(payload: &str) -> Option<AssertionInfo> {
+    let expression = payload.strip_prefix("assertion failed: ")?;
+    if expression.is_empty() || expression.contains('\n') {
+        return None;
+    }
+    Some(AssertionInfo {
+        kind: AssertionKind::Bool,
+        expression: Some(expression.to_owned()),
+        left: None,
+        right: None,
+        message: None,
+    })
+}
+
+impl Unwind {
+    /// Parse the panic payload as a failure message of the assertion macros.
+    ///
+    /// This method recognizes the messages generated by `assert!` (without
+    /// a custom message), `assert_eq!` and `assert_ne!`, in both the current
+    /// and the older formats of the standard library. If the payload does
+    /// not look like one of these formats, `None` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, AssertionKind};
+    ///
+    /// let unwind = maybe_unwind(|| assert_eq!(1 + 1, 3, "math is broken")).unwrap_err();
+    /// let info = unwind.assertion_info().unwrap();
+    /// assert_eq!(info.kind(), AssertionKind::Eq);
+    /// assert_eq!(info.left(), Some("2"));
+    /// assert_eq!(info.right(), Some("3"));
+    /// assert_eq!(info.message(), Some("math is broken"));
+    /// ```
+    pub fn assertion_info(&self) -> Option<AssertionInfo> {
+        AssertionInfo::parse(self.payload_str())
+    }
+}
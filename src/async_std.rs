@@ -0,0 +1,77 @@
+//! Spawning the async-std tasks that capture the panics.
+//!
+//! The functions are the counterparts of [`spawn_maybe_unwind`] and
+//! [`spawn_blocking_maybe_unwind`] of the `tokio` feature, and are kept in
+//! this module so that both features can be enabled at the same time.
+//!
+//! [`spawn_maybe_unwind`]: ../fn.spawn_maybe_unwind.html
+//! [`spawn_blocking_maybe_unwind`]: ../fn.spawn_blocking_maybe_unwind.html
+
+use crate::{
+    futures::MaybeUnwind,
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use ::async_std::task::{self, JoinHandle};
+use std::{
+    future::Future,
+    panic::{AssertUnwindSafe, Location},
+};
+
+/// Spawn a future onto the async-std executor, capturing the panic information if it panics.
+///
+/// The task is polled by [`MaybeUnwind`], so a panic inside of it is returned
+/// as `Err(unwind)` from the `JoinHandle`, rather than being resumed in the
+/// task awaiting the handle. The location where this function is called is
+/// recorded as [`Unwind::caller`].
+///
+/// The future is wrapped by `AssertUnwindSafe`, since the task is not
+/// polled again after a panic.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::async_std::spawn_maybe_unwind;
+///
+/// maybe_unwind::set_hook();
+///
+/// async_std::task::block_on(async {
+///     let res = spawn_maybe_unwind(async { panic!("oops") }).await;
+///     assert_eq!(res.unwrap_err().message(), "oops");
+/// });
+/// ```
+///
+/// [`MaybeUnwind`]: ../struct.MaybeUnwind.html
+/// [`Unwind::caller`]: ../struct.Unwind.html#method.caller
+#[cfg_attr(docs, doc(cfg(feature = "async-std")))]
+#[track_caller]
+pub fn spawn_maybe_unwind<F>(future: F) -> JoinHandle<Result<F::Output, Unwind>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    task::spawn(MaybeUnwind::new_at(
+        AssertUnwindSafe(future),
+        Location::caller(),
+    ))
+}
+
+/// Run a blocking closure on the blocking thread pool of async-std,
+/// capturing the panic information if it panics.
+///
+/// This is the blocking variant of [`spawn_maybe_unwind`], built on
+/// `async_std::task::spawn_blocking`.
+///
+/// [`spawn_maybe_unwind`]: ./fn.spawn_maybe_unwind.html
+#[cfg_attr(docs, doc(cfg(feature = "async-std")))]
+#[track_caller]
+pub fn spawn_blocking_maybe_unwind<F, R>(f: F) -> JoinHandle<Result<R, Unwind>>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let caller = Location::caller();
+    task::spawn_blocking(move || {
+        maybe_unwind_at(caller, &CaptureOptions::default(), AssertUnwindSafe(f))
+    })
+}
@@ -1,20 +1,424 @@
+use crate::{
+    options::CaptureOptions,
+    resolver::{ResolvedBacktrace, ResolverRef},
+};
 #[cfg(backtrace)]
 pub(crate) use std::backtrace::Backtrace;
+#[cfg(backtrace)]
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether to capture the backtrace at the entry of every scope,
+/// set by `HookOptions::capture_scope_backtrace`.
+static SCOPE_BACKTRACE: AtomicBool = AtomicBool::new(false);
 
 #[cfg(not(backtrace))]
 #[derive(Debug)]
 pub(crate) enum Backtrace {}
 
+/// The status of the stack backtrace of a captured panic.
+///
+/// This value is returned from [`Unwind::backtrace_status`], and explains
+/// why the backtrace is missing from the report.
+///
+/// [`Unwind::backtrace_status`]: ./struct.Unwind.html#method.backtrace_status
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BacktraceStatus {
+    /// The backtrace was captured.
+    Captured,
+
+    /// Capturing the backtrace was disabled, either by the environment
+    /// variables or by [`BacktraceMode::Never`].
+    ///
+    /// [`BacktraceMode::Never`]: ./enum.BacktraceMode.html#variant.Never
+    Disabled,
+
+    /// Capturing the backtrace is not supported by the platform or by this
+    /// build, i.e. the `backtrace` feature is not enabled.
+    Unsupported,
+
+    /// The panic information was not captured by the panic hook,
+    /// e.g. the hook is not installed.
+    CaptureFailed,
+}
+
+impl BacktraceStatus {
+    pub(crate) fn of(backtrace: Option<&Backtrace>) -> Self {
+        #[cfg(backtrace)]
+        {
+            use std::backtrace::BacktraceStatus as Status;
+
+            match backtrace.map(Backtrace::status) {
+                Some(Status::Captured) => Self::Captured,
+                Some(Status::Disabled) | None => Self::Disabled,
+                Some(Status::Unsupported) => Self::Unsupported,
+                Some(_) => Self::CaptureFailed,
+            }
+        }
+
+        #[cfg(not(backtrace))]
+        {
+            let _ = backtrace;
+            Self::Unsupported
+        }
+    }
+
+    /// Return the note rendered into the report instead of the backtrace.
+    pub(crate) fn note(self) -> Option<&'static str> {
+        match self {
+            Self::Captured => None,
+            Self::Disabled => {
+                Some("run with `RUST_BACKTRACE=1` environment variable to display a backtrace")
+            }
+            Self::Unsupported => Some("stack backtraces are not supported in this build"),
+            Self::CaptureFailed => Some("the stack backtrace was not captured by the panic hook"),
+        }
+    }
+}
+
+pub(crate) fn set_scope_backtrace(enabled: bool) {
+    SCOPE_BACKTRACE.store(enabled, Ordering::Relaxed);
+}
+
+/// Captures the backtrace at the entry of a scope, if enabled.
+#[inline]
+pub(crate) fn capture_at_scope_entry(options: &CaptureOptions) -> Option<Backtrace> {
+    if !options.scope_backtrace && !SCOPE_BACKTRACE.load(Ordering::Relaxed) {
+        return None;
+    }
+    scope_entry_backtrace()
+}
+
+#[cfg(backtrace)]
+#[cold]
+#[inline(never)]
+fn scope_entry_backtrace() -> Option<Backtrace> {
+    Some(Backtrace::force_capture())
+}
+
+#[cfg(not(backtrace))]
+fn scope_entry_backtrace() -> Option<Backtrace> {
+    None
+}
+
 #[cfg(backtrace)]
 macro_rules! capture_backtrace {
-    () => {
-        Some($crate::backtrace::Backtrace::capture())
-    };
+    ($mode:expr) => {{
+        use crate::{backtrace::Backtrace, options::BacktraceMode};
+        match $mode {
+            BacktraceMode::Auto => Some(Backtrace::capture()),
+            BacktraceMode::Always => Some(Backtrace::force_capture()),
+            BacktraceMode::Never => None,
+        }
+    }};
 }
 
 #[cfg(not(backtrace))]
 macro_rules! capture_backtrace {
-    () => {
+    ($mode:expr) => {{
+        let _ = $mode;
         None
+    }};
+}
+
+/// Submits the captured backtrace to the background resolver, if any.
+///
+/// Returns the original backtrace if it is not submitted.
+#[cfg(backtrace)]
+pub(crate) fn offload(
+    backtrace: Option<Backtrace>,
+    resolver: Option<&ResolverRef>,
+    max_frames: Option<usize>,
+) -> (Option<Backtrace>, Option<ResolvedBacktrace>) {
+    use std::backtrace::BacktraceStatus;
+
+    match (backtrace, resolver) {
+        (Some(backtrace), Some(resolver)) if backtrace.status() == BacktraceStatus::Captured => {
+            match resolver.submit(backtrace, max_frames) {
+                Ok(resolved) => (None, Some(resolved)),
+                Err(backtrace) => (Some(backtrace), None),
+            }
+        }
+        (backtrace, _) => (backtrace, None),
+    }
+}
+
+#[cfg(not(backtrace))]
+pub(crate) fn offload(
+    backtrace: Option<Backtrace>,
+    resolver: Option<&ResolverRef>,
+    max_frames: Option<usize>,
+) -> (Option<Backtrace>, Option<ResolvedBacktrace>) {
+    let _ = (resolver, max_frames);
+    (backtrace, None)
+}
+
+/// Renders the backtrace into a string on the background resolver, keeping
+/// the first `max_frames` frames.
+#[cfg(backtrace)]
+pub(crate) fn resolve(backtrace: &Backtrace, max_frames: Option<usize>) -> Option<String> {
+    let text = backtrace.to_string();
+    match max_frames {
+        Some(max_frames) => Some(truncate_text(&text, max_frames).unwrap_or(text)),
+        None => Some(text),
+    }
+}
+
+#[cfg(not(backtrace))]
+pub(crate) fn resolve(backtrace: &Backtrace, max_frames: Option<usize>) -> Option<String> {
+    let _ = max_frames;
+    match *backtrace {}
+}
+
+/// Renders the captured backtrace into a string if it has more frames than `max_frames`.
+///
+/// Returns the original backtrace and `None` if it is within the limit.
+#[cfg(backtrace)]
+pub(crate) fn truncate(
+    backtrace: Option<Backtrace>,
+    max_frames: Option<usize>,
+) -> (Option<Backtrace>, Option<String>) {
+    use std::backtrace::BacktraceStatus;
+
+    match (backtrace, max_frames) {
+        (Some(backtrace), Some(max_frames)) if backtrace.status() == BacktraceStatus::Captured => {
+            match truncate_text(&backtrace.to_string(), max_frames) {
+                Some(text) => (None, Some(text)),
+                None => (Some(backtrace), None),
+            }
+        }
+        (backtrace, _) => (backtrace, None),
+    }
+}
+
+#[cfg(not(backtrace))]
+pub(crate) fn truncate(
+    backtrace: Option<Backtrace>,
+    max_frames: Option<usize>,
+) -> (Option<Backtrace>, Option<String>) {
+    let _ = max_frames;
+    (backtrace, None)
+}
+
+/// Keeps the first `max_frames` frames of the `Display` representation of `Backtrace`.
+///
+/// The representation looks like:
+///
+/// ```text
+///    0: foo::bar
+///              at ./src/lib.rs:3:5
+///    1: baz
+/// note: Some details are omitted, ...
+/// ```
+#[cfg(backtrace)]
+fn truncate_text(text: &str, max_frames: usize) -> Option<String> {
+    fn is_frame(line: &str) -> bool {
+        let line = line.trim_start();
+        let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        digits > 0 && line[digits..].starts_with(':')
+    }
+
+    let frames = text.lines().filter(|line| is_frame(line)).count();
+    if frames <= max_frames {
+        return None;
+    }
+
+    let mut out = String::new();
+    let mut seen = 0;
+    let mut marker = Some(format!(
+        "      \u{2026} {} frames omitted",
+        frames - max_frames
+    ));
+    for line in text.lines() {
+        if is_frame(line) {
+            seen += 1;
+        }
+        if seen > max_frames {
+            if is_frame(line) || line.starts_with(' ') {
+                continue;
+            }
+            if let Some(marker) = marker.take() {
+                out.push_str(&marker);
+                out.push('\n');
+            }
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    if let Some(marker) = marker {
+        out.push_str(&marker);
+        out.push('\n');
+    }
+    Some(out)
+}
+
+/// A frame of the stack backtrace captured by the panic hook.
+///
+/// This type is returned from [`Unwind::backtrace_frames`].
+///
+/// [`Unwind::backtrace_frames`]: ./struct.Unwind.html#method.backtrace_frames
+#[cfg(backtrace)]
+#[cfg_attr(docs, doc(cfg(feature = "backtrace")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    symbol_name: Option<String>,
+    filename: Option<PathBuf>,
+    lineno: Option<u32>,
+}
+
+#[cfg(backtrace)]
+impl Frame {
+    /// Return the demangled name of the function of this frame.
+    #[inline]
+    pub fn symbol_name(&self) -> Option<&str> {
+        self.symbol_name.as_deref()
+    }
+
+    /// Return the name of the source file of this frame.
+    #[inline]
+    pub fn filename(&self) -> Option<&Path> {
+        self.filename.as_deref()
+    }
+
+    /// Return the line number of this frame.
+    #[inline]
+    pub fn lineno(&self) -> Option<u32> {
+        self.lineno
+    }
+
+    /// Return whether this frame belongs to the standard library,
+    /// the panic runtime or this crate.
+    ///
+    /// Such frames appear in every captured backtrace, and are usually
+    /// skipped when looking for the frame to blame.
+    pub fn is_internal(&self) -> bool {
+        is_internal(
+            self.symbol_name.as_deref(),
+            self.filename.as_ref().and_then(|f| f.to_str()),
+        )
+    }
+}
+
+/// Return whether the frame belongs to the standard library, the panic runtime or this crate.
+pub(crate) fn is_internal(symbol_name: Option<&str>, filename: Option<&str>) -> bool {
+    const INTERNAL_CRATES: &[&str] = &[
+        "std::",
+        "core::",
+        "alloc::",
+        "panic_unwind::",
+        "panic_abort::",
+        "maybe_unwind::",
+    ];
+    const INTERNAL_SYMBOLS: &[&str] = &["__rust", "rust_begin_unwind", "rust_panic"];
+
+    if filename.map_or(false, |f| f.starts_with("/rustc/")) {
+        return true;
+    }
+
+    let name = match symbol_name {
+        Some(name) => name.trim_start_matches(['<', '&']),
+        None => return false,
     };
+    let name = name.trim_start_matches("mut ").trim_start_matches("dyn ");
+    INTERNAL_CRATES.iter().any(|krate| name.starts_with(krate))
+        || INTERNAL_SYMBOLS.iter().any(|sym| name.starts_with(sym))
+}
+
+/// An iterator over the frames parsed from the `Debug` representation of `Backtrace`,
+/// which is the only stable way to access the resolved frames.
+///
+/// The representation looks like:
+///
+/// ```text
+/// Backtrace [{ fn: "foo::bar", file: "./src/lib.rs", line: 3 }, { fn: "baz" }]
+/// ```
+#[cfg(backtrace)]
+pub(crate) struct Frames {
+    repr: String,
+    pos: usize,
+}
+
+#[cfg(backtrace)]
+impl Frames {
+    pub(crate) fn new(backtrace: &Backtrace) -> Self {
+        let repr = format!("{:?}", backtrace);
+        let pos = repr.find('[').map_or(repr.len(), |pos| pos + 1);
+        Self { repr, pos }
+    }
+}
+
+#[cfg(backtrace)]
+impl Iterator for Frames {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.repr[self.pos..];
+        let (frame, rest) = parse_frame(rest.trim_start_matches(", "))?;
+        self.pos = self.repr.len() - rest.len();
+        Some(frame)
+    }
+}
+
+#[cfg(backtrace)]
+fn parse_frame(s: &str) -> Option<(Frame, &str)> {
+    let mut s = s.strip_prefix("{ fn: ")?;
+
+    let symbol_name = if let Some(rest) = s.strip_prefix("<unknown>") {
+        s = rest;
+        None
+    } else {
+        // The symbol name is written without escaping.
+        let rest = s.strip_prefix('"')?;
+        let end = [", file: ", " }"]
+            .iter()
+            .filter_map(|delim| rest.find(&format!("\"{}", delim)))
+            .min()?;
+        s = &rest[end + 1..];
+        Some(rest[..end].to_owned())
+    };
+
+    let mut filename = None;
+    if let Some(rest) = s.strip_prefix(", file: \"") {
+        let (name, rest) = parse_escaped(rest)?;
+        filename = Some(PathBuf::from(name));
+        s = rest;
+    }
+
+    let mut lineno = None;
+    if let Some(rest) = s.strip_prefix(", line: ") {
+        let end = rest.find(|c: char| !c.is_ascii_digit())?;
+        lineno = rest[..end].parse().ok();
+        s = &rest[end..];
+    }
+
+    let s = s.strip_prefix(" }")?;
+    Some((
+        Frame {
+            symbol_name,
+            filename,
+            lineno,
+        },
+        s,
+    ))
+}
+
+/// Parse the rest of a string literal written by `Debug`.
+#[cfg(backtrace)]
+fn parse_escaped(s: &str) -> Option<(String, &str)> {
+    let mut out = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, &s[i + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => out.push('\r'),
+                c => out.push(c),
+            },
+            c => out.push(c),
+        }
+    }
+    None
 }
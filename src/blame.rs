@@ -0,0 +1,269 @@
+use crate::{
+    backtrace::is_internal,
+    location::LocationStyle,
+    snapshot::UnwindSnapshot,
+    unwind::{Location, Unwind},
+};
+use std::{fmt, path::Path};
+
+/// The fragments of the paths of the source trees of the dependencies downloaded by Cargo.
+const DEPENDENCY_MARKERS: &[&str] = &["/.cargo/registry/", "/.cargo/git/"];
+
+/// The source from which the frame of a [`Blame`] was chosen.
+///
+/// [`Blame`]: ./struct.Blame.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BlameSource {
+    /// The location of the panic reported by the panic hook.
+    Location,
+
+    /// A frame of the captured stack backtrace, chosen because the location
+    /// of the panic was outside of the workspace or missing.
+    Backtrace,
+}
+
+/// The most relevant frame of the user code for a captured panic.
+///
+/// This value is returned from [`Unwind::blame`] and [`UnwindSnapshot::blame`],
+/// and its `Display` representation is suitable for a one-line summary,
+/// e.g. `my_crate::parser::parse_expr at src/parser.rs:88`.
+///
+/// [`Unwind::blame`]: ./struct.Unwind.html#method.blame
+/// [`UnwindSnapshot::blame`]: ./struct.UnwindSnapshot.html#method.blame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blame {
+    symbol_name: Option<String>,
+    location: Location,
+    source: BlameSource,
+}
+
+impl Blame {
+    /// Return the name of the function of the blamed frame, if it is known.
+    ///
+    /// The name is taken from the backtrace, so it is missing if the
+    /// backtrace was not captured.
+    #[inline]
+    pub fn symbol_name(&self) -> Option<&str> {
+        self.symbol_name.as_deref()
+    }
+
+    /// Return the location of the blamed frame.
+    #[inline]
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// Return the source from which the blamed frame was chosen.
+    #[inline]
+    pub fn source(&self) -> BlameSource {
+        self.source
+    }
+}
+
+impl fmt::Display for Blame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = self.location.display_style(LocationStyle::FileLineOnly);
+        match &self.symbol_name {
+            Some(symbol_name) => write!(f, "{} at {}", symbol_name, location),
+            None => location.fmt(f),
+        }
+    }
+}
+
+impl Unwind {
+    /// Return the most relevant frame of the user code for this panic.
+    ///
+    /// The location of the panic is used if it is inside of the workspace.
+    /// If it points into a dependency instead, e.g. a panic raised by a crate
+    /// downloaded from a registry, the innermost frame of the backtrace
+    /// inside of the workspace is returned, and the location is used only if
+    /// no such frame is found. [`Blame::source`] tells which one was used.
+    ///
+    /// A source file is considered a part of the workspace unless its path
+    /// contains `/.cargo/registry/`, `/.cargo/git/` or starts with `/rustc/`.
+    /// Use [`blame_within`] to specify the workspace explicitly.
+    ///
+    /// This method returns `None` if neither the location nor the backtrace
+    /// was captured.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use maybe_unwind::{maybe_unwind, BlameSource};
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// let blame = unwind.blame().unwrap();
+    /// assert_eq!(blame.source(), BlameSource::Location);
+    /// println!("blame: {}", blame);
+    /// ```
+    ///
+    /// [`Blame::source`]: ./struct.Blame.html#method.source
+    /// [`blame_within`]: #method.blame_within
+    pub fn blame(&self) -> Option<Blame> {
+        blame(self.location(), self.backtrace_text().as_deref(), None)
+    }
+
+    /// Return the most relevant frame of the user code for this panic,
+    /// considering only the source files under `workspace_prefix` as the
+    /// part of the workspace.
+    ///
+    /// The prefix is compared by the path components in the same way as
+    /// [`originates_from`]. See [`blame`] for details.
+    ///
+    /// [`originates_from`]: #method.originates_from
+    /// [`blame`]: #method.blame
+    pub fn blame_within(&self, workspace_prefix: &str) -> Option<Blame> {
+        blame(
+            self.location(),
+            self.backtrace_text().as_deref(),
+            Some(workspace_prefix),
+        )
+    }
+}
+
+impl UnwindSnapshot {
+    /// Return the most relevant frame of the user code for this panic.
+    ///
+    /// See [`Unwind::blame`] for details.
+    ///
+    /// [`Unwind::blame`]: ./struct.Unwind.html#method.blame
+    pub fn blame(&self) -> Option<Blame> {
+        blame(self.location(), self.backtrace(), None)
+    }
+
+    /// Return the most relevant frame of the user code for this panic,
+    /// considering only the source files under `workspace_prefix` as the
+    /// part of the workspace.
+    ///
+    /// See [`Unwind::blame_within`] for details.
+    ///
+    /// [`Unwind::blame_within`]: ./struct.Unwind.html#method.blame_within
+    pub fn blame_within(&self, workspace_prefix: &str) -> Option<Blame> {
+        blame(self.location(), self.backtrace(), Some(workspace_prefix))
+    }
+}
+
+fn blame(
+    location: Option<&Location>,
+    backtrace: Option<&str>,
+    workspace_prefix: Option<&str>,
+) -> Option<Blame> {
+    let frames = backtrace.map_or_else(Vec::new, parse_frames);
+
+    if let Some(location) = location {
+        if in_workspace(location.file(), workspace_prefix) {
+            return Some(blame_location(location, &frames));
+        }
+    }
+
+    let frame = frames.iter().find(|frame| {
+        !is_internal(
+            Some(frame.symbol_name),
+            frame.location.as_ref().map(|l| l.file()),
+        ) && frame
+            .location
+            .as_ref()
+            .map_or(false, |l| in_workspace(l.file(), workspace_prefix))
+    });
+    match frame {
+        Some(frame) => Some(Blame {
+            symbol_name: Some(frame.symbol_name.to_owned()),
+            location: frame.location.clone()?,
+            source: BlameSource::Backtrace,
+        }),
+        None => location.map(|location| blame_location(location, &frames)),
+    }
+}
+
+/// Blame the location of the panic, with the name of the function looked up from the backtrace.
+fn blame_location(location: &Location, frames: &[TextFrame<'_>]) -> Blame {
+    let symbol_name = frames
+        .iter()
+        .find(|frame| {
+            frame.location.as_ref().map_or(false, |l| {
+                l.line() == location.line() && same_file(l.file(), location.file())
+            })
+        })
+        .map(|frame| frame.symbol_name.to_owned());
+    Blame {
+        symbol_name,
+        location: location.clone(),
+        source: BlameSource::Location,
+    }
+}
+
+fn in_workspace(file: &str, workspace_prefix: Option<&str>) -> bool {
+    let file = normalize(file);
+    match workspace_prefix {
+        Some(prefix) => Path::new(&file).starts_with(normalize(prefix)),
+        None => {
+            !file.starts_with("/rustc/")
+                && !DEPENDENCY_MARKERS
+                    .iter()
+                    .any(|marker| file.contains(marker))
+        }
+    }
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    normalize(a) == normalize(b)
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches("./").to_owned()
+}
+
+/// A frame parsed from the `Display` representation of `Backtrace`.
+struct TextFrame<'a> {
+    symbol_name: &'a str,
+    location: Option<Location>,
+}
+
+/// Parse the frames from the `Display` representation of `Backtrace`.
+///
+/// The representation looks like:
+///
+/// ```text
+///    0: foo::bar
+///              at ./src/lib.rs:3:5
+///    1: baz
+/// ```
+fn parse_frames(text: &str) -> Vec<TextFrame<'_>> {
+    let mut frames: Vec<TextFrame<'_>> = vec![];
+    for line in text.lines() {
+        let line = line.trim_start();
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut() {
+                if frame.location.is_none() {
+                    frame.location = parse_location(location);
+                }
+            }
+            continue;
+        }
+
+        let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            continue;
+        }
+        if let Some(symbol_name) = line[digits..].strip_prefix(": ") {
+            frames.push(TextFrame {
+                symbol_name,
+                location: None,
+            });
+        }
+    }
+    frames
+}
+
+/// Parse `file:line:column` written by `Backtrace`.
+fn parse_location(s: &str) -> Option<Location> {
+    let (rest, column) = s.rsplit_once(':')?;
+    let (file, line) = rest.rsplit_once(':')?;
+    Some(Location::new(
+        file.trim_start_matches("./"),
+        line.parse().ok()?,
+        column.parse().ok()?,
+    ))
+}
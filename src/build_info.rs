@@ -0,0 +1,78 @@
+/// How the availability of `std::backtrace` was determined by the build script.
+///
+/// The detection is controlled by the `MAYBE_UNWIND_BACKTRACE` environment
+/// variable at the build time. See [`build_info`] for details.
+///
+/// [`build_info`]: ./fn.build_info.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BacktraceDetection {
+    /// The compiler was probed (`auto`, the default).
+    Auto,
+    /// The backtraces were enabled without probing (`on`).
+    ForcedOn,
+    /// The backtraces were disabled without probing (`off`).
+    ForcedOff,
+}
+
+/// The capabilities compiled into this crate, returned from [`build_info`].
+///
+/// [`build_info`]: ./fn.build_info.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BuildInfo {
+    /// Whether the panic information is captured, i.e. the `capture` feature is enabled.
+    pub capture: bool,
+    /// Whether the stack backtraces can be captured.
+    pub backtrace: bool,
+    /// How the availability of the stack backtraces was determined.
+    pub backtrace_detection: BacktraceDetection,
+    /// Whether the panic hook receives `std::panic::PanicHookInfo` (Rust 1.81 or later).
+    pub panic_hook_info: bool,
+    /// Whether the hook is composed with `std::panic::update_hook` (nightly only).
+    pub panic_update_hook: bool,
+    /// Whether the panics unwind, as returned from [`unwinding_supported`].
+    ///
+    /// [`unwinding_supported`]: ./fn.unwinding_supported.html
+    pub unwinding: bool,
+}
+
+/// Return the capabilities compiled into this crate.
+///
+/// Some of the capabilities depend on the compiler, and are detected by the
+/// build script by compiling a few probes. In the sandboxed builds that
+/// forbid the build scripts to spawn the compiler, the detection can be
+/// skipped by setting the `MAYBE_UNWIND_BACKTRACE` environment variable:
+///
+/// * `auto` (or unset): probe the compiler
+/// * `on`: enable the stack backtraces, which requires `std::backtrace`
+///   (Rust 1.65 or later)
+/// * `off`: disable the stack backtraces
+///
+/// With `on` or `off`, the other capabilities detected by the probes fall
+/// back to the ones available on every supported compiler.
+///
+/// # Example
+///
+/// ```
+/// let info = maybe_unwind::build_info();
+/// if !info.backtrace {
+///     eprintln!("note: maybe-unwind is built without the backtrace support");
+/// }
+/// ```
+#[inline]
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        capture: cfg!(feature = "capture"),
+        backtrace: cfg!(backtrace),
+        // Unset if the crate is compiled without the build script.
+        backtrace_detection: match option_env!("MAYBE_UNWIND_BACKTRACE_MODE") {
+            Some("on") => BacktraceDetection::ForcedOn,
+            Some("off") => BacktraceDetection::ForcedOff,
+            _ => BacktraceDetection::Auto,
+        },
+        panic_hook_info: cfg!(panic_hook_info),
+        panic_update_hook: cfg!(panic_update_hook),
+        unwinding: cfg!(panic = "unwind"),
+    }
+}
@@ -0,0 +1,471 @@
+use crate::{
+    backtrace::{Backtrace, BacktraceStatus},
+    options::LocationPolicy,
+    redact,
+    resolver::ResolvedBacktrace,
+    unwind::Location,
+};
+use std::{borrow::Cow, collections::BTreeMap, error, fmt, thread::Thread, time::SystemTime};
+
+/// The reason why the panic information was not captured, returned from
+/// [`Unwind::why_no_capture`] and [`Unwind::capture_miss`].
+///
+/// The reason is determined when the panic is caught, from the state of
+/// the scope and the panic hook at that time.
+///
+/// [`Unwind::why_no_capture`]: ./struct.Unwind.html#method.why_no_capture
+/// [`Unwind::capture_miss`]: ./struct.Unwind.html#method.capture_miss
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CaptureMiss {
+    /// No panic hook capturing the panic information has been installed,
+    /// neither by [`set_hook`] nor by a custom hook calling [`capture_panic_info`].
+    ///
+    /// [`set_hook`]: ./fn.set_hook.html
+    /// [`capture_panic_info`]: ./fn.capture_panic_info.html
+    NoHook,
+
+    /// The panic hook installed by this crate was replaced by another hook,
+    /// e.g. by `std::panic::set_hook` in a dependency.
+    HookReplaced,
+
+    /// The panic hook seems to be installed, but was not called while the
+    /// scope was active. This is typically the case for the panics raised by
+    /// `std::panic::resume_unwind`, which does not call the panic hook.
+    Resumed,
+
+    /// The panic hook was called, but failed to capture the panic information.
+    Failed(CaptureError),
+
+    /// The capture is disabled on the current thread by [`disable_for_current_thread`].
+    ///
+    /// [`disable_for_current_thread`]: ./fn.disable_for_current_thread.html
+    DisabledForThread,
+
+    /// The capture is disabled by the `capture` feature.
+    FeatureDisabled,
+}
+
+impl CaptureMiss {
+    /// Return whether the capture was disabled, rather than missed by the active scope.
+    pub(crate) fn is_disabled(self) -> bool {
+        match self {
+            Self::DisabledForThread | Self::FeatureDisabled => true,
+            Self::NoHook | Self::HookReplaced | Self::Resumed | Self::Failed(..) => false,
+        }
+    }
+}
+
+impl fmt::Display for CaptureMiss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoHook => f.write_str(
+                "no panic hook capturing the panic information is installed \
+                 (call `maybe_unwind::set_hook`)",
+            ),
+            Self::HookReplaced => {
+                f.write_str("the panic hook installed by maybe-unwind was replaced by another hook")
+            }
+            Self::Resumed => f.write_str(
+                "the panic hook was not called, e.g. the panic was resumed by `resume_unwind`",
+            ),
+            Self::Failed(err) => write!(f, "capture failed: {}", err),
+            Self::DisabledForThread => f.write_str("the capture is disabled on the current thread"),
+            Self::FeatureDisabled => {
+                f.write_str("the `capture` feature of maybe-unwind is disabled")
+            }
+        }
+    }
+}
+
+/// The result of a successful call of [`try_capture_panic_info`].
+///
+/// [`try_capture_panic_info`]: ./fn.try_capture_panic_info.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CaptureStatus {
+    /// The panic information was captured into the innermost scope.
+    Captured,
+
+    /// The panic was only counted, since the innermost scope has reached
+    /// the limit of [`CaptureOptions::max_captures_per_scope`].
+    ///
+    /// [`CaptureOptions::max_captures_per_scope`]: ./struct.CaptureOptions.html#method.max_captures_per_scope
+    Suppressed,
+
+    /// The panic occurred outside of the capture scopes, or the capture is
+    /// disabled by the `capture` feature or on the current thread.
+    NotInScope,
+}
+
+impl CaptureStatus {
+    /// Return whether the panic was handled by the capture scope, i.e. it
+    /// should not be reported by the panic hook any further.
+    #[inline]
+    pub fn is_handled(self) -> bool {
+        match self {
+            Self::Captured | Self::Suppressed => true,
+            Self::NotInScope => false,
+        }
+    }
+}
+
+/// The error type returned from [`try_capture_panic_info`], describing why
+/// the panic information inside of a capture scope could not be captured.
+///
+/// The error is also reported to the capture scope, as a note of the captured
+/// information (see [`CapturedInfo::notes`]) or by [`Unwind::capture_miss`].
+///
+/// [`try_capture_panic_info`]: ./fn.try_capture_panic_info.html
+/// [`CapturedInfo::notes`]: ./struct.CapturedInfo.html#method.notes
+/// [`Unwind::capture_miss`]: ./struct.Unwind.html#method.capture_miss
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CaptureError {
+    /// The capture was requested while the current thread was still capturing
+    /// another panic, e.g. by a payload formatter calling the panic hook.
+    Reentrant,
+
+    /// The thread local context has already been destroyed, e.g. the panic
+    /// occurred in the destructor of another thread local value.
+    ContextDestroyed,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Reentrant => "the capture was reentered while capturing another panic",
+            Self::ContextDestroyed => "the thread local context has been destroyed",
+        })
+    }
+}
+
+impl error::Error for CaptureError {}
+
+/// The panic information captured by the panic hook.
+///
+/// This value is passed to the observers registered by [`add_observer`],
+/// and is also accessible from [`Unwind::captured`].
+///
+/// [`add_observer`]: ./fn.add_observer.html
+/// [`Unwind::captured`]: ./struct.Unwind.html#method.captured
+#[derive(Debug)]
+pub struct CapturedInfo {
+    /// The message, unless disabled by `capture_message(false)`.
+    pub(crate) message: Option<Cow<'static, str>>,
+    pub(crate) location: Option<Location>,
+    #[cfg_attr(not(backtrace), allow(dead_code))]
+    pub(crate) backtrace: Option<Backtrace>,
+    /// The backtrace rendered and truncated by `CaptureOptions::max_backtrace_frames`.
+    pub(crate) truncated_backtrace: Option<String>,
+    /// The backtrace handed to the background resolver.
+    pub(crate) resolved_backtrace: Option<ResolvedBacktrace>,
+    /// The backtrace captured at the entry of the scope.
+    #[cfg_attr(not(backtrace), allow(dead_code))]
+    pub(crate) scope_backtrace: Option<Backtrace>,
+    pub(crate) context_frames: Vec<String>,
+    /// The handle of the panicking thread, whose name is read on demand.
+    pub(crate) thread: Thread,
+    pub(crate) timestamp: Option<SystemTime>,
+    /// The process-global sequence number allocated by the panic hook.
+    pub(crate) sequence: u64,
+    pub(crate) notes: Vec<String>,
+    pub(crate) annotations: Vec<(String, String)>,
+    pub(crate) env: BTreeMap<String, Option<String>>,
+    pub(crate) panic_depth: u32,
+    pub(crate) scope_label: Option<Cow<'static, str>>,
+    pub(crate) raw_hook_message: Option<String>,
+    pub(crate) suppressed_captures: usize,
+    /// The panics captured earlier in the same scope, in the order of capture.
+    pub(crate) prior: Vec<PriorCapture>,
+    pub(crate) location_policy: LocationPolicy,
+    #[cfg(feature = "tracing-error")]
+    pub(crate) span_trace: Option<tracing_error::SpanTrace>,
+}
+
+/// The maximum number of the earlier panics remembered by a capture.
+const MAX_PRIOR: usize = 16;
+
+/// A panic captured earlier in the same scope, kept for `Unwind::best_location`.
+#[derive(Debug, Clone)]
+pub(crate) struct PriorCapture {
+    message: Option<Cow<'static, str>>,
+    location: Option<Location>,
+}
+
+impl CapturedInfo {
+    /// Return the panic message with the redactor applied.
+    ///
+    /// See [`Unwind::message`] for details.
+    ///
+    /// The message is empty if it was not captured because of
+    /// [`CaptureOptions::capture_message`].
+    ///
+    /// [`Unwind::message`]: ./struct.Unwind.html#method.message
+    /// [`CaptureOptions::capture_message`]: ./struct.CaptureOptions.html#method.capture_message
+    #[inline]
+    pub fn message(&self) -> Cow<'_, str> {
+        self.message
+            .as_deref()
+            .map_or(Cow::Borrowed(""), redact::redact)
+    }
+
+    /// Return the information about the location from which the panic originated.
+    #[inline]
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// Return the annotations that were active when the panic occurred.
+    #[inline]
+    pub fn context_frames(&self) -> &[String] {
+        &self.context_frames
+    }
+
+    /// Return the name of the thread on which the panic occurred.
+    #[inline]
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread.name()
+    }
+
+    /// Return the label attached to the scope by [`set_scope_label`]
+    /// or [`maybe_unwind_named`].
+    ///
+    /// [`set_scope_label`]: ./fn.set_scope_label.html
+    /// [`maybe_unwind_named`]: ./fn.maybe_unwind_named.html
+    #[inline]
+    pub fn scope_label(&self) -> Option<&str> {
+        self.scope_label.as_deref()
+    }
+
+    /// Return the time when the panic information was captured.
+    ///
+    /// This value is not available on the platforms without the system clock,
+    /// such as `wasm32-unknown-unknown`.
+    #[inline]
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// Return the sequence number of this panic.
+    ///
+    /// See [`Unwind::sequence`] for details.
+    ///
+    /// [`Unwind::sequence`]: ./struct.Unwind.html#method.sequence
+    #[inline]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Return the key-value annotations attached by the callback of
+    /// [`HookOptions::on_capture_mut`], in the order they were attached.
+    ///
+    /// [`HookOptions::on_capture_mut`]: ./struct.HookOptions.html#method.on_capture_mut
+    #[inline]
+    pub fn annotations(&self) -> &[(String, String)] {
+        &self.annotations
+    }
+
+    /// Attach a key-value annotation to the captured information.
+    ///
+    /// This is intended to be called by the callback of
+    /// [`HookOptions::on_capture_mut`], for recording the application-specific
+    /// data at the moment of the capture. The same key may be attached more
+    /// than once.
+    ///
+    /// [`HookOptions::on_capture_mut`]: ./struct.HookOptions.html#method.on_capture_mut
+    pub fn annotate(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.annotations.push((key.into(), value.into()));
+    }
+
+    /// Return the environment variables recorded at the moment of the capture,
+    /// with the redactor applied.
+    ///
+    /// See [`Unwind::env`] for details.
+    ///
+    /// [`Unwind::env`]: ./struct.Unwind.html#method.env
+    #[inline]
+    pub fn env(&self) -> Cow<'_, BTreeMap<String, Option<String>>> {
+        redact::redact_env(&self.env)
+    }
+
+    /// Return the notes about the problems that occurred while capturing,
+    /// such as a panic raised by an observer.
+    #[inline]
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    /// Return the number of panics in flight on the thread when this panic
+    /// occurred, including itself.
+    ///
+    /// See [`Unwind::panic_depth`] for details.
+    ///
+    /// [`Unwind::panic_depth`]: ./struct.Unwind.html#method.panic_depth
+    #[inline]
+    pub fn panic_depth(&self) -> u32 {
+        self.panic_depth
+    }
+
+    /// Return the panic information rendered by the panic hook as it is.
+    ///
+    /// See [`Unwind::hook_message`] for details.
+    ///
+    /// [`Unwind::hook_message`]: ./struct.Unwind.html#method.hook_message
+    #[inline]
+    pub fn hook_message(&self) -> Option<&str> {
+        self.raw_hook_message.as_deref()
+    }
+
+    /// Return the number of the panics in the same scope that were not captured.
+    ///
+    /// See [`Unwind::suppressed_capture_count`] for details.
+    ///
+    /// [`Unwind::suppressed_capture_count`]: ./struct.Unwind.html#method.suppressed_capture_count
+    #[inline]
+    pub fn suppressed_capture_count(&self) -> usize {
+        self.suppressed_captures
+    }
+
+    /// Return the status of the stack backtrace.
+    #[inline]
+    pub fn backtrace_status(&self) -> BacktraceStatus {
+        if self.truncated_backtrace.is_some() || self.resolved_backtrace.is_some() {
+            return BacktraceStatus::Captured;
+        }
+        BacktraceStatus::of(self.backtrace.as_ref())
+    }
+
+    /// Return the text representation of the captured stack backtrace.
+    pub(crate) fn backtrace_text(&self) -> Option<String> {
+        if let Some(truncated) = self.truncated_backtrace.as_ref() {
+            return Some(truncated.clone());
+        }
+        if let Some(resolved) = self.resolved_backtrace.as_ref() {
+            return resolved.wait();
+        }
+
+        #[cfg(backtrace)]
+        {
+            use std::backtrace::BacktraceStatus;
+
+            if let Some(backtrace) = self.backtrace.as_ref() {
+                if let BacktraceStatus::Captured = backtrace.status() {
+                    return Some(backtrace.to_string());
+                }
+            }
+        }
+        None
+    }
+
+    /// Return the text representation of the backtrace captured at the entry of the scope.
+    pub(crate) fn scope_backtrace_text(&self) -> Option<String> {
+        #[cfg(backtrace)]
+        {
+            use std::backtrace::BacktraceStatus;
+
+            if let Some(backtrace) = self.scope_backtrace.as_ref() {
+                if let BacktraceStatus::Captured = backtrace.status() {
+                    return Some(backtrace.to_string());
+                }
+            }
+        }
+        None
+    }
+
+    /// Make a copy of this value, with the backtrace rendered as a text.
+    #[cfg(feature = "history")]
+    pub(crate) fn to_detached(&self) -> Self {
+        Self {
+            message: self.message.clone(),
+            location: self.location.clone(),
+            backtrace: None,
+            truncated_backtrace: self.backtrace_text(),
+            resolved_backtrace: None,
+            scope_backtrace: None,
+            context_frames: self.context_frames.clone(),
+            thread: self.thread.clone(),
+            timestamp: self.timestamp,
+            sequence: self.sequence,
+            notes: self.notes.clone(),
+            annotations: self.annotations.clone(),
+            env: self.env.clone(),
+            panic_depth: self.panic_depth,
+            scope_label: self.scope_label.clone(),
+            raw_hook_message: self.raw_hook_message.clone(),
+            suppressed_captures: self.suppressed_captures,
+            prior: self.prior.clone(),
+            location_policy: self.location_policy,
+            #[cfg(feature = "tracing-error")]
+            span_trace: self.span_trace.clone(),
+        }
+    }
+
+    /// Take the panics captured earlier than this one, and append this one.
+    pub(crate) fn take_prior(&mut self) -> Vec<PriorCapture> {
+        let mut prior = std::mem::take(&mut self.prior);
+        if prior.len() < MAX_PRIOR {
+            prior.push(PriorCapture {
+                message: self.message.clone(),
+                location: self.location.clone(),
+            });
+        }
+        prior
+    }
+
+    /// Pick the location by the policy, given the rendered final payload.
+    pub(crate) fn best_location(&self, payload: &str) -> Option<&Location> {
+        let mut candidates = self
+            .prior
+            .iter()
+            .map(|prior| (prior.message.as_deref(), prior.location.as_ref()))
+            .chain(Some((self.message.as_deref(), self.location.as_ref())));
+        let best = match self.location_policy {
+            LocationPolicy::Last => return self.location.as_ref(),
+            LocationPolicy::Earliest => candidates.next(),
+            LocationPolicy::MatchingPayload => candidates
+                .clone()
+                .find(|(message, _)| {
+                    message.map_or(false, |message| {
+                        !message.is_empty() && payload.contains(message)
+                    })
+                })
+                .or_else(|| candidates.next()),
+        };
+        best.and_then(|(_, location)| location)
+    }
+
+    /// Get the stack backtrace captured by the panic hook.
+    ///
+    /// See [`Unwind::backtrace`] for the availability of this method.
+    ///
+    /// [`Unwind::backtrace`]: ./struct.Unwind.html#method.backtrace
+    #[cfg(backtrace)]
+    #[cfg_attr(docs, doc(cfg(feature = "backtrace")))]
+    #[inline]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+
+    /// Get the stack backtrace handed to the background resolver.
+    ///
+    /// See [`Unwind::resolved_backtrace`] for details.
+    ///
+    /// [`Unwind::resolved_backtrace`]: ./struct.Unwind.html#method.resolved_backtrace
+    #[inline]
+    pub fn resolved_backtrace(&self) -> Option<&ResolvedBacktrace> {
+        self.resolved_backtrace.as_ref()
+    }
+
+    /// Get the stack backtrace captured at the entry of the scope.
+    ///
+    /// See [`Unwind::scope_backtrace`] for details.
+    ///
+    /// [`Unwind::scope_backtrace`]: ./struct.Unwind.html#method.scope_backtrace
+    #[cfg(backtrace)]
+    #[cfg_attr(docs, doc(cfg(feature = "backtrace")))]
+    #[inline]
+    pub fn scope_backtrace(&self) -> Option<&Backtrace> {
+        self.scope_backtrace.as_ref()
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    assertion::AssertionInfo,
+    captured::CapturedInfo,
+    snapshot::UnwindSnapshot,
+    unwind::{self, Unwind},
+};
+use std::fmt;
+
+/// The coarse category of a panic, for bucketing the failures in the reports.
+///
+/// This value is returned from [`Unwind::classify`].
+///
+/// [`Unwind::classify`]: ./struct.Unwind.html#method.classify
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PanicKind {
+    /// A failed `assert!`, `assert_eq!` or `assert_ne!` without a custom
+    /// message of `assert!`.
+    Assertion,
+    /// An explicit panic with a message that is not recognized as one of the
+    /// other kinds, e.g. `panic!`, `unreachable!` or `expect`.
+    Explicit,
+    /// An index or a range out of the bounds of a slice, a string or a collection.
+    IndexOutOfBounds,
+    /// `unwrap` on a `None` or an `Err`, or `unwrap_err` on an `Ok`.
+    Unwrap,
+    /// An arithmetic overflow or a division by zero.
+    Overflow,
+    /// A payload that is not a string, e.g. raised by `panic_any`.
+    Other,
+}
+
+impl PanicKind {
+    /// All of the kinds, for looking up the kind by its name.
+    pub(crate) const ALL: [PanicKind; 6] = [
+        PanicKind::Assertion,
+        PanicKind::Explicit,
+        PanicKind::IndexOutOfBounds,
+        PanicKind::Unwrap,
+        PanicKind::Overflow,
+        PanicKind::Other,
+    ];
+
+    /// Return the name of this kind in `snake_case`, as written by `to_map`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PanicKind::Assertion => "assertion",
+            PanicKind::Explicit => "explicit",
+            PanicKind::IndexOutOfBounds => "index_out_of_bounds",
+            PanicKind::Unwrap => "unwrap",
+            PanicKind::Overflow => "overflow",
+            PanicKind::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for PanicKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// The prefixes of the messages generated by the standard library, including
+// the formats of the older and the newer toolchains.
+const UNWRAP: &[&str] = &[
+    "called `Option::unwrap()` on a `None` value",
+    "called `Result::unwrap()` on an `Err` value",
+    "called `Result::unwrap_err()` on an `Ok` value",
+];
+
+const INDEX_OUT_OF_BOUNDS: &[&str] = &[
+    "index out of bounds: ",
+    "range start index ",
+    "range end index ",
+    "slice index starts at ",
+    "byte index ",
+    "start byte index ",
+    "end byte index ",
+    "begin <= end (",
+    "begin > end (",
+    "byte range starts at ",
+    "removal index (is ",
+    "insertion index (is ",
+    "swap_remove index (is ",
+];
+
+const OVERFLOW: &[&str] = &[
+    "attempt to add with overflow",
+    "attempt to subtract with overflow",
+    "attempt to multiply with overflow",
+    "attempt to divide with overflow",
+    "attempt to calculate the remainder with overflow",
+    "attempt to negate with overflow",
+    "attempt to shift left with overflow",
+    "attempt to shift right with overflow",
+    "attempt to divide by zero",
+    "attempt to calculate the remainder with a divisor of zero",
+];
+
+/// Classify the panic by its message, or `None` for a non-string payload.
+fn classify(message: Option<&str>) -> PanicKind {
+    let message = match message {
+        Some(message) => message,
+        None => return PanicKind::Other,
+    };
+    let starts_with_any = |prefixes: &[&str]| prefixes.iter().any(|p| message.starts_with(p));
+    if starts_with_any(UNWRAP) {
+        PanicKind::Unwrap
+    } else if starts_with_any(INDEX_OUT_OF_BOUNDS) {
+        PanicKind::IndexOutOfBounds
+    } else if starts_with_any(OVERFLOW) {
+        PanicKind::Overflow
+    } else if AssertionInfo::parse(message).is_some() {
+        PanicKind::Assertion
+    } else {
+        PanicKind::Explicit
+    }
+}
+
+/// Classify the panic by the captured message, in which the payloads other
+/// than strings are replaced with the placeholder.
+fn classify_message(message: Option<&str>) -> PanicKind {
+    classify(message.filter(|&m| m != unwind::UNKNOWN_PAYLOAD))
+}
+
+impl Unwind {
+    /// Classify this panic into a coarse [`PanicKind`].
+    ///
+    /// The kind is determined by matching the payload against the messages
+    /// generated by the standard library, such as ``called `Option::unwrap()`
+    /// on a `None` value``, `index out of bounds` or `attempt to add with
+    /// overflow`, and the formats recognized by [`assertion_info`]. The other
+    /// string payloads are classified as [`PanicKind::Explicit`], and the
+    /// payloads of the other types as [`PanicKind::Other`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, PanicKind};
+    ///
+    /// let unwind = maybe_unwind(|| None::<i32>.unwrap()).unwrap_err();
+    /// assert_eq!(unwind.classify(), PanicKind::Unwrap);
+    ///
+    /// let unwind = maybe_unwind(|| assert_eq!(1 + 1, 3)).unwrap_err();
+    /// assert_eq!(unwind.classify(), PanicKind::Assertion);
+    /// ```
+    ///
+    /// [`PanicKind`]: ./enum.PanicKind.html
+    /// [`assertion_info`]: #method.assertion_info
+    /// [`PanicKind::Explicit`]: ./enum.PanicKind.html#variant.Explicit
+    /// [`PanicKind::Other`]: ./enum.PanicKind.html#variant.Other
+    pub fn classify(&self) -> PanicKind {
+        classify(unwind::downcast_str(self.payload()))
+    }
+}
+
+impl CapturedInfo {
+    /// Classify the captured panic by its message.
+    ///
+    /// See [`Unwind::classify`] for the rules. If the message was not
+    /// captured, the panic is classified as [`PanicKind::Other`].
+    ///
+    /// [`Unwind::classify`]: ./struct.Unwind.html#method.classify
+    /// [`PanicKind::Other`]: ./enum.PanicKind.html#variant.Other
+    pub fn classify(&self) -> PanicKind {
+        classify_message(self.message.as_deref())
+    }
+}
+
+impl UnwindSnapshot {
+    /// Classify the panic by its message.
+    ///
+    /// See [`Unwind::classify`] for the rules.
+    ///
+    /// [`Unwind::classify`]: ./struct.Unwind.html#method.classify
+    pub fn classify(&self) -> PanicKind {
+        classify_message(Some(&self.message))
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{
+    captured::CapturedInfo,
+    observer::{add_observer, remove_observer, ObserverId},
+    unwind::Location,
+};
+use std::{
+    fmt,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread::{self, ThreadId},
+    time::{Duration, SystemTime},
+};
+
+/// An owned copy of the panic information delivered by [`PanicCollector`].
+///
+/// [`PanicCollector`]: ./struct.PanicCollector.html
+#[derive(Debug, Clone)]
+pub struct CollectedPanic {
+    message: String,
+    location: Option<Location>,
+    context_frames: Vec<String>,
+    thread_id: ThreadId,
+    thread_name: Option<String>,
+    scope_label: Option<String>,
+    timestamp: Option<SystemTime>,
+    sequence: u64,
+    panic_depth: u32,
+}
+
+impl CollectedPanic {
+    fn new(info: &CapturedInfo) -> Self {
+        Self {
+            message: info.message().into_owned(),
+            location: info.location().cloned(),
+            context_frames: info.context_frames().to_vec(),
+            thread_id: thread::current().id(),
+            thread_name: info.thread_name().map(ToOwned::to_owned),
+            scope_label: info.scope_label().map(ToOwned::to_owned),
+            timestamp: info.timestamp(),
+            sequence: info.sequence(),
+            panic_depth: info.panic_depth(),
+        }
+    }
+
+    /// Return the panic message with the redactor applied.
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return the information about the location from which the panic originated.
+    #[inline]
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// Return the annotations that were active when the panic occurred.
+    #[inline]
+    pub fn context_frames(&self) -> &[String] {
+        &self.context_frames
+    }
+
+    /// Return the identifier of the thread on which the panic occurred.
+    #[inline]
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// Return the name of the thread on which the panic occurred.
+    #[inline]
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    /// Return the label attached to the scope by [`set_scope_label`].
+    ///
+    /// [`set_scope_label`]: ./fn.set_scope_label.html
+    #[inline]
+    pub fn scope_label(&self) -> Option<&str> {
+        self.scope_label.as_deref()
+    }
+
+    /// Return the time when the panic information was captured.
+    #[inline]
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// Return the sequence number of the panic.
+    ///
+    /// See [`Unwind::sequence`] for details.
+    ///
+    /// [`Unwind::sequence`]: ./struct.Unwind.html#method.sequence
+    #[inline]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Return the number of panics in flight on the thread when this panic
+    /// occurred, including itself.
+    #[inline]
+    pub fn panic_depth(&self) -> u32 {
+        self.panic_depth
+    }
+}
+
+/// A collector that forwards every captured panic to a [`PanicReceiver`].
+///
+/// The collector registers itself as an observer (see [`add_observer`]),
+/// and sends a [`CollectedPanic`] for each panic captured by `maybe_unwind`
+/// on any thread, as soon as the panic is caught. The observer is removed
+/// when the collector is dropped, after which the receiver returns the
+/// remaining panics and then reports disconnection.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, set_scope_label, PanicCollector};
+/// use std::thread;
+///
+/// maybe_unwind::set_hook();
+/// let (collector, receiver) = PanicCollector::new();
+///
+/// thread::spawn(|| {
+///     let _ = maybe_unwind(|| {
+///         set_scope_label("worker");
+///         panic!("oops");
+///     });
+/// })
+/// .join()
+/// .unwrap();
+///
+/// drop(collector);
+/// let panics = receiver.drain();
+/// assert!(panics.iter().any(|p| p.scope_label() == Some("worker")));
+/// ```
+///
+/// [`PanicReceiver`]: ./struct.PanicReceiver.html
+/// [`add_observer`]: ./fn.add_observer.html
+/// [`CollectedPanic`]: ./struct.CollectedPanic.html
+pub struct PanicCollector {
+    id: ObserverId,
+}
+
+impl PanicCollector {
+    /// Create a collector and the receiver paired with it.
+    pub fn new() -> (PanicCollector, PanicReceiver) {
+        let (tx, rx) = mpsc::channel();
+        // `Sender` is not `Sync` on older compilers.
+        let tx: Mutex<Sender<CollectedPanic>> = Mutex::new(tx);
+        let id = add_observer(move |info| {
+            let panic = CollectedPanic::new(info);
+            let tx = tx.lock().unwrap_or_else(|err| err.into_inner());
+            let _ = tx.send(panic);
+        });
+        (PanicCollector { id }, PanicReceiver { rx })
+    }
+}
+
+impl fmt::Debug for PanicCollector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PanicCollector")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl Drop for PanicCollector {
+    fn drop(&mut self) {
+        remove_observer(self.id);
+    }
+}
+
+/// The receiving half of [`PanicCollector`].
+///
+/// [`PanicCollector`]: ./struct.PanicCollector.html
+#[derive(Debug)]
+pub struct PanicReceiver {
+    rx: Receiver<CollectedPanic>,
+}
+
+impl PanicReceiver {
+    /// Block the current thread until a panic is collected.
+    ///
+    /// Returns `None` if the collector has been dropped and all of the
+    /// collected panics have been received.
+    pub fn recv(&self) -> Option<CollectedPanic> {
+        self.rx.recv().ok()
+    }
+
+    /// Block the current thread until a panic is collected or the timeout elapses.
+    ///
+    /// Returns `None` on timeout, or if the collector has been dropped and all
+    /// of the collected panics have been received.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<CollectedPanic> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    /// Return a collected panic without blocking, if any.
+    pub fn try_recv(&self) -> Option<CollectedPanic> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Return all of the panics collected so far without blocking.
+    ///
+    /// The panics delivered from multiple threads may be received out of
+    /// order, so they are sorted by [`CollectedPanic::sequence`].
+    ///
+    /// [`CollectedPanic::sequence`]: ./struct.CollectedPanic.html#method.sequence
+    pub fn drain(&self) -> Vec<CollectedPanic> {
+        let mut panics: Vec<_> = self.rx.try_iter().collect();
+        panics.sort_by_key(CollectedPanic::sequence);
+        panics
+    }
+}
@@ -0,0 +1,179 @@
+use crate::hook::{self, HookOptions, PanicHook, PanicHookFn, PanicHookInfo};
+use std::{
+    panic,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+};
+
+/// The options of a composed hook, which are replaced by `set_hook_with`
+/// instead of composing the hook again.
+type SharedOptions = Arc<RwLock<Arc<HookOptions>>>;
+
+/// The addresses of the live hooks created by `compose` and `set_hook_with`,
+/// with their options.
+static COMPOSED: Mutex<Vec<(usize, SharedOptions)>> = Mutex::new(Vec::new());
+
+/// The marker captured by the composed hook, which unregisters the hook on drop.
+pub(crate) struct Marker {
+    addr: AtomicUsize,
+    options: SharedOptions,
+}
+
+impl Marker {
+    /// Register the address of the composed hook holding this marker.
+    pub(crate) fn register(&self, hook: &PanicHookFn) {
+        let addr = address(hook);
+        self.addr.store(addr, Ordering::SeqCst);
+        COMPOSED
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push((addr, Arc::clone(&self.options)));
+    }
+}
+
+impl Drop for Marker {
+    fn drop(&mut self) {
+        let addr = self.addr.load(Ordering::SeqCst);
+        let mut composed = COMPOSED.lock().unwrap_or_else(|err| err.into_inner());
+        composed.retain(|&(a, _)| a != addr);
+    }
+}
+
+fn address(hook: &PanicHookFn) -> usize {
+    hook as *const PanicHookFn as *const () as usize
+}
+
+/// Return the options of `hook` if it is composed by this crate.
+fn options_of(hook: &PanicHookFn) -> Option<SharedOptions> {
+    let addr = address(hook);
+    let composed = COMPOSED.lock().unwrap_or_else(|err| err.into_inner());
+    composed
+        .iter()
+        .find(|(a, _)| *a == addr)
+        .map(|(_, options)| Arc::clone(options))
+}
+
+/// Return whether `hook` already captures the panic information.
+pub(crate) fn is_composed(hook: &PanicHookFn) -> bool {
+    hook::is_installed_hook(hook) || options_of(hook).is_some()
+}
+
+/// Replace the options of `hook` if it already captures the panic information.
+///
+/// This returns `false` if `hook` does not capture the panic information.
+/// The capturing hooks without the options, e.g. the one replaced by the
+/// quiet scopes, are left as they are.
+pub(crate) fn replace_options(hook: &PanicHookFn, options: HookOptions) -> bool {
+    match options_of(hook) {
+        Some(shared) => {
+            *shared.write().unwrap_or_else(|err| err.into_inner()) = Arc::new(options);
+            true
+        }
+        None => hook::is_installed_hook(hook),
+    }
+}
+
+/// Create the body of a composed hook, which is registered by the returned
+/// marker once it is boxed.
+pub(crate) fn composed_fn(
+    options: HookOptions,
+) -> (
+    impl Fn(&PanicHookInfo<'_>, &PanicHook) + Send + Sync + 'static,
+    Arc<Marker>,
+) {
+    let marker = Arc::new(Marker {
+        addr: AtomicUsize::new(0),
+        options: Arc::new(RwLock::new(Arc::new(options))),
+    });
+    let captured_marker = Arc::clone(&marker);
+    let f = move |info: &PanicHookInfo<'_>, prev: &PanicHook| {
+        // The lock is not held while the hook is running, so that the
+        // options can be replaced by the previous hook.
+        let options = Arc::clone(
+            &captured_marker
+                .options
+                .read()
+                .unwrap_or_else(|err| err.into_inner()),
+        );
+        hook::call_hook(&options, info, prev)
+    };
+    (f, marker)
+}
+
+/// Compose a panic hook that captures the panic information with `prev`.
+///
+/// The returned hook calls [`capture_panic_info`] and then delegates to
+/// `prev` according to `options`, in the same manner as the hook installed
+/// by [`set_hook_with`]. Unlike `set_hook_with`, this function does not
+/// install the hook by itself, so that the frameworks owning the panic hook
+/// can install it in their preferred order.
+///
+/// If `prev` is already composed by this function or installed by this
+/// crate, it is returned as it is, so the panic information is never
+/// captured twice.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{compose, maybe_unwind, HookOptions};
+/// use std::panic;
+///
+/// // e.g. the hook installed by an error reporting framework.
+/// panic::set_hook(Box::new(|info| eprintln!("custom: {}", info)));
+///
+/// let hook = compose(panic::take_hook(), HookOptions::new());
+/// panic::set_hook(hook);
+///
+/// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+/// assert!(unwind.location().is_some());
+/// ```
+///
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+/// [`set_hook_with`]: ./fn.set_hook_with.html
+pub fn compose(prev: Box<PanicHookFn>, options: HookOptions) -> Box<PanicHookFn> {
+    if is_composed(&*prev) {
+        return prev;
+    }
+
+    let (f, marker) = composed_fn(options);
+    let hook: Box<PanicHookFn> = Box::new(move |info| f(info, &*prev));
+    marker.register(&*hook);
+    hook
+}
+
+/// Compose the capturing panic hook with the current hook, and install it.
+///
+/// This is a shorthand of `panic::set_hook(compose(panic::take_hook(), options))`
+/// for adding the capture to the hook installed by another framework rather
+/// than replacing it. It returns `true` if the hook is newly installed, and
+/// `false` if the current hook already captures the panic information or
+/// the current thread is panicking.
+///
+/// See [`compose`] for details.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{compose_with_current_hook, HookOptions};
+///
+/// assert!(compose_with_current_hook(HookOptions::new()));
+/// assert!(!compose_with_current_hook(HookOptions::new()));
+/// ```
+///
+/// [`compose`]: ./fn.compose.html
+pub fn compose_with_current_hook(options: HookOptions) -> bool {
+    if thread::panicking() {
+        return false;
+    }
+
+    let prev = panic::take_hook();
+    if is_composed(&*prev) {
+        hook::replace_hook(prev, true);
+        return false;
+    }
+    hook::replace_hook(compose(prev, options), true);
+    true
+}
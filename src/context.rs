@@ -1,54 +1,626 @@
-use crate::unwind::Captured;
-use std::{cell::Cell, ptr::NonNull};
+use crate::{
+    abort,
+    captured::{CaptureError, CaptureStatus, CapturedInfo},
+    options::CaptureOptions,
+    outcome,
+};
+use std::{
+    any::Any,
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    panic::{self, UnwindSafe},
+    thread,
+};
 
-pub(crate) struct Context<'a> {
-    pub(crate) captured: &'a mut Option<Captured>,
+/// The snapshot of the innermost `maybe_unwind` scope on the current thread.
+#[derive(Clone, Copy)]
+pub(crate) struct Scope {
+    /// The nesting depth of the active scopes, or zero if no scope is active.
+    depth: usize,
+    /// The generation of the innermost scope, which is unique to each scope
+    /// entered on this thread.
+    generation: u64,
+    /// The number of panics in flight when the innermost scope was entered.
+    outer_panics: u32,
+    /// The number of panics in flight, including the one captured
+    /// in the innermost scope.
+    in_flight: u32,
 }
 
-impl Context<'_> {
-    pub(crate) fn is_set() -> bool {
-        TLS_CTX.with(|tls| tls.get().is_some())
+impl Scope {
+    /// Returns a copy of the options of this scope.
+    ///
+    /// The copy is only made by the panic hook, so that the borrow of the
+    /// stack is not held while capturing.
+    pub(crate) fn options(&self) -> CaptureOptions {
+        OPTIONS
+            .with(|options| options.borrow().get(self.depth.wrapping_sub(1)).cloned())
+            .unwrap_or_default()
     }
 
-    pub(crate) fn try_with<F, R>(f: F) -> Result<R, AccessError>
-    where
-        F: FnOnce(&mut Context<'_>) -> R,
-    {
-        let ctx_ptr = TLS_CTX.with(|tls| tls.take());
-        let _guard = Guard(ctx_ptr);
-        match ctx_ptr {
-            Some(mut ctx_ptr) => unsafe { Ok(f(ctx_ptr.as_mut())) },
-            None => Err(AccessError(())),
+    /// Returns the number of panics in flight when a panic occurs in this scope,
+    /// including the new one.
+    pub(crate) fn panic_depth(&self) -> u32 {
+        self.outer_panics + 1
+    }
+}
+
+/// The state of the `maybe_unwind` scopes on the current thread.
+///
+/// The fields are separate cells so that entering and exiting a scope, which
+/// happen on every call regardless of whether a panic occurs, only touch the
+/// fields they update instead of copying the whole state.
+struct State {
+    depth: Cell<usize>,
+    generation: Cell<u64>,
+    /// The number of scopes entered on this thread so far.
+    generations: Cell<u64>,
+    /// Whether the panic hook is capturing the panic information.
+    capturing: Cell<bool>,
+    outer_panics: Cell<u32>,
+    in_flight: Cell<u32>,
+    /// The depth of the innermost labeled scope, or zero if no scope has a label.
+    labeled: Cell<usize>,
+    /// Whether the capture is enabled on this thread.
+    enabled: Cell<bool>,
+    /// The last capture failure and the generation of the scope it occurred in.
+    failure: Cell<Option<(u64, CaptureError)>>,
+}
+
+impl State {
+    #[inline]
+    fn innermost(&self) -> Scope {
+        Scope {
+            depth: self.depth.get(),
+            generation: self.generation.get(),
+            outer_panics: self.outer_panics.get(),
+            in_flight: self.in_flight.get(),
         }
     }
+
+    #[inline]
+    fn set_innermost(&self, scope: &Scope) {
+        self.depth.set(scope.depth);
+        self.generation.set(scope.generation);
+        self.outer_panics.set(scope.outer_panics);
+        self.in_flight.set(scope.in_flight);
+    }
 }
 
 thread_local! {
-    pub(crate) static TLS_CTX: Cell<Option<NonNull<Context<'static>>>> = Cell::new(None);
+    static STATE: State = const {
+        State {
+            depth: Cell::new(0),
+            generation: Cell::new(0),
+            generations: Cell::new(0),
+            capturing: Cell::new(false),
+            outer_panics: Cell::new(0),
+            in_flight: Cell::new(0),
+            labeled: Cell::new(0),
+            enabled: Cell::new(true),
+            failure: Cell::new(None),
+        }
+    };
+    static CAPTURED: RefCell<Vec<Slot>> = const { RefCell::new(Vec::new()) };
+    /// The copies of the options of the active scopes, indexed by the depth minus one.
+    static OPTIONS: RefCell<Vec<CaptureOptions>> = const { RefCell::new(Vec::new()) };
+    static LABELS: RefCell<Vec<(usize, Cow<'static, str>)>> = const { RefCell::new(Vec::new()) };
 }
 
-pub(crate) struct Guard(pub(crate) Option<NonNull<Context<'static>>>);
+/// The captured information tagged with the scope that received it.
+///
+/// A slot is only observable by the scope of the same generation, so the
+/// panics captured in the nested scopes never leak into the outer scopes
+/// and vice versa, regardless of the order in which they are captured.
+struct Slot {
+    depth: usize,
+    generation: u64,
+    captured: CapturedInfo,
+    /// The number of the panics captured in the scope so far.
+    captures: usize,
+    /// The number of the panics not captured due to `max_captures`.
+    suppressed: usize,
+}
 
-impl Drop for Guard {
-    fn drop(&mut self) {
-        TLS_CTX.with(|tls| {
-            tls.set(self.0.take());
+/// Runs `f` within a new scope, catching the panic in `f`.
+///
+/// Entering a scope only bumps the depth and generation counters, and the
+/// information captured by the panic hook is returned only if it was
+/// recorded while this scope was the innermost one.
+///
+/// If the scope is entered while the thread is unwinding, e.g. in a
+/// destructor, the panics in `f` are counted as secondary panics.
+#[inline]
+pub(crate) fn scope<F, R>(options: &CaptureOptions, f: F) -> Result<R, Caught>
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    let entered = match enter(options) {
+        Some(entered) => entered,
+        None => {
+            return panic::catch_unwind(f).map_err(|payload| Caught {
+                payload,
+                captured: None,
+                failure: None,
+                entered: false,
+            })
+        }
+    };
+    let res = panic::catch_unwind(f);
+    if exit(&entered) {
+        remove_labels(entered.prev.depth);
+    }
+    if res.is_err() {
+        outcome::clear();
+    }
+    res.map_err(|payload| Caught {
+        payload,
+        captured: take_captured(&entered),
+        failure: take_failure(&entered),
+        entered: true,
+    })
+}
+
+/// The panic caught by `scope`.
+pub(crate) struct Caught {
+    pub(crate) payload: Box<dyn Any + Send>,
+    /// The information captured in the scope, if any.
+    pub(crate) captured: Option<Box<CapturedInfo>>,
+    /// The last failure of the capture in the scope, if any.
+    pub(crate) failure: Option<CaptureError>,
+    /// Whether the scope was entered, i.e. the capture was enabled.
+    pub(crate) entered: bool,
+}
+
+/// The token of an entered scope, holding the state of the enclosing scope.
+struct Entered {
+    prev: Scope,
+    generation: u64,
+}
+
+/// Enters a new scope, or returns `None` if the capture is disabled.
+#[inline]
+fn enter(options: &CaptureOptions) -> Option<Entered> {
+    if !cfg!(feature = "capture") {
+        return None;
+    }
+    STATE.with(|state| {
+        if !state.enabled.get() {
+            return None;
+        }
+        let prev = state.innermost();
+        let outer_panics = if thread::panicking() {
+            prev.in_flight.max(1)
+        } else {
+            0
+        };
+        let generation = state.generations.get() + 1;
+        state.generations.set(generation);
+        drop(push_options(prev.depth, options));
+        state.set_innermost(&Scope {
+            depth: prev.depth + 1,
+            generation,
+            outer_panics,
+            in_flight: outer_panics,
         });
+        Some(Entered { prev, generation })
+    })
+}
+
+/// Exits the scope, and returns whether the labels of the exited scopes remain.
+#[inline]
+fn exit(entered: &Entered) -> bool {
+    drop(truncate_options(entered.prev.depth));
+    STATE.with(|state| {
+        state.set_innermost(&entered.prev);
+        state.labeled.get() > entered.prev.depth
+    })
+}
+
+/// Pushes a copy of the options of the scope entered at `depth + 1`, and
+/// returns the options of the scopes exited without `exit`, if any.
+///
+/// The returned values are dropped by the caller after the stack is
+/// released, since dropping them may run arbitrary code, e.g. the
+/// destructor of a payload describer.
+#[inline]
+fn push_options(depth: usize, options: &CaptureOptions) -> Vec<CaptureOptions> {
+    OPTIONS.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let stale = split_off(&mut stack, depth);
+        stack.push(options.clone());
+        stale
+    })
+}
+
+/// Removes the options of the scopes deeper than `depth`, and returns them.
+#[inline]
+fn truncate_options(depth: usize) -> Vec<CaptureOptions> {
+    OPTIONS.with(|stack| split_off(&mut stack.borrow_mut(), depth))
+}
+
+#[inline]
+fn split_off(stack: &mut Vec<CaptureOptions>, depth: usize) -> Vec<CaptureOptions> {
+    if stack.len() > depth {
+        stack.split_off(depth)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Takes the information captured in the exited scope.
+#[cold]
+#[inline(never)]
+fn take_captured(entered: &Entered) -> Option<Box<CapturedInfo>> {
+    let depth = entered.prev.depth;
+    let generation = entered.generation;
+    CAPTURED.with(|slots| {
+        let mut slots = slots.borrow_mut();
+        // The slots of the inner scopes are no longer reachable.
+        let pos = slots.iter().position(|s| s.depth > depth)?;
+        let slot = slots.drain(pos..).find(|s| s.generation == generation)?;
+        let mut captured = Box::new(slot.captured);
+        captured.suppressed_captures = slot.suppressed;
+        Some(captured)
+    })
+}
+
+/// Takes the capture failure recorded while the exited scope was the innermost one.
+#[cold]
+#[inline(never)]
+fn take_failure(entered: &Entered) -> Option<CaptureError> {
+    STATE.with(|state| match state.failure.get() {
+        Some((generation, err)) if generation == entered.generation => {
+            state.failure.set(None);
+            Some(err)
+        }
+        _ => None,
+    })
+}
+
+/// Returns `true` if the current thread is inside of a `maybe_unwind` scope.
+///
+/// It fails only if the thread local context has been destroyed.
+pub(crate) fn is_set() -> Result<bool, CaptureError> {
+    if !cfg!(feature = "capture") {
+        return Ok(false);
+    }
+    STATE
+        .try_with(|state| state.enabled.get() && state.depth.get() > 0)
+        .map_err(|_| CaptureError::ContextDestroyed)
+}
+
+/// Disable the capture of the panic information on the current thread.
+///
+/// While the capture is disabled, [`maybe_unwind`] behaves like plain
+/// `catch_unwind`: it neither registers the scope nor receives the panic
+/// information, and [`capture_panic_info`] returns `false` immediately,
+/// so the panic is forwarded to the previous hook by [`set_hook`].
+/// It is intended for the threads raising a huge number of intentional
+/// panics, e.g. in a profiling harness, while the other threads keep
+/// capturing the panics.
+///
+/// The capture is enabled by default on every thread.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind;
+///
+/// maybe_unwind::set_hook();
+///
+/// maybe_unwind::disable_for_current_thread();
+/// assert!(!maybe_unwind::is_enabled());
+///
+/// let unwind = maybe_unwind(|| std::panic::panic_any(42)).unwrap_err();
+/// assert!(unwind.location().is_none());
+///
+/// maybe_unwind::enable_for_current_thread();
+/// ```
+///
+/// [`maybe_unwind`]: ./fn.maybe_unwind.html
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+/// [`set_hook`]: ./fn.set_hook.html
+#[inline]
+pub fn disable_for_current_thread() {
+    STATE.with(|state| state.enabled.set(false));
+}
+
+/// Enable the capture of the panic information on the current thread,
+/// disabled by [`disable_for_current_thread`].
+///
+/// [`disable_for_current_thread`]: ./fn.disable_for_current_thread.html
+#[inline]
+pub fn enable_for_current_thread() {
+    STATE.with(|state| state.enabled.set(true));
+}
+
+/// Return whether the capture of the panic information is enabled on the current thread.
+///
+/// It always returns `false` if the `capture` feature is disabled.
+#[inline]
+pub fn is_enabled() -> bool {
+    cfg!(feature = "capture") && STATE.with(|state| state.enabled.get())
+}
+
+/// Return whether the current thread is inside of a `maybe_unwind` scope.
+///
+/// The scope is tracked per thread, so this function only tells about the
+/// scopes entered on the current thread: a closure running on another thread
+/// spawned from inside of a scope is not in the scope. The asynchronous
+/// adaptors such as [`MaybeUnwind`] enter the scope only while polling the
+/// inner future.
+///
+/// It always returns `false` if the capture is disabled, by the `capture`
+/// feature or on the current thread, since no scope is entered in that case.
+/// It only reads a thread local value, so it is cheap enough for choosing
+/// whether to report a failure eagerly, e.g. in custom assertion macros.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{in_scope, maybe_unwind};
+///
+/// assert!(!in_scope());
+/// let _ = maybe_unwind(|| assert!(in_scope()));
+/// ```
+///
+/// [`MaybeUnwind`]: ./struct.MaybeUnwind.html
+#[inline]
+pub fn in_scope() -> bool {
+    scope_depth() > 0
+}
+
+/// Return the number of the nested `maybe_unwind` scopes on the current thread.
+///
+/// It is zero outside of the scopes. See [`in_scope`] for details.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, scope_depth};
+///
+/// assert_eq!(scope_depth(), 0);
+/// let _ = maybe_unwind(|| {
+///     assert_eq!(scope_depth(), 1);
+///     let _ = maybe_unwind(|| assert_eq!(scope_depth(), 2));
+/// });
+/// ```
+///
+/// [`in_scope`]: ./fn.in_scope.html
+#[inline]
+pub fn scope_depth() -> usize {
+    if !cfg!(feature = "capture") {
+        return 0;
     }
+    STATE.try_with(|state| state.depth.get()).unwrap_or(0)
 }
 
-pub(crate) struct AccessError(());
+/// Return the label of the innermost labeled `maybe_unwind` scope on the current thread.
+///
+/// The label is the one attached by [`set_scope_label`] or the named scopes
+/// such as [`maybe_unwind_named`], and is inherited by the nested scopes that
+/// have no label of their own. It is `None` outside of the scopes. See
+/// [`in_scope`] for details.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{current_scope_label, maybe_unwind, maybe_unwind_named};
+///
+/// let _ = maybe_unwind_named("suite::case", || {
+///     let _ = maybe_unwind(|| {
+///         assert_eq!(current_scope_label().as_deref(), Some("suite::case"));
+///     });
+/// });
+/// assert_eq!(current_scope_label(), None);
+/// ```
+///
+/// [`set_scope_label`]: ./fn.set_scope_label.html
+/// [`maybe_unwind_named`]: ./fn.maybe_unwind_named.html
+/// [`in_scope`]: ./fn.in_scope.html
+#[inline]
+pub fn current_scope_label() -> Option<String> {
+    if !cfg!(feature = "capture") {
+        return None;
+    }
+    scope_label().map(Cow::into_owned)
+}
 
-macro_rules! with_set_ctx {
-    ($ctx:expr, $body:block) => {{
-        use crate::context::{Context, Guard, TLS_CTX};
-        use std::{mem, ptr::NonNull};
-        let ctx = $ctx;
-        let old_ctx = unsafe {
-            let ctx_ptr = mem::transmute::<_, &mut Context<'static>>(ctx);
-            TLS_CTX.with(|tls| tls.replace(Some(NonNull::from(ctx_ptr))))
+/// Attach a label to the innermost `maybe_unwind` scope on the current thread.
+///
+/// The label is recorded into the panic information captured in the scope,
+/// including the nested scopes that have no label of their own, and can be
+/// retrieved by [`Unwind::scope_label`] and [`CapturedInfo::scope_label`].
+/// It is useful for telling which test or task a panic belongs to, e.g. when
+/// collecting the panics from multiple threads by [`PanicCollector`].
+///
+/// Setting a label again replaces the previous one, and the label is removed
+/// when the scope exits. This function does nothing if the current thread
+/// is not inside of a scope or the `capture` feature is disabled.
+///
+/// [`Unwind::scope_label`]: ./struct.Unwind.html#method.scope_label
+/// [`CapturedInfo::scope_label`]: ./struct.CapturedInfo.html#method.scope_label
+/// [`PanicCollector`]: ./struct.PanicCollector.html
+pub fn set_scope_label(label: impl Into<Cow<'static, str>>) {
+    if !cfg!(feature = "capture") {
+        return;
+    }
+    let depth = STATE.with(|state| state.depth.get());
+    if depth == 0 {
+        return;
+    }
+    let label = label.into();
+    LABELS.with(|labels| {
+        if let Ok(mut labels) = labels.try_borrow_mut() {
+            match labels.last_mut() {
+                Some(last) if last.0 == depth => last.1 = label,
+                _ => labels.push((depth, label)),
+            }
+            STATE.with(|state| state.labeled.set(depth));
+        }
+    });
+}
+
+/// Removes the labels of the scopes deeper than `depth`.
+#[cold]
+#[inline(never)]
+fn remove_labels(depth: usize) {
+    LABELS.with(|labels| {
+        if let Ok(mut labels) = labels.try_borrow_mut() {
+            let len = labels.partition_point(|(d, _)| *d <= depth);
+            labels.truncate(len);
+            let innermost = labels.last().map_or(0, |(d, _)| *d);
+            STATE.with(|state| state.labeled.set(innermost));
+        }
+    });
+}
+
+/// Returns the label of the innermost labeled scope on the current thread.
+pub(crate) fn scope_label() -> Option<Cow<'static, str>> {
+    if STATE.with(|state| state.labeled.get()) == 0 {
+        return None;
+    }
+    LABELS
+        .try_with(|labels| {
+            let labels = labels.try_borrow().ok()?;
+            labels.last().map(|(_, label)| label.clone())
+        })
+        .ok()
+        .flatten()
+}
+
+/// Stores the information captured by `f` into the innermost scope.
+///
+/// The scope is marked as capturing while running `f`, so that the panic
+/// raised during capturing is not recorded. The failures are recorded
+/// into the innermost scope, and reported when the scope exits.
+pub(crate) fn try_capture<F>(f: F) -> Result<CaptureStatus, CaptureError>
+where
+    F: FnOnce(&Scope, &CaptureOptions) -> CapturedInfo,
+{
+    let scope = STATE
+        .try_with(|state| {
+            if state.depth.get() == 0 {
+                return Ok(None);
+            }
+            if state.capturing.get() {
+                state
+                    .failure
+                    .set(Some((state.generation.get(), CaptureError::Reentrant)));
+                return Err(CaptureError::Reentrant);
+            }
+            state.capturing.set(true);
+            Ok(Some(state.innermost()))
+        })
+        .map_err(|_| CaptureError::ContextDestroyed)??;
+    let scope = match scope {
+        Some(scope) => scope,
+        None => return Ok(CaptureStatus::NotInScope),
+    };
+    let _guard = Guard;
+    let options = scope.options();
+    if cfg!(panic = "unwind") && suppress(&scope, &options) {
+        STATE.with(|state| state.in_flight.set(scope.panic_depth()));
+        return Ok(CaptureStatus::Suppressed);
+    }
+    let mut captured = f(&scope, &options);
+
+    if !cfg!(panic = "unwind") {
+        abort::store(captured);
+        return Ok(CaptureStatus::Captured);
+    }
+
+    STATE.with(|state| state.in_flight.set(scope.panic_depth()));
+    let stored = CAPTURED.with(|slots| {
+        slots
+            .try_borrow_mut()
+            .map(|mut slots| {
+                let (captures, suppressed) = slots
+                    .iter_mut()
+                    .rev()
+                    .find(|s| s.generation == scope.generation)
+                    .map_or((0, 0), |s| {
+                        captured.prior = s.captured.take_prior();
+                        (s.captures, s.suppressed)
+                    });
+                // Keep the slots of the outer scopes, which may be receiving
+                // the panics still in flight, e.g. when this panic is raised
+                // in a destructor during unwinding.
+                slots.retain(|s| s.depth < scope.depth);
+                slots.push(Slot {
+                    depth: scope.depth,
+                    generation: scope.generation,
+                    captured,
+                    captures: captures + 1,
+                    suppressed,
+                });
+            })
+            .is_ok()
+    });
+    if !stored {
+        STATE.with(|state| {
+            state
+                .failure
+                .set(Some((scope.generation, CaptureError::Reentrant)))
+        });
+        return Err(CaptureError::Reentrant);
+    }
+    Ok(CaptureStatus::Captured)
+}
+
+/// Counts the panic instead of capturing it if the scope has reached `max_captures`.
+fn suppress(scope: &Scope, options: &CaptureOptions) -> bool {
+    let max = match options.max_captures {
+        Some(max) => max,
+        None => return false,
+    };
+    CAPTURED.with(|slots| {
+        let mut slots = match slots.try_borrow_mut() {
+            Ok(slots) => slots,
+            Err(..) => return false,
         };
-        let _guard = Guard(old_ctx);
-        $body
-    }};
+        match slots
+            .iter_mut()
+            .rev()
+            .find(|s| s.generation == scope.generation)
+        {
+            Some(slot) if slot.captures >= max => {
+                slot.suppressed += 1;
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Calls `f` with the information of the panic captured in the innermost
+/// scope that is still unwinding, if any.
+pub(crate) fn with_in_flight<F>(f: F)
+where
+    F: FnOnce(&CapturedInfo),
+{
+    let scope = STATE.with(State::innermost);
+    if scope.depth == 0 || scope.in_flight <= scope.outer_panics {
+        return;
+    }
+    CAPTURED.with(|slots| {
+        if let Ok(slots) = slots.try_borrow() {
+            if let Some(slot) = slots
+                .iter()
+                .rev()
+                .find(|s| s.generation == scope.generation)
+            {
+                f(&slot.captured);
+            }
+        }
+    });
+}
+
+struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        STATE.with(|state| state.capturing.set(false));
+    }
 }
@@ -0,0 +1,173 @@
+use crate::{captured::CapturedInfo, frames, hook::PanicHookInfo, payload, redact};
+use std::{
+    borrow::Cow,
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The default number of the crash reports kept in the directory.
+const DEFAULT_MAX_FILES: usize = 16;
+
+const PREFIX: &str = "crash-";
+const SUFFIX: &str = ".txt";
+
+/// The sequence number distinguishing the reports written at the same time.
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// The options of the crash reports written by the panic hook.
+#[derive(Debug, Clone)]
+pub(crate) struct CrashReportOptions {
+    pub(crate) dir: Option<PathBuf>,
+    pub(crate) captured: bool,
+    pub(crate) max_files: usize,
+}
+
+impl Default for CrashReportOptions {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            captured: false,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+impl CrashReportOptions {
+    /// Write the report of an uncaptured panic, if enabled.
+    pub(crate) fn write_uncaptured(&self, info: &PanicHookInfo<'_>, timestamp: Option<SystemTime>) {
+        if let Some(dir) = &self.dir {
+            let report = Report {
+                message: payload::render(info.payload()),
+                location: info.location().map(ToString::to_string),
+                context_frames: frames::snapshot(),
+                backtrace: capture_backtrace(),
+                captured: false,
+            };
+            // The errors are ignored since this is called inside of the panic hook.
+            let _ = write_file(dir, self.max_files, timestamp, &report.render());
+        }
+    }
+
+    /// Write the report of a captured panic, if enabled.
+    pub(crate) fn write_captured(&self, captured: &CapturedInfo) {
+        match &self.dir {
+            Some(dir) if self.captured => {
+                let report = Report {
+                    message: Cow::Borrowed(captured.message.as_deref().unwrap_or_default()),
+                    location: captured.location.as_ref().map(ToString::to_string),
+                    context_frames: captured.context_frames.clone(),
+                    backtrace: captured.backtrace_text(),
+                    captured: true,
+                };
+                let _ = write_file(dir, self.max_files, captured.timestamp, &report.render());
+            }
+            _ => (),
+        }
+    }
+}
+
+struct Report<'a> {
+    message: Cow<'a, str>,
+    location: Option<String>,
+    context_frames: Vec<String>,
+    backtrace: Option<String>,
+    captured: bool,
+}
+
+impl Report<'_> {
+    /// Render the report in the format of the default panic hook,
+    /// followed by the context frames.
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let thread = thread::current();
+        let _ = write!(out, "thread '{}' ", thread.name().unwrap_or("<unnamed>"));
+        match &self.location {
+            Some(location) => {
+                let _ = write!(out, "panicked at {}:", location);
+            }
+            None => out.push_str("panicked:"),
+        }
+        out.push('\n');
+        out.push_str(&redact::redact(&self.message));
+        out.push('\n');
+
+        if self.captured {
+            out.push_str("note: the panic was captured by a capture scope\n");
+        }
+        if !self.context_frames.is_empty() {
+            out.push_str("context:\n");
+            for frame in &self.context_frames {
+                let _ = writeln!(out, "    {}", redact::redact(frame));
+            }
+        }
+        match &self.backtrace {
+            Some(backtrace) => {
+                out.push_str("stack backtrace:\n");
+                out.push_str(backtrace);
+            }
+            None => out.push_str(
+                "note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n",
+            ),
+        }
+        out
+    }
+}
+
+#[cfg(backtrace)]
+fn capture_backtrace() -> Option<String> {
+    use std::backtrace::{Backtrace, BacktraceStatus};
+
+    let backtrace = Backtrace::capture();
+    match backtrace.status() {
+        BacktraceStatus::Captured => Some(backtrace.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(not(backtrace))]
+fn capture_backtrace() -> Option<String> {
+    None
+}
+
+/// Write the report into a new file named after the timestamp,
+/// and remove the oldest files exceeding `max_files`.
+fn write_file(
+    dir: &Path,
+    max_files: usize,
+    timestamp: Option<SystemTime>,
+    report: &str,
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let since_epoch = timestamp
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    // The names are sorted in the order of the timestamps.
+    let name = format!(
+        "{}{:010}.{:09}-{}-{}{}",
+        PREFIX,
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos(),
+        process::id(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        SUFFIX,
+    );
+    fs::write(dir.join(name), report)?;
+
+    let mut names: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with(PREFIX) && name.ends_with(SUFFIX))
+        .collect();
+    if names.len() > max_files {
+        names.sort();
+        for name in &names[..names.len() - max_files] {
+            let _ = fs::remove_file(dir.join(name));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use crate::{
+    backtrace::BacktraceStatus,
+    location::LocationStyle,
+    snapshot::UnwindSnapshot,
+    unwind::{Location, Unwind},
+};
+use std::fmt;
+
+/// The options for rendering the report of the captured panic.
+///
+/// The report has the same layout as the alternate `Display` output of
+/// [`Unwind`], which uses the default options. The options are applied
+/// to the panic location and the location of the capture scope, while
+/// the backtrace is rendered as is.
+///
+/// [`Unwind`]: ./struct.Unwind.html
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DisplayOptions {
+    location_style: LocationStyle,
+    normalize_separators: bool,
+    std_style: bool,
+}
+
+impl DisplayOptions {
+    /// Create a new `DisplayOptions` with the default values.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the style of rendering the locations.
+    ///
+    /// The default value is `LocationStyle::Colon`.
+    #[inline]
+    pub fn location_style(mut self, style: LocationStyle) -> Self {
+        self.location_style = style;
+        self
+    }
+
+    /// Specify whether to replace the Windows path separators (`\`) in the
+    /// locations with `/`.
+    ///
+    /// The default value is `false`.
+    #[inline]
+    pub fn normalize_separators(mut self, enabled: bool) -> Self {
+        self.normalize_separators = enabled;
+        self
+    }
+
+    /// Specify whether to render the report in the same format as the
+    /// default panic hook of the standard library, i.e.
+    /// `thread 'main' panicked at src/main.rs:2:5:\noops` followed by the
+    /// backtrace or the hint about `RUST_BACKTRACE`.
+    ///
+    /// The text rendered by the panic hook is used as it is if it was captured
+    /// with [`CaptureOptions::hook_message`]. Otherwise, the report is built
+    /// from the location and the message. The ID of the thread printed by the
+    /// recent toolchains is not reproduced. The hint is added to every report
+    /// whose backtrace was disabled, while the default hook prints it only
+    /// for the first panic.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`CaptureOptions::hook_message`]: ./struct.CaptureOptions.html#method.hook_message
+    #[inline]
+    pub fn std_style(mut self, enabled: bool) -> Self {
+        self.std_style = enabled;
+        self
+    }
+
+    pub(crate) fn location(&self, location: &Location) -> String {
+        location
+            .display_style(self.location_style)
+            .normalize_separators(self.normalize_separators)
+            .to_string()
+    }
+}
+
+impl Unwind {
+    /// Return a helper for rendering the report of this panic with the specified options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, DisplayOptions, LocationStyle};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// let options = DisplayOptions::new().location_style(LocationStyle::Msvc);
+    /// let report = unwind.display_with(options).to_string();
+    /// assert!(report.starts_with("panicked at src"));
+    /// assert!(report.contains("): oops\n"));
+    /// ```
+    pub fn display_with(&self, options: DisplayOptions) -> impl fmt::Display + '_ {
+        DisplayUnwind {
+            unwind: self,
+            options,
+        }
+    }
+}
+
+impl UnwindSnapshot {
+    /// Return a helper for rendering the report of this snapshot with the specified options.
+    ///
+    /// See [`Unwind::display_with`] for details.
+    ///
+    /// [`Unwind::display_with`]: ./struct.Unwind.html#method.display_with
+    pub fn display_with(&self, options: DisplayOptions) -> impl fmt::Display + '_ {
+        DisplaySnapshot {
+            snapshot: self,
+            options,
+        }
+    }
+}
+
+struct DisplayUnwind<'a> {
+    unwind: &'a Unwind,
+    options: DisplayOptions,
+}
+
+impl fmt::Display for DisplayUnwind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.options.std_style {
+            let backtrace = self.unwind.backtrace_text();
+            return StdStyle {
+                thread_name: self.unwind.thread_name(),
+                hook_message: self.unwind.hook_message(),
+                message: &self.unwind.message(),
+                location: self.unwind.location(),
+                backtrace: backtrace.as_deref(),
+                backtrace_status: self.unwind.backtrace_status(),
+            }
+            .write(f, &self.options);
+        }
+        self.unwind.write_report(f, None, &self.options)
+    }
+}
+
+struct DisplaySnapshot<'a> {
+    snapshot: &'a UnwindSnapshot,
+    options: DisplayOptions,
+}
+
+impl fmt::Display for DisplaySnapshot<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.options.std_style {
+            return StdStyle {
+                thread_name: self.snapshot.thread_name(),
+                hook_message: None,
+                message: self.snapshot.message(),
+                location: self.snapshot.location(),
+                backtrace: self.snapshot.backtrace.as_deref(),
+                backtrace_status: self.snapshot.backtrace_status,
+            }
+            .write(f, &self.options);
+        }
+        self.snapshot.report().write(f, None, &self.options)
+    }
+}
+
+/// The report in the format of the default panic hook.
+struct StdStyle<'a> {
+    thread_name: Option<&'a str>,
+    hook_message: Option<&'a str>,
+    message: &'a str,
+    location: Option<&'a Location>,
+    backtrace: Option<&'a str>,
+    backtrace_status: BacktraceStatus,
+}
+
+impl StdStyle<'_> {
+    fn write(&self, f: &mut fmt::Formatter<'_>, options: &DisplayOptions) -> fmt::Result {
+        write!(f, "thread '{}' ", self.thread_name.unwrap_or("<unnamed>"))?;
+        match (self.hook_message, self.location) {
+            (Some(hook_message), _) => f.write_str(hook_message)?,
+            (None, Some(location)) => write!(
+                f,
+                "panicked at {}:\n{}",
+                options.location(location),
+                self.message
+            )?,
+            (None, None) => write!(f, "panicked:\n{}", self.message)?,
+        }
+        f.write_str("\n")?;
+
+        match (self.backtrace, self.backtrace_status) {
+            (Some(backtrace), _) => write!(f, "stack backtrace:\n{}", backtrace)?,
+            (None, BacktraceStatus::Disabled) => f.write_str(
+                "note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n",
+            )?,
+            _ => (),
+        }
+        Ok(())
+    }
+}
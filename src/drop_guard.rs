@@ -0,0 +1,133 @@
+use crate::{
+    context,
+    options::CaptureOptions,
+    orphan,
+    unwind::{self, Unwind},
+};
+use std::{
+    borrow::Cow,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    process, thread,
+};
+
+type Teardown = Box<dyn FnOnce() + 'static>;
+
+/// A guard that runs the teardown closures on drop, capturing their panics.
+///
+/// The panics in destructors, e.g. in the teardown of the fixtures, occur
+/// after the test body has returned and are not caught by the capture scope
+/// around it. `DropGuard` runs each teardown closure in its own capture
+/// scope labeled with the label of the guard, and routes the panic to the
+/// process-global list retrieved by [`take_orphaned_unwinds`] instead of
+/// unwinding through the function that is returning. The observers and the
+/// history are notified as usual.
+///
+/// The teardown closures are run in the reverse order of registration, like
+/// the destructors of local variables. If a teardown closure panics while the
+/// thread is already unwinding, the report is printed to stderr and the
+/// process is aborted, as the panic would otherwise be lost.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{take_orphaned_unwinds, DropGuard};
+///
+/// maybe_unwind::set_hook();
+///
+/// {
+///     let _guard = DropGuard::with_teardown("fixture::database", || {
+///         panic!("failed to remove the temporary database");
+///     });
+///     // ... the test body ...
+/// }
+///
+/// let unwinds = take_orphaned_unwinds();
+/// let unwind = unwinds
+///     .iter()
+///     .find(|unwind| unwind.scope_label() == Some("fixture::database"))
+///     .unwrap();
+/// assert_eq!(unwind.message(), "failed to remove the temporary database");
+/// ```
+///
+/// [`take_orphaned_unwinds`]: ./fn.take_orphaned_unwinds.html
+pub struct DropGuard {
+    label: Cow<'static, str>,
+    caller: &'static panic::Location<'static>,
+    teardowns: Vec<Teardown>,
+}
+
+impl fmt::Debug for DropGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropGuard")
+            .field("label", &self.label)
+            .field("teardowns", &self.teardowns.len())
+            .finish()
+    }
+}
+
+impl DropGuard {
+    /// Create a new `DropGuard` without any teardown closure.
+    #[inline]
+    #[track_caller]
+    pub fn new(label: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            label: label.into(),
+            caller: panic::Location::caller(),
+            teardowns: Vec::new(),
+        }
+    }
+
+    /// Create a new `DropGuard` with a teardown closure.
+    #[inline]
+    #[track_caller]
+    pub fn with_teardown<F>(label: impl Into<Cow<'static, str>>, f: F) -> Self
+    where
+        F: FnOnce() + 'static,
+    {
+        let mut guard = Self::new(label);
+        guard.add_teardown(f);
+        guard
+    }
+
+    /// Register a teardown closure, which is run before the ones registered so far.
+    pub fn add_teardown<F>(&mut self, f: F)
+    where
+        F: FnOnce() + 'static,
+    {
+        self.teardowns.push(Box::new(f));
+    }
+
+    /// Return the label of this guard.
+    #[inline]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn run(&self, teardown: Teardown) -> Result<(), Unwind> {
+        let label = self.label.clone();
+        unwind::maybe_unwind_at(
+            self.caller,
+            &CaptureOptions::default(),
+            AssertUnwindSafe(move || {
+                context::set_scope_label(label);
+                teardown()
+            }),
+        )
+    }
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        while let Some(teardown) = self.teardowns.pop() {
+            if let Err(unwind) = self.run(teardown) {
+                if thread::panicking() {
+                    eprintln!("{:#}", unwind);
+                    eprintln!("a teardown of `{}` panicked while unwinding", self.label);
+                    process::abort();
+                }
+                orphan::push(unwind);
+            }
+        }
+    }
+}
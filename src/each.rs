@@ -0,0 +1,325 @@
+use crate::{
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use std::{
+    error,
+    fmt::{self, Debug},
+    iter::FusedIterator,
+    panic::{AssertUnwindSafe, Location},
+};
+
+/// Invokes a closure for each item, collecting the results without stopping at panics.
+///
+/// The closure is invoked with a reference to each item, and the item is
+/// returned together with the result of the invocation. A panic in one item
+/// does not prevent the remaining items from being processed.
+///
+/// As with [`maybe_unwind_retry`], the closure is not required to be
+/// `UnwindSafe` and it must tolerate the state left by a panicked invocation.
+///
+/// The failed items can be summarized using [`FailureSummary`].
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind_each, FailureSummary};
+///
+/// maybe_unwind::set_hook();
+///
+/// let results = maybe_unwind_each(vec![1, 2, 3], |&n| {
+///     assert!(n != 2, "two is not allowed");
+///     n * 10
+/// });
+/// assert!(results[1].1.is_err());
+///
+/// let summary = FailureSummary::new(&results);
+/// assert_eq!(summary.len(), 1);
+/// ```
+///
+/// [`maybe_unwind_retry`]: ./fn.maybe_unwind_retry.html
+/// [`FailureSummary`]: ./struct.FailureSummary.html
+#[track_caller]
+pub fn maybe_unwind_each<I, F, R>(items: I, mut f: F) -> Vec<(I::Item, Result<R, Unwind>)>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item) -> R,
+{
+    let caller = Location::caller();
+    let options = CaptureOptions::default();
+    items
+        .into_iter()
+        .map(|item| {
+            let res = maybe_unwind_at(caller, &options, AssertUnwindSafe(|| f(&item)));
+            (item, res)
+        })
+        .collect()
+}
+
+/// A summary of the failed items returned from [`maybe_unwind_each`].
+///
+/// [`maybe_unwind_each`]: ./fn.maybe_unwind_each.html
+#[derive(Debug)]
+pub struct FailureSummary<'a, T> {
+    failures: Vec<(usize, &'a T, &'a Unwind)>,
+    total: usize,
+}
+
+impl<'a, T> FailureSummary<'a, T> {
+    /// Create a summary from the results of `maybe_unwind_each`.
+    pub fn new<R>(results: &'a [(T, Result<R, Unwind>)]) -> Self {
+        Self {
+            failures: results
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (item, res))| res.as_ref().err().map(|unwind| (i, item, unwind)))
+                .collect(),
+            total: results.len(),
+        }
+    }
+
+    /// Return whether all items completed without panicking.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Return the number of the failed items.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.failures.len()
+    }
+
+    /// Return an iterator over the index, the item and the captured panic of the failed items.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &'a T, &'a Unwind)> + '_ {
+        self.failures.iter().copied()
+    }
+}
+
+impl<T: Debug> fmt::Display for FailureSummary<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} items panicked",
+            self.failures.len(),
+            self.total
+        )?;
+        for (i, item, unwind) in &self.failures {
+            write!(f, "\n  item {} ({:?}): panicked", i, item)?;
+            if let Some(location) = unwind.location() {
+                write!(f, " at {}", location)?;
+            }
+            write!(f, ": {}", unwind.message())?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Debug> error::Error for FailureSummary<'_, T> {}
+
+/// An extension trait for `Iterator`s that provides adaptors for invoking
+/// a closure on each item without stopping at panics.
+pub trait IterMaybeUnwindExt: Iterator + Sized {
+    /// Invokes a closure on each item, capturing the cause of an unwinding
+    /// panic if one occurs.
+    ///
+    /// This is a lazy variant of [`maybe_unwind_each`] for iterator pipelines.
+    /// The returned iterator yields `Result<R, Unwind>` for each item.
+    /// As with `maybe_unwind_each`, the closure is not required to be
+    /// `UnwindSafe` and it must tolerate the state left by a panicked
+    /// invocation.
+    ///
+    /// The location where this method is called is recorded as
+    /// [`Unwind::caller`] of the captured panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::IterMaybeUnwindExt as _;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let (ok, failed) = (1..=4)
+    ///     .map_maybe_unwind(|n| {
+    ///         assert!(n % 2 == 1, "{} is even", n);
+    ///         n * 10
+    ///     })
+    ///     .partition_unwinds();
+    /// assert_eq!(ok, [10, 30]);
+    /// assert_eq!(failed[0].0, 1);
+    /// assert_eq!(failed[1].1.message(), "4 is even");
+    /// ```
+    ///
+    /// [`maybe_unwind_each`]: ./fn.maybe_unwind_each.html
+    /// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+    #[track_caller]
+    fn map_maybe_unwind<F, R>(self, f: F) -> MapMaybeUnwind<Self, F>
+    where
+        F: FnMut(Self::Item) -> R,
+    {
+        MapMaybeUnwind {
+            iter: self,
+            f,
+            caller: Location::caller(),
+        }
+    }
+
+    /// Advances this iterator inside of a capture scope, capturing the cause
+    /// of an unwinding panic raised by `next()` itself.
+    ///
+    /// This is useful for the iterators whose `next()` runs the user code, such
+    /// as the discovery of the test cases, so that a panic does not kill the
+    /// enumeration of the items yielded before. The returned iterator yields
+    /// `Ok` for each item, and an `Err` for the first panic, after which it
+    /// returns `None` since this iterator may be left broken by the panic.
+    ///
+    /// The location where this method is called is recorded as
+    /// [`Unwind::caller`] of the captured panic.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::IterMaybeUnwindExt as _;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let cases = ["a", "b", "bad", "c"].iter().map(|&name| {
+    ///     assert!(name != "bad", "invalid case");
+    ///     name
+    /// });
+    /// let results: Vec<_> = cases.maybe_unwind_iter().collect();
+    /// assert_eq!(results.len(), 3);
+    /// assert_eq!(*results[1].as_ref().unwrap(), "b");
+    /// assert_eq!(results[2].as_ref().unwrap_err().message(), "invalid case");
+    /// ```
+    ///
+    /// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+    #[track_caller]
+    fn maybe_unwind_iter(self) -> MaybeUnwindIter<Self> {
+        MaybeUnwindIter {
+            iter: self,
+            panicked: false,
+            caller: Location::caller(),
+        }
+    }
+
+    /// Collects the results into the successful values and the captured panics.
+    ///
+    /// Each captured panic is paired with the index of the result in this
+    /// iterator, which is the index of the original item when used after
+    /// [`map_maybe_unwind`].
+    ///
+    /// [`map_maybe_unwind`]: #method.map_maybe_unwind
+    fn partition_unwinds<R>(self) -> (Vec<R>, Vec<(usize, Unwind)>)
+    where
+        Self: Iterator<Item = Result<R, Unwind>>,
+    {
+        let mut values = vec![];
+        let mut unwinds = vec![];
+        for (i, res) in self.enumerate() {
+            match res {
+                Ok(value) => values.push(value),
+                Err(unwind) => unwinds.push((i, unwind)),
+            }
+        }
+        (values, unwinds)
+    }
+}
+
+impl<I: Iterator> IterMaybeUnwindExt for I {}
+
+/// An iterator for the [`map_maybe_unwind`] method.
+///
+/// [`map_maybe_unwind`]: ./trait.IterMaybeUnwindExt.html#method.map_maybe_unwind
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct MapMaybeUnwind<I, F> {
+    iter: I,
+    f: F,
+    caller: &'static Location<'static>,
+}
+
+impl<I: Debug, F> Debug for MapMaybeUnwind<I, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapMaybeUnwind")
+            .field("iter", &self.iter)
+            .field("caller", &self.caller)
+            .finish()
+    }
+}
+
+impl<I, F, R> Iterator for MapMaybeUnwind<I, F>
+where
+    I: Iterator,
+    F: FnMut(I::Item) -> R,
+{
+    type Item = Result<R, Unwind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        let f = &mut self.f;
+        Some(maybe_unwind_at(
+            self.caller,
+            &CaptureOptions::default(),
+            AssertUnwindSafe(|| f(item)),
+        ))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// An iterator for the [`maybe_unwind_iter`] method.
+///
+/// [`maybe_unwind_iter`]: ./trait.IterMaybeUnwindExt.html#method.maybe_unwind_iter
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct MaybeUnwindIter<I> {
+    iter: I,
+    panicked: bool,
+    caller: &'static Location<'static>,
+}
+
+impl<I: Debug> Debug for MaybeUnwindIter<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MaybeUnwindIter")
+            .field("iter", &self.iter)
+            .field("panicked", &self.panicked)
+            .field("caller", &self.caller)
+            .finish()
+    }
+}
+
+impl<I: Iterator> Iterator for MaybeUnwindIter<I> {
+    type Item = Result<I::Item, Unwind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.panicked {
+            return None;
+        }
+        let iter = &mut self.iter;
+        match maybe_unwind_at(
+            self.caller,
+            &CaptureOptions::default(),
+            AssertUnwindSafe(|| iter.next()),
+        ) {
+            Ok(item) => item.map(Ok),
+            Err(unwind) => {
+                self.panicked = true;
+                Some(Err(unwind))
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.panicked {
+            (0, Some(0))
+        } else {
+            // Any of the items may be replaced by a panic, which ends the iteration.
+            (0, self.iter.size_hint().1)
+        }
+    }
+}
+
+impl<I: FusedIterator> FusedIterator for MaybeUnwindIter<I> {}
@@ -0,0 +1,57 @@
+use crate::unwind::Unwind;
+use std::{error, fmt};
+
+/// The error type of the adaptors whose inner values fail with their own errors.
+///
+/// This is returned from the future of [`Future01MaybeUnwindExt::maybe_unwind`]
+/// and from the methods of [`MaybeUnwindSink`].
+///
+/// [`Future01MaybeUnwindExt::maybe_unwind`]: ./trait.Future01MaybeUnwindExt.html#method.maybe_unwind
+/// [`MaybeUnwindSink`]: ./struct.MaybeUnwindSink.html
+#[derive(Debug)]
+#[cfg_attr(docs, doc(cfg(any(feature = "futures01", feature = "sink"))))]
+pub enum MaybeUnwindError<E> {
+    /// The inner value failed with an error.
+    Inner(E),
+
+    /// The inner value panicked.
+    Unwind(Unwind),
+}
+
+impl<E> MaybeUnwindError<E> {
+    /// Return the error of the inner value, if it failed.
+    #[inline]
+    pub fn inner(&self) -> Option<&E> {
+        match self {
+            MaybeUnwindError::Inner(err) => Some(err),
+            MaybeUnwindError::Unwind(..) => None,
+        }
+    }
+
+    /// Return the captured panic, if the inner value panicked.
+    #[inline]
+    pub fn unwind(&self) -> Option<&Unwind> {
+        match self {
+            MaybeUnwindError::Inner(..) => None,
+            MaybeUnwindError::Unwind(unwind) => Some(unwind),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for MaybeUnwindError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaybeUnwindError::Inner(err) => err.fmt(f),
+            MaybeUnwindError::Unwind(unwind) => unwind.fmt(f),
+        }
+    }
+}
+
+impl<E: error::Error + 'static> error::Error for MaybeUnwindError<E> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            MaybeUnwindError::Inner(err) => Some(err),
+            MaybeUnwindError::Unwind(..) => None,
+        }
+    }
+}
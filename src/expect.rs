@@ -0,0 +1,174 @@
+use crate::options::CaptureOptions;
+use crate::unwind::{maybe_unwind_at, Unwind};
+use std::{error, fmt, panic::UnwindSafe};
+
+/// The pattern that the panic payload is expected to match.
+///
+/// A pattern can be constructed from a string slice or a `String`,
+/// which matches the payloads containing the string.
+#[derive(Debug, Clone)]
+pub struct PayloadPattern {
+    kind: PatternKind,
+}
+
+#[derive(Debug, Clone)]
+enum PatternKind {
+    Contains(String),
+    Exact(String),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl PayloadPattern {
+    /// Create a pattern that matches the payloads containing the specified string.
+    #[inline]
+    pub fn contains(s: impl Into<String>) -> Self {
+        Self {
+            kind: PatternKind::Contains(s.into()),
+        }
+    }
+
+    /// Create a pattern that matches the payloads equal to the specified string.
+    #[inline]
+    pub fn exact(s: impl Into<String>) -> Self {
+        Self {
+            kind: PatternKind::Exact(s.into()),
+        }
+    }
+
+    /// Create a pattern that matches the payloads matching the specified regular expression.
+    #[cfg(feature = "regex")]
+    #[cfg_attr(docs, doc(cfg(feature = "regex")))]
+    #[inline]
+    pub fn regex(re: regex::Regex) -> Self {
+        Self {
+            kind: PatternKind::Regex(re),
+        }
+    }
+
+    /// Return whether the specified payload string matches this pattern.
+    pub fn matches(&self, payload: &str) -> bool {
+        match &self.kind {
+            PatternKind::Contains(s) => payload.contains(s.as_str()),
+            PatternKind::Exact(s) => payload == s,
+            #[cfg(feature = "regex")]
+            PatternKind::Regex(re) => re.is_match(payload),
+        }
+    }
+}
+
+impl From<&str> for PayloadPattern {
+    #[inline]
+    fn from(s: &str) -> Self {
+        Self::contains(s)
+    }
+}
+
+impl From<String> for PayloadPattern {
+    #[inline]
+    fn from(s: String) -> Self {
+        Self::contains(s)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl From<regex::Regex> for PayloadPattern {
+    #[inline]
+    fn from(re: regex::Regex) -> Self {
+        Self::regex(re)
+    }
+}
+
+impl fmt::Display for PayloadPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PatternKind::Contains(s) => write!(f, "containing {:?}", s),
+            PatternKind::Exact(s) => write!(f, "equal to {:?}", s),
+            #[cfg(feature = "regex")]
+            PatternKind::Regex(re) => write!(f, "matching /{}/", re),
+        }
+    }
+}
+
+/// The error type returned from [`expect_unwind`].
+///
+/// [`expect_unwind`]: ./fn.expect_unwind.html
+#[derive(Debug)]
+pub enum ExpectError {
+    /// The closure returned without panicking.
+    DidNotPanic,
+
+    /// The closure panicked, but the payload did not match the pattern.
+    WrongPayload {
+        /// The captured panic.
+        unwind: Box<Unwind>,
+        /// The expected pattern.
+        pattern: PayloadPattern,
+    },
+}
+
+impl fmt::Display for ExpectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpectError::DidNotPanic => f.write_str("the closure did not panic"),
+            ExpectError::WrongPayload { unwind, pattern } => {
+                write!(
+                    f,
+                    "panic payload did not match the expected pattern\n      panic message: {:?}\n expected pattern: {}",
+                    unwind.message(),
+                    pattern,
+                )?;
+                if let Some(location) = unwind.location() {
+                    write!(f, "\n       panicked at: {}", location)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for ExpectError {}
+
+/// Invokes a closure that is expected to panic with the payload matching the pattern.
+///
+/// This is a replacement of `#[should_panic(expected = "...")]` for custom test libraries.
+/// If the closure panics and the payload matches the pattern, the captured panic
+/// is returned. Otherwise, an `ExpectError` describing the mismatch is returned,
+/// whose `Display` output can be used directly as the message of the test failure.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{expect_unwind, ExpectError, PayloadPattern};
+///
+/// std::panic::set_hook(Box::new(|info| {
+///     maybe_unwind::capture_panic_info(info);
+/// }));
+///
+/// let unwind = expect_unwind("out of bounds", || {
+///     let v: Vec<i32> = vec![];
+///     v[0]
+/// })
+/// .unwrap();
+/// assert!(unwind.location().is_some());
+///
+/// let err = expect_unwind(PayloadPattern::exact("oops"), || ()).unwrap_err();
+/// assert!(matches!(err, ExpectError::DidNotPanic));
+/// ```
+#[track_caller]
+pub fn expect_unwind<P, F, R>(pattern: P, f: F) -> Result<Unwind, ExpectError>
+where
+    P: Into<PayloadPattern>,
+    F: FnOnce() -> R + UnwindSafe,
+{
+    let caller = std::panic::Location::caller();
+    let pattern = pattern.into();
+    match maybe_unwind_at(caller, &CaptureOptions::default(), f) {
+        Ok(..) => Err(ExpectError::DidNotPanic),
+        Err(unwind) if pattern.matches(unwind.payload_str()) => Ok(unwind),
+        Err(unwind) => Err(ExpectError::WrongPayload {
+            unwind: Box::new(unwind),
+            pattern,
+        }),
+    }
+}
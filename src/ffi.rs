@@ -0,0 +1,88 @@
+//! Utilities for guarding FFI boundaries against unwinding panics.
+//!
+//! Unwinding across an `extern "C"` boundary aborts the process, so the
+//! callbacks exposed to foreign code must catch all panics and report
+//! them by other means, typically as an error code. The functions in
+//! this module help with the common pattern where the foreign side
+//! receives an error code and then calls back into Rust to fetch the
+//! details of the panic.
+//!
+//! # Example
+//!
+//! ```
+//! use maybe_unwind::ffi;
+//!
+//! extern "C" fn callback(value: i32) -> i32 {
+//!     match ffi::guard(|| {
+//!         assert!(value >= 0, "negative value");
+//!         value * 2
+//!     }) {
+//!         Ok(value) => value,
+//!         Err(..) => -1,
+//!     }
+//! }
+//!
+//! assert_eq!(callback(-1), -1);
+//! let last = ffi::last_ffi_unwind().unwrap();
+//! assert_eq!(last.message(), "negative value");
+//! ```
+
+use crate::{
+    options::CaptureOptions,
+    snapshot::UnwindSnapshot,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use std::{
+    cell::RefCell,
+    panic::{Location, UnwindSafe},
+};
+
+thread_local! {
+    static LAST_UNWIND: RefCell<Option<UnwindSnapshot>> = const { RefCell::new(None) };
+}
+
+/// Invokes a closure at the FFI boundary, capturing the cause of an
+/// unwinding panic if one occurs.
+///
+/// In addition to returning the captured panic, the snapshot of it is stored
+/// in the thread-local storage so that it can be retrieved later by
+/// [`last_ffi_unwind`]. Only the last panic is kept for each thread.
+///
+/// This function never unwinds as long as the destructor of the panic
+/// payload does not panic, so it is safe to call it inside `extern "C"`
+/// functions.
+///
+/// [`last_ffi_unwind`]: ./fn.last_ffi_unwind.html
+#[track_caller]
+pub fn guard<F, R>(f: F) -> Result<R, Unwind>
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    let res = maybe_unwind_at(Location::caller(), &CaptureOptions::default(), f);
+    if let Err(ref unwind) = res {
+        let snapshot = unwind.to_snapshot();
+        let _ = LAST_UNWIND.try_with(|last| {
+            last.replace(Some(snapshot));
+        });
+    }
+    res
+}
+
+/// Return the snapshot of the last panic captured by [`guard`] on the current thread.
+///
+/// [`guard`]: ./fn.guard.html
+pub fn last_ffi_unwind() -> Option<UnwindSnapshot> {
+    LAST_UNWIND
+        .try_with(|last| last.borrow().clone())
+        .ok()
+        .flatten()
+}
+
+/// Clear the last panic captured by [`guard`] on the current thread.
+///
+/// [`guard`]: ./fn.guard.html
+pub fn clear_last_ffi_unwind() {
+    let _ = LAST_UNWIND.try_with(|last| {
+        last.replace(None);
+    });
+}
@@ -0,0 +1,83 @@
+use std::cell::RefCell;
+
+thread_local! {
+    static FRAMES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(crate) struct FrameGuard(());
+
+impl FrameGuard {
+    pub(crate) fn push(frame: String) -> Self {
+        if cfg!(feature = "capture") {
+            FRAMES.with(|frames| frames.borrow_mut().push(frame));
+        }
+        FrameGuard(())
+    }
+}
+
+impl Drop for FrameGuard {
+    fn drop(&mut self) {
+        if !cfg!(feature = "capture") {
+            return;
+        }
+        let _ = FRAMES.try_with(|frames| {
+            frames.borrow_mut().pop();
+        });
+    }
+}
+
+pub(crate) fn snapshot() -> Vec<String> {
+    FRAMES
+        .try_with(|frames| {
+            frames
+                .try_borrow()
+                .map(|frames| frames.clone())
+                .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
+
+/// Invokes a closure with an annotation describing what it is doing.
+///
+/// The annotations are stacked on the current thread while the closure
+/// is running, and the panic hook records a snapshot of the stack into
+/// the captured information. The recorded annotations can be retrieved
+/// by [`Unwind::context_frames`], in order from the outermost one.
+///
+/// The annotation is removed when the closure returns, regardless of
+/// whether it returns normally or by unwinding.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, with_context};
+///
+/// std::panic::set_hook(Box::new(|info| {
+///     maybe_unwind::capture_panic_info(info);
+/// }));
+///
+/// let unwind = maybe_unwind(|| {
+///     with_context("while seeding the database", || {
+///         with_context("while running migration 3", || panic!("oops"));
+///     });
+/// })
+/// .unwrap_err();
+///
+/// assert_eq!(
+///     unwind.context_frames(),
+///     ["while seeding the database", "while running migration 3"],
+/// );
+/// ```
+///
+/// [`Unwind::context_frames`]: ./struct.Unwind.html#method.context_frames
+#[inline]
+pub fn with_context<F, R>(frame: impl Into<String>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    if !cfg!(feature = "capture") {
+        return f();
+    }
+    let _guard = FrameGuard::push(frame.into());
+    f()
+}
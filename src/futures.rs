@@ -1,21 +1,183 @@
-use crate::unwind::{maybe_unwind, Unwind};
+//! The adaptors of the asynchronous computations.
+//!
+//! The items in this module are also re-exported from the crate root.
+
+use crate::{
+    context::set_scope_label,
+    frames::FrameGuard,
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, maybe_unwind_timed_at, Unwind},
+};
 use futures_core::{
-    future::Future,
+    future::{FusedFuture, Future},
     task::{self, Poll},
 };
 use std::{
-    panic::{AssertUnwindSafe, UnwindSafe},
+    borrow::Cow,
+    fmt,
+    mem::ManuallyDrop,
+    panic::{AssertUnwindSafe, Location, UnwindSafe},
     pin::Pin,
+    ptr,
+    time::{Duration, Instant},
 };
 
+#[cfg(feature = "stream")]
+#[cfg_attr(docs, doc(cfg(feature = "stream")))]
+pub use crate::stream::{MaybeUnwindStream, StreamMaybeUnwindExt};
+
+#[cfg(feature = "sink")]
+#[cfg_attr(docs, doc(cfg(feature = "sink")))]
+pub use crate::sink_adaptor::{MaybeUnwindSink, SinkMaybeUnwindExt};
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docs, doc(cfg(feature = "tokio")))]
+pub use crate::tokio::{spawn_blocking_maybe_unwind, spawn_maybe_unwind};
+
+#[cfg(feature = "futures-executor")]
+#[cfg_attr(docs, doc(cfg(feature = "futures-executor")))]
+pub use crate::local_pool::{spawn_local_maybe_unwind, LocalPoolExt};
+
+#[cfg(feature = "futures01")]
+#[cfg_attr(docs, doc(cfg(feature = "futures01")))]
+pub use crate::futures01::{Future01MaybeUnwindExt, MaybeUnwind01};
+
+#[cfg(any(feature = "futures01", feature = "sink"))]
+#[cfg_attr(docs, doc(cfg(any(feature = "futures01", feature = "sink"))))]
+pub use crate::error::MaybeUnwindError;
+
 /// A future for the [`maybe_unwind`] method.
 ///
+/// The inner future is dropped inside a capture scope as well.
+/// If its destructor panics, the panic does not unwind through the
+/// executor and the captured information is stored so that it can be
+/// retrieved by [`take_orphaned_unwinds`].
+///
+/// Once the inner future has completed or panicked, the adaptor is
+/// terminated and must not be polled again. Polling a terminated
+/// adaptor panics with the message `MaybeUnwind polled after completion`
+/// rather than touching the inner future, which may be left in a broken
+/// state by the panic.
+///
 /// [`maybe_unwind`]: ./trait.FutureMaybeUnwindExt.html#method.maybe_unwind
+/// [`take_orphaned_unwinds`]: ./fn.take_orphaned_unwinds.html
 #[derive(Debug)]
 #[cfg_attr(docs, doc(cfg(feature = "futures")))]
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct MaybeUnwind<F> {
-    inner: F,
+    inner: ManuallyDrop<F>,
+    caller: &'static Location<'static>,
+    elapsed: Option<Duration>,
+    name: Option<Cow<'static, str>>,
+    terminated: bool,
+}
+
+impl<F> MaybeUnwind<F>
+where
+    F: Future + UnwindSafe,
+{
+    /// Create an adaptor that catches unwinding panics while polling `inner`.
+    ///
+    /// This is the same as [`FutureMaybeUnwindExt::maybe_unwind`], and is
+    /// useful for constructing the adaptor inside other combinators.
+    /// The location where this function is called is recorded as
+    /// [`Unwind::caller`] of the captured panics.
+    ///
+    /// [`FutureMaybeUnwindExt::maybe_unwind`]: ./trait.FutureMaybeUnwindExt.html#method.maybe_unwind
+    /// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+    #[track_caller]
+    #[inline]
+    pub fn new(inner: F) -> Self {
+        Self::new_at(inner, Location::caller())
+    }
+}
+
+impl<F> MaybeUnwind<F> {
+    pub(crate) fn new_at(inner: F, caller: &'static Location<'static>) -> Self {
+        Self {
+            inner: ManuallyDrop::new(inner),
+            caller,
+            elapsed: None,
+            name: None,
+            terminated: false,
+        }
+    }
+
+    /// Acquire a reference to the inner future.
+    #[inline]
+    pub fn get_ref(&self) -> &F {
+        &self.inner
+    }
+
+    /// Acquire a mutable reference to the inner future.
+    ///
+    /// After a panic was captured, the inner future may be left in a broken
+    /// state, and it is up to the caller to decide whether to touch it again.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut F {
+        &mut self.inner
+    }
+
+    /// Acquire a pinned mutable reference to the inner future.
+    ///
+    /// The same caveat as [`get_mut`] applies to the future after a panic.
+    ///
+    /// [`get_mut`]: #method.get_mut
+    #[inline]
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut F> {
+        // Safety: the inner future is structurally pinned. It is never moved
+        // out of a pinned adaptor, and is dropped in place by `Drop`.
+        unsafe { self.map_unchecked_mut(|me| &mut *me.inner) }
+    }
+
+    /// Consume this adaptor, returning the inner future.
+    ///
+    /// The inner future is returned as it is, even if it has completed or
+    /// panicked. In particular, a future that panicked while being polled
+    /// may be left in a broken state, and polling it again may cause
+    /// another panic or an unexpected result.
+    #[inline]
+    pub fn into_inner(self) -> F {
+        let mut me = ManuallyDrop::new(self);
+        // Safety: the inner future is moved out exactly once, and the
+        // destructor of the adaptor is not run.
+        unsafe {
+            ptr::drop_in_place(&mut me.name);
+            ManuallyDrop::take(&mut me.inner)
+        }
+    }
+
+    /// Return the total time spent in polling the inner future so far.
+    ///
+    /// The time while the future was pending is not included.
+    /// This value is available only if the adaptor was created by
+    /// [`maybe_unwind_timed`] and the platform has a monotonic clock.
+    ///
+    /// [`maybe_unwind_timed`]: ./trait.FutureMaybeUnwindExt.html#method.maybe_unwind_timed
+    #[inline]
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+}
+
+impl<F> Drop for MaybeUnwind<F> {
+    fn drop(&mut self) {
+        let inner = &mut self.inner;
+        let name = &self.name;
+        // Safety: the inner future is dropped in place exactly once and
+        // is never accessed afterwards.
+        let res = maybe_unwind_at(
+            self.caller,
+            &CaptureOptions::default(),
+            AssertUnwindSafe(|| {
+                set_name(name);
+                unsafe { ManuallyDrop::drop(inner) }
+            }),
+        );
+        if let Err(unwind) = res {
+            crate::orphan::push(unwind);
+        }
+    }
 }
 
 impl<F> Future for MaybeUnwind<F>
@@ -25,8 +187,125 @@ where
     type Output = Result<F::Output, Unwind>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        let inner = unsafe { self.map_unchecked_mut(|me| &mut me.inner) };
-        maybe_unwind(AssertUnwindSafe(|| inner.poll(cx)))?.map(Ok)
+        let me = unsafe { self.get_unchecked_mut() };
+        if me.terminated {
+            panic!("MaybeUnwind polled after completion");
+        }
+
+        let inner = unsafe { Pin::new_unchecked(&mut *me.inner) };
+        let name = &me.name;
+        let poll = |cx: &mut task::Context<'_>| {
+            set_name(name);
+            inner.poll(cx)
+        };
+        let res = match me.elapsed {
+            Some(total) => {
+                let (elapsed, res) = poll_maybe_unwind_timed_at(me.caller, cx, poll);
+                me.elapsed = elapsed.map(|elapsed| total + elapsed);
+                futures_core::ready!(res).map_err(|mut unwind| {
+                    unwind.set_elapsed(me.elapsed);
+                    unwind
+                })
+            }
+            None => futures_core::ready!(poll_maybe_unwind_at(me.caller, cx, poll)),
+        };
+        me.terminated = true;
+        Poll::Ready(res)
+    }
+}
+
+fn set_name(name: &Option<Cow<'static, str>>) {
+    if let Some(name) = name {
+        set_scope_label(name.clone());
+    }
+}
+
+impl<F> FusedFuture for MaybeUnwind<F>
+where
+    F: Future + UnwindSafe,
+{
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+/// Invokes a poll function, capturing the cause of an unwinding panic if one occurs.
+///
+/// This is a building block for hand-written futures that want the same
+/// behavior as [`MaybeUnwind`] without wrapping the whole future.
+/// The closure is called with the task context inside a capture scope,
+/// and the panic is converted into `Poll::Ready(Err(unwind))`.
+///
+/// Unlike [`maybe_unwind`], the closure is not required to be `UnwindSafe`.
+/// The caller is responsible for not observing any broken state after
+/// a panic is captured, e.g. by not polling the inner state again.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{poll_maybe_unwind, Unwind};
+/// use std::{
+///     future::Future,
+///     pin::Pin,
+///     task::{Context, Poll},
+/// };
+///
+/// struct MyFuture<F>(F);
+///
+/// impl<F: Future + Unpin> Future for MyFuture<F> {
+///     type Output = Result<F::Output, Unwind>;
+///
+///     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+///         poll_maybe_unwind(cx, |cx| Pin::new(&mut self.0).poll(cx))
+///     }
+/// }
+/// ```
+///
+/// [`MaybeUnwind`]: ./struct.MaybeUnwind.html
+/// [`maybe_unwind`]: ./fn.maybe_unwind.html
+#[cfg_attr(docs, doc(cfg(feature = "futures")))]
+#[track_caller]
+pub fn poll_maybe_unwind<F, T>(cx: &mut task::Context<'_>, f: F) -> Poll<Result<T, Unwind>>
+where
+    F: FnOnce(&mut task::Context<'_>) -> Poll<T>,
+{
+    poll_maybe_unwind_at(Location::caller(), cx, f)
+}
+
+pub(crate) fn poll_maybe_unwind_at<F, T>(
+    caller: &'static Location<'static>,
+    cx: &mut task::Context<'_>,
+    f: F,
+) -> Poll<Result<T, Unwind>>
+where
+    F: FnOnce(&mut task::Context<'_>) -> Poll<T>,
+{
+    let options = CaptureOptions::default();
+    transpose(maybe_unwind_at(
+        caller,
+        &options,
+        AssertUnwindSafe(|| f(cx)),
+    ))
+}
+
+fn poll_maybe_unwind_timed_at<F, T>(
+    caller: &'static Location<'static>,
+    cx: &mut task::Context<'_>,
+    f: F,
+) -> (Option<Duration>, Poll<Result<T, Unwind>>)
+where
+    F: FnOnce(&mut task::Context<'_>) -> Poll<T>,
+{
+    let options = CaptureOptions::default();
+    let (elapsed, res) = maybe_unwind_timed_at(caller, &options, AssertUnwindSafe(|| f(cx)));
+    (elapsed, transpose(res))
+}
+
+fn transpose<T>(res: Result<Poll<T>, Unwind>) -> Poll<Result<T, Unwind>> {
+    match res {
+        Ok(Poll::Pending) => Poll::Pending,
+        Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+        Err(unwind) => Poll::Ready(Err(unwind)),
     }
 }
 
@@ -39,6 +318,9 @@ pub trait FutureMaybeUnwindExt: Future + Sized {
     /// This is a variant of [`catch_unwind`] that also captures
     /// the panic information.
     ///
+    /// The location where this method is called is recorded as
+    /// [`Unwind::caller`] of the captured panics.
+    ///
     /// # Example
     ///
     /// ```
@@ -56,12 +338,435 @@ pub trait FutureMaybeUnwindExt: Future + Sized {
     /// ```
     ///
     /// [`catch_unwind`]: https://docs.rs/futures/0.3/futures/future/trait.FutureExt.html#method.catch_unwind
+    /// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+    #[track_caller]
     fn maybe_unwind(self) -> MaybeUnwind<Self>
     where
         Self: UnwindSafe,
     {
-        MaybeUnwind { inner: self }
+        MaybeUnwind::new_at(self, Location::caller())
+    }
+
+    /// Catches unwinding panics while polling the future, measuring the time
+    /// spent in polling it.
+    ///
+    /// This is the same as [`maybe_unwind`], except that the total time spent
+    /// in polling the inner future is available from [`MaybeUnwind::elapsed`]
+    /// and [`Unwind::elapsed`]. The time while the future was pending, e.g.
+    /// waiting for I/O or timers, is not included.
+    ///
+    /// [`maybe_unwind`]: #method.maybe_unwind
+    /// [`MaybeUnwind::elapsed`]: ./struct.MaybeUnwind.html#method.elapsed
+    /// [`Unwind::elapsed`]: ./struct.Unwind.html#method.elapsed
+    #[track_caller]
+    fn maybe_unwind_timed(self) -> MaybeUnwind<Self>
+    where
+        Self: UnwindSafe,
+    {
+        let mut adaptor = MaybeUnwind::new_at(self, Location::caller());
+        adaptor.elapsed = Some(Duration::from_secs(0));
+        adaptor
+    }
+
+    /// Catches unwinding panics while polling the future, attaching the
+    /// specified name to the capture scope.
+    ///
+    /// This is an asynchronous variant of [`maybe_unwind_named`].
+    /// The name is attached to the scope at every poll, so that it is
+    /// available from [`Unwind::scope_label`] of the captured panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::FutureMaybeUnwindExt as _;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// # futures_executor::block_on(async {
+    /// let unwind = async { panic!("oops") }
+    ///     .maybe_unwind_named("suite::case")
+    ///     .await
+    ///     .unwrap_err();
+    /// assert_eq!(unwind.scope_label(), Some("suite::case"));
+    /// # });
+    /// ```
+    ///
+    /// [`maybe_unwind_named`]: ./fn.maybe_unwind_named.html
+    /// [`Unwind::scope_label`]: ./struct.Unwind.html#method.scope_label
+    #[track_caller]
+    fn maybe_unwind_named(self, name: impl Into<Cow<'static, str>>) -> MaybeUnwind<Self>
+    where
+        Self: UnwindSafe,
+    {
+        let mut adaptor = MaybeUnwind::new_at(self, Location::caller());
+        adaptor.name = Some(name.into());
+        adaptor
+    }
+
+    /// Catches unwinding panics while polling the future, giving up if the
+    /// future does not complete within the specified duration.
+    ///
+    /// The timer is started at the first poll, by calling `sleep` with
+    /// `timeout` to create the delay future, so that any executor's timer can
+    /// be plugged in, e.g. `futures_timer::Delay::new` or `tokio::time::sleep`.
+    /// If the delay completes first, the inner future is dropped with the
+    /// adaptor and [`UnwindOrTimeout::Timeout`] is returned, which records
+    /// how long it waited and the scope label and context frames that were
+    /// last active while polling the inner future.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{FutureMaybeUnwindExt as _, UnwindOrTimeout};
+    /// use std::{future, time::Duration};
+    /// # struct Delay;
+    /// # impl Delay {
+    /// #     fn new(_: Duration) -> future::Ready<()> { future::ready(()) }
+    /// # }
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// # futures_executor::block_on(async {
+    /// let res = future::pending::<()>()
+    ///     .maybe_unwind_timeout(Duration::from_millis(10), Delay::new)
+    ///     .await;
+    /// assert!(matches!(res, Err(UnwindOrTimeout::Timeout(..))));
+    /// # });
+    /// ```
+    ///
+    /// [`UnwindOrTimeout::Timeout`]: ./enum.UnwindOrTimeout.html#variant.Timeout
+    #[track_caller]
+    fn maybe_unwind_timeout<S, D>(
+        self,
+        timeout: Duration,
+        sleep: S,
+    ) -> MaybeUnwindTimeout<Self, S, D>
+    where
+        Self: UnwindSafe,
+        S: FnOnce(Duration) -> D,
+        D: Future,
+    {
+        MaybeUnwindTimeout {
+            inner: MaybeUnwind::new_at(
+                Track {
+                    inner: self,
+                    scope_label: None,
+                    context_frames: vec![],
+                },
+                Location::caller(),
+            ),
+            timeout,
+            sleep: Some(sleep),
+            delay: None,
+            started: None,
+            terminated: false,
+        }
+    }
+
+    /// Annotates the future with a description of what it is doing.
+    ///
+    /// This is a method chaining variant of [`with_context_async`]. The
+    /// annotation is held by the returned future and pushed onto the stack
+    /// of the thread polling it at every poll, so it is recorded even if the
+    /// panic occurs after an `.await` point on another worker thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::FutureMaybeUnwindExt as _;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// # futures_executor::block_on(async {
+    /// let unwind = async { panic!("oops") }
+    ///     .context("while running migration 3")
+    ///     .context("while seeding the database")
+    ///     .maybe_unwind()
+    ///     .await
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     unwind.context_frames(),
+    ///     ["while seeding the database", "while running migration 3"],
+    /// );
+    /// # });
+    /// ```
+    ///
+    /// [`with_context_async`]: ./fn.with_context_async.html
+    #[inline]
+    fn context(self, frame: impl Into<String>) -> WithContext<Self> {
+        with_context_async(frame, self)
     }
 }
 
 impl<F: Future> FutureMaybeUnwindExt for F {}
+
+/// The error returned from the [`maybe_unwind_timeout`] adaptor.
+///
+/// [`maybe_unwind_timeout`]: ./trait.FutureMaybeUnwindExt.html#method.maybe_unwind_timeout
+#[derive(Debug)]
+#[cfg_attr(docs, doc(cfg(feature = "futures")))]
+pub enum UnwindOrTimeout {
+    /// The inner future panicked.
+    Unwind(Unwind),
+
+    /// The inner future did not complete in time.
+    Timeout(Timeout),
+}
+
+impl UnwindOrTimeout {
+    /// Return the captured panic if the inner future panicked.
+    #[inline]
+    pub fn unwind(&self) -> Option<&Unwind> {
+        match self {
+            UnwindOrTimeout::Unwind(unwind) => Some(unwind),
+            UnwindOrTimeout::Timeout(..) => None,
+        }
+    }
+
+    /// Return the information about the timeout if the inner future did not complete in time.
+    #[inline]
+    pub fn timeout(&self) -> Option<&Timeout> {
+        match self {
+            UnwindOrTimeout::Unwind(..) => None,
+            UnwindOrTimeout::Timeout(timeout) => Some(timeout),
+        }
+    }
+}
+
+impl fmt::Display for UnwindOrTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnwindOrTimeout::Unwind(unwind) => fmt::Display::fmt(unwind, f),
+            UnwindOrTimeout::Timeout(timeout) => fmt::Display::fmt(timeout, f),
+        }
+    }
+}
+
+/// The information about a future that did not complete in time.
+#[derive(Debug, Clone)]
+#[cfg_attr(docs, doc(cfg(feature = "futures")))]
+pub struct Timeout {
+    waited: Duration,
+    scope_label: Option<Cow<'static, str>>,
+    context_frames: Vec<String>,
+}
+
+impl Timeout {
+    /// Return how long the adaptor waited since the first poll.
+    ///
+    /// This is the configured timeout on the platforms without a monotonic
+    /// clock, such as `wasm32-unknown-unknown`.
+    #[inline]
+    pub fn waited(&self) -> Duration {
+        self.waited
+    }
+
+    /// Return the scope label that was last active while polling the inner future.
+    #[inline]
+    pub fn scope_label(&self) -> Option<&str> {
+        self.scope_label.as_deref()
+    }
+
+    /// Return the context frames that were last active while polling the inner future.
+    ///
+    /// The frames pushed by [`with_context_async`] inside of the inner future
+    /// are removed at the end of each poll, so only the frames outside of
+    /// the adaptor are recorded.
+    ///
+    /// [`with_context_async`]: ./fn.with_context_async.html
+    #[inline]
+    pub fn context_frames(&self) -> &[String] {
+        &self.context_frames
+    }
+}
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out after {:?}", self.waited)?;
+        if let Some(label) = &self.scope_label {
+            write!(f, " in {}", label)?;
+        }
+        for frame in &self.context_frames {
+            write!(f, "\n    {}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// A future that records the scope label and the context frames at every pending poll.
+#[derive(Debug)]
+struct Track<F> {
+    inner: F,
+    scope_label: Option<Cow<'static, str>>,
+    context_frames: Vec<String>,
+}
+
+impl<F: Future> Future for Track<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let me = unsafe { self.get_unchecked_mut() };
+        let inner = unsafe { Pin::new_unchecked(&mut me.inner) };
+        let poll = inner.poll(cx);
+        if poll.is_pending() {
+            // Keep the ones recorded by the previous polls, since the label
+            // set by the inner future is removed at the end of each poll.
+            if let Some(label) = crate::context::scope_label() {
+                me.scope_label = Some(label);
+            }
+            let frames = crate::frames::snapshot();
+            if !frames.is_empty() {
+                me.context_frames = frames;
+            }
+        }
+        poll
+    }
+}
+
+/// A future for the [`maybe_unwind_timeout`] method.
+///
+/// As with [`MaybeUnwind`], the adaptor must not be polled again after it has completed.
+///
+/// [`maybe_unwind_timeout`]: ./trait.FutureMaybeUnwindExt.html#method.maybe_unwind_timeout
+/// [`MaybeUnwind`]: ./struct.MaybeUnwind.html
+#[cfg_attr(docs, doc(cfg(feature = "futures")))]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct MaybeUnwindTimeout<F, S, D> {
+    inner: MaybeUnwind<Track<F>>,
+    timeout: Duration,
+    sleep: Option<S>,
+    delay: Option<Pin<Box<D>>>,
+    started: Option<Instant>,
+    terminated: bool,
+}
+
+impl<F, S, D> fmt::Debug for MaybeUnwindTimeout<F, S, D>
+where
+    F: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MaybeUnwindTimeout")
+            .field("inner", &self.inner.get_ref().inner)
+            .field("timeout", &self.timeout)
+            .field("terminated", &self.terminated)
+            .finish()
+    }
+}
+
+impl<F, S, D> Future for MaybeUnwindTimeout<F, S, D>
+where
+    F: Future + UnwindSafe,
+    S: FnOnce(Duration) -> D,
+    D: Future,
+{
+    type Output = Result<F::Output, UnwindOrTimeout>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // Safety: `inner` is structurally pinned, and the other fields are never pinned.
+        let me = unsafe { self.get_unchecked_mut() };
+        if me.terminated {
+            panic!("MaybeUnwindTimeout polled after completion");
+        }
+        if let Some(sleep) = me.sleep.take() {
+            me.started = crate::unwind::now();
+            me.delay = Some(Box::pin(sleep(me.timeout)));
+        }
+
+        let inner = unsafe { Pin::new_unchecked(&mut me.inner) };
+        if let Poll::Ready(res) = inner.poll(cx) {
+            me.terminated = true;
+            return Poll::Ready(res.map_err(UnwindOrTimeout::Unwind));
+        }
+
+        let delay = me
+            .delay
+            .as_mut()
+            .expect("the delay is created at the first poll");
+        futures_core::ready!(delay.as_mut().poll(cx));
+        me.terminated = true;
+        let track = me.inner.get_mut();
+        Poll::Ready(Err(UnwindOrTimeout::Timeout(Timeout {
+            waited: me.started.map_or(me.timeout, |started| started.elapsed()),
+            scope_label: track.scope_label.take(),
+            context_frames: std::mem::take(&mut track.context_frames),
+        })))
+    }
+}
+
+impl<F, S, D> FusedFuture for MaybeUnwindTimeout<F, S, D>
+where
+    F: Future + UnwindSafe,
+    S: FnOnce(Duration) -> D,
+    D: Future,
+{
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+/// A future for the [`with_context_async`] function and the [`context`] method.
+///
+/// [`with_context_async`]: ./fn.with_context_async.html
+/// [`context`]: ./trait.FutureMaybeUnwindExt.html#method.context
+#[derive(Debug)]
+#[cfg_attr(docs, doc(cfg(feature = "futures")))]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WithContext<F> {
+    inner: F,
+    frame: String,
+}
+
+impl<F> Future for WithContext<F>
+where
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let me = unsafe { self.get_unchecked_mut() };
+        let _guard = FrameGuard::push(me.frame.clone());
+        let inner = unsafe { Pin::new_unchecked(&mut me.inner) };
+        inner.poll(cx)
+    }
+}
+
+/// Annotates a future with a description of what it is doing.
+///
+/// This is an asynchronous variant of [`with_context`].
+/// The annotation is pushed onto the stack of the thread polling
+/// the future at every poll, so it is available even if the future
+/// is moved to another thread between polls.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{with_context_async, FutureMaybeUnwindExt as _};
+///
+/// std::panic::set_hook(Box::new(|info| {
+///     maybe_unwind::capture_panic_info(info);
+/// }));
+///
+/// # futures_executor::block_on(async {
+/// let unwind = with_context_async("while fetching the data", async {
+///     panic!("oops");
+/// })
+/// .maybe_unwind()
+/// .await
+/// .unwrap_err();
+///
+/// assert_eq!(unwind.context_frames(), ["while fetching the data"]);
+/// # });
+/// ```
+///
+/// [`with_context`]: ./fn.with_context.html
+#[cfg_attr(docs, doc(cfg(feature = "futures")))]
+pub fn with_context_async<F>(frame: impl Into<String>, future: F) -> WithContext<F>
+where
+    F: Future,
+{
+    WithContext {
+        inner: future,
+        frame: frame.into(),
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{error::MaybeUnwindError, options::CaptureOptions, unwind::maybe_unwind_at};
+use ::futures01::{Async, Future, Poll};
+use std::panic::{AssertUnwindSafe, Location, UnwindSafe};
+
+/// An extension trait for the futures of `futures` 0.1 that provides
+/// an adaptor for capturing the unwinding panic information.
+///
+/// This is the counterpart of [`FutureMaybeUnwindExt`] for the legacy
+/// `Future` trait, e.g. for the services still running on tokio 0.1.
+///
+/// [`FutureMaybeUnwindExt`]: ./trait.FutureMaybeUnwindExt.html
+#[cfg_attr(docs, doc(cfg(feature = "futures01")))]
+pub trait Future01MaybeUnwindExt: Future + Sized {
+    /// Catches unwinding panics while polling the future.
+    ///
+    /// The panic is returned as [`MaybeUnwindError::Unwind`], and the error
+    /// of the inner future as [`MaybeUnwindError::Inner`]. The location where
+    /// this method is called is recorded as [`Unwind::caller`] of the captured
+    /// panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures01::{future, Future as _};
+    /// use maybe_unwind::{Future01MaybeUnwindExt as _, MaybeUnwindError};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let res = future::lazy(|| -> Result<(), ()> { panic!("oops") })
+    ///     .maybe_unwind()
+    ///     .wait();
+    /// match res {
+    ///     Err(MaybeUnwindError::Unwind(unwind)) => assert!(unwind.location().is_some()),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    ///
+    /// [`MaybeUnwindError::Unwind`]: ./enum.MaybeUnwindError.html#variant.Unwind
+    /// [`MaybeUnwindError::Inner`]: ./enum.MaybeUnwindError.html#variant.Inner
+    /// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+    #[track_caller]
+    fn maybe_unwind(self) -> MaybeUnwind01<Self>
+    where
+        Self: UnwindSafe,
+    {
+        MaybeUnwind01 {
+            inner: Some(self),
+            caller: Location::caller(),
+            terminated: false,
+        }
+    }
+}
+
+impl<F: Future> Future01MaybeUnwindExt for F {}
+
+/// A future for the [`maybe_unwind`] method of [`Future01MaybeUnwindExt`].
+///
+/// As with [`MaybeUnwind`], the inner future is dropped inside a capture
+/// scope, and the adaptor must not be polled again after the inner future
+/// has completed or panicked.
+///
+/// [`maybe_unwind`]: ./trait.Future01MaybeUnwindExt.html#method.maybe_unwind
+/// [`Future01MaybeUnwindExt`]: ./trait.Future01MaybeUnwindExt.html
+/// [`MaybeUnwind`]: ./struct.MaybeUnwind.html
+#[derive(Debug)]
+#[cfg_attr(docs, doc(cfg(feature = "futures01")))]
+#[must_use = "futures do nothing unless polled"]
+pub struct MaybeUnwind01<F> {
+    inner: Option<F>,
+    caller: &'static Location<'static>,
+    terminated: bool,
+}
+
+impl<F> MaybeUnwind01<F> {
+    /// Acquire a reference to the inner future.
+    #[inline]
+    pub fn get_ref(&self) -> &F {
+        self.inner
+            .as_ref()
+            .expect("the inner future has been dropped")
+    }
+
+    /// Acquire a mutable reference to the inner future.
+    ///
+    /// After a panic was captured, the inner future may be left in a broken
+    /// state, and it is up to the caller to decide whether to touch it again.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut F {
+        self.inner
+            .as_mut()
+            .expect("the inner future has been dropped")
+    }
+}
+
+impl<F> Drop for MaybeUnwind01<F> {
+    fn drop(&mut self) {
+        let inner = &mut self.inner;
+        let res = maybe_unwind_at(
+            self.caller,
+            &CaptureOptions::default(),
+            AssertUnwindSafe(|| drop(inner.take())),
+        );
+        if let Err(unwind) = res {
+            crate::orphan::push(unwind);
+        }
+    }
+}
+
+impl<F> Future for MaybeUnwind01<F>
+where
+    F: Future + UnwindSafe,
+{
+    type Item = F::Item;
+    type Error = MaybeUnwindError<F::Error>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if self.terminated {
+            panic!("MaybeUnwind01 polled after completion");
+        }
+
+        let inner = self
+            .inner
+            .as_mut()
+            .expect("the inner future has been dropped");
+        let res = match maybe_unwind_at(
+            self.caller,
+            &CaptureOptions::default(),
+            AssertUnwindSafe(|| inner.poll()),
+        ) {
+            Ok(Ok(Async::NotReady)) => return Ok(Async::NotReady),
+            Ok(Ok(Async::Ready(item))) => Ok(Async::Ready(item)),
+            Ok(Err(err)) => Err(MaybeUnwindError::Inner(err)),
+            Err(unwind) => Err(MaybeUnwindError::Unwind(unwind)),
+        };
+        self.terminated = true;
+        res
+    }
+}
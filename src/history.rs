@@ -0,0 +1,102 @@
+use crate::captured::CapturedInfo;
+use std::{collections::VecDeque, sync::Mutex};
+
+/// The default number of the panics kept in the history.
+const DEFAULT_CAPACITY: usize = 64;
+
+static HISTORY: Mutex<History> = Mutex::new(History {
+    entries: None,
+    capacity: DEFAULT_CAPACITY,
+});
+
+struct History {
+    entries: Option<VecDeque<CapturedInfo>>,
+    capacity: usize,
+}
+
+impl History {
+    fn truncate(&mut self) {
+        if let Some(entries) = self.entries.as_mut() {
+            let excess = entries.len().saturating_sub(self.capacity);
+            entries.drain(..excess);
+        }
+    }
+}
+
+/// Return the most recent panics captured by `maybe_unwind` in the process,
+/// from the oldest to the newest.
+///
+/// Every panic caught by a capture scope is recorded into a process-global
+/// history, regardless of whether the returned [`Unwind`] is inspected or not.
+/// It is useful for post-mortem debugging at the end of a long test session.
+/// The number of the recorded panics is bounded by [`set_history_capacity`],
+/// and the older ones are discarded first.
+///
+/// The returned values are copies of the recorded information, and
+/// [`CapturedInfo::backtrace`] returns `None` for them.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{clear_history, history, maybe_unwind, set_scope_label};
+///
+/// maybe_unwind::set_hook();
+/// clear_history();
+///
+/// let _ = maybe_unwind(|| {
+///     set_scope_label("example");
+///     panic!("oops");
+/// });
+///
+/// let recent = history();
+/// let last = recent.last().unwrap();
+/// assert_eq!(last.message(), "oops");
+/// assert_eq!(last.scope_label(), Some("example"));
+/// ```
+///
+/// [`Unwind`]: ./struct.Unwind.html
+/// [`CapturedInfo::backtrace`]: ./struct.CapturedInfo.html#method.backtrace
+/// [`set_history_capacity`]: ./fn.set_history_capacity.html
+#[cfg_attr(docs, doc(cfg(feature = "history")))]
+pub fn history() -> Vec<CapturedInfo> {
+    let history = HISTORY.lock().unwrap_or_else(|err| err.into_inner());
+    history
+        .entries
+        .iter()
+        .flatten()
+        .map(|info| info.to_detached())
+        .collect()
+}
+
+/// Discard all the panics recorded in the history.
+#[cfg_attr(docs, doc(cfg(feature = "history")))]
+pub fn clear_history() {
+    let mut history = HISTORY.lock().unwrap_or_else(|err| err.into_inner());
+    history.entries = None;
+}
+
+/// Set the maximum number of the panics recorded in the history.
+///
+/// The oldest panics beyond the new capacity are discarded immediately.
+/// Setting the capacity to zero disables the recording. The default
+/// capacity is 64.
+#[cfg_attr(docs, doc(cfg(feature = "history")))]
+pub fn set_history_capacity(capacity: usize) {
+    let mut history = HISTORY.lock().unwrap_or_else(|err| err.into_inner());
+    history.capacity = capacity;
+    history.truncate();
+}
+
+/// Append a copy of the captured information to the history.
+pub(crate) fn record(captured: &CapturedInfo) {
+    let entry = captured.to_detached();
+    let mut history = HISTORY.lock().unwrap_or_else(|err| err.into_inner());
+    if history.capacity == 0 {
+        return;
+    }
+    history
+        .entries
+        .get_or_insert_with(VecDeque::new)
+        .push_back(entry);
+    history.truncate();
+}
@@ -1,8 +1,597 @@
+//! The panic hook and the capture of the panic information.
+//!
+//! The items in this module are also re-exported from the crate root.
+
 use crate::{
-    context::Context,
-    unwind::{Captured, Location},
+    abort, backtrace,
+    captured::{CaptureMiss, CapturedInfo},
+    context,
+    crash_file::CrashReportOptions,
+    frames,
+    json_stream::JsonSink,
+    outcome, payload, stats,
+    unwind::Location,
+};
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    env, fmt,
+    io::Write,
+    panic,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::SystemTime,
 };
-use std::panic::PanicInfo;
+
+pub use crate::{
+    abort::{install_abort_reporter, take_aborted_panic},
+    captured::{CaptureError, CaptureStatus},
+    compose::{compose, compose_with_current_hook},
+    observer::{add_observer, remove_observer, ObserverId},
+    outcome::{last_hook_outcome, HookOutcome},
+    self_check::{self_check, SelfCheckError},
+};
+
+#[cfg(panic_hook_info)]
+use std::panic::PanicHookInfo as StdPanicHookInfo;
+// The old name is deprecated on the newer compilers if the detection is skipped.
+#[cfg(not(panic_hook_info))]
+#[allow(deprecated)]
+use std::panic::PanicInfo as StdPanicHookInfo;
+
+/// The type of the argument passed to the panic hook.
+///
+/// This is an alias of `std::panic::PanicHookInfo` on Rust 1.81 or later,
+/// and of `std::panic::PanicInfo` on older compilers. It can be used for
+/// writing the signature of a custom panic hook that compiles on both
+/// without deprecation warnings.
+// `PanicHookInfo` is used only if the build script detects it.
+#[cfg_attr(panic_hook_info, allow(clippy::incompatible_msrv))]
+#[cfg_attr(not(panic_hook_info), allow(deprecated))]
+pub type PanicHookInfo<'a> = StdPanicHookInfo<'a>;
+
+/// The type of the boxed panic hooks, as taken by `std::panic::take_hook`.
+pub type PanicHookFn = dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static;
+
+pub(crate) type PanicHook = PanicHookFn;
+
+/// The address of the capturing hook most recently installed by this crate,
+/// or zero if the hook installed last does not capture the panic information.
+static INSTALLED: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether `capture_panic_info` has ever been called, e.g. by a custom hook.
+static CALLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the panic message is captured, set by `HookOptions::capture_message`.
+static CAPTURE_MESSAGE: AtomicBool = AtomicBool::new(true);
+
+/// The sequence number allocated to the next panic processed by `capture_panic_info`.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Whether the current thread is running the self-check, whose panic
+    /// must not be forwarded to the previous hook.
+    static PROBING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn address(hook: &PanicHook) -> usize {
+    hook as *const PanicHook as *const () as usize
+}
+
+/// Replace the panic hook, recording whether the new hook captures the panic information.
+pub(crate) fn replace_hook(hook: Box<PanicHook>, captures: bool) {
+    let addr = if captures { address(&*hook) } else { 0 };
+    panic::set_hook(hook);
+    INSTALLED.store(addr, Ordering::SeqCst);
+}
+
+/// Return whether `hook` is the capturing hook installed by this crate.
+pub(crate) fn is_installed_hook(hook: &PanicHook) -> bool {
+    INSTALLED.load(Ordering::SeqCst) == address(hook)
+}
+
+/// Return whether the capturing hook installed by this crate has been replaced
+/// by another hook, or `None` if the hook cannot be inspected.
+///
+/// The current hook is inspected by taking it and setting it back.
+pub(crate) fn is_replaced() -> Option<bool> {
+    if thread::panicking() {
+        return None;
+    }
+    let current = panic::take_hook();
+    let installed = is_installed_hook(&*current);
+    panic::set_hook(current);
+    Some(!installed)
+}
+
+/// Guess why the panic caught by an active scope was not captured by the hook.
+#[cold]
+pub(crate) fn diagnose_miss() -> CaptureMiss {
+    if !hook_installed() {
+        if CALLED.load(Ordering::Relaxed) {
+            // A custom hook calls `capture_panic_info`, but was not called.
+            return CaptureMiss::Resumed;
+        }
+        return CaptureMiss::NoHook;
+    }
+    match is_replaced() {
+        Some(true) => CaptureMiss::HookReplaced,
+        Some(false) | None => CaptureMiss::Resumed,
+    }
+}
+
+/// Return whether the captured panic is forwarded to the previous hook.
+fn forward_captured(options: &HookOptions) -> bool {
+    options.also_forward_captured && !PROBING.with(|probing| probing.get())
+}
+
+/// Run `f` without forwarding the captured panics to the previous hook.
+pub(crate) fn probe<R>(f: impl FnOnce() -> R) -> R {
+    struct Guard(bool);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            PROBING.with(|probing| probing.set(self.0));
+        }
+    }
+
+    let _guard = Guard(PROBING.with(|probing| probing.replace(true)));
+    f()
+}
+
+/// Return whether the capturing panic hook is installed by this crate.
+///
+/// The hook is tracked by the installation functions of this crate, namely
+/// [`set_hook`], [`set_hook_with`], [`run`] and [`maybe_unwind_quiet`], so
+/// this function returns `false` if the panic information is captured only
+/// by a custom hook calling [`capture_panic_info`]. It also cannot detect
+/// that the hook was replaced later by `std::panic::set_hook`; use
+/// [`self_check`] for verifying that the panic information is actually
+/// captured.
+///
+/// # Example
+///
+/// ```
+/// maybe_unwind::set_hook();
+/// assert!(maybe_unwind::hook_installed());
+/// ```
+///
+/// [`set_hook`]: ./fn.set_hook.html
+/// [`set_hook_with`]: ./fn.set_hook_with.html
+/// [`run`]: ./fn.run.html
+/// [`maybe_unwind_quiet`]: ./fn.maybe_unwind_quiet.html
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+/// [`self_check`]: ./fn.self_check.html
+#[inline]
+pub fn hook_installed() -> bool {
+    INSTALLED.load(Ordering::SeqCst) != 0
+}
+
+/// The options for installing the panic hook by [`set_hook_with`].
+///
+/// [`set_hook_with`]: ./fn.set_hook_with.html
+#[derive(Debug, Clone, Default)]
+pub struct HookOptions {
+    also_forward_captured: bool,
+    json_sink: Option<JsonSink>,
+    on_capture: Option<OnCapture>,
+    crash_report: CrashReportOptions,
+    scope_backtrace: bool,
+    skip_message: bool,
+    #[cfg(feature = "sentry")]
+    report_to_sentry: bool,
+}
+
+/// The callback registered by `HookOptions::on_capture_mut`.
+#[derive(Clone)]
+struct OnCapture(Arc<dyn Fn(&mut CapturedInfo) + Send + Sync + 'static>);
+
+impl fmt::Debug for OnCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnCapture { .. }")
+    }
+}
+
+impl HookOptions {
+    /// Create a new `HookOptions` with the default values.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify whether to forward the captured panics to the previous hook as well.
+    ///
+    /// If enabled, the previous hook is called for every panic regardless of
+    /// whether the panic information was captured, e.g. for keeping the panic
+    /// messages printed by the default hook in the raw logs. The panics inside
+    /// of [`maybe_unwind_quiet`] are still not forwarded, since the quiet scope
+    /// replaces the hook while it is active.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`maybe_unwind_quiet`]: ./fn.maybe_unwind_quiet.html
+    #[inline]
+    pub fn also_forward_captured(mut self, enabled: bool) -> Self {
+        self.also_forward_captured = enabled;
+        self
+    }
+
+    /// Specify whether to capture the stack backtrace at the entry of every
+    /// capture scope.
+    ///
+    /// This enables [`CaptureOptions::capture_scope_backtrace`] for all scopes,
+    /// including those entered by the dependencies. The setting is process-global
+    /// and is replaced whenever the hook is installed, so it is intended for
+    /// the debugging sessions rather than left enabled in production.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`CaptureOptions::capture_scope_backtrace`]: ./struct.CaptureOptions.html#method.capture_scope_backtrace
+    #[inline]
+    pub fn capture_scope_backtrace(mut self, enabled: bool) -> Self {
+        self.scope_backtrace = enabled;
+        self
+    }
+
+    /// Specify whether to copy the panic message into the captured information
+    /// in all capture scopes.
+    ///
+    /// Disabling it overrides [`CaptureOptions::capture_message`] of every
+    /// scope, including those entered by the dependencies. As with
+    /// [`capture_scope_backtrace`], the setting is process-global and is
+    /// replaced whenever the hook is installed.
+    ///
+    /// The default value is `true`.
+    ///
+    /// [`CaptureOptions::capture_message`]: ./struct.CaptureOptions.html#method.capture_message
+    /// [`capture_scope_backtrace`]: #method.capture_scope_backtrace
+    #[inline]
+    pub fn capture_message(mut self, enabled: bool) -> Self {
+        self.skip_message = !enabled;
+        self
+    }
+
+    /// Stream every captured panic to `sink` as a line of JSON.
+    ///
+    /// The line is written and flushed inside of the panic hook, as soon as
+    /// the panic information is captured, so that the panics are visible
+    /// while a long test run is still in progress. The object has the
+    /// following fields:
+    ///
+    /// * `timestamp`: the time of the capture as `{"secs": .., "nanos": ..}`
+    ///   relative to the UNIX epoch, or `null` without the system clock
+    /// * `sequence`: the number returned from [`Unwind::sequence`], for
+    ///   ordering the lines written by multiple threads
+    /// * `thread`: the name of the panicking thread, or `null`
+    /// * `scope_label`: the label of the capture scope, or `null`
+    /// * `message`: the panic message with the redactor applied
+    /// * `file`, `line` and `column`: the panic location, or `null`s
+    /// * `fingerprint`: a stable hash of the panic location as 16 hex digits,
+    ///   for grouping the same panics across the runs
+    /// * `kind`: the category of the panic, such as `"unwrap"` or
+    ///   `"assertion"`, as returned from [`Unwind::classify`]
+    ///
+    /// The sink is shared by the threads behind a lock, and the I/O errors
+    /// are ignored. The sink must not panic, since a panic inside of the
+    /// panic hook aborts the process.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, HookOptions};
+    ///
+    /// maybe_unwind::set_hook_with(HookOptions::new().stream_json_to(Box::new(std::io::stderr())));
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// assert!(unwind.location().is_some());
+    /// ```
+    ///
+    /// [`Unwind::classify`]: ./struct.Unwind.html#method.classify
+    /// [`Unwind::sequence`]: ./struct.Unwind.html#method.sequence
+    pub fn stream_json_to(mut self, sink: Box<dyn Write + Send>) -> Self {
+        self.json_sink = Some(JsonSink::new(sink));
+        self
+    }
+
+    /// Call `f` with every captured panic information before it is stored
+    /// into the capture scope.
+    ///
+    /// The callback is called inside of the panic hook, on the panicking
+    /// thread, after the built-in fields such as the location and the context
+    /// frames are filled. It can enrich the captured information with the
+    /// application-specific data at the moment of the capture, e.g. the test
+    /// case running on the thread, by [`CapturedInfo::annotate`]. The
+    /// annotations are available from [`Unwind::annotations`] and rendered
+    /// in the report. The JSON sink set by [`stream_json_to`] is written after
+    /// the callback returns.
+    ///
+    /// The capture scope is marked as capturing while the callback is running,
+    /// so a capture requested by the callback fails as reentrant. The callback
+    /// must not panic, since a panic inside of the panic hook aborts the process.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, HookOptions};
+    ///
+    /// maybe_unwind::set_hook_with(HookOptions::new().on_capture_mut(|info| {
+    ///     info.annotate("test_case", "suite::case");
+    /// }));
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// assert_eq!(
+    ///     unwind.annotations(),
+    ///     [("test_case".to_owned(), "suite::case".to_owned())],
+    /// );
+    /// ```
+    ///
+    /// [`CapturedInfo::annotate`]: ./struct.CapturedInfo.html#method.annotate
+    /// [`Unwind::annotations`]: ./struct.Unwind.html#method.annotations
+    /// [`stream_json_to`]: #method.stream_json_to
+    pub fn on_capture_mut<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mut CapturedInfo) + Send + Sync + 'static,
+    {
+        self.on_capture = Some(OnCapture(Arc::new(f)));
+        self
+    }
+
+    /// Write a crash report file into `dir` for every panic that was not captured.
+    ///
+    /// The report is written inside of the panic hook, before the previous
+    /// hook is called, so that the panic escaping all the capture scopes of
+    /// a long-running process is kept on disk after its stderr is gone.
+    /// The report contains the panic message and location in the format of
+    /// the default panic hook, the name of the panicking thread, the context
+    /// frames, and the stack backtrace if enabled by `RUST_BACKTRACE` (and
+    /// supported by this build).
+    ///
+    /// The file is named after the time of the panic, e.g.
+    /// `crash-1700000000.000000000-1234-0.txt`, and the directory is created
+    /// if it does not exist. The oldest files are removed when the number of
+    /// the reports in the directory exceeds [`max_crash_reports`].
+    /// The filesystem errors are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use maybe_unwind::HookOptions;
+    ///
+    /// maybe_unwind::set_hook_with(HookOptions::new().write_crash_report_to("target/crashes"));
+    /// ```
+    ///
+    /// [`max_crash_reports`]: #method.max_crash_reports
+    pub fn write_crash_report_to(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.crash_report.dir = Some(dir.into());
+        self
+    }
+
+    /// Specify whether to write the crash reports of the captured panics as well.
+    ///
+    /// This option takes effect only if [`write_crash_report_to`] is set.
+    /// The report of a captured panic is written after the callback of
+    /// [`on_capture_mut`] returns, with a note that the panic was captured.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`write_crash_report_to`]: #method.write_crash_report_to
+    /// [`on_capture_mut`]: #method.on_capture_mut
+    #[inline]
+    pub fn crash_report_captured(mut self, enabled: bool) -> Self {
+        self.crash_report.captured = enabled;
+        self
+    }
+
+    /// Specify the maximum number of the crash reports kept in the directory.
+    ///
+    /// The value is clamped to at least `1`. The default value is `16`.
+    #[inline]
+    pub fn max_crash_reports(mut self, max: usize) -> Self {
+        self.crash_report.max_files = max.max(1);
+        self
+    }
+
+    /// Specify whether to send the panics that were not captured to Sentry.
+    ///
+    /// If enabled, the hook builds the event of every panic escaping all the
+    /// capture scopes in the same manner as [`Unwind::to_sentry_event`],
+    /// with the mechanism marked as not handled, and passes it to the current
+    /// Sentry hub before the previous hook is called. The captured panics are
+    /// left to the caller, which can report them by `to_sentry_event`.
+    ///
+    /// This replaces the panic integration of the `sentry` crate, which
+    /// should be disabled to avoid reporting the same panic twice.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`Unwind::to_sentry_event`]: ./struct.Unwind.html#method.to_sentry_event
+    #[cfg(feature = "sentry")]
+    #[cfg_attr(docs, doc(cfg(feature = "sentry")))]
+    #[inline]
+    pub fn report_uncaptured_to_sentry(mut self, enabled: bool) -> Self {
+        self.report_to_sentry = enabled;
+        self
+    }
+}
+
+/// Install the panic hook that captures the panic information.
+///
+/// The installed hook calls [`capture_panic_info`], and forwards the
+/// panic information to the previously registered hook only if the
+/// panic was not captured. If the compiler supports it, the hook is
+/// composed with the previous one by `std::panic::update_hook`.
+///
+/// This function is equivalent to `set_hook_with(HookOptions::default())`,
+/// and does nothing if the current thread is panicking. If the capturing
+/// hook is already installed, e.g. by the previous call, the hook is kept
+/// as it is instead of being composed again, so that every panic is captured
+/// only once.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind;
+///
+/// maybe_unwind::set_hook();
+///
+/// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+/// assert!(unwind.location().is_some());
+/// ```
+///
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+#[inline]
+pub fn set_hook() {
+    set_hook_with(HookOptions::default());
+}
+
+/// Install the panic hook that captures the panic information with the specified options.
+///
+/// See [`set_hook`] for details. If the hook is already installed by this
+/// function or [`compose`], its options are replaced with `options`.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, HookOptions};
+///
+/// // The panic message is also printed by the default hook.
+/// maybe_unwind::set_hook_with(HookOptions::new().also_forward_captured(true));
+///
+/// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+/// assert!(unwind.location().is_some());
+/// ```
+///
+/// [`set_hook`]: ./fn.set_hook.html
+/// [`compose`]: ./fn.compose.html
+pub fn set_hook_with(options: HookOptions) {
+    backtrace::set_scope_backtrace(options.scope_backtrace);
+    CAPTURE_MESSAGE.store(!options.skip_message, Ordering::Relaxed);
+
+    if thread::panicking() {
+        return;
+    }
+
+    let current = panic::take_hook();
+    if crate::compose::replace_options(&*current, options.clone()) {
+        replace_hook(current, true);
+        return;
+    }
+
+    #[cfg(panic_update_hook)]
+    {
+        panic::set_hook(current);
+        let (f, marker) = crate::compose::composed_fn(options);
+        panic::update_hook(move |prev, info| f(info, prev));
+        // The composed hook is allocated by `update_hook`.
+        let hook = panic::take_hook();
+        marker.register(&*hook);
+        replace_hook(hook, true);
+    }
+
+    #[cfg(not(panic_update_hook))]
+    {
+        replace_hook(compose(current, options), true);
+    }
+}
+
+/// The guard that restores the previous panic hook on drop.
+pub(crate) struct HookGuard {
+    prev: Option<Arc<Box<PanicHook>>>,
+    prev_installed: bool,
+}
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        if let Some(prev) = self.prev.take() {
+            if !thread::panicking() {
+                restore_hook(prev, self.prev_installed);
+            }
+        }
+    }
+}
+
+/// Reinstall the hook replaced by a hook forwarding to it.
+///
+/// The original box is reinstalled as it is, so that the hook is still
+/// recognized by its address, e.g. as the one composed by this crate.
+pub(crate) fn restore_hook(prev: Arc<Box<PanicHook>>, prev_installed: bool) {
+    // Drop the forwarding hook, which shares `prev`.
+    drop(panic::take_hook());
+    match Arc::try_unwrap(prev) {
+        Ok(prev) => replace_hook(prev, prev_installed),
+        // The forwarding hook is still held by someone else.
+        Err(prev) => replace_hook(Box::new(move |info| (**prev)(info)), prev_installed),
+    }
+}
+
+/// Install the capturing panic hook with `options` temporarily, unless the
+/// current hook already captures the panic information.
+pub(crate) fn install_hook(options: HookOptions) -> Option<HookGuard> {
+    if thread::panicking() {
+        return None;
+    }
+
+    let current = panic::take_hook();
+    if crate::compose::is_composed(&*current) {
+        replace_hook(current, true);
+        return None;
+    }
+    panic::set_hook(current);
+    install_hook_fn(move |info, prev| call_hook(&options, info, prev))
+}
+
+pub(crate) fn call_hook(options: &HookOptions, info: &PanicHookInfo<'_>, prev: &PanicHook) {
+    let status = capture_panic_info_with(info, |captured| {
+        if let Some(on_capture) = &options.on_capture {
+            (on_capture.0)(captured);
+        }
+        options.crash_report.write_captured(captured);
+        if let Some(sink) = &options.json_sink {
+            if !PROBING.with(|probing| probing.get()) {
+                sink.emit(captured);
+            }
+        }
+    });
+    let handled = status.map_or(false, CaptureStatus::is_handled);
+    if !handled {
+        options.crash_report.write_uncaptured(info, now());
+        #[cfg(feature = "sentry")]
+        {
+            if options.report_to_sentry {
+                crate::sentry::report_uncaptured(info);
+            }
+        }
+    }
+    if !handled || forward_captured(options) {
+        prev(info);
+    }
+}
+
+/// Install the capturing panic hook composed with the previous hook,
+/// in the same manner as `std::panic::update_hook`.
+pub(crate) fn install_hook_fn<H>(hook: H) -> Option<HookGuard>
+where
+    H: Fn(&PanicHookInfo<'_>, &PanicHook) + Send + Sync + 'static,
+{
+    if thread::panicking() {
+        return None;
+    }
+
+    let prev = panic::take_hook();
+    let prev_installed = is_installed_hook(&*prev);
+    let prev = Arc::new(prev);
+    let forward = Arc::clone(&prev);
+    replace_hook(Box::new(move |info| hook(info, &**forward)), true);
+
+    Some(HookGuard {
+        prev: Some(prev),
+        prev_installed,
+    })
+}
 
 /// Capture the panic information.
 ///
@@ -12,15 +601,23 @@ use std::panic::PanicInfo;
 ///
 /// If the panic location is outside of the closure passed to
 /// `maybe_unwind`, this function does nothing and just return
-/// `false`.
+/// `false`. It also always returns `false` if the `capture` feature
+/// is disabled, or if the capture failed. This function is a shorthand
+/// of [`try_capture_panic_info`] for telling whether the panic still
+/// needs to be reported.
+///
+/// This function does not print the panic message by itself, so the
+/// caller is responsible for reporting the panics that were not captured.
+/// The only exception is the report of a double panic enabled by
+/// [`install_abort_reporter`].
 ///
 /// # Example
 ///
 /// ```
-/// use maybe_unwind::{maybe_unwind, capture_panic_info};
-/// use std::panic::{self, PanicInfo};
+/// use maybe_unwind::{maybe_unwind, capture_panic_info, PanicHookInfo};
+/// use std::panic;
 ///
-/// fn my_hook(info: &PanicInfo) {
+/// fn my_hook(info: &PanicHookInfo) {
 ///     let captured = capture_panic_info(info);
 ///
 ///     if !captured {
@@ -32,20 +629,148 @@ use std::panic::PanicInfo;
 /// let res = maybe_unwind(|| { panic!("oops"); });
 /// assert!(res.is_err());
 /// ```
+///
+/// [`try_capture_panic_info`]: ./fn.try_capture_panic_info.html
+/// [`install_abort_reporter`]: ./fn.install_abort_reporter.html
+#[inline]
+pub fn capture_panic_info(info: &PanicHookInfo<'_>) -> bool {
+    try_capture_panic_info(info).map_or(false, CaptureStatus::is_handled)
+}
+
+/// Capture the panic information, reporting what happened to it.
+///
+/// This function behaves like [`capture_panic_info`], but tells the reason
+/// why the panic information was not captured. The failure to capture the
+/// panic inside of a capture scope is returned as [`CaptureError`], and
+/// is also recorded into the scope, so that it is reported by
+/// [`Unwind::capture_miss`] or [`CapturedInfo::notes`] instead of
+/// vanishing silently.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, try_capture_panic_info, CaptureStatus};
+/// use std::panic;
+///
+/// panic::set_hook(Box::new(|info| match try_capture_panic_info(info) {
+///     Ok(CaptureStatus::NotInScope) => eprintln!("{}", info),
+///     Ok(_) => (),
+///     Err(err) => eprintln!("{} ({})", info, err),
+/// }));
+///
+/// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+/// assert!(unwind.location().is_some());
+/// ```
+///
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+/// [`CaptureError`]: ./enum.CaptureError.html
+/// [`Unwind::capture_miss`]: ./struct.Unwind.html#method.capture_miss
+/// [`CapturedInfo::notes`]: ./struct.CapturedInfo.html#method.notes
 #[inline]
-pub fn capture_panic_info(info: &PanicInfo) -> bool {
-    if !Context::is_set() {
-        return false;
+pub fn try_capture_panic_info(info: &PanicHookInfo<'_>) -> Result<CaptureStatus, CaptureError> {
+    capture_panic_info_with(info, |_| ())
+}
+
+/// Capture the panic information, calling `on_capture` with the captured values.
+pub(crate) fn capture_panic_info_with<F>(
+    info: &PanicHookInfo<'_>,
+    on_capture: F,
+) -> Result<CaptureStatus, CaptureError>
+where
+    F: FnOnce(&mut CapturedInfo),
+{
+    // Nothing is recorded, including the stats and the outcome.
+    if !cfg!(feature = "capture") {
+        return Ok(CaptureStatus::NotInScope);
+    }
+
+    CALLED.store(true, Ordering::Relaxed);
+    // Allocated even if the panic is not captured, so that the gaps are visible.
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let status = capture_in_scope(info, sequence, on_capture);
+    outcome::record(&status);
+    stats::record(info, &status, now());
+    status
+}
+
+fn capture_in_scope<F>(
+    info: &PanicHookInfo<'_>,
+    sequence: u64,
+    on_capture: F,
+) -> Result<CaptureStatus, CaptureError>
+where
+    F: FnOnce(&mut CapturedInfo),
+{
+    if !context::is_set()? {
+        return Ok(CaptureStatus::NotInScope);
     }
 
-    let backtrace = capture_backtrace!();
+    abort::report_double_panic(info);
 
-    let _ = Context::try_with(|ctx| {
-        ctx.captured.replace(Captured {
+    context::try_capture(|scope, options| {
+        let (backtrace, resolved_backtrace) = backtrace::offload(
+            capture_backtrace!(options.backtrace),
+            options.resolver.as_ref(),
+            options.max_backtrace_frames,
+        );
+        let (backtrace, truncated_backtrace) =
+            backtrace::truncate(backtrace, options.max_backtrace_frames);
+        // Nothing is allocated unless the message or the backtrace is captured.
+        let capture_message = !options.skip_message && CAPTURE_MESSAGE.load(Ordering::Relaxed);
+        let mut captured = CapturedInfo {
+            message: if capture_message {
+                Some(match info.payload().downcast_ref::<&'static str>() {
+                    Some(msg) => Cow::Borrowed(*msg),
+                    None => Cow::Owned(payload::render(info.payload()).into_owned()),
+                })
+            } else {
+                None
+            },
             location: info.location().map(|loc| Location::from_std(loc)),
             backtrace,
-        });
-    });
+            truncated_backtrace,
+            resolved_backtrace,
+            scope_backtrace: None,
+            context_frames: frames::snapshot(),
+            thread: thread::current(),
+            timestamp: now(),
+            sequence,
+            notes: vec![],
+            annotations: vec![],
+            env: options
+                .env_vars
+                .iter()
+                .map(|name| {
+                    let value = env::var_os(name).map(|value| value.to_string_lossy().into_owned());
+                    (name.clone(), value)
+                })
+                .collect(),
+            panic_depth: scope.panic_depth(),
+            scope_label: context::scope_label(),
+            raw_hook_message: if options.hook_message {
+                Some(info.to_string())
+            } else {
+                None
+            },
+            suppressed_captures: 0,
+            prior: vec![],
+            location_policy: options.location_policy,
+            #[cfg(feature = "tracing-error")]
+            span_trace: crate::span_trace::capture(options),
+        };
+        on_capture(&mut captured);
+        stats::remember_captured(&captured);
+        captured
+    })
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn now() -> Option<SystemTime> {
+    Some(SystemTime::now())
+}
 
-    true
+// `SystemTime::now` panics on this target.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn now() -> Option<SystemTime> {
+    None
 }
@@ -0,0 +1,115 @@
+use crate::{
+    display::DisplayOptions,
+    snapshot::UnwindSnapshot,
+    unwind::{Location, Unwind},
+};
+use std::{
+    any::Any,
+    error, fmt, io,
+    sync::{Mutex, MutexGuard},
+};
+
+/// An error type converted from `Unwind`.
+///
+/// Unlike `Unwind`, this type is `Send + Sync` and implements
+/// `std::error::Error`, so it can be passed to the APIs that expect
+/// `io::Error` or `Box<dyn Error + Send + Sync>`. The `Display` output is
+/// the same as the alternate `Display` output of `Unwind`, except that
+/// the backtrace is omitted.
+///
+/// The panic payload is not `Sync`, so it is moved behind a mutex and
+/// can be taken back by [`take_payload`].
+///
+/// [`take_payload`]: #method.take_payload
+#[derive(Debug)]
+pub struct UnwindError {
+    snapshot: UnwindSnapshot,
+    payload: Mutex<Option<Box<dyn Any + Send + 'static>>>,
+}
+
+impl UnwindError {
+    /// Return the summary of the captured panic.
+    #[inline]
+    pub fn snapshot(&self) -> &UnwindSnapshot {
+        &self.snapshot
+    }
+
+    /// Return the information about the location from which the panic originated.
+    #[inline]
+    pub fn location(&self) -> Option<&Location> {
+        self.snapshot.location()
+    }
+
+    /// Return the location where the capture scope was established.
+    #[inline]
+    pub fn caller(&self) -> &Location {
+        self.snapshot.caller()
+    }
+
+    /// Take the original panic payload, e.g. for resuming the panic.
+    ///
+    /// This method returns `None` if the payload has already been taken.
+    pub fn take_payload(&self) -> Option<Box<dyn Any + Send + 'static>> {
+        self.lock_payload().take()
+    }
+
+    fn lock_payload(&self) -> MutexGuard<'_, Option<Box<dyn Any + Send + 'static>>> {
+        self.payload.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl fmt::Display for UnwindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut summary = String::new();
+        self.snapshot
+            .report()
+            .write_summary(&mut summary, None, &DisplayOptions::default())?;
+        f.write_str(summary.trim_end())
+    }
+}
+
+impl error::Error for UnwindError {}
+
+impl From<Unwind> for UnwindError {
+    fn from(unwind: Unwind) -> Self {
+        Self {
+            snapshot: unwind.to_snapshot(),
+            payload: Mutex::new(Some(unwind.into_payload())),
+        }
+    }
+}
+
+impl From<Unwind> for io::Error {
+    /// Convert `Unwind` into an `io::Error` of the kind `Other`.
+    ///
+    /// The inner error is an [`UnwindError`], which can be recovered
+    /// by `io::Error::get_ref` and downcasting.
+    ///
+    /// [`UnwindError`]: ./struct.UnwindError.html
+    fn from(unwind: Unwind) -> Self {
+        io::Error::new(io::ErrorKind::Other, UnwindError::from(unwind))
+    }
+}
+
+impl Unwind {
+    /// Convert itself into a boxed error.
+    ///
+    /// The concrete type of the returned error is [`UnwindError`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, UnwindError};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let err = maybe_unwind(|| panic!("oops")).unwrap_err().into_boxed_error();
+    /// let err = err.downcast::<UnwindError>().unwrap();
+    /// assert!(err.location().is_some());
+    /// ```
+    ///
+    /// [`UnwindError`]: ./struct.UnwindError.html
+    pub fn into_boxed_error(self) -> Box<dyn error::Error + Send + Sync + 'static> {
+        Box::new(UnwindError::from(self))
+    }
+}
@@ -0,0 +1,391 @@
+use crate::{
+    backtrace::BacktraceStatus,
+    json::{
+        json_duration, json_location, json_option, json_optional_map, json_pairs, json_str,
+        json_strs,
+    },
+    snapshot::UnwindSnapshot,
+    unwind::Location,
+};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fmt::Write as _,
+    io::{self, Read, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const MAGIC: &[u8; 4] = b"MUWS";
+
+/// The version of the binary encoding written by `UnwindSnapshot::write_to`.
+///
+/// This value must be bumped whenever the layout of the encoding changes.
+const VERSION: u8 = 1;
+
+impl UnwindSnapshot {
+    /// Write this snapshot in a compact binary encoding, e.g. for sending
+    /// it from a child process to the parent over a pipe.
+    ///
+    /// The encoding starts with a magic number and a version byte, followed
+    /// by the length of the record and the length-prefixed fields. The record
+    /// can be read back by [`read_from`], which rejects the records written
+    /// by an incompatible version of this crate instead of misparsing them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, UnwindSnapshot};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let snapshot = maybe_unwind(|| panic!("oops")).unwrap_err().to_snapshot();
+    ///
+    /// let mut buf = vec![];
+    /// snapshot.write_to(&mut buf).unwrap();
+    /// let decoded = UnwindSnapshot::read_from(&buf[..]).unwrap();
+    /// assert_eq!(decoded, snapshot);
+    /// ```
+    ///
+    /// [`read_from`]: #method.read_from
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut record = Encoder::default();
+        record.str(&self.message);
+        record.option(self.location.as_ref(), Encoder::location);
+        record.location(&self.caller);
+        record.strs(&self.context_frames);
+        record.option(self.thread_name.as_deref(), Encoder::str);
+        record.option(self.timestamp.as_ref(), Encoder::system_time);
+        record.option(self.elapsed.as_ref(), Encoder::duration);
+        record.strs(&self.notes);
+        record.u32(self.panic_depth);
+        record.option(self.scope_label.as_deref(), Encoder::str);
+        record.option(self.backtrace.as_deref(), Encoder::str);
+        record.u8(encode_status(self.backtrace_status));
+        record.option(self.max_message_len.as_ref(), |e, &n| e.u64(n as u64));
+        record.strs(&self.causes);
+        record.pairs(&self.annotations);
+        record.optional_map(&self.env);
+        record.option(self.scope_backtrace.as_deref(), Encoder::str);
+
+        let len = u32::try_from(record.buf.len())
+            .map_err(|_| invalid_data("the snapshot is too large to encode"))?;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(&record.buf)?;
+        writer.flush()
+    }
+
+    /// Read a snapshot written by [`write_to`].
+    ///
+    /// Exactly one record is consumed from the reader, so multiple snapshots
+    /// written to a stream can be read one by one. An error of the kind
+    /// `InvalidData` is returned if the record is malformed or was written
+    /// in an unsupported version of the encoding.
+    ///
+    /// [`write_to`]: #method.write_to
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; 9];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC[..] {
+            return Err(invalid_data("not an encoded snapshot"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported snapshot encoding version {} (expected {})",
+                header[4], VERSION
+            )));
+        }
+        let len = u32::from_le_bytes([header[5], header[6], header[7], header[8]]);
+
+        // The buffer grows as the data arrives, rather than trusting the length.
+        let mut buf = vec![];
+        reader.take(u64::from(len)).read_to_end(&mut buf)?;
+        if buf.len() != len as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the snapshot record is truncated",
+            ));
+        }
+
+        let mut record = Decoder { buf: &buf };
+        let snapshot = UnwindSnapshot {
+            message: record.string()?,
+            location: record.option(Decoder::location)?,
+            caller: record.location()?,
+            context_frames: record.strings()?,
+            thread_name: record.option(Decoder::string)?,
+            timestamp: record.option(Decoder::system_time)?,
+            elapsed: record.option(Decoder::duration)?,
+            notes: record.strings()?,
+            panic_depth: record.u32()?,
+            scope_label: record.option(Decoder::string)?,
+            backtrace: record.option(Decoder::string)?,
+            backtrace_status: decode_status(record.u8()?)?,
+            max_message_len: record.option(|d| d.u64().map(|n| n as usize))?,
+            causes: record.strings()?,
+            annotations: record.pairs()?,
+            env: record.optional_map()?,
+            scope_backtrace: record.option(Decoder::string)?,
+        };
+        if !record.buf.is_empty() {
+            return Err(invalid_data("unexpected trailing data in the snapshot"));
+        }
+        Ok(snapshot)
+    }
+
+    /// Render this snapshot as a single line of JSON, terminated by a newline.
+    ///
+    /// This is intended for line-oriented protocols. The object contains
+    /// the `version` of the encoding and the fields of the snapshot, with
+    /// the times and durations written as `{"secs": .., "nanos": ..}`
+    /// (the timestamp is relative to the UNIX epoch), and the `kind` of the
+    /// panic returned from [`classify`].
+    ///
+    /// [`classify`]: #method.classify
+    pub fn to_json_line(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(out, "{{\"version\":{}", VERSION);
+        out.push_str(",\"message\":");
+        json_str(&mut out, &self.message);
+        out.push_str(",\"kind\":");
+        json_str(&mut out, self.classify().as_str());
+        out.push_str(",\"location\":");
+        json_option(&mut out, self.location.as_ref(), json_location);
+        out.push_str(",\"caller\":");
+        json_location(&mut out, &self.caller);
+        out.push_str(",\"context_frames\":");
+        json_strs(&mut out, &self.context_frames);
+        out.push_str(",\"annotations\":");
+        json_pairs(&mut out, &self.annotations);
+        out.push_str(",\"env\":");
+        json_optional_map(&mut out, &self.env);
+        out.push_str(",\"thread_name\":");
+        json_option(&mut out, self.thread_name.as_deref(), json_str);
+        out.push_str(",\"timestamp\":");
+        json_option(
+            &mut out,
+            self.timestamp
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .as_ref(),
+            json_duration,
+        );
+        out.push_str(",\"elapsed\":");
+        json_option(&mut out, self.elapsed.as_ref(), json_duration);
+        out.push_str(",\"notes\":");
+        json_strs(&mut out, &self.notes);
+        out.push_str(",\"causes\":");
+        json_strs(&mut out, &self.causes);
+        let _ = write!(out, ",\"panic_depth\":{}", self.panic_depth);
+        out.push_str(",\"scope_label\":");
+        json_option(&mut out, self.scope_label.as_deref(), json_str);
+        out.push_str(",\"backtrace\":");
+        json_option(&mut out, self.backtrace.as_deref(), json_str);
+        out.push_str(",\"backtrace_status\":");
+        json_str(&mut out, status_name(self.backtrace_status));
+        out.push_str(",\"scope_backtrace\":");
+        json_option(&mut out, self.scope_backtrace.as_deref(), json_str);
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+fn encode_status(status: BacktraceStatus) -> u8 {
+    match status {
+        BacktraceStatus::Captured => 0,
+        BacktraceStatus::Disabled => 1,
+        BacktraceStatus::Unsupported => 2,
+        BacktraceStatus::CaptureFailed => 3,
+    }
+}
+
+fn decode_status(n: u8) -> io::Result<BacktraceStatus> {
+    match n {
+        0 => Ok(BacktraceStatus::Captured),
+        1 => Ok(BacktraceStatus::Disabled),
+        2 => Ok(BacktraceStatus::Unsupported),
+        3 => Ok(BacktraceStatus::CaptureFailed),
+        n => Err(invalid_data(format!("invalid backtrace status {}", n))),
+    }
+}
+
+fn status_name(status: BacktraceStatus) -> &'static str {
+    match status {
+        BacktraceStatus::Captured => "captured",
+        BacktraceStatus::Disabled => "disabled",
+        BacktraceStatus::Unsupported => "unsupported",
+        BacktraceStatus::CaptureFailed => "capture_failed",
+    }
+}
+
+#[derive(Default)]
+struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    fn u8(&mut self, n: u8) {
+        self.buf.push(n);
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.buf.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn u64(&mut self, n: u64) {
+        self.buf.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn str(&mut self, s: &str) {
+        // The record length is checked to fit in `u32`, and so is each field.
+        self.u32(s.len() as u32);
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn strs(&mut self, strs: &[String]) {
+        self.u32(strs.len() as u32);
+        for s in strs {
+            self.str(s);
+        }
+    }
+
+    fn pairs(&mut self, pairs: &[(String, String)]) {
+        self.u32(pairs.len() as u32);
+        for (key, value) in pairs {
+            self.str(key);
+            self.str(value);
+        }
+    }
+
+    fn optional_map(&mut self, map: &BTreeMap<String, Option<String>>) {
+        self.u32(map.len() as u32);
+        for (key, value) in map {
+            self.str(key);
+            self.option(value.as_deref(), Self::str);
+        }
+    }
+
+    fn option<T: ?Sized>(&mut self, value: Option<&T>, f: impl FnOnce(&mut Self, &T)) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                f(self, value);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn location(&mut self, loc: &Location) {
+        self.str(loc.file());
+        self.u32(loc.line());
+        self.u32(loc.column());
+    }
+
+    fn duration(&mut self, d: &Duration) {
+        self.u64(d.as_secs());
+        self.u32(d.subsec_nanos());
+    }
+
+    fn system_time(&mut self, t: &SystemTime) {
+        // The times before the epoch are not expected from the panic hook.
+        self.duration(&t.duration_since(UNIX_EPOCH).unwrap_or_default());
+    }
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn bytes(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.buf.len() < n {
+            return Err(invalid_data("the snapshot record is malformed"));
+        }
+        let (bytes, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let b = self.bytes(8)?;
+        Ok(u64::from_le_bytes([
+            b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7],
+        ]))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid_data("invalid UTF-8 in the snapshot"))
+    }
+
+    fn strings(&mut self) -> io::Result<Vec<String>> {
+        let count = self.u32()?;
+        // Each string takes at least 4 bytes, which bounds the allocation.
+        let mut strings = Vec::with_capacity((count as usize).min(self.buf.len() / 4));
+        for _ in 0..count {
+            strings.push(self.string()?);
+        }
+        Ok(strings)
+    }
+
+    fn pairs(&mut self) -> io::Result<Vec<(String, String)>> {
+        let count = self.u32()?;
+        // Each pair takes at least 8 bytes, which bounds the allocation.
+        let mut pairs = Vec::with_capacity((count as usize).min(self.buf.len() / 8));
+        for _ in 0..count {
+            pairs.push((self.string()?, self.string()?));
+        }
+        Ok(pairs)
+    }
+
+    fn optional_map(&mut self) -> io::Result<BTreeMap<String, Option<String>>> {
+        let count = self.u32()?;
+        let mut map = BTreeMap::new();
+        for _ in 0..count {
+            map.insert(self.string()?, self.option(Decoder::string)?);
+        }
+        Ok(map)
+    }
+
+    fn option<T>(&mut self, f: impl FnOnce(&mut Self) -> io::Result<T>) -> io::Result<Option<T>> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => f(self).map(Some),
+            _ => Err(invalid_data("the snapshot record is malformed")),
+        }
+    }
+
+    fn location(&mut self) -> io::Result<Location> {
+        Ok(Location::new(self.string()?, self.u32()?, self.u32()?))
+    }
+
+    fn duration(&mut self) -> io::Result<Duration> {
+        let secs = self.u64()?;
+        let nanos = self.u32()?;
+        if nanos >= 1_000_000_000 {
+            return Err(invalid_data("the snapshot record is malformed"));
+        }
+        Ok(Duration::new(secs, nanos))
+    }
+
+    fn system_time(&mut self) -> io::Result<SystemTime> {
+        let since_epoch = self.duration()?;
+        UNIX_EPOCH
+            .checked_add(since_epoch)
+            .ok_or_else(|| invalid_data("the timestamp is out of range"))
+    }
+}
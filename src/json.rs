@@ -0,0 +1,88 @@
+use crate::unwind::Location;
+use std::{collections::BTreeMap, fmt::Write as _, time::Duration};
+
+pub(crate) fn json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+pub(crate) fn json_strs(out: &mut String, strs: &[String]) {
+    out.push('[');
+    for (i, s) in strs.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        json_str(out, s);
+    }
+    out.push(']');
+}
+
+/// Writes the key-value pairs as an array of `{"key": .., "value": ..}`.
+pub(crate) fn json_pairs(out: &mut String, pairs: &[(String, String)]) {
+    out.push('[');
+    for (i, (key, value)) in pairs.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"key\":");
+        json_str(out, key);
+        out.push_str(",\"value\":");
+        json_str(out, value);
+        out.push('}');
+    }
+    out.push(']');
+}
+
+/// Writes the map as an object, with the missing values written as `null`.
+pub(crate) fn json_optional_map(out: &mut String, map: &BTreeMap<String, Option<String>>) {
+    out.push('{');
+    for (i, (key, value)) in map.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        json_str(out, key);
+        out.push(':');
+        json_option(out, value.as_deref(), json_str);
+    }
+    out.push('}');
+}
+
+pub(crate) fn json_option<T: ?Sized>(out: &mut String, value: Option<&T>, f: fn(&mut String, &T)) {
+    match value {
+        Some(value) => f(out, value),
+        None => out.push_str("null"),
+    }
+}
+
+pub(crate) fn json_location(out: &mut String, loc: &Location) {
+    out.push_str("{\"file\":");
+    json_str(out, loc.file());
+    let _ = write!(
+        out,
+        ",\"line\":{},\"column\":{}}}",
+        loc.line(),
+        loc.column()
+    );
+}
+
+pub(crate) fn json_duration(out: &mut String, d: &Duration) {
+    let _ = write!(
+        out,
+        "{{\"secs\":{},\"nanos\":{}}}",
+        d.as_secs(),
+        d.subsec_nanos()
+    );
+}
@@ -0,0 +1,103 @@
+use crate::{
+    captured::CapturedInfo,
+    json::{json_duration, json_option, json_str},
+};
+use std::{
+    cell::Cell,
+    fmt::{self, Write as _},
+    io::Write,
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+thread_local! {
+    /// Whether the current thread is writing a line to a sink.
+    static EMITTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The sink to which the captured panics are streamed as JSON Lines.
+#[derive(Clone)]
+pub(crate) struct JsonSink(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl fmt::Debug for JsonSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("JsonSink { .. }")
+    }
+}
+
+impl JsonSink {
+    pub(crate) fn new(sink: Box<dyn Write + Send>) -> Self {
+        Self(Arc::new(Mutex::new(sink)))
+    }
+
+    /// Write the captured panic as a line, and flush the sink.
+    ///
+    /// This is called inside of the panic hook, so the errors are ignored
+    /// and the sink is never re-entered from the same thread.
+    pub(crate) fn emit(&self, captured: &CapturedInfo) {
+        if EMITTING.with(|emitting| emitting.replace(true)) {
+            return;
+        }
+        let _guard = Guard;
+
+        let line = to_json_line(captured);
+        let mut sink = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        let _ = sink.write_all(line.as_bytes()).and_then(|()| sink.flush());
+    }
+}
+
+struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        EMITTING.with(|emitting| emitting.set(false));
+    }
+}
+
+fn to_json_line(captured: &CapturedInfo) -> String {
+    // Rendering the backtrace is too expensive to be done for every line.
+    let map = captured.to_map_with(false);
+
+    let mut out = String::new();
+    out.push_str("{\"timestamp\":");
+    json_option(
+        &mut out,
+        captured
+            .timestamp
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .as_ref(),
+        json_duration,
+    );
+    let _ = write!(out, ",\"sequence\":{}", captured.sequence);
+    out.push_str(",\"thread\":");
+    json_option(&mut out, non_empty(&map["thread"]), json_str);
+    out.push_str(",\"scope_label\":");
+    json_option(&mut out, non_empty(&map["scope"]), json_str);
+    out.push_str(",\"message\":");
+    json_str(&mut out, &map["message"]);
+    out.push_str(",\"file\":");
+    json_option(&mut out, non_empty(&map["file"]), json_str);
+    // The line and the column are written as the numbers.
+    for key in ["line", "column"] {
+        let _ = write!(
+            out,
+            ",\"{}\":{}",
+            key,
+            non_empty(&map[key]).unwrap_or("null")
+        );
+    }
+    out.push_str(",\"fingerprint\":");
+    json_str(&mut out, &map["fingerprint"]);
+    out.push_str(",\"kind\":");
+    json_str(&mut out, &map["kind"]);
+    out.push_str("}\n");
+    out
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
@@ -21,28 +21,308 @@ if let Err(unwind) = maybe_unwind(|| do_something()) {
 }
 # fn do_something() {}
 ```
+
+# Disabling the capture
+
+The panic information is captured only if the `capture` feature, enabled by
+default, is turned on. When it is turned off, [`maybe_unwind`] is equivalent
+to [`catch_unwind`], [`capture_panic_info`] returns `false` immediately, and
+all the captured information of [`Unwind`] (such as [`Unwind::location`])
+are unavailable. The API remains unchanged so that the downstream code
+compiles identically.
+
+# Feature flags
+
+The integrations with the other crates are gated by the additive features,
+so that enabling one of them does not pull in the others:
+
+* `futures` (default): the adaptors of `Future`s, built on `futures-core`
+* `stream` (default): the adaptor of `Stream`s, which implies `futures`
+* `sink`: the adaptor of `Sink`s, built on `futures-sink`, which implies `futures`
+* `tokio`: spawning the Tokio tasks that capture the panics, which implies `futures`
+* `async-std`: spawning the async-std tasks that capture the panics, exported
+  from the [`async_std`] module, which implies `futures`
+* `futures-executor`: spawning the `!Send` futures onto the local executors and
+  running `LocalPool` while capturing the panics, which implies `futures`
+* `rayon`: joining the Rayon closures that capture the panics
+* `futures01`: the adaptor of the futures of `futures` 0.1
+* `tracing-error`: capturing the `SpanTrace` of the panicking thread
+* `sentry`: converting the captured panics into the Sentry events, and
+  reporting the panics that were not captured to Sentry from the hook
+* `history`, `macros`, `proptest`, `quickcheck`, `libtest-mimic`, `regex` and `backtrace`
+
+Every feature compiles on its own with `--no-default-features`, which is
+checked by `tests/build-matrix.sh` (or by `cargo hack check --each-feature`).
+
+The support of `std::backtrace` is detected by the build script, which can
+be overridden by the `MAYBE_UNWIND_BACKTRACE` environment variable in the
+builds that must not spawn the compiler. See [`build_info`] for details.
+
+# Modules
+
+The public items are re-exported from the crate root, and are also grouped
+into the modules by their topics:
+
+* [`hook`]: the panic hook and the capture of the panic information
+* [`unwind`]: the capture scopes and the captured panics
+* [`futures`]: the adaptors of the asynchronous computations
+* [`thread`]: the control of the capture on the current thread
+* [`report`]: the rendering of the captured panics into reports
+
+The [`prelude`] re-exports the extension traits and the most common
+functions, so that a single glob import is enough for the most tests:
+
+```
+use maybe_unwind::prelude::*;
+
+set_hook();
+
+let (ok, failed) = (1..=3)
+    .map_maybe_unwind(|n| {
+        assert!(n != 2, "two");
+        n
+    })
+    .partition_unwinds();
+assert_eq!(ok, [1, 3]);
+assert_eq!(failed[0].1.message(), "two");
+```
+
+[`maybe_unwind`]: ./fn.maybe_unwind.html
+[`capture_panic_info`]: ./fn.capture_panic_info.html
+[`Unwind`]: ./struct.Unwind.html
+[`Unwind::location`]: ./struct.Unwind.html#method.location
+[`build_info`]: ./fn.build_info.html
 !*/
 
 #![doc(html_root_url = "https://docs.rs/maybe-unwind/0.3.1")]
 #![deny(missing_docs)]
 #![forbid(clippy::todo, clippy::unimplemented)]
-#![cfg_attr(backtrace, feature(backtrace))]
+#![cfg_attr(backtrace_feature, feature(backtrace))]
+#![cfg_attr(panic_update_hook, feature(panic_update_hook))]
+// `std::backtrace` is used only if the build script detects it.
+#![cfg_attr(backtrace, allow(clippy::incompatible_msrv))]
 #![cfg_attr(docs, feature(doc_cfg))]
 
+mod abort;
+mod assertion;
 #[macro_use]
 mod backtrace;
-#[macro_use]
+mod blame;
+mod build_info;
+mod captured;
+mod classify;
+mod collector;
+mod compose;
 mod context;
-mod hook;
-mod unwind;
+mod crash_file;
+mod display;
+mod drop_guard;
+mod each;
+mod expect;
+mod frames;
+mod interop;
+mod ipc;
+mod json;
+mod json_stream;
+mod location;
+mod map;
+mod normalize;
+mod observer;
+mod options;
+mod orphan;
+mod outcome;
+mod pattern;
+mod payload;
+mod poison;
+mod quiet;
+mod redact;
+mod resolver;
+mod retry;
+mod run;
+mod self_check;
+mod shield;
+mod sink;
+mod snapshot;
+#[cfg(feature = "tracing-error")]
+mod span_trace;
+mod stats;
+mod suite;
+mod unwind_set;
+
+pub mod ffi;
+pub mod hook;
+pub mod prelude;
+pub mod report;
+pub mod testing;
+pub mod thread;
+pub mod unwind;
 
 pub use crate::{
-    hook::capture_panic_info,
-    unwind::{maybe_unwind, Location, Unwind},
+    abort::{install_abort_reporter, take_aborted_panic, unwinding_supported},
+    assertion::{AssertionInfo, AssertionKind},
+    backtrace::BacktraceStatus,
+    blame::{Blame, BlameSource},
+    build_info::{build_info, BacktraceDetection, BuildInfo},
+    captured::{CaptureError, CaptureMiss, CaptureStatus, CapturedInfo},
+    classify::PanicKind,
+    collector::{CollectedPanic, PanicCollector, PanicReceiver},
+    compose::{compose, compose_with_current_hook},
+    context::{
+        current_scope_label, disable_for_current_thread, enable_for_current_thread, in_scope,
+        is_enabled, scope_depth, set_scope_label,
+    },
+    display::DisplayOptions,
+    drop_guard::DropGuard,
+    each::{
+        maybe_unwind_each, FailureSummary, IterMaybeUnwindExt, MapMaybeUnwind, MaybeUnwindIter,
+    },
+    expect::{expect_unwind, ExpectError, PayloadPattern},
+    frames::with_context,
+    hook::{
+        capture_panic_info, hook_installed, set_hook, set_hook_with, try_capture_panic_info,
+        HookOptions, PanicHookFn, PanicHookInfo,
+    },
+    interop::UnwindError,
+    location::{
+        interner_stats, set_interner_capacity, DisplayLocation, InternerStats, LocationStyle,
+    },
+    normalize::NormalizeOptions,
+    observer::{add_observer, remove_observer, ObserverId},
+    options::{BacktraceMode, CaptureOptions, LocationPolicy},
+    orphan::take_orphaned_unwinds,
+    outcome::{last_hook_outcome, HookOutcome},
+    pattern::{ParsePatternError, UnwindPattern},
+    payload::{
+        clear_payload_describer, clear_payload_formatter, register_payload_error,
+        register_payload_formatter, set_payload_describer,
+    },
+    poison::{clear_poison, is_poisoned, poison, poison_report, PoisonRecord},
+    quiet::maybe_unwind_quiet,
+    redact::{clear_redactor, set_redactor},
+    resolver::{spawn_backtrace_resolver, ResolvedBacktrace, ResolverHandle},
+    retry::{maybe_unwind_retry, maybe_unwind_retry_with, RetryUnwind},
+    run::{run, run_with, RunOptions, UnwindReport},
+    self_check::{self_check, SelfCheckError},
+    shield::{shield_fn, shield_fn_mut, ShieldOptions},
+    snapshot::UnwindSnapshot,
+    stats::{panic_stats, persist_stats_on_panic, persist_stats_on_panic_with, PanicStats},
+    suite::{CaseOutcome, CaseResult, SuiteRunner, SuiteSummary},
+    unwind::{
+        maybe_unwind, maybe_unwind_mut, maybe_unwind_named, maybe_unwind_timed, maybe_unwind_with,
+        maybe_unwind_with_data, Location, Unwind, UnwindMeta,
+    },
+    unwind_set::UnwindSet,
 };
 
+#[cfg(backtrace)]
+#[cfg_attr(docs, doc(cfg(feature = "backtrace")))]
+pub use crate::backtrace::Frame;
+
 #[cfg(feature = "futures")]
-mod futures;
+#[cfg_attr(docs, doc(cfg(feature = "futures")))]
+pub mod futures;
 
 #[cfg(feature = "futures")]
-pub use futures::{FutureMaybeUnwindExt, MaybeUnwind};
+#[cfg_attr(docs, doc(cfg(feature = "futures")))]
+pub use crate::futures::{
+    poll_maybe_unwind, with_context_async, FutureMaybeUnwindExt, MaybeUnwind, MaybeUnwindTimeout,
+    Timeout, UnwindOrTimeout, WithContext,
+};
+
+#[cfg(feature = "stream")]
+mod stream;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(docs, doc(cfg(feature = "stream")))]
+pub use crate::stream::{MaybeUnwindStream, StreamMaybeUnwindExt};
+
+#[cfg(feature = "sink")]
+mod sink_adaptor;
+
+#[cfg(feature = "sink")]
+#[cfg_attr(docs, doc(cfg(feature = "sink")))]
+pub use crate::sink_adaptor::{MaybeUnwindSink, SinkMaybeUnwindExt};
+
+#[cfg(feature = "tokio")]
+mod tokio;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docs, doc(cfg(feature = "tokio")))]
+pub use crate::tokio::{spawn_blocking_maybe_unwind, spawn_maybe_unwind};
+
+#[cfg(feature = "async-std")]
+#[cfg_attr(docs, doc(cfg(feature = "async-std")))]
+pub mod async_std;
+
+#[cfg(feature = "futures-executor")]
+mod local_pool;
+
+#[cfg(feature = "futures-executor")]
+#[cfg_attr(docs, doc(cfg(feature = "futures-executor")))]
+pub use crate::local_pool::{spawn_local_maybe_unwind, LocalPoolExt};
+
+#[cfg(feature = "rayon")]
+mod rayon;
+
+#[cfg(feature = "rayon")]
+#[cfg_attr(docs, doc(cfg(feature = "rayon")))]
+pub use crate::rayon::join_maybe_unwind;
+
+#[cfg(feature = "futures01")]
+mod futures01;
+
+#[cfg(feature = "futures01")]
+#[cfg_attr(docs, doc(cfg(feature = "futures01")))]
+pub use crate::futures01::{Future01MaybeUnwindExt, MaybeUnwind01};
+
+#[cfg(any(feature = "futures01", feature = "sink"))]
+mod error;
+
+#[cfg(any(feature = "futures01", feature = "sink"))]
+#[cfg_attr(docs, doc(cfg(any(feature = "futures01", feature = "sink"))))]
+pub use crate::error::MaybeUnwindError;
+
+#[cfg(feature = "history")]
+mod history;
+
+#[cfg(feature = "history")]
+#[cfg_attr(docs, doc(cfg(feature = "history")))]
+pub use crate::history::{clear_history, history, set_history_capacity};
+
+#[cfg(feature = "macros")]
+#[cfg_attr(docs, doc(cfg(feature = "macros")))]
+pub use maybe_unwind_macros::guard;
+
+#[cfg(feature = "proptest")]
+mod proptest;
+
+#[cfg(feature = "proptest")]
+#[cfg_attr(docs, doc(cfg(feature = "proptest")))]
+pub use crate::proptest::maybe_unwind_prop;
+
+#[cfg(feature = "quickcheck")]
+mod quickcheck;
+
+#[cfg(feature = "quickcheck")]
+#[cfg_attr(docs, doc(cfg(feature = "quickcheck")))]
+pub use crate::quickcheck::{maybe_unwind_qc, maybe_unwind_qc_property, MaybeUnwindProperty};
+
+#[cfg(feature = "sentry")]
+mod sentry;
+
+#[cfg(feature = "libtest-mimic")]
+mod libtest_mimic;
+
+#[cfg(feature = "libtest-mimic")]
+#[cfg_attr(docs, doc(cfg(feature = "libtest-mimic")))]
+pub use crate::libtest_mimic::trial;
+
+#[cfg(all(target_arch = "wasm32", feature = "console_error_panic_hook"))]
+mod wasm;
+
+#[cfg(all(target_arch = "wasm32", feature = "console_error_panic_hook"))]
+#[cfg_attr(
+    docs,
+    doc(cfg(all(target_arch = "wasm32", feature = "console_error_panic_hook")))
+)]
+pub use crate::wasm::set_console_hook;
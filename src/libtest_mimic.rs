@@ -0,0 +1,72 @@
+use crate::{
+    hook::set_hook,
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use ::libtest_mimic::{Failed, Trial};
+use std::{
+    panic::{Location, UnwindSafe},
+    sync::Once,
+};
+
+impl Unwind {
+    /// Converts this value into a failure of `libtest-mimic` trial.
+    ///
+    /// Unlike the blanket `From` implementation provided by `libtest-mimic`,
+    /// which only keeps the panic message, the failure message contains
+    /// the full report of the alternate `Display` (the location, the
+    /// context frames and the backtrace).
+    #[cfg_attr(docs, doc(cfg(feature = "libtest-mimic")))]
+    pub fn into_failed(self) -> Failed {
+        let msg = format!("{:#}", self);
+        Failed::from(msg.trim_end())
+    }
+}
+
+/// Creates a `libtest-mimic` trial whose panics are reported as failures.
+///
+/// The panic hook is installed by [`set_hook`] at the first run of the trial,
+/// and the panic raised by `f` is converted into [`Failed`] by using
+/// [`Unwind::into_failed`].
+///
+/// The trials that are expected to panic (like `#[should_panic]`) can be
+/// expressed by combining with [`expect_unwind`].
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{expect_unwind, trial};
+///
+/// let trials = vec![
+///     trial("adds", || {
+///         assert_eq!(1 + 1, 2);
+///         Ok(())
+///     }),
+///     trial("overflows", || {
+///         expect_unwind("overflow", || panic!("arithmetic overflow"))
+///         .map(drop)
+///         .map_err(Into::into)
+///     }),
+/// ];
+/// # let _ = trials;
+/// ```
+///
+/// [`set_hook`]: ./fn.set_hook.html
+/// [`Failed`]: https://docs.rs/libtest-mimic/0.8/libtest_mimic/struct.Failed.html
+/// [`Unwind::into_failed`]: ./struct.Unwind.html#method.into_failed
+/// [`expect_unwind`]: ./fn.expect_unwind.html
+#[cfg_attr(docs, doc(cfg(feature = "libtest-mimic")))]
+#[track_caller]
+pub fn trial<F>(name: impl Into<String>, f: F) -> Trial
+where
+    F: FnOnce() -> Result<(), Failed> + Send + UnwindSafe + 'static,
+{
+    static HOOK: Once = Once::new();
+
+    let caller = Location::caller();
+    Trial::test(name, move || {
+        HOOK.call_once(set_hook);
+        maybe_unwind_at(caller, &CaptureOptions::default(), f)
+            .unwrap_or_else(|unwind| Err(unwind.into_failed()))
+    })
+}
@@ -0,0 +1,173 @@
+use crate::{
+    futures::MaybeUnwind,
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+    unwind_set::UnwindSet,
+};
+use futures_executor::LocalPool;
+use futures_util::{
+    future::{FutureExt as _, RemoteHandle},
+    pin_mut,
+    task::{LocalSpawn, LocalSpawnExt as _, SpawnError},
+};
+use std::{
+    future::Future,
+    panic::{AssertUnwindSafe, Location},
+};
+
+/// The label of the panics of the tasks collected by `LocalPoolExt`.
+const TASK_LABEL: &str = "spawned task";
+
+/// Spawn a future onto a local executor, capturing the panic information if it panics.
+///
+/// This is the counterpart of [`spawn_maybe_unwind`] for the executors that
+/// run `!Send` futures on the current thread, such as `LocalPool`. The task is
+/// polled by [`MaybeUnwind`], and its result, including the captured panic,
+/// is retrieved from the returned `RemoteHandle`. As with the handle returned
+/// by `LocalSpawnExt::spawn_local_with_handle`, dropping the handle cancels
+/// the task unless `RemoteHandle::forget` is called. The location where this
+/// function is called is recorded as [`Unwind::caller`].
+///
+/// The future is wrapped by `AssertUnwindSafe`, since the task is not
+/// polled again after a panic.
+///
+/// # Example
+///
+/// ```
+/// use futures_executor::LocalPool;
+/// use maybe_unwind::spawn_local_maybe_unwind;
+/// use std::rc::Rc;
+///
+/// maybe_unwind::set_hook();
+///
+/// let mut pool = LocalPool::new();
+/// let state = Rc::new(42);
+/// let handle = spawn_local_maybe_unwind(&pool.spawner(), async move {
+///     assert_eq!(*state, 0, "unexpected state");
+/// })
+/// .unwrap();
+///
+/// let unwind = pool.run_until(handle).unwrap_err();
+/// assert!(unwind.message().contains("unexpected state"));
+/// ```
+///
+/// [`spawn_maybe_unwind`]: ./fn.spawn_maybe_unwind.html
+/// [`MaybeUnwind`]: ./struct.MaybeUnwind.html
+/// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+#[cfg_attr(docs, doc(cfg(feature = "futures-executor")))]
+#[track_caller]
+pub fn spawn_local_maybe_unwind<S, F>(
+    spawner: &S,
+    future: F,
+) -> Result<RemoteHandle<Result<F::Output, Unwind>>, SpawnError>
+where
+    S: LocalSpawn + ?Sized,
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    let (remote, handle) =
+        MaybeUnwind::new_at(AssertUnwindSafe(future), Location::caller()).remote_handle();
+    spawner.spawn_local(remote)?;
+    Ok(handle)
+}
+
+/// An extension trait for `LocalPool` that captures the panics of all the
+/// tasks running on the pool.
+///
+/// The pool is run inside of a capture scope, so that the panics of the
+/// tasks spawned without [`spawn_local_maybe_unwind`], e.g. by third-party
+/// code, are captured as well. Such a panic is collected into the returned
+/// [`UnwindSet`] with the label `spawned task`, the panicked task is dropped
+/// by the pool, and the pool keeps running the remaining tasks. The location
+/// where the method is called is recorded as [`Unwind::caller`].
+///
+/// [`spawn_local_maybe_unwind`]: ./fn.spawn_local_maybe_unwind.html
+/// [`UnwindSet`]: ./struct.UnwindSet.html
+/// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+#[cfg_attr(docs, doc(cfg(feature = "futures-executor")))]
+pub trait LocalPoolExt {
+    /// Run the pool until `future` completes, capturing the panics of all the tasks.
+    ///
+    /// The result of `future` itself, including its own panic, is returned
+    /// together with the panics of the other tasks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_executor::LocalPool;
+    /// use maybe_unwind::LocalPoolExt as _;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let mut pool = LocalPool::new();
+    /// let (output, unwinds) = pool.run_until_maybe_unwind(async { 42 });
+    /// assert_eq!(output.unwrap(), 42);
+    /// assert!(unwinds.is_empty());
+    /// ```
+    #[track_caller]
+    fn run_until_maybe_unwind<F>(&mut self, future: F) -> (Result<F::Output, Unwind>, UnwindSet)
+    where
+        F: Future;
+
+    /// Run all the tasks in the pool to completion, capturing their panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_executor::LocalPool;
+    /// use futures_util::task::LocalSpawnExt as _;
+    /// use maybe_unwind::LocalPoolExt as _;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let mut pool = LocalPool::new();
+    /// let spawner = pool.spawner();
+    /// spawner.spawn_local(async { panic!("background task failed") }).unwrap();
+    /// spawner.spawn_local(async {}).unwrap();
+    ///
+    /// let unwinds = pool.run_maybe_unwind();
+    /// assert_eq!(unwinds.len(), 1);
+    /// ```
+    #[track_caller]
+    fn run_maybe_unwind(&mut self) -> UnwindSet;
+}
+
+impl LocalPoolExt for LocalPool {
+    #[track_caller]
+    fn run_until_maybe_unwind<F>(&mut self, future: F) -> (Result<F::Output, Unwind>, UnwindSet)
+    where
+        F: Future,
+    {
+        let caller = Location::caller();
+        // The panics of `future` are captured by the adaptor, so that only
+        // the panics of the other tasks escape from `run_until`.
+        let future = MaybeUnwind::new_at(AssertUnwindSafe(future), caller);
+        pin_mut!(future);
+        let mut unwinds = UnwindSet::new();
+        loop {
+            let res = maybe_unwind_at(
+                caller,
+                &CaptureOptions::default(),
+                AssertUnwindSafe(|| self.run_until(future.as_mut())),
+            );
+            match res {
+                Ok(output) => return (output, unwinds),
+                Err(unwind) => unwinds.push(TASK_LABEL, unwind),
+            }
+        }
+    }
+
+    #[track_caller]
+    fn run_maybe_unwind(&mut self) -> UnwindSet {
+        let caller = Location::caller();
+        let mut unwinds = UnwindSet::new();
+        while let Err(unwind) = maybe_unwind_at(
+            caller,
+            &CaptureOptions::default(),
+            AssertUnwindSafe(|| self.run()),
+        ) {
+            unwinds.push(TASK_LABEL, unwind);
+        }
+        unwinds
+    }
+}
@@ -0,0 +1,210 @@
+use crate::unwind::Location;
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fmt,
+    path::Path,
+    sync::{Mutex, MutexGuard},
+};
+
+/// The default maximum number of the file names kept by the interner,
+/// which bounds the memory leaked by the interned file names.
+const DEFAULT_INTERNER_CAPACITY: usize = 256;
+
+static INTERNER: Mutex<Interner> = Mutex::new(Interner {
+    files: None,
+    capacity: DEFAULT_INTERNER_CAPACITY,
+    bytes: 0,
+    rejected: 0,
+});
+
+/// The pool of the file names of the panic locations, which are leaked
+/// to share them among the captured locations.
+struct Interner {
+    files: Option<HashSet<&'static str>>,
+    capacity: usize,
+    bytes: usize,
+    rejected: usize,
+}
+
+fn lock_interner() -> MutexGuard<'static, Interner> {
+    INTERNER.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Returns the interned file name, or allocates it if the interner is full.
+pub(crate) fn intern(file: &str) -> Cow<'static, str> {
+    let mut interner = lock_interner();
+    let interner = &mut *interner;
+    let files = interner.files.get_or_insert_with(HashSet::new);
+    if let Some(interned) = files.get(file) {
+        return Cow::Borrowed(*interned);
+    }
+    if files.len() >= interner.capacity {
+        interner.rejected += 1;
+        return Cow::Owned(file.to_owned());
+    }
+    let interned: &'static str = Box::leak(file.into());
+    files.insert(interned);
+    interner.bytes += interned.len();
+    Cow::Borrowed(interned)
+}
+
+/// The statistics of the interner of the file names, returned from [`interner_stats`].
+///
+/// [`interner_stats`]: ./fn.interner_stats.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct InternerStats {
+    /// The number of the interned file names.
+    pub entries: usize,
+
+    /// The total length of the interned file names, in bytes.
+    pub bytes: usize,
+
+    /// The maximum number of the interned file names.
+    pub capacity: usize,
+
+    /// The number of the file names allocated separately since the interner was full.
+    pub rejected: usize,
+}
+
+/// Return the statistics of the interner of the file names of the panic locations.
+///
+/// The file names of the locations captured by the panic hook are interned,
+/// so that the repeated panics at the same files share the storage of the
+/// file name. The interned file names are never freed, and the number of
+/// them is bounded by [`set_interner_capacity`]. The interning is transparent
+/// to [`Location::file`].
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{interner_stats, maybe_unwind};
+///
+/// maybe_unwind::set_hook();
+///
+/// for _ in 0..3 {
+///     let _ = maybe_unwind(|| panic!("oops"));
+/// }
+/// let stats = interner_stats();
+/// # if cfg!(feature = "capture") {
+/// assert_eq!(stats.entries, 1);
+/// # }
+/// ```
+///
+/// [`set_interner_capacity`]: ./fn.set_interner_capacity.html
+/// [`Location::file`]: ./struct.Location.html#method.file
+pub fn interner_stats() -> InternerStats {
+    let interner = lock_interner();
+    InternerStats {
+        entries: interner.files.as_ref().map_or(0, HashSet::len),
+        bytes: interner.bytes,
+        capacity: interner.capacity,
+        rejected: interner.rejected,
+    }
+}
+
+/// Set the maximum number of the file names kept by the interner.
+///
+/// After the limit is reached, the file names that are not interned yet are
+/// allocated for each captured location, as if the interner were disabled.
+/// The file names already interned are kept, even if the new limit is less
+/// than the number of them. The default value is 256, and `0` disables the
+/// interning of the new file names, e.g. in a long-running process that
+/// should not leak the file names. The usage of the interner is reported by
+/// [`interner_stats`].
+///
+/// [`interner_stats`]: ./fn.interner_stats.html
+pub fn set_interner_capacity(capacity: usize) {
+    lock_interner().capacity = capacity;
+}
+
+/// The style of rendering a [`Location`].
+///
+/// [`Location`]: ./struct.Location.html
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LocationStyle {
+    /// `file:line:column`, the same as the `Display` of `Location`.
+    #[default]
+    Colon,
+
+    /// `file(line,column)`, recognized by MSVC-style tools such as Visual Studio.
+    Msvc,
+
+    /// `file:line`, without the column.
+    FileLineOnly,
+}
+
+/// A helper for rendering a [`Location`] in the specified style.
+///
+/// This value is returned from [`Location::display_style`].
+///
+/// [`Location`]: ./struct.Location.html
+/// [`Location::display_style`]: ./struct.Location.html#method.display_style
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayLocation<'a> {
+    location: &'a Location,
+    style: LocationStyle,
+    normalize_separators: bool,
+}
+
+impl DisplayLocation<'_> {
+    /// Specify whether to replace the Windows path separators (`\`) with `/`.
+    ///
+    /// The default value is `false`.
+    #[inline]
+    pub fn normalize_separators(mut self, enabled: bool) -> Self {
+        self.normalize_separators = enabled;
+        self
+    }
+}
+
+impl fmt::Display for DisplayLocation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let loc = self.location;
+        if self.normalize_separators && loc.file().contains('\\') {
+            f.write_str(&loc.file().replace('\\', "/"))?;
+        } else {
+            f.write_str(loc.file())?;
+        }
+        match self.style {
+            LocationStyle::Colon => write!(f, ":{}:{}", loc.line(), loc.column()),
+            LocationStyle::Msvc => write!(f, "({},{})", loc.line(), loc.column()),
+            LocationStyle::FileLineOnly => write!(f, ":{}", loc.line()),
+        }
+    }
+}
+
+impl Location {
+    /// Return the name of the source file as a `Path`.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        Path::new(self.file())
+    }
+
+    /// Return a helper for rendering this location in the specified style.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{Location, LocationStyle};
+    ///
+    /// let loc = Location::new(r"src\main.rs", 3, 5);
+    /// assert_eq!(loc.display_style(LocationStyle::Msvc).to_string(), r"src\main.rs(3,5)");
+    /// assert_eq!(
+    ///     loc.display_style(LocationStyle::FileLineOnly)
+    ///         .normalize_separators(true)
+    ///         .to_string(),
+    ///     "src/main.rs:3",
+    /// );
+    /// ```
+    #[inline]
+    pub fn display_style(&self, style: LocationStyle) -> DisplayLocation<'_> {
+        DisplayLocation {
+            location: self,
+            style,
+            normalize_separators: false,
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    captured::CapturedInfo,
+    classify::PanicKind,
+    snapshot::UnwindSnapshot,
+    unwind::{Location, Unwind},
+};
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// The fields of a panic rendered into the map returned from `to_map`.
+struct Fields<'a> {
+    message: Cow<'a, str>,
+    location: Option<&'a Location>,
+    thread: Option<&'a str>,
+    scope: Option<&'a str>,
+    kind: PanicKind,
+    backtrace: Option<String>,
+}
+
+impl Fields<'_> {
+    fn into_map(self) -> BTreeMap<&'static str, String> {
+        // The fingerprint is derived from the message rendered with the
+        // redactor applied, so that every form of a panic has the same one.
+        let fingerprint = fingerprint(self.location, &self.message);
+
+        let mut map = BTreeMap::new();
+        map.insert("message", self.message.into_owned());
+        map.insert(
+            "file",
+            self.location
+                .map_or_else(String::new, |loc| loc.file().to_owned()),
+        );
+        map.insert(
+            "line",
+            self.location
+                .map_or_else(String::new, |loc| loc.line().to_string()),
+        );
+        map.insert(
+            "column",
+            self.location
+                .map_or_else(String::new, |loc| loc.column().to_string()),
+        );
+        map.insert("thread", self.thread.unwrap_or_default().to_owned());
+        map.insert("scope", self.scope.unwrap_or_default().to_owned());
+        map.insert("fingerprint", format!("{:016x}", fingerprint));
+        map.insert("kind", self.kind.as_str().to_owned());
+        map.insert("backtrace", self.backtrace.unwrap_or_default());
+        map
+    }
+}
+
+/// Compute the FNV-1a hash of the panic location, or of the message if the
+/// location is unknown, which is stable across the runs and the platforms.
+pub(crate) fn fingerprint(location: Option<&Location>, message: &str) -> u64 {
+    fn hash(mut state: u64, bytes: &[u8]) -> u64 {
+        for &b in bytes {
+            state ^= u64::from(b);
+            state = state.wrapping_mul(0x0100_0000_01b3);
+        }
+        state
+    }
+
+    let state = 0xcbf2_9ce4_8422_2325;
+    match location {
+        Some(loc) => {
+            let state = hash(state, loc.file().as_bytes());
+            let state = hash(state, &[0]);
+            let state = hash(state, &loc.line().to_le_bytes());
+            hash(state, &loc.column().to_le_bytes())
+        }
+        None => hash(state, message.as_bytes()),
+    }
+}
+
+impl Unwind {
+    /// Render this panic into a map of the canonical keys and the string values,
+    /// for the tools that need the key-value access without `serde`.
+    ///
+    /// The map always contains exactly the following keys, whose names are
+    /// kept stable across the releases:
+    ///
+    /// * `message`: the panic message with the redactor applied
+    /// * `file`, `line` and `column`: the location of the panic, in the same
+    ///   form as written by the `Display` of [`Location`]
+    /// * `thread`: the name of the panicking thread
+    /// * `scope`: the label of the capture scope
+    /// * `fingerprint`: a stable hash of the panic location (or the `message`
+    ///   above if the location is unknown) as 16 hex digits
+    /// * `kind`: the name of the [`PanicKind`] returned from [`classify`]
+    /// * `backtrace`: the text of the captured stack backtrace
+    ///
+    /// The values that are not available are empty strings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use maybe_unwind::maybe_unwind;
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// let map = unwind.to_map();
+    /// assert_eq!(map["message"], "oops");
+    /// assert_eq!(map["line"], unwind.location().unwrap().line().to_string());
+    /// ```
+    ///
+    /// [`Location`]: ./struct.Location.html
+    /// [`PanicKind`]: ./enum.PanicKind.html
+    /// [`classify`]: #method.classify
+    pub fn to_map(&self) -> BTreeMap<&'static str, String> {
+        Fields {
+            message: self.message(),
+            location: self.location(),
+            thread: self.thread_name(),
+            scope: self.scope_label(),
+            kind: self.classify(),
+            backtrace: self.backtrace_text(),
+        }
+        .into_map()
+    }
+}
+
+impl CapturedInfo {
+    /// Render the captured information into a map of the canonical keys and the string values.
+    ///
+    /// See [`Unwind::to_map`] for the keys.
+    ///
+    /// [`Unwind::to_map`]: ./struct.Unwind.html#method.to_map
+    #[inline]
+    pub fn to_map(&self) -> BTreeMap<&'static str, String> {
+        self.to_map_with(true)
+    }
+
+    /// Render the map, leaving the `backtrace` empty unless `backtrace` is `true`.
+    pub(crate) fn to_map_with(&self, backtrace: bool) -> BTreeMap<&'static str, String> {
+        Fields {
+            message: self.message(),
+            location: self.location(),
+            thread: self.thread_name(),
+            scope: self.scope_label(),
+            kind: self.classify(),
+            backtrace: if backtrace {
+                self.backtrace_text()
+            } else {
+                None
+            },
+        }
+        .into_map()
+    }
+}
+
+impl UnwindSnapshot {
+    /// Render this snapshot into a map of the canonical keys and the string values.
+    ///
+    /// See [`Unwind::to_map`] for the keys.
+    ///
+    /// [`Unwind::to_map`]: ./struct.Unwind.html#method.to_map
+    pub fn to_map(&self) -> BTreeMap<&'static str, String> {
+        Fields {
+            message: Cow::Borrowed(self.message()),
+            location: self.location(),
+            thread: self.thread_name(),
+            scope: self.scope_label(),
+            kind: self.classify(),
+            backtrace: self.backtrace().map(ToOwned::to_owned),
+        }
+        .into_map()
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    display::DisplayOptions,
+    unwind::{Location, Unwind},
+};
+use std::{env, fmt::Write as _};
+
+/// The options for rendering a normalized report of the captured panic.
+///
+/// The normalized report is intended to be stable across machines and runs,
+/// e.g. for snapshot testing. Each normalization can be toggled individually.
+#[derive(Debug, Clone)]
+pub struct NormalizeOptions {
+    path_prefixes: Vec<String>,
+    redact_line_column: bool,
+    strip_addresses: bool,
+    trim_backtrace: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            path_prefixes: env::current_dir()
+                .ok()
+                .and_then(|dir| dir.to_str().map(ToOwned::to_owned))
+                .into_iter()
+                .collect(),
+            redact_line_column: true,
+            strip_addresses: true,
+            trim_backtrace: true,
+        }
+    }
+}
+
+impl NormalizeOptions {
+    /// Create a new `NormalizeOptions` with the default values.
+    ///
+    /// By default, the current working directory is stripped from
+    /// the file paths and all other normalizations are enabled.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a path prefix stripped from the file paths.
+    ///
+    /// The file paths are rendered relative to the first matching prefix.
+    pub fn path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.path_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Remove all path prefixes, including the current working directory.
+    pub fn clear_path_prefixes(mut self) -> Self {
+        self.path_prefixes.clear();
+        self
+    }
+
+    /// Specify whether to replace the line and column numbers with `LL:CC`.
+    ///
+    /// The default value is `true`.
+    pub fn redact_line_column(mut self, enabled: bool) -> Self {
+        self.redact_line_column = enabled;
+        self
+    }
+
+    /// Specify whether to strip the instruction addresses from the backtrace.
+    ///
+    /// The default value is `true`.
+    pub fn strip_addresses(mut self, enabled: bool) -> Self {
+        self.strip_addresses = enabled;
+        self
+    }
+
+    /// Specify whether to remove the frames of the panic machinery and
+    /// the runtime from the backtrace, like the short backtrace format
+    /// of the standard library.
+    ///
+    /// The default value is `true`.
+    pub fn trim_backtrace(mut self, enabled: bool) -> Self {
+        self.trim_backtrace = enabled;
+        self
+    }
+
+    fn path(&self, file: &str) -> String {
+        let file = file.replace('\\', "/");
+        if let Some(rest) = file.strip_prefix("./") {
+            return rest.to_owned();
+        }
+        for prefix in &self.path_prefixes {
+            let prefix = prefix.replace('\\', "/");
+            let prefix = prefix.trim_end_matches('/');
+            if prefix.is_empty() {
+                continue;
+            }
+            if let Some(rest) = file.strip_prefix(prefix) {
+                if rest.starts_with('/') {
+                    return rest.trim_start_matches('/').to_owned();
+                }
+            }
+        }
+        file
+    }
+
+    fn file_line_column(&self, file: &str, line: &str, column: &str) -> String {
+        if self.redact_line_column {
+            format!("{}:LL:CC", self.path(file))
+        } else {
+            format!("{}:{}:{}", self.path(file), line, column)
+        }
+    }
+
+    pub(crate) fn location(&self, location: &Location) -> String {
+        self.file_line_column(
+            location.file(),
+            &location.line().to_string(),
+            &location.column().to_string(),
+        )
+    }
+
+    #[cfg_attr(not(backtrace), allow(dead_code))]
+    pub(crate) fn backtrace(&self, text: &str) -> String {
+        let mut frames: Vec<Vec<&str>> = vec![];
+        for line in text.lines() {
+            if is_frame_header(line) || frames.is_empty() {
+                frames.push(vec![line]);
+            } else if let Some(frame) = frames.last_mut() {
+                frame.push(line);
+            }
+        }
+
+        if self.trim_backtrace {
+            let frame_contains = |frame: &Vec<&str>, pat: &str| frame[0].contains(pat);
+            if let Some(end) = frames
+                .iter()
+                .position(|frame| frame_contains(frame, "__rust_begin_short_backtrace"))
+            {
+                frames.truncate(end);
+            }
+            if let Some(start) = frames
+                .iter()
+                .rposition(|frame| frame_contains(frame, "__rust_end_short_backtrace"))
+            {
+                frames.drain(..=start);
+            }
+        }
+
+        let mut out = String::new();
+        let mut index = 0;
+        for frame in frames {
+            for line in frame {
+                if is_frame_header(line) {
+                    let (_, symbol) = line.split_at(line.find(':').unwrap_or(0) + 1);
+                    let mut symbol = symbol.trim_start();
+                    if self.strip_addresses {
+                        symbol = strip_address(symbol);
+                    }
+                    let _ = writeln!(out, "{:>4}: {}", index, symbol);
+                    index += 1;
+                } else if let Some(at) = line.trim_start().strip_prefix("at ") {
+                    let _ = writeln!(out, "             at {}", self.source_line(at));
+                } else {
+                    let _ = writeln!(out, "{}", line);
+                }
+            }
+        }
+        out
+    }
+
+    fn source_line(&self, at: &str) -> String {
+        let mut parts = at.rsplitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(column), Some(line), Some(file))
+                if is_number(column) && is_number(line) && !file.is_empty() =>
+            {
+                self.file_line_column(file, line, column)
+            }
+            _ => self.path(at),
+        }
+    }
+}
+
+fn is_number(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_frame_header(line: &str) -> bool {
+    let line = line.trim_start();
+    match line.find(':') {
+        Some(pos) => is_number(&line[..pos]),
+        None => false,
+    }
+}
+
+fn strip_address(symbol: &str) -> &str {
+    if let Some(rest) = symbol.strip_prefix("0x") {
+        let end = rest
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(rest.len());
+        return rest[end..]
+            .trim_start()
+            .trim_start_matches('-')
+            .trim_start();
+    }
+    symbol
+}
+
+impl Location {
+    /// Render this location according to the normalization options.
+    pub fn normalized_with(&self, options: &NormalizeOptions) -> String {
+        options.location(self)
+    }
+}
+
+impl Unwind {
+    /// Render the report of this panic with the default normalization options.
+    ///
+    /// The report has the same layout as the alternate `Display`
+    /// output, but the volatile parts are normalized so that it can
+    /// be compared across machines and runs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    ///
+    /// std::panic::set_hook(Box::new(|info| {
+    ///     maybe_unwind::capture_panic_info(info);
+    /// }));
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// assert!(unwind.normalized().contains(":LL:CC: oops\n"));
+    /// ```
+    pub fn normalized(&self) -> String {
+        self.normalized_with(&NormalizeOptions::default())
+    }
+
+    /// Render the report of this panic with the specified normalization options.
+    pub fn normalized_with(&self, options: &NormalizeOptions) -> String {
+        let mut out = String::new();
+        let _ = self.write_report(&mut out, Some(options), &DisplayOptions::default());
+        out
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    captured::CapturedInfo,
+    context::{self, Caught},
+    options::{BacktraceMode, CaptureOptions},
+    payload,
+};
+use std::{
+    cell::Cell,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+type Observer = dyn Fn(&CapturedInfo) + Send + Sync + 'static;
+
+static OBSERVERS: RwLock<Vec<(ObserverId, Arc<Observer>)>> = RwLock::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static NOTIFYING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The identifier of an observer registered by [`add_observer`].
+///
+/// [`add_observer`]: ./fn.add_observer.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u64);
+
+/// Register a function called with every panic information captured by `maybe_unwind`.
+///
+/// The observers are process-global, and are called in the order of registration
+/// on the thread where the panic occurred, after the panic was caught by the
+/// capture scope. A panic raised by an observer is caught and recorded as a note
+/// in the captured information (see [`CapturedInfo::notes`]) instead of being
+/// propagated. The observers are not called for the panics occurred while
+/// running the observers themselves.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{add_observer, maybe_unwind, remove_observer};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static COUNT: AtomicUsize = AtomicUsize::new(0);
+///
+/// maybe_unwind::set_hook();
+/// let id = add_observer(|_info| {
+///     COUNT.fetch_add(1, Ordering::SeqCst);
+/// });
+///
+/// let _ = maybe_unwind(|| panic!("oops"));
+/// assert!(COUNT.load(Ordering::SeqCst) >= 1);
+///
+/// assert!(remove_observer(id));
+/// ```
+///
+/// [`CapturedInfo::notes`]: ./struct.CapturedInfo.html#method.notes
+pub fn add_observer<F>(f: F) -> ObserverId
+where
+    F: Fn(&CapturedInfo) + Send + Sync + 'static,
+{
+    let id = ObserverId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    OBSERVERS
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .push((id, Arc::new(f)));
+    id
+}
+
+/// Unregister the observer registered by [`add_observer`].
+///
+/// Returns `false` if the observer has already been removed.
+///
+/// [`add_observer`]: ./fn.add_observer.html
+pub fn remove_observer(id: ObserverId) -> bool {
+    let mut observers = OBSERVERS.write().unwrap_or_else(|err| err.into_inner());
+    let len = observers.len();
+    observers.retain(|(observer_id, _)| *observer_id != id);
+    observers.len() != len
+}
+
+pub(crate) fn notify(captured: &mut CapturedInfo) {
+    if NOTIFYING.with(|notifying| notifying.replace(true)) {
+        return;
+    }
+    let _guard = Guard;
+
+    let observers: Vec<Arc<Observer>> = OBSERVERS
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+        .map(|(_, observer)| Arc::clone(observer))
+        .collect();
+    if observers.is_empty() {
+        return;
+    }
+
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Never);
+    for observer in observers {
+        let res = context::scope(&options, AssertUnwindSafe(|| observer(captured)));
+        if let Err(Caught {
+            payload,
+            captured: inner,
+            ..
+        }) = res
+        {
+            let message = payload::render(&*payload);
+            let note = match inner.as_ref().and_then(|inner| inner.location.as_ref()) {
+                Some(loc) => format!("an observer panicked at {}: {}", loc, message),
+                None => format!("an observer panicked: {}", message),
+            };
+            captured.notes.push(note);
+        }
+    }
+}
+
+struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        NOTIFYING.with(|notifying| notifying.set(false));
+    }
+}
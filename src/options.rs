@@ -0,0 +1,347 @@
+use crate::{
+    payload::PayloadDescriber,
+    resolver::{ResolverHandle, ResolverRef},
+};
+use std::any::Any;
+
+/// The options for capturing the panic information in a single scope.
+///
+/// The options are carried by the capture scope established by
+/// [`maybe_unwind_with`], and consulted by [`capture_panic_info`]
+/// when a panic occurs inside the scope. Nested scopes use their
+/// own options independently.
+///
+/// [`maybe_unwind_with`]: ./fn.maybe_unwind_with.html
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+#[derive(Debug, Clone, Default)]
+pub struct CaptureOptions {
+    pub(crate) backtrace: BacktraceMode,
+    pub(crate) max_backtrace_frames: Option<usize>,
+    pub(crate) max_message_len: Option<usize>,
+    pub(crate) describer: Option<PayloadDescriber>,
+    pub(crate) hook_message: bool,
+    pub(crate) max_captures: Option<usize>,
+    pub(crate) env_vars: Vec<String>,
+    pub(crate) scope_backtrace: bool,
+    pub(crate) resolver: Option<ResolverRef>,
+    pub(crate) location_policy: LocationPolicy,
+    pub(crate) skip_message: bool,
+    #[cfg(feature = "tracing-error")]
+    pub(crate) skip_span_trace: bool,
+}
+
+impl CaptureOptions {
+    /// Create a new `CaptureOptions` with the default values.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify whether the stack backtrace is captured or not.
+    ///
+    /// The default value is `BacktraceMode::Auto`.
+    #[inline]
+    pub fn backtrace(mut self, mode: BacktraceMode) -> Self {
+        self.backtrace = mode;
+        self
+    }
+
+    /// Specify the maximum number of the frames kept in the stack backtrace.
+    ///
+    /// If the captured backtrace has more frames than this value, it is
+    /// rendered into a string by the panic hook, the frames after the limit
+    /// are replaced with a marker like `… 42 frames omitted`, and the original
+    /// backtrace is dropped to bound the memory usage. In that case,
+    /// [`Unwind::backtrace`] and [`Unwind::backtrace_frames`] return `None`,
+    /// while the report still contains the truncated backtrace.
+    ///
+    /// The backtrace is not truncated by default.
+    ///
+    /// [`Unwind::backtrace`]: ./struct.Unwind.html#method.backtrace
+    /// [`Unwind::backtrace_frames`]: ./struct.Unwind.html#method.backtrace_frames
+    #[inline]
+    pub fn max_backtrace_frames(mut self, max: usize) -> Self {
+        self.max_backtrace_frames = Some(max);
+        self
+    }
+
+    /// Specify the background resolver that resolves the symbols of the stack backtrace.
+    ///
+    /// The backtrace captured by the panic hook is handed to the worker
+    /// thread of the resolver unresolved, and its text is retrieved through
+    /// [`Unwind::resolved_backtrace`]. In that case, [`Unwind::backtrace`]
+    /// returns `None`, while the report still contains the backtrace, waiting
+    /// for the worker if necessary. The truncation by [`max_backtrace_frames`]
+    /// is also done by the worker.
+    ///
+    /// The options do not keep the resolver running. After the handle is
+    /// dropped, the backtraces are kept unresolved as usual.
+    ///
+    /// [`Unwind::resolved_backtrace`]: ./struct.Unwind.html#method.resolved_backtrace
+    /// [`Unwind::backtrace`]: ./struct.Unwind.html#method.backtrace
+    /// [`max_backtrace_frames`]: #method.max_backtrace_frames
+    #[inline]
+    pub fn resolve_via(mut self, resolver: &ResolverHandle) -> Self {
+        self.resolver = Some(resolver.to_ref());
+        self
+    }
+
+    /// Specify whether to capture the `SpanTrace` of the panicking thread.
+    ///
+    /// The span trace records the `tracing` spans entered at the moment of
+    /// the panic, which often tells more than the stack backtrace in the
+    /// asynchronous code. It is only captured if the subscriber is configured
+    /// with `tracing_error::ErrorLayer`, and is retrieved through
+    /// [`Unwind::span_trace`].
+    ///
+    /// The default value is `true`.
+    ///
+    /// [`Unwind::span_trace`]: ./struct.Unwind.html#method.span_trace
+    #[cfg(feature = "tracing-error")]
+    #[cfg_attr(docs, doc(cfg(feature = "tracing-error")))]
+    #[inline]
+    pub fn capture_span_trace(mut self, enabled: bool) -> Self {
+        self.skip_span_trace = !enabled;
+        self
+    }
+
+    /// Specify whether to capture the stack backtrace at the entry of this scope.
+    ///
+    /// The backtrace tells how the scope was reached, which the location of
+    /// [`Unwind::caller`] covers only partially, e.g. when the scope is entered
+    /// by a helper shared by many tests. It is available from
+    /// [`Unwind::scope_backtrace`], and is written into the report as the
+    /// `scope established here:` section. The symbols are resolved lazily,
+    /// but walking the stack adds a cost to every entry of the scope, so it
+    /// is disabled by default. It can also be enabled for all scopes by
+    /// [`HookOptions::capture_scope_backtrace`].
+    ///
+    /// As with the panic backtrace, the backtrace is captured only if the
+    /// backtrace is supported.
+    ///
+    /// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+    /// [`Unwind::scope_backtrace`]: ./struct.Unwind.html#method.scope_backtrace
+    /// [`HookOptions::capture_scope_backtrace`]: ./struct.HookOptions.html#method.capture_scope_backtrace
+    #[inline]
+    pub fn capture_scope_backtrace(mut self, enabled: bool) -> Self {
+        self.scope_backtrace = enabled;
+        self
+    }
+
+    /// Specify the maximum number of the panics captured in this scope.
+    ///
+    /// The panics caught inside of the scope, e.g. by `catch_unwind` in a
+    /// dependency, are also captured by the panic hook, and capturing the
+    /// backtrace of every one of them can be expensive under a storm of
+    /// panics. After the limit is reached, the panic hook only counts the
+    /// panics instead of capturing them, and the count is available from
+    /// [`Unwind::suppressed_capture_count`]. The first panic in the scope is
+    /// always captured, so values less than 1 are treated as 1.
+    ///
+    /// Note that if the panic escaping the scope is not captured, the
+    /// returned [`Unwind`] carries the information of the last captured one.
+    ///
+    /// The number of the captured panics is not limited by default.
+    ///
+    /// [`Unwind`]: ./struct.Unwind.html
+    /// [`Unwind::suppressed_capture_count`]: ./struct.Unwind.html#method.suppressed_capture_count
+    #[inline]
+    pub fn max_captures_per_scope(mut self, max: usize) -> Self {
+        self.max_captures = Some(max.max(1));
+        self
+    }
+
+    /// Specify whether to keep the panic information rendered by the panic
+    /// hook as it is, i.e. the `Display` output of `PanicHookInfo`.
+    ///
+    /// The text is available from [`Unwind::hook_message`], and is used by
+    /// [`DisplayOptions::std_style`] for reproducing the output of the default
+    /// panic hook. It is disabled by default to avoid the allocation.
+    ///
+    /// [`Unwind::hook_message`]: ./struct.Unwind.html#method.hook_message
+    /// [`DisplayOptions::std_style`]: ./struct.DisplayOptions.html#method.std_style
+    #[inline]
+    pub fn hook_message(mut self, enabled: bool) -> Self {
+        self.hook_message = enabled;
+        self
+    }
+
+    /// Specify whether to copy the panic message into the captured information.
+    ///
+    /// The message is usually copied by the panic hook, which allocates unless
+    /// the payload is a `&'static str`. If only the location of the panics is
+    /// needed, e.g. in a suite of the negative tests raising a lot of panics,
+    /// disabling it together with the backtrace by [`BacktraceMode::Never`]
+    /// lets the panic hook capture the panic without any heap allocation.
+    /// [`Unwind::message`] is still available from the payload, while
+    /// [`CapturedInfo::message`] returns an empty string.
+    ///
+    /// The message is also skipped in all scopes if disabled by
+    /// [`HookOptions::capture_message`]. The default value is `true`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind_with, BacktraceMode, CaptureOptions};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let options = CaptureOptions::new()
+    ///     .capture_message(false)
+    ///     .backtrace(BacktraceMode::Never);
+    /// let unwind = maybe_unwind_with(options, || panic!("oops: {}", 42)).unwrap_err();
+    /// assert!(unwind.location().is_some());
+    /// assert_eq!(unwind.message(), "oops: 42");
+    /// assert_eq!(unwind.captured().unwrap().message(), "");
+    /// ```
+    ///
+    /// [`BacktraceMode::Never`]: ./enum.BacktraceMode.html#variant.Never
+    /// [`Unwind::message`]: ./struct.Unwind.html#method.message
+    /// [`CapturedInfo::message`]: ./struct.CapturedInfo.html#method.message
+    /// [`HookOptions::capture_message`]: ./struct.HookOptions.html#method.capture_message
+    #[inline]
+    pub fn capture_message(mut self, enabled: bool) -> Self {
+        self.skip_message = !enabled;
+        self
+    }
+
+    /// Specify the maximum number of the characters of the panic message
+    /// rendered into the report.
+    ///
+    /// The longer messages are truncated with an ellipsis when the report
+    /// is rendered, i.e. by the alternate `Display` of `Unwind`. The panic
+    /// payload and [`Unwind::message`] are kept intact.
+    ///
+    /// The message is not truncated by default.
+    ///
+    /// [`Unwind::message`]: ./struct.Unwind.html#method.message
+    #[inline]
+    pub fn max_message_len(mut self, max: usize) -> Self {
+        self.max_message_len = Some(max);
+        self
+    }
+
+    /// Specify the environment variables recorded at the moment of the capture.
+    ///
+    /// The values of the listed variables are available from [`Unwind::env`]
+    /// and are written into the JSON of [`UnwindSnapshot`], which helps to
+    /// reproduce the failures depending on the configuration, e.g. `RUST_LOG`
+    /// or the random seeds. The variables that are not set are recorded as
+    /// `None`, so that they can be told apart from the empty ones. As with the
+    /// panic message, the values are redacted by the redactor registered by
+    /// [`set_redactor`] when they are retrieved.
+    ///
+    /// No variables are recorded by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind_with, CaptureOptions};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let options = CaptureOptions::new().snapshot_env(&["RUST_LOG", "SEED"]);
+    /// let unwind = maybe_unwind_with(options, || panic!("flaky")).unwrap_err();
+    /// for (name, value) in unwind.env().iter() {
+    ///     println!("{} = {:?}", name, value);
+    /// }
+    /// ```
+    ///
+    /// [`Unwind::env`]: ./struct.Unwind.html#method.env
+    /// [`UnwindSnapshot`]: ./struct.UnwindSnapshot.html
+    /// [`set_redactor`]: ./fn.set_redactor.html
+    pub fn snapshot_env(mut self, names: &[&str]) -> Self {
+        self.env_vars = names.iter().map(|&name| name.to_owned()).collect();
+        self
+    }
+
+    /// Specify how [`Unwind::best_location`] picks the location among the
+    /// panics captured in this scope.
+    ///
+    /// The default value is `LocationPolicy::MatchingPayload`.
+    ///
+    /// [`Unwind::best_location`]: ./struct.Unwind.html#method.best_location
+    #[inline]
+    pub fn location_policy(mut self, policy: LocationPolicy) -> Self {
+        self.location_policy = policy;
+        self
+    }
+
+    /// Specify the function that describes the panic payloads of the unknown types in this scope.
+    ///
+    /// The describer overrides the one registered by [`set_payload_describer`]
+    /// while the scope is active, including the nested scopes without their own
+    /// describer. See [`set_payload_describer`] for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind_with, CaptureOptions};
+    /// use std::panic::panic_any;
+    ///
+    /// struct Opaque(u32);
+    ///
+    /// let options = CaptureOptions::new().payload_describer(|payload| {
+    ///     payload
+    ///         .downcast_ref::<Opaque>()
+    ///         .map(|opaque| format!("opaque payload #{}", opaque.0))
+    /// });
+    /// let unwind = maybe_unwind_with(options, || panic_any(Opaque(7))).unwrap_err();
+    /// assert_eq!(unwind.payload_str(), "opaque payload #7");
+    /// ```
+    ///
+    /// [`set_payload_describer`]: ./fn.set_payload_describer.html
+    pub fn payload_describer<F>(mut self, describer: F) -> Self
+    where
+        F: Fn(&(dyn Any + Send)) -> Option<String> + Send + Sync + 'static,
+    {
+        self.describer = Some(PayloadDescriber::new(describer));
+        self
+    }
+}
+
+/// The strategy for capturing the stack backtrace.
+///
+/// The backtrace is captured only if the backtrace is supported
+/// by the compiler, regardless of this value.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BacktraceMode {
+    /// Capture the backtrace if it is enabled by the environment
+    /// variables, i.e. `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`.
+    #[default]
+    Auto,
+
+    /// Always capture the backtrace regardless of the environment variables.
+    Always,
+
+    /// Never capture the backtrace.
+    Never,
+}
+
+/// The policy of [`Unwind::best_location`] for picking the location among
+/// the panics captured in a scope.
+///
+/// The panic hook is called for every panic inside of the scope, including
+/// the ones caught by `catch_unwind` in the middle, e.g. by a middleware that
+/// inspects the payload and panics again with the original message. Then the
+/// location captured last is the one of the middleware, rather than the
+/// original panic site.
+///
+/// [`Unwind::best_location`]: ./struct.Unwind.html#method.best_location
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LocationPolicy {
+    /// Prefer the earliest panic whose message is contained in the final
+    /// payload, falling back to the earliest panic.
+    #[default]
+    MatchingPayload,
+
+    /// Use the earliest panic captured in the scope.
+    Earliest,
+
+    /// Use the panic captured last, as [`Unwind::location`] does.
+    ///
+    /// [`Unwind::location`]: ./struct.Unwind.html#method.location
+    Last,
+}
@@ -0,0 +1,28 @@
+use crate::unwind::Unwind;
+use std::sync::{Mutex, MutexGuard};
+
+static ORPHANS: Mutex<Vec<Unwind>> = Mutex::new(Vec::new());
+
+fn lock_orphans() -> MutexGuard<'static, Vec<Unwind>> {
+    ORPHANS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+pub(crate) fn push(unwind: Unwind) {
+    lock_orphans().push(unwind);
+}
+
+/// Take the unwinding panics that could not be returned to any caller.
+///
+/// Some panics are captured in places where there is no caller waiting
+/// for the result, e.g. while dropping the inner future of [`MaybeUnwind`]
+/// or running the teardown closures of [`DropGuard`].
+/// Instead of unwinding through the executor, such panics are stored in
+/// a process-global list and can be retrieved with this function.
+///
+/// The stored values are removed from the list by calling this function.
+///
+/// [`MaybeUnwind`]: ./struct.MaybeUnwind.html
+/// [`DropGuard`]: ./struct.DropGuard.html
+pub fn take_orphaned_unwinds() -> Vec<Unwind> {
+    std::mem::take(&mut *lock_orphans())
+}
@@ -0,0 +1,130 @@
+use crate::{
+    captured::{CaptureError, CaptureStatus},
+    context,
+};
+use std::{borrow::Cow, cell::RefCell};
+
+thread_local! {
+    static LAST: RefCell<Option<HookOutcome>> = const { RefCell::new(None) };
+}
+
+/// The outcome of the last call of [`capture_panic_info`] on the current thread.
+///
+/// This value is returned from [`last_hook_outcome`].
+///
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+/// [`last_hook_outcome`]: ./fn.last_hook_outcome.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookOutcome {
+    status: Result<CaptureStatus, CaptureError>,
+    scope_label: Option<Cow<'static, str>>,
+    scope_depth: usize,
+}
+
+impl Default for HookOutcome {
+    fn default() -> Self {
+        Self {
+            status: Ok(CaptureStatus::NotInScope),
+            scope_label: None,
+            scope_depth: 0,
+        }
+    }
+}
+
+impl HookOutcome {
+    /// Return the result of the capture, as returned from [`try_capture_panic_info`].
+    ///
+    /// [`try_capture_panic_info`]: ./fn.try_capture_panic_info.html
+    #[inline]
+    pub fn status(&self) -> Result<CaptureStatus, CaptureError> {
+        self.status
+    }
+
+    /// Return whether the panic was handled by the capture scope, i.e. it
+    /// should not be reported by the panic hook any further.
+    ///
+    /// This is the value returned from [`capture_panic_info`].
+    ///
+    /// [`capture_panic_info`]: ./fn.capture_panic_info.html
+    #[inline]
+    pub fn is_handled(&self) -> bool {
+        self.status.map_or(false, CaptureStatus::is_handled)
+    }
+
+    /// Return the label of the innermost labeled scope at the time of the capture.
+    #[inline]
+    pub fn scope_label(&self) -> Option<&str> {
+        self.scope_label.as_deref()
+    }
+
+    /// Return the nesting depth of the capture scopes at the time of the
+    /// capture, or zero if the panic occurred outside of them.
+    #[inline]
+    pub fn scope_depth(&self) -> usize {
+        self.scope_depth
+    }
+}
+
+/// Return the outcome of the last call of [`capture_panic_info`] on the current thread.
+///
+/// The outcome is available to the rest of the panic hook right after the
+/// capture, so that the stages of a panic hook, such as a logger placed
+/// after the capture, can tell whether the panic is already owned by a
+/// capture scope without passing the flag around. The outcome is reset
+/// when the capture scope catches the panic, and the default value, which
+/// reports [`CaptureStatus::NotInScope`], is returned if the capture has
+/// not been called since then.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{capture_panic_info, last_hook_outcome, maybe_unwind_named};
+/// use std::panic;
+///
+/// panic::set_hook(Box::new(|info| {
+///     capture_panic_info(info);
+///
+///     // The second stage, e.g. writing the log file.
+///     let outcome = last_hook_outcome();
+///     if outcome.is_handled() {
+///         eprintln!("handled by harness ({:?}): {}", outcome.scope_label(), info);
+///     } else {
+///         eprintln!("{}", info);
+///     }
+/// }));
+///
+/// let _ = maybe_unwind_named("case", || panic!("oops"));
+/// assert!(!last_hook_outcome().is_handled());
+/// ```
+///
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+/// [`CaptureStatus::NotInScope`]: ./enum.CaptureStatus.html#variant.NotInScope
+pub fn last_hook_outcome() -> HookOutcome {
+    LAST.try_with(|last| last.try_borrow().ok().and_then(|last| last.clone()))
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Record the outcome of the capture of the current panic.
+pub(crate) fn record(status: &Result<CaptureStatus, CaptureError>) {
+    let outcome = HookOutcome {
+        status: *status,
+        scope_label: context::scope_label(),
+        scope_depth: context::scope_depth(),
+    };
+    set(Some(outcome));
+}
+
+/// Reset the outcome once the panic is caught.
+pub(crate) fn clear() {
+    set(None);
+}
+
+fn set(outcome: Option<HookOutcome>) {
+    let _ = LAST.try_with(|last| {
+        if let Ok(mut last) = last.try_borrow_mut() {
+            *last = outcome;
+        }
+    });
+}
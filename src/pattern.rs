@@ -0,0 +1,358 @@
+use crate::{classify::PanicKind, expect::PayloadPattern, unwind::Unwind};
+use std::{error, fmt, ops::RangeInclusive, str::FromStr};
+
+/// The expected properties of a panic, for comparing the captured panics
+/// against the golden files.
+///
+/// A pattern specifies any subset of the message, the suffix of the source
+/// file, the line (or a range of lines), the [`PanicKind`] and the context
+/// frames, and the criteria that are not specified are ignored. The panics
+/// are compared by [`Unwind::matches`], and [`Unwind::explain_mismatch`]
+/// tells which of the criteria failed.
+///
+/// A pattern can also be parsed from the compact syntax intended for the
+/// fixture files, see the implementation of `FromStr`.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, PanicKind, UnwindPattern};
+///
+/// maybe_unwind::set_hook();
+///
+/// let unwind = maybe_unwind(|| None::<i32>.unwrap()).unwrap_err();
+/// let pattern = UnwindPattern::new()
+///     .file_suffix(file!())
+///     .kind(PanicKind::Unwrap)
+///     .message("`None`");
+/// assert!(unwind.matches(&pattern));
+///
+/// let pattern = UnwindPattern::new().kind(PanicKind::Assertion);
+/// assert_eq!(
+///     unwind.explain_mismatch(&pattern),
+///     "kind: expected assertion, found unwrap",
+/// );
+/// ```
+///
+/// [`PanicKind`]: ./enum.PanicKind.html
+/// [`Unwind::matches`]: ./struct.Unwind.html#method.matches
+/// [`Unwind::explain_mismatch`]: ./struct.Unwind.html#method.explain_mismatch
+#[derive(Debug, Clone, Default)]
+pub struct UnwindPattern {
+    message: Option<PayloadPattern>,
+    file_suffix: Option<String>,
+    lines: Option<RangeInclusive<u32>>,
+    kind: Option<PanicKind>,
+    context_frames: Vec<String>,
+}
+
+impl UnwindPattern {
+    /// Create a pattern that matches any panic.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the pattern of the panic message.
+    ///
+    /// The message is compared after the redactor is applied, as returned
+    /// from [`Unwind::message`]. A string slice matches the messages
+    /// containing it, and [`PayloadPattern::exact`] matches the message
+    /// exactly.
+    ///
+    /// [`Unwind::message`]: ./struct.Unwind.html#method.message
+    /// [`PayloadPattern::exact`]: ./struct.PayloadPattern.html#method.exact
+    pub fn message(mut self, pattern: impl Into<PayloadPattern>) -> Self {
+        self.message = Some(pattern.into());
+        self
+    }
+
+    /// Specify the trailing path components of the file where the panic occurred.
+    ///
+    /// The suffix matches the whole components only, e.g. `"lib.rs"` matches
+    /// `src/lib.rs` but not `src/mylib.rs`, and the path separators `/` and
+    /// `\` are not distinguished.
+    pub fn file_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.file_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Specify the line where the panic occurred.
+    #[inline]
+    pub fn line(self, line: u32) -> Self {
+        self.line_range(line..=line)
+    }
+
+    /// Specify the inclusive range of the lines where the panic is allowed to occur.
+    pub fn line_range(mut self, lines: RangeInclusive<u32>) -> Self {
+        self.lines = Some(lines);
+        self
+    }
+
+    /// Specify the kind of the panic returned from [`Unwind::classify`].
+    ///
+    /// [`Unwind::classify`]: ./struct.Unwind.html#method.classify
+    pub fn kind(mut self, kind: PanicKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Add a substring that one of the context frames must contain.
+    ///
+    /// This method can be called multiple times, and each substring is
+    /// searched in the frames independently.
+    pub fn context_frame(mut self, substring: impl Into<String>) -> Self {
+        self.context_frames.push(substring.into());
+        self
+    }
+
+    /// Return the descriptions of the criteria that `unwind` fails to satisfy.
+    fn mismatches(&self, unwind: &Unwind) -> Vec<String> {
+        let mut mismatches = vec![];
+
+        if let Some(pattern) = &self.message {
+            let message = unwind.message();
+            if !pattern.matches(&message) {
+                mismatches.push(format!(
+                    "message: expected {}, found {:?}",
+                    pattern, message
+                ));
+            }
+        }
+
+        let file = unwind.location().map(|loc| loc.file());
+        if let Some(suffix) = &self.file_suffix {
+            match file {
+                Some(file) if path_ends_with(file, suffix) => (),
+                Some(file) => mismatches.push(format!(
+                    "file: expected a path ending with {:?}, found {:?}",
+                    suffix, file
+                )),
+                None => mismatches.push(format!(
+                    "file: expected a path ending with {:?}, found no location",
+                    suffix
+                )),
+            }
+        }
+
+        if let Some(lines) = &self.lines {
+            let expected = if lines.start() == lines.end() {
+                lines.start().to_string()
+            } else {
+                format!("a line in {}..={}", lines.start(), lines.end())
+            };
+            match unwind.location().map(|loc| loc.line()) {
+                Some(line) if lines.contains(&line) => (),
+                Some(line) => {
+                    mismatches.push(format!("line: expected {}, found {}", expected, line))
+                }
+                None => mismatches.push(format!("line: expected {}, found no location", expected)),
+            }
+        }
+
+        if let Some(kind) = self.kind {
+            let found = unwind.classify();
+            if found != kind {
+                mismatches.push(format!("kind: expected {}, found {}", kind, found));
+            }
+        }
+
+        let frames = unwind.context_frames();
+        for substring in &self.context_frames {
+            if !frames
+                .iter()
+                .any(|frame| frame.contains(substring.as_str()))
+            {
+                mismatches.push(format!(
+                    "context frame: expected a frame containing {:?}, found {:?}",
+                    substring, frames
+                ));
+            }
+        }
+
+        mismatches
+    }
+}
+
+/// Return whether the trailing components of `path` are equal to `suffix`.
+fn path_ends_with(path: &str, suffix: &str) -> bool {
+    let is_sep = |c: char| c == '/' || c == '\\';
+    let mut path = path.rsplit(is_sep);
+    suffix
+        .rsplit(is_sep)
+        .all(|component| path.next() == Some(component))
+}
+
+/// Parse the compact syntax for the fixture files.
+///
+/// The pattern consists of the tokens separated by whitespace, all of which
+/// are optional:
+///
+/// * `<file>[:<line>[-<line>]]`: the suffix of the file and the line, or the
+///   inclusive range of the lines, e.g. `src/lib.rs:12` or `lib.rs:10-20`.
+///   This must be the first token.
+/// * `#<kind>`: the kind of the panic in the form of [`PanicKind::as_str`],
+///   e.g. `#unwrap`.
+/// * `@<substring>`: a substring of a context frame, without whitespace.
+/// * `~<substring>` or `=<message>`: a substring of the message, or the
+///   whole message. This must be the last token, and takes the rest of the
+///   input including the whitespace.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, with_context, UnwindPattern};
+///
+/// maybe_unwind::set_hook();
+///
+/// let unwind = maybe_unwind(|| {
+///     with_context("loading config", || panic!("missing key: port"))
+/// })
+/// .unwrap_err();
+///
+/// let pattern: UnwindPattern = "#explicit @config ~missing key".parse().unwrap();
+/// assert!(unwind.matches(&pattern));
+///
+/// let pattern: UnwindPattern = "=missing key: host".parse().unwrap();
+/// assert!(!unwind.matches(&pattern));
+///
+/// assert!("lib.rs:20-10".parse::<UnwindPattern>().is_err());
+/// ```
+///
+/// [`PanicKind::as_str`]: ./enum.PanicKind.html#method.as_str
+impl FromStr for UnwindPattern {
+    type Err = ParsePatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pattern = UnwindPattern::new();
+        let mut rest = s.trim_start();
+        let mut first = true;
+        while !rest.is_empty() {
+            let (token, remaining) = match rest.find(char::is_whitespace) {
+                Some(end) => (&rest[..end], rest[end..].trim_start()),
+                None => (rest, ""),
+            };
+            if let Some(substring) = rest.strip_prefix('~') {
+                pattern = pattern.message(PayloadPattern::contains(substring.trim_end()));
+                break;
+            } else if let Some(message) = rest.strip_prefix('=') {
+                pattern = pattern.message(PayloadPattern::exact(message.trim_end()));
+                break;
+            } else if let Some(name) = token.strip_prefix('#') {
+                let kind = PanicKind::ALL
+                    .iter()
+                    .copied()
+                    .find(|kind| kind.as_str() == name)
+                    .ok_or_else(|| ParsePatternError::new(format!("unknown kind {:?}", name)))?;
+                pattern = pattern.kind(kind);
+            } else if let Some(substring) = token.strip_prefix('@') {
+                pattern = pattern.context_frame(substring);
+            } else if first {
+                pattern = parse_location(pattern, token)?;
+            } else {
+                return Err(ParsePatternError::new(format!(
+                    "unexpected token {:?}",
+                    token
+                )));
+            }
+            first = false;
+            rest = remaining;
+        }
+        Ok(pattern)
+    }
+}
+
+fn parse_location(pattern: UnwindPattern, token: &str) -> Result<UnwindPattern, ParsePatternError> {
+    let (file, lines) = match token.rsplit_once(':') {
+        Some((file, lines)) => (file, Some(lines)),
+        None => (token, None),
+    };
+    if file.is_empty() {
+        return Err(ParsePatternError::new(format!(
+            "missing file in {:?}",
+            token
+        )));
+    }
+    let pattern = pattern.file_suffix(file);
+    let lines = match lines {
+        Some(lines) => lines,
+        None => return Ok(pattern),
+    };
+
+    let parse_line = |line: &str| {
+        line.parse::<u32>()
+            .map_err(|_| ParsePatternError::new(format!("invalid line {:?}", line)))
+    };
+    match lines.split_once('-') {
+        Some((start, end)) => {
+            let (start, end) = (parse_line(start)?, parse_line(end)?);
+            if start > end {
+                return Err(ParsePatternError::new(format!(
+                    "empty line range {:?}",
+                    lines
+                )));
+            }
+            Ok(pattern.line_range(start..=end))
+        }
+        None => Ok(pattern.line(parse_line(lines)?)),
+    }
+}
+
+/// The error type returned from the `FromStr` implementation of [`UnwindPattern`].
+///
+/// [`UnwindPattern`]: ./struct.UnwindPattern.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePatternError {
+    message: String,
+}
+
+impl ParsePatternError {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid unwind pattern: {}", self.message)
+    }
+}
+
+impl error::Error for ParsePatternError {}
+
+impl Unwind {
+    /// Return whether this panic satisfies all of the criteria specified by `expected`.
+    ///
+    /// See [`UnwindPattern`] for details.
+    ///
+    /// [`UnwindPattern`]: ./struct.UnwindPattern.html
+    #[inline]
+    pub fn matches(&self, expected: &UnwindPattern) -> bool {
+        expected.mismatches(self).is_empty()
+    }
+
+    /// Describe the criteria of `expected` that this panic fails to satisfy,
+    /// one per line.
+    ///
+    /// The description is empty if the panic matches the pattern. Each line
+    /// is prefixed with the name of the criterion, i.e. `message`, `file`,
+    /// `line`, `kind` or `context frame`, followed by the expected and the
+    /// actual values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, UnwindPattern};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// let pattern: UnwindPattern = "main.rs ~failure".parse().unwrap();
+    /// let explanation = unwind.explain_mismatch(&pattern);
+    /// assert_eq!(explanation.lines().count(), 2);
+    /// assert!(explanation.starts_with("message: expected containing \"failure\", found \"oops\""));
+    /// ```
+    pub fn explain_mismatch(&self, expected: &UnwindPattern) -> String {
+        expected.mismatches(self).join("\n")
+    }
+}
@@ -0,0 +1,375 @@
+use crate::unwind::{self, Resumed};
+use std::{
+    any::{Any, TypeId},
+    borrow::Cow,
+    cell::RefCell,
+    error::Error,
+    fmt,
+    panic::{RefUnwindSafe, UnwindSafe},
+    sync::{Arc, RwLock},
+};
+
+type Formatter = dyn Fn(&(dyn Any + Send)) -> Option<String> + Send + Sync + 'static;
+
+type ErrorExtractor = dyn for<'a> Fn(&'a (dyn Any + Send)) -> Option<&'a (dyn Error + 'static)>
+    + Send
+    + Sync
+    + 'static;
+
+/// The functions registered for a payload type.
+enum Registered {
+    Formatter(Arc<Formatter>),
+    Error(Arc<ErrorExtractor>),
+}
+
+static FORMATTERS: RwLock<Vec<(TypeId, Registered)>> = RwLock::new(Vec::new());
+
+type Describer = dyn Fn(&(dyn Any + Send)) -> Option<String> + Send + Sync + 'static;
+
+static DESCRIBER: RwLock<Option<Arc<Describer>>> = RwLock::new(None);
+
+thread_local! {
+    /// The describer of the innermost capture scope that has one.
+    static SCOPE_DESCRIBER: RefCell<Option<PayloadDescriber>> = const { RefCell::new(None) };
+}
+
+/// The payload describer attached to a capture scope by `CaptureOptions::payload_describer`.
+#[derive(Clone)]
+pub(crate) struct PayloadDescriber(Arc<Describer>);
+
+impl PayloadDescriber {
+    pub(crate) fn new<F>(describer: F) -> Self
+    where
+        F: Fn(&(dyn Any + Send)) -> Option<String> + Send + Sync + 'static,
+    {
+        Self(Arc::new(describer))
+    }
+}
+
+// The describer is `Sync`, so its interior mutability, if any, is guarded against
+// the concurrent access anyway. This keeps `CaptureOptions` unwind-safe.
+impl UnwindSafe for PayloadDescriber {}
+impl RefUnwindSafe for PayloadDescriber {}
+
+impl fmt::Debug for PayloadDescriber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PayloadDescriber { .. }")
+    }
+}
+
+/// Register a function that renders the panic payload of the specified type.
+///
+/// The formatter is used when the payload is neither `&str` nor `String`,
+/// e.g. a value passed to `std::panic::panic_any`, whenever the panic message
+/// is rendered, i.e. by [`Unwind::message`], the `Display` implementation of
+/// `Unwind` and the message captured by the panic hook. The raw payload string
+/// returned by [`Unwind::payload_str`] is not affected.
+///
+/// The formatters are process-global, and registering a formatter for the same
+/// type replaces the previous one. Since the formatter is also called inside
+/// the panic hook, a panic in the formatter aborts the process.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, register_payload_formatter};
+/// use std::panic::panic_any;
+///
+/// struct AssertionFailed {
+///     expected: i32,
+///     actual: i32,
+/// }
+///
+/// register_payload_formatter(|payload: &AssertionFailed| {
+///     format!("expected {}, got {}", payload.expected, payload.actual)
+/// });
+///
+/// let unwind = maybe_unwind(|| {
+///     panic_any(AssertionFailed { expected: 1, actual: 2 });
+/// })
+/// .unwrap_err();
+/// assert_eq!(unwind.to_string(), "expected 1, got 2");
+/// ```
+///
+/// [`Unwind::message`]: ./struct.Unwind.html#method.message
+/// [`Unwind::payload_str`]: ./struct.Unwind.html#method.payload_str
+pub fn register_payload_formatter<T, F>(formatter: F)
+where
+    T: Any,
+    F: Fn(&T) -> String + Send + Sync + 'static,
+{
+    let formatter: Arc<Formatter> =
+        Arc::new(move |payload| payload.downcast_ref::<T>().map(&formatter));
+    register::<T>(Registered::Formatter(formatter));
+}
+
+/// Register a function that extracts the error value from the panic payload of the specified type.
+///
+/// The extracted error is returned from [`Unwind::payload_as_error`], and is
+/// used for rendering the panic message by its `Display` implementation and
+/// the `caused by:` lines of its source chain in the alternate `Display`
+/// output of `Unwind`. The payloads of the types `Box<dyn Error + Send + Sync>`
+/// and `Box<dyn Error + Send>` are recognized without registration.
+///
+/// The registration shares the registry with [`register_payload_formatter`],
+/// that is, it replaces the formatter registered for the same type and vice
+/// versa, and can be removed by [`clear_payload_formatter`].
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, register_payload_error};
+/// use std::{fmt, panic::panic_any};
+///
+/// #[derive(Debug)]
+/// struct Failure(std::io::Error);
+///
+/// impl fmt::Display for Failure {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         f.write_str("failed to process the request")
+///     }
+/// }
+///
+/// impl std::error::Error for Failure {
+///     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+///         Some(&self.0)
+///     }
+/// }
+///
+/// register_payload_error(|payload: &Failure| payload);
+///
+/// maybe_unwind::set_hook();
+/// let unwind = maybe_unwind(|| {
+///     panic_any(Failure(std::io::Error::new(std::io::ErrorKind::Other, "disconnected")));
+/// })
+/// .unwrap_err();
+/// assert_eq!(unwind.to_string(), "failed to process the request");
+/// assert!(format!("{:#}", unwind).contains("caused by: disconnected\n"));
+/// ```
+///
+/// [`Unwind::payload_as_error`]: ./struct.Unwind.html#method.payload_as_error
+/// [`register_payload_formatter`]: ./fn.register_payload_formatter.html
+/// [`clear_payload_formatter`]: ./fn.clear_payload_formatter.html
+pub fn register_payload_error<T, F>(extractor: F)
+where
+    T: Any,
+    F: for<'a> Fn(&'a T) -> &'a (dyn Error + 'static) + Send + Sync + 'static,
+{
+    let extractor: Arc<ErrorExtractor> =
+        Arc::new(move |payload| payload.downcast_ref::<T>().map(&extractor));
+    register::<T>(Registered::Error(extractor));
+}
+
+fn register<T: Any>(registered: Registered) {
+    let mut formatters = FORMATTERS.write().unwrap_or_else(|err| err.into_inner());
+    match formatters
+        .iter_mut()
+        .find(|(id, _)| *id == TypeId::of::<T>())
+    {
+        Some((_, prev)) => *prev = registered,
+        None => formatters.push((TypeId::of::<T>(), registered)),
+    }
+}
+
+/// Register a function that describes the panic payloads of the unknown types.
+///
+/// The describer is the last resort for rendering the panic payload, which is
+/// consulted only if the payload is neither `&str` nor `String` and no
+/// formatter or error extractor is registered for its type. Unlike the
+/// formatters registered by [`register_payload_formatter`], the describer
+/// receives the type-erased payload, so it can use its own downcast logic,
+/// e.g. for the opaque payloads thrown by the foreign-language glue code.
+///
+/// The description is used by [`Unwind::payload_str`] as well as the rendered
+/// panic message. It is computed when the panic is caught, so the describer
+/// registered afterwards does not affect the existing `Unwind`s. The describer
+/// specified by [`CaptureOptions::payload_describer`] takes precedence over
+/// this one inside the scope.
+///
+/// The describer is process-global, and replaces the previously registered one.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, set_payload_describer};
+/// use std::panic::panic_any;
+///
+/// struct ForeignException(i32);
+///
+/// set_payload_describer(|payload| {
+///     let exception = payload.downcast_ref::<ForeignException>()?;
+///     Some(format!("foreign exception (code = {})", exception.0))
+/// });
+///
+/// let unwind = maybe_unwind(|| panic_any(ForeignException(42))).unwrap_err();
+/// assert_eq!(unwind.payload_str(), "foreign exception (code = 42)");
+/// # maybe_unwind::clear_payload_describer();
+/// ```
+///
+/// [`register_payload_formatter`]: ./fn.register_payload_formatter.html
+/// [`Unwind::payload_str`]: ./struct.Unwind.html#method.payload_str
+/// [`CaptureOptions::payload_describer`]: ./struct.CaptureOptions.html#method.payload_describer
+pub fn set_payload_describer<F>(describer: F)
+where
+    F: Fn(&(dyn Any + Send)) -> Option<String> + Send + Sync + 'static,
+{
+    *DESCRIBER.write().unwrap_or_else(|err| err.into_inner()) = Some(Arc::new(describer));
+}
+
+/// Unregister the describer registered by [`set_payload_describer`].
+///
+/// [`set_payload_describer`]: ./fn.set_payload_describer.html
+pub fn clear_payload_describer() {
+    DESCRIBER
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .take();
+}
+
+/// Run `f` with the describer of the capture scope, if any.
+#[inline]
+pub(crate) fn with_scope_describer<F, R>(describer: Option<&PayloadDescriber>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    match describer {
+        Some(describer) => with_scope_describer_slow(describer, f),
+        None => f(),
+    }
+}
+
+#[cold]
+fn with_scope_describer_slow<F, R>(describer: &PayloadDescriber, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    struct Guard(Option<PayloadDescriber>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            let prev = self.0.take();
+            let _ = SCOPE_DESCRIBER.try_with(|current| *current.borrow_mut() = prev);
+        }
+    }
+
+    let prev = SCOPE_DESCRIBER.with(|current| current.borrow_mut().replace(describer.clone()));
+    let _guard = Guard(prev);
+    f()
+}
+
+/// Describe the panic payload of an unknown type by the describer of the
+/// innermost scope or the global one.
+fn describe(payload: &(dyn Any + Send)) -> Option<String> {
+    let describer = SCOPE_DESCRIBER
+        .try_with(|current| {
+            let current = current.try_borrow().ok()?;
+            current.as_ref().map(|describer| Arc::clone(&describer.0))
+        })
+        .ok()
+        .flatten()
+        .or_else(|| {
+            DESCRIBER
+                .read()
+                .unwrap_or_else(|err| err.into_inner())
+                .clone()
+        })?;
+    describer(payload)
+}
+
+/// Describe the panic payload if it cannot be rendered otherwise.
+pub(crate) fn describe_unknown(
+    payload: &(dyn Any + Send),
+    captured_message: Option<&str>,
+) -> Option<String> {
+    if render_known(payload).is_some() {
+        return None;
+    }
+    describe(payload).or_else(|| {
+        captured_message
+            .filter(|message| *message != unwind::UNKNOWN_PAYLOAD)
+            .map(ToOwned::to_owned)
+    })
+}
+
+/// Unregister the formatter registered by [`register_payload_formatter`] or
+/// [`register_payload_error`] for the specified type.
+///
+/// This function returns `true` if a formatter was registered.
+///
+/// [`register_payload_formatter`]: ./fn.register_payload_formatter.html
+/// [`register_payload_error`]: ./fn.register_payload_error.html
+pub fn clear_payload_formatter<T: Any>() -> bool {
+    let mut formatters = FORMATTERS.write().unwrap_or_else(|err| err.into_inner());
+    let len = formatters.len();
+    formatters.retain(|(id, _)| *id != TypeId::of::<T>());
+    formatters.len() != len
+}
+
+/// Render the panic payload, using the registered formatter or the describer if any.
+pub(crate) fn render(payload: &(dyn Any + Send)) -> Cow<'_, str> {
+    render_known(payload)
+        .or_else(|| describe(payload).map(Cow::Owned))
+        .unwrap_or(Cow::Borrowed(unwind::payload_str(payload)))
+}
+
+/// Render the panic payload of the built-in or registered types.
+pub(crate) fn render_known(payload: &(dyn Any + Send)) -> Option<Cow<'_, str>> {
+    if let Some(s) = unwind::downcast_str(payload) {
+        return Some(Cow::Borrowed(s));
+    }
+    if let Some(resumed) = payload.downcast_ref::<Resumed>() {
+        return Some(resumed.unwind().render_payload());
+    }
+
+    match registered(payload) {
+        Some(Registered::Formatter(formatter)) => formatter(payload),
+        Some(Registered::Error(extractor)) => extractor(payload).map(|err| err.to_string()),
+        None => builtin_error(payload).map(|err| err.to_string()),
+    }
+    .map(Cow::Owned)
+}
+
+/// Extract the error value from the panic payload, if any.
+pub(crate) fn as_error(payload: &(dyn Any + Send)) -> Option<&(dyn Error + 'static)> {
+    if let Some(resumed) = payload.downcast_ref::<Resumed>() {
+        return resumed.unwind().payload_as_error();
+    }
+    match registered(payload) {
+        Some(Registered::Error(extractor)) => extractor(payload),
+        _ => builtin_error(payload),
+    }
+}
+
+/// Render the source chain of the error carried by the panic payload.
+pub(crate) fn causes(payload: &(dyn Any + Send)) -> Vec<String> {
+    let mut causes = vec![];
+    let mut source = as_error(payload).and_then(|err| err.source());
+    while let Some(err) = source {
+        causes.push(err.to_string());
+        source = err.source();
+    }
+    causes
+}
+
+fn registered(payload: &(dyn Any + Send)) -> Option<Registered> {
+    let type_id = (*payload).type_id();
+    FORMATTERS
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+        .find(|(id, _)| *id == type_id)
+        .map(|(_, registered)| match registered {
+            Registered::Formatter(formatter) => Registered::Formatter(Arc::clone(formatter)),
+            Registered::Error(extractor) => Registered::Error(Arc::clone(extractor)),
+        })
+}
+
+fn builtin_error(payload: &(dyn Any + Send)) -> Option<&(dyn Error + 'static)> {
+    if let Some(err) = payload.downcast_ref::<Box<dyn Error + Send + Sync>>() {
+        return Some(&**err);
+    }
+    if let Some(err) = payload.downcast_ref::<Box<dyn Error + Send>>() {
+        return Some(&**err);
+    }
+    None
+}
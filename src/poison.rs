@@ -0,0 +1,120 @@
+use crate::unwind::Location;
+use std::{fmt, sync::Mutex};
+
+static POISONED: Mutex<Vec<PoisonRecord>> = Mutex::new(Vec::new());
+
+/// The record of a poisoned key, returned from [`poison_report`].
+///
+/// [`poison_report`]: ./fn.poison_report.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoisonRecord {
+    key: String,
+    location: Option<Location>,
+    message: Option<String>,
+}
+
+impl PoisonRecord {
+    /// Return the poisoned key.
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Return the location of the panic that poisoned the key.
+    ///
+    /// It returns `None` if the key was poisoned by [`poison`], or the
+    /// location of the panic was not captured.
+    ///
+    /// [`poison`]: ./fn.poison.html
+    #[inline]
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// Return the message of the panic that poisoned the key.
+    ///
+    /// It returns `None` if the key was poisoned by [`poison`].
+    ///
+    /// [`poison`]: ./fn.poison.html
+    #[inline]
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+impl fmt::Display for PoisonRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` is poisoned", self.key)?;
+        match (&self.location, &self.message) {
+            (Some(location), Some(message)) => {
+                write!(f, " by the panic at {}: {}", location, message)
+            }
+            (None, Some(message)) => write!(f, " by the panic: {}", message),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Mark the shared state identified by `key` as poisoned.
+///
+/// The poisoned keys are recorded in a process-global registry, so that
+/// the tests sharing a fixture can tell whether a previous test left it
+/// in an inconsistent state. Use [`Unwind::poison`] to also record the
+/// panic that poisoned the key.
+///
+/// If the key is already poisoned, the existing record is kept, since the
+/// first panic is usually the cause of the subsequent ones.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{clear_poison, is_poisoned, poison};
+///
+/// poison("database");
+/// assert!(is_poisoned("database"));
+///
+/// assert!(clear_poison("database"));
+/// assert!(!is_poisoned("database"));
+/// ```
+///
+/// [`Unwind::poison`]: ./struct.Unwind.html#method.poison
+pub fn poison(key: &str) {
+    poison_with(key, None, None);
+}
+
+/// Return whether the shared state identified by `key` is poisoned.
+pub fn is_poisoned(key: &str) -> bool {
+    let poisoned = POISONED.lock().unwrap_or_else(|err| err.into_inner());
+    poisoned.iter().any(|record| record.key == key)
+}
+
+/// Remove the poison of the shared state identified by `key`, e.g. after
+/// the fixture is rebuilt.
+///
+/// It returns `true` if the key was poisoned.
+pub fn clear_poison(key: &str) -> bool {
+    let mut poisoned = POISONED.lock().unwrap_or_else(|err| err.into_inner());
+    let len = poisoned.len();
+    poisoned.retain(|record| record.key != key);
+    poisoned.len() != len
+}
+
+/// Return the records of the currently poisoned keys, in the order they were poisoned.
+pub fn poison_report() -> Vec<PoisonRecord> {
+    POISONED
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+}
+
+pub(crate) fn poison_with(key: &str, location: Option<Location>, message: Option<String>) {
+    let mut poisoned = POISONED.lock().unwrap_or_else(|err| err.into_inner());
+    if poisoned.iter().any(|record| record.key == key) {
+        return;
+    }
+    poisoned.push(PoisonRecord {
+        key: key.to_owned(),
+        location,
+        message,
+    });
+}
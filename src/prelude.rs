@@ -0,0 +1,30 @@
+//! A prelude for the common use of this crate.
+//!
+//! ```
+//! use maybe_unwind::prelude::*;
+//!
+//! set_hook();
+//!
+//! let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+//! assert_eq!(unwind.message(), "oops");
+//! ```
+
+#[doc(no_inline)]
+pub use crate::{
+    each::IterMaybeUnwindExt,
+    expect::expect_unwind,
+    hook::set_hook,
+    unwind::{maybe_unwind, Unwind},
+};
+
+#[cfg(feature = "futures")]
+#[doc(no_inline)]
+pub use crate::futures::FutureMaybeUnwindExt;
+
+#[cfg(feature = "stream")]
+#[doc(no_inline)]
+pub use crate::stream::StreamMaybeUnwindExt;
+
+#[cfg(feature = "futures01")]
+#[doc(no_inline)]
+pub use crate::futures01::Future01MaybeUnwindExt;
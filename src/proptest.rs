@@ -0,0 +1,37 @@
+use crate::{
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use ::proptest::test_runner::TestCaseError;
+use std::panic::{Location, UnwindSafe};
+
+impl From<Unwind> for TestCaseError {
+    fn from(unwind: Unwind) -> Self {
+        let reason = format!("{:#}", unwind);
+        TestCaseError::fail(reason.trim_end().to_owned())
+    }
+}
+
+/// Invokes a closure in a property test, converting the panic into a failed test case.
+///
+/// The reason of the failure contains the location of the panic in addition
+/// to the message, so that the shrunken failures remain diagnosable.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_prop;
+/// use proptest::prelude::*;
+///
+/// proptest!(|(x in 0..100i32)| {
+///     maybe_unwind_prop(|| assert!(x < 100))?;
+/// });
+/// ```
+#[cfg_attr(docs, doc(cfg(feature = "proptest")))]
+#[track_caller]
+pub fn maybe_unwind_prop<F, R>(f: F) -> Result<R, TestCaseError>
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    maybe_unwind_at(Location::caller(), &CaptureOptions::default(), f).map_err(Into::into)
+}
@@ -0,0 +1,159 @@
+use crate::{
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use ::quickcheck::{Arbitrary, Gen, TestResult, Testable};
+use std::{
+    fmt,
+    marker::PhantomData,
+    panic::{AssertUnwindSafe, Location, UnwindSafe},
+};
+
+/// Invokes a closure in a quickcheck property, converting the panic into an errored test result.
+///
+/// The closure returns whether the property holds. If it panics, the result
+/// is `TestResult::error` whose message is the alternate `Display` of the
+/// captured [`Unwind`], which contains the location of the panic in addition
+/// to the message. Since the panic does not unwind through the property,
+/// quickcheck shrinks the arguments as with the plain failures.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_qc;
+/// use quickcheck::{quickcheck, TestResult};
+///
+/// fn prop(xs: Vec<u32>) -> TestResult {
+///     maybe_unwind_qc(|| xs.iter().rev().rev().eq(xs.iter()))
+/// }
+/// quickcheck(prop as fn(Vec<u32>) -> TestResult);
+/// ```
+///
+/// [`Unwind`]: ./struct.Unwind.html
+#[cfg_attr(docs, doc(cfg(feature = "quickcheck")))]
+#[track_caller]
+pub fn maybe_unwind_qc<F>(f: F) -> TestResult
+where
+    F: FnOnce() -> bool + UnwindSafe,
+{
+    match maybe_unwind_at(Location::caller(), &CaptureOptions::default(), f) {
+        Ok(holds) => TestResult::from_bool(holds),
+        Err(unwind) => TestResult::error(render(&unwind)),
+    }
+}
+
+/// Wrap a property of the arguments of type `A` into a `Testable`,
+/// converting the panics into the errored test results.
+///
+/// This is the counterpart of [`maybe_unwind_qc`] for the closures, which
+/// do not implement `Testable` unlike the function pointers. The property
+/// takes the multiple arguments as a tuple. The arguments of a failure are
+/// shrunk in the same manner as quickcheck, and the result is reported by
+/// `TestResult::error` whose message contains the shrunk arguments, and the
+/// alternate `Display` of the captured [`Unwind`] if the property panicked.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_qc_property;
+/// use quickcheck::QuickCheck;
+///
+/// maybe_unwind::set_hook();
+///
+/// let limit = 50;
+/// let res = QuickCheck::new().quicktest(maybe_unwind_qc_property(move |(a, b): (u8, u8)| {
+///     assert!(a.min(b) < limit, "too large: {} and {}", a, b);
+///     true
+/// }));
+/// assert!(res.unwrap_err().is_error());
+/// ```
+///
+/// [`maybe_unwind_qc`]: ./fn.maybe_unwind_qc.html
+/// [`Unwind`]: ./struct.Unwind.html
+#[cfg_attr(docs, doc(cfg(feature = "quickcheck")))]
+#[track_caller]
+pub fn maybe_unwind_qc_property<A, F>(f: F) -> MaybeUnwindProperty<A, F>
+where
+    A: Arbitrary + fmt::Debug,
+    F: Fn(A) -> bool + 'static,
+{
+    MaybeUnwindProperty {
+        f,
+        caller: Location::caller(),
+        _marker: PhantomData,
+    }
+}
+
+/// A property returned from [`maybe_unwind_qc_property`].
+///
+/// [`maybe_unwind_qc_property`]: ./fn.maybe_unwind_qc_property.html
+#[cfg_attr(docs, doc(cfg(feature = "quickcheck")))]
+pub struct MaybeUnwindProperty<A, F> {
+    f: F,
+    caller: &'static Location<'static>,
+    _marker: PhantomData<fn(A)>,
+}
+
+impl<A, F> fmt::Debug for MaybeUnwindProperty<A, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MaybeUnwindProperty")
+            .field("caller", &self.caller)
+            .finish()
+    }
+}
+
+impl<A, F> MaybeUnwindProperty<A, F>
+where
+    F: Fn(A) -> bool,
+    A: fmt::Debug,
+{
+    /// Run the property, and return the errored result if it fails.
+    fn check(&self, args: A) -> Option<TestResult> {
+        let repr = format!("{:?}", args);
+        match maybe_unwind_at(
+            self.caller,
+            &CaptureOptions::default(),
+            AssertUnwindSafe(|| (self.f)(args)),
+        ) {
+            Ok(true) => None,
+            Ok(false) => Some(TestResult::error(format!(
+                "the property does not hold for {}",
+                repr
+            ))),
+            Err(unwind) => Some(TestResult::error(format!(
+                "the property panicked for {}: {}",
+                repr,
+                render(&unwind)
+            ))),
+        }
+    }
+}
+
+impl<A, F> Testable for MaybeUnwindProperty<A, F>
+where
+    A: Arbitrary + fmt::Debug,
+    F: Fn(A) -> bool + 'static,
+{
+    fn result(&self, g: &mut Gen) -> TestResult {
+        let args = A::arbitrary(g);
+        let mut failure = match self.check(args.clone()) {
+            Some(failure) => failure,
+            None => return TestResult::passed(),
+        };
+
+        // Switch over to the first shrunk value that still fails,
+        // until none of them fails.
+        let mut candidates = args.shrink();
+        while let Some(args) = candidates.next() {
+            if let Some(shrunk) = self.check(args.clone()) {
+                failure = shrunk;
+                candidates = args.shrink();
+            }
+        }
+        failure
+    }
+}
+
+fn render(unwind: &Unwind) -> String {
+    format!("{:#}", unwind).trim_end().to_owned()
+}
@@ -0,0 +1,124 @@
+use crate::{
+    hook::{capture_panic_info, is_installed_hook, replace_hook, restore_hook, PanicHook},
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use std::{
+    cell::Cell,
+    panic::{self, Location, UnwindSafe},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// The hook replaced by the active quiet scopes.
+struct Quiet {
+    count: usize,
+    prev: Option<Arc<Box<PanicHook>>>,
+    prev_installed: bool,
+}
+
+static QUIET: Mutex<Quiet> = Mutex::new(Quiet {
+    count: 0,
+    prev: None,
+    prev_installed: false,
+});
+
+thread_local! {
+    static QUIET_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Invokes a closure without printing the panic message by the default hook.
+///
+/// This function is the same as [`maybe_unwind`], except that the panic hook
+/// is temporarily replaced with the one that captures the panic information
+/// and does not forward the panics raised inside of the closure to the
+/// previous hook. The previous hook is restored when the last quiet scope in
+/// the process exits, even if the closure panicked. This is intended for the
+/// environments where the process-global hook cannot be installed by
+/// [`set_hook`], e.g. it is owned by another framework.
+///
+/// The quiet scopes on multiple threads share the replaced hook, and the
+/// panics on other threads that are not captured are still forwarded to the
+/// previous hook. However, the panics captured by `maybe_unwind` on other
+/// threads during the window are also silenced, and the panic hook set by
+/// others during the window is overwritten when the previous hook is restored.
+///
+/// The quiet scope takes precedence over [`HookOptions::also_forward_captured`],
+/// that is, the panics inside of the closure are not forwarded to the
+/// previous hook even if the hook is installed with that option.
+///
+/// If this function is called while the current thread is panicking, e.g.
+/// in a destructor, the hook is not replaced since it cannot be changed.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_quiet;
+///
+/// // Nothing is printed to stderr.
+/// let unwind = maybe_unwind_quiet(|| panic!("oops")).unwrap_err();
+/// assert!(unwind.location().is_some());
+/// ```
+///
+/// [`maybe_unwind`]: ./fn.maybe_unwind.html
+/// [`set_hook`]: ./fn.set_hook.html
+/// [`HookOptions::also_forward_captured`]: ./struct.HookOptions.html#method.also_forward_captured
+#[track_caller]
+pub fn maybe_unwind_quiet<F, R>(f: F) -> Result<R, Unwind>
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    let caller = Location::caller();
+    let _guard = QuietGuard::enter();
+    maybe_unwind_at(caller, &CaptureOptions::default(), f)
+}
+
+struct QuietGuard {
+    active: bool,
+}
+
+impl QuietGuard {
+    fn enter() -> Self {
+        if thread::panicking() {
+            return Self { active: false };
+        }
+
+        let mut quiet = QUIET.lock().unwrap_or_else(|err| err.into_inner());
+        if quiet.count == 0 {
+            let prev = panic::take_hook();
+            quiet.prev_installed = is_installed_hook(&*prev);
+            let prev = Arc::new(prev);
+            let forward = Arc::clone(&prev);
+            replace_hook(
+                Box::new(move |info| {
+                    if !capture_panic_info(info) && QUIET_DEPTH.with(|depth| depth.get()) == 0 {
+                        (**forward)(info);
+                    }
+                }),
+                true,
+            );
+            quiet.prev = Some(prev);
+        }
+        quiet.count += 1;
+        QUIET_DEPTH.with(|depth| depth.set(depth.get() + 1));
+
+        Self { active: true }
+    }
+}
+
+impl Drop for QuietGuard {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+
+        QUIET_DEPTH.with(|depth| depth.set(depth.get() - 1));
+        let mut quiet = QUIET.lock().unwrap_or_else(|err| err.into_inner());
+        quiet.count -= 1;
+        if quiet.count == 0 {
+            if let Some(prev) = quiet.prev.take() {
+                restore_hook(prev, quiet.prev_installed);
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use std::panic::{AssertUnwindSafe, Location};
+
+/// Run two closures potentially in parallel by `rayon::join`, capturing the
+/// panic information of each of them.
+///
+/// Unlike `rayon::join`, which waits for both closures and then propagates
+/// the panic of either one, the panics are returned to the caller, so that
+/// the result of the other closure is not lost. Each closure runs in its own
+/// capture scope on the thread picked by Rayon, and the location where this
+/// function is called is recorded as [`Unwind::caller`] of both.
+///
+/// The closures are wrapped by `AssertUnwindSafe`, as in `rayon::join`.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::join_maybe_unwind;
+///
+/// maybe_unwind::set_hook();
+///
+/// let (a, b) = join_maybe_unwind(|| 1 + 1, || -> i32 { panic!("oops") });
+/// assert_eq!(a.unwrap(), 2);
+/// assert_eq!(b.unwrap_err().message(), "oops");
+/// ```
+///
+/// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+#[cfg_attr(docs, doc(cfg(feature = "rayon")))]
+#[track_caller]
+pub fn join_maybe_unwind<A, B, RA, RB>(a: A, b: B) -> (Result<RA, Unwind>, Result<RB, Unwind>)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    let caller = Location::caller();
+    ::rayon::join(
+        move || maybe_unwind_at(caller, &CaptureOptions::default(), AssertUnwindSafe(a)),
+        move || maybe_unwind_at(caller, &CaptureOptions::default(), AssertUnwindSafe(b)),
+    )
+}
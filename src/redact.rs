@@ -0,0 +1,119 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+type Redactor = dyn Fn(&str) -> String + Send + Sync + 'static;
+
+static REDACTOR: RwLock<Option<Arc<Redactor>>> = RwLock::new(None);
+
+/// Register a function that redacts the sensitive data in the rendered panic reports.
+///
+/// The redactor is applied whenever the panic message or the context
+/// annotations are rendered into strings, i.e. by [`Unwind::message`],
+/// the `Display` implementation of `Unwind`, the normalized reports and
+/// the snapshots. It is never applied to the raw payload accessible via
+/// [`Unwind::payload`] and [`Unwind::payload_str`].
+///
+/// The redactor is process-global, and replaces the previously registered one.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, set_redactor};
+///
+/// set_redactor(|s| s.replace("hunter2", "[REDACTED]"));
+///
+/// let unwind = maybe_unwind(|| panic!("token = hunter2")).unwrap_err();
+/// assert_eq!(unwind.payload_str(), "token = hunter2");
+/// assert_eq!(unwind.to_string(), "token = [REDACTED]");
+/// # maybe_unwind::clear_redactor();
+/// ```
+///
+/// [`Unwind::message`]: ./struct.Unwind.html#method.message
+/// [`Unwind::payload`]: ./struct.Unwind.html#method.payload
+/// [`Unwind::payload_str`]: ./struct.Unwind.html#method.payload_str
+pub fn set_redactor<F>(redactor: F)
+where
+    F: Fn(&str) -> String + Send + Sync + 'static,
+{
+    *REDACTOR.write().unwrap_or_else(|err| err.into_inner()) = Some(Arc::new(redactor));
+}
+
+/// Unregister the redactor registered by [`set_redactor`].
+///
+/// [`set_redactor`]: ./fn.set_redactor.html
+pub fn clear_redactor() {
+    REDACTOR
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .take();
+}
+
+pub(crate) fn redact(s: &str) -> Cow<'_, str> {
+    let redactor = REDACTOR
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone();
+    match redactor {
+        Some(redactor) => Cow::Owned(redactor(s)),
+        None => Cow::Borrowed(s),
+    }
+}
+
+/// Redacts the values of the annotations, keeping the keys as they are.
+pub(crate) fn redact_annotations(annotations: &[(String, String)]) -> Cow<'_, [(String, String)]> {
+    if annotations.is_empty()
+        || REDACTOR
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .is_none()
+    {
+        return Cow::Borrowed(annotations);
+    }
+    Cow::Owned(
+        annotations
+            .iter()
+            .map(|(key, value)| (key.clone(), redact(value).into_owned()))
+            .collect(),
+    )
+}
+
+/// Redacts the values of the environment variables, keeping the names as they are.
+pub(crate) fn redact_env(
+    env: &BTreeMap<String, Option<String>>,
+) -> Cow<'_, BTreeMap<String, Option<String>>> {
+    if env.is_empty()
+        || REDACTOR
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .is_none()
+    {
+        return Cow::Borrowed(env);
+    }
+    Cow::Owned(
+        env.iter()
+            .map(|(name, value)| {
+                let value = value.as_ref().map(|value| redact(value).into_owned());
+                (name.clone(), value)
+            })
+            .collect(),
+    )
+}
+
+pub(crate) fn redact_all(frames: &[String]) -> Cow<'_, [String]> {
+    if REDACTOR
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .is_none()
+    {
+        return Cow::Borrowed(frames);
+    }
+    Cow::Owned(
+        frames
+            .iter()
+            .map(|frame| redact(frame).into_owned())
+            .collect(),
+    )
+}
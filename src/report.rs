@@ -0,0 +1,132 @@
+//! The rendering of the captured panics into reports.
+//!
+//! The items in this module are also re-exported from the crate root.
+
+use crate::{backtrace::BacktraceStatus, unwind::Location};
+use std::{borrow::Cow, fmt};
+
+pub use crate::{
+    blame::{Blame, BlameSource},
+    display::DisplayOptions,
+    location::{DisplayLocation, LocationStyle},
+    normalize::NormalizeOptions,
+    run::{run, run_with, RunOptions, UnwindReport},
+};
+
+/// The components of the panic report shared by `Unwind` and `UnwindSnapshot`.
+///
+/// The message, context frames, annotations and notes are expected to be redacted already.
+pub(crate) struct Report<'a> {
+    pub(crate) message: Cow<'a, str>,
+    pub(crate) location: Option<&'a Location>,
+    pub(crate) caller: &'a Location,
+    pub(crate) context_frames: Cow<'a, [String]>,
+    pub(crate) annotations: Cow<'a, [(String, String)]>,
+    pub(crate) notes: Cow<'a, [String]>,
+    pub(crate) causes: Cow<'a, [String]>,
+    pub(crate) panic_depth: u32,
+    pub(crate) scope_label: Option<&'a str>,
+    pub(crate) span_trace: Option<String>,
+    pub(crate) backtrace: Option<&'a str>,
+    pub(crate) backtrace_status: BacktraceStatus,
+    pub(crate) scope_backtrace: Option<&'a str>,
+    pub(crate) max_message_len: Option<usize>,
+}
+
+impl Report<'_> {
+    pub(crate) fn write(
+        &self,
+        f: &mut dyn fmt::Write,
+        normalize: Option<&NormalizeOptions>,
+        display: &DisplayOptions,
+    ) -> fmt::Result {
+        self.write_summary(f, normalize, display)?;
+
+        if let Some(span_trace) = &self.span_trace {
+            writeln!(f, "span trace:")?;
+            writeln!(f, "{}", span_trace.trim_end())?;
+        }
+
+        if let Some(backtrace) = self.backtrace {
+            writeln!(f, "stack backtrace:")?;
+            match normalize {
+                Some(options) => f.write_str(&options.backtrace(backtrace))?,
+                None => writeln!(f, "{}", backtrace)?,
+            }
+        } else if let Some(note) = self.backtrace_status.note() {
+            writeln!(f, "note: {}", note)?;
+        }
+
+        if let Some(backtrace) = self.scope_backtrace {
+            writeln!(f, "scope established here:")?;
+            match normalize {
+                Some(options) => f.write_str(&options.backtrace(backtrace))?,
+                None => writeln!(f, "{}", backtrace)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the report except for the backtrace section.
+    pub(crate) fn write_summary(
+        &self,
+        f: &mut dyn fmt::Write,
+        normalize: Option<&NormalizeOptions>,
+        display: &DisplayOptions,
+    ) -> fmt::Result {
+        let location = |loc: &Location| match normalize {
+            Some(options) => options.location(loc),
+            None => display.location(loc),
+        };
+
+        let message = match self.max_message_len {
+            Some(max) => truncate_message(&self.message, max),
+            None => Cow::Borrowed(&*self.message),
+        };
+        f.write_str("panicked")?;
+        if let Some(label) = self.scope_label {
+            write!(f, " in {}", label)?;
+        }
+        if let Some(loc) = self.location {
+            writeln!(f, " at {}: {}", location(loc), message)?;
+        } else {
+            writeln!(f, ": {}", message)?;
+        }
+        for cause in self.causes.iter() {
+            writeln!(f, "caused by: {}", cause)?;
+        }
+        writeln!(f, "captured at {}", location(self.caller))?;
+
+        if !self.context_frames.is_empty() {
+            writeln!(f, "context:")?;
+            for frame in self.context_frames.iter() {
+                writeln!(f, "    {}", frame)?;
+            }
+        }
+
+        if !self.annotations.is_empty() {
+            writeln!(f, "annotations:")?;
+            for (key, value) in self.annotations.iter() {
+                writeln!(f, "    {} = {}", key, value)?;
+            }
+        }
+
+        if self.panic_depth > 1 {
+            writeln!(f, "note: panicked while processing panic")?;
+        }
+        for note in self.notes.iter() {
+            writeln!(f, "note: {}", note)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Truncates the message to `max` characters, appending an ellipsis if truncated.
+fn truncate_message(message: &str, max: usize) -> Cow<'_, str> {
+    match message.char_indices().nth(max) {
+        Some((end, _)) => Cow::Owned(format!("{}\u{2026}", &message[..end])),
+        None => Cow::Borrowed(message),
+    }
+}
@@ -0,0 +1,271 @@
+use crate::backtrace::{self, Backtrace};
+use std::{
+    fmt,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// The name of the worker thread spawned by `spawn_backtrace_resolver`.
+const THREAD_NAME: &str = "maybe-unwind-resolver";
+
+/// Spawn a background thread that resolves the symbols of the stack backtraces.
+///
+/// The backtrace is captured on the panicking thread as usual, but resolving
+/// its symbols, which can be expensive for large binaries, is deferred to
+/// the returned worker for the capture scopes configured with
+/// [`CaptureOptions::resolve_via`], so that neither the panic hook nor the
+/// thread inspecting the [`Unwind`] pays for it. The result is retrieved
+/// through [`Unwind::resolved_backtrace`].
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind_with, spawn_backtrace_resolver, BacktraceMode, CaptureOptions};
+///
+/// maybe_unwind::set_hook();
+///
+/// let resolver = spawn_backtrace_resolver();
+/// let options = CaptureOptions::new()
+///     .backtrace(BacktraceMode::Always)
+///     .resolve_via(&resolver);
+///
+/// let unwind = maybe_unwind_with(options, || panic!("oops")).unwrap_err();
+/// if let Some(resolved) = unwind.resolved_backtrace() {
+///     let text = resolved.wait();
+///     # let _ = text;
+/// }
+/// ```
+///
+/// [`CaptureOptions::resolve_via`]: ./struct.CaptureOptions.html#method.resolve_via
+/// [`Unwind`]: ./struct.Unwind.html
+/// [`Unwind::resolved_backtrace`]: ./struct.Unwind.html#method.resolved_backtrace
+pub fn spawn_backtrace_resolver() -> ResolverHandle {
+    let (tx, rx) = mpsc::channel::<Job>();
+    let worker = thread::Builder::new()
+        .name(THREAD_NAME.to_owned())
+        .spawn(move || {
+            for job in rx {
+                job.run();
+            }
+        })
+        .ok();
+    // If the thread could not be spawned, the backtraces are resolved as usual.
+    let sender = worker.as_ref().map(|_| tx);
+    ResolverHandle {
+        sender: ResolverRef(Arc::new(Mutex::new(sender))),
+        worker,
+    }
+}
+
+/// The handle of the background resolver spawned by [`spawn_backtrace_resolver`].
+///
+/// Dropping the handle shuts down the worker thread. The backtraces already
+/// submitted to the worker are resolved before it exits, so that the pending
+/// [`ResolvedBacktrace`]s are always completed, and the scopes configured with
+/// this resolver keep the backtraces unresolved on their own afterwards.
+///
+/// [`spawn_backtrace_resolver`]: ./fn.spawn_backtrace_resolver.html
+/// [`ResolvedBacktrace`]: ./struct.ResolvedBacktrace.html
+pub struct ResolverHandle {
+    sender: ResolverRef,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl fmt::Debug for ResolverHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResolverHandle")
+            .field("running", &self.sender.is_running())
+            .finish()
+    }
+}
+
+impl Drop for ResolverHandle {
+    fn drop(&mut self) {
+        // Closing the channel lets the worker exit after draining the queue.
+        self.sender.lock().take();
+        if let Some(worker) = self.worker.take() {
+            if worker.thread().id() != thread::current().id() {
+                let _ = worker.join();
+            }
+        }
+    }
+}
+
+impl ResolverHandle {
+    pub(crate) fn to_ref(&self) -> ResolverRef {
+        self.sender.clone()
+    }
+}
+
+/// The reference to the resolver held by `CaptureOptions`, which does not
+/// keep the worker running.
+#[derive(Clone)]
+pub(crate) struct ResolverRef(Arc<Mutex<Option<Sender<Job>>>>);
+
+impl fmt::Debug for ResolverRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ResolverRef { .. }")
+    }
+}
+
+impl ResolverRef {
+    fn lock(&self) -> MutexGuard<'_, Option<Sender<Job>>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn is_running(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// Submit the backtrace to the worker, or give it back if the worker has
+    /// been shut down.
+    #[cfg(backtrace)]
+    pub(crate) fn submit(
+        &self,
+        backtrace: Backtrace,
+        max_frames: Option<usize>,
+    ) -> Result<ResolvedBacktrace, Backtrace> {
+        let sender = self.lock();
+        let sender = match sender.as_ref() {
+            Some(sender) => sender,
+            None => return Err(backtrace),
+        };
+        let resolved = ResolvedBacktrace::pending();
+        let job = Job {
+            backtrace,
+            max_frames,
+            slot: PendingSlot(Some(resolved.clone())),
+        };
+        match sender.send(job) {
+            Ok(()) => Ok(resolved),
+            Err(mpsc::SendError(mut job)) => {
+                job.slot.0 = None;
+                Err(job.backtrace)
+            }
+        }
+    }
+}
+
+/// The backtrace submitted to the worker.
+pub(crate) struct Job {
+    backtrace: Backtrace,
+    max_frames: Option<usize>,
+    slot: PendingSlot,
+}
+
+impl Job {
+    fn run(mut self) {
+        let text = backtrace::resolve(&self.backtrace, self.max_frames);
+        if let Some(slot) = self.slot.0.take() {
+            slot.complete(text);
+        }
+    }
+}
+
+/// The result of a job, completed on drop if the job did not complete it.
+struct PendingSlot(Option<ResolvedBacktrace>);
+
+impl Drop for PendingSlot {
+    fn drop(&mut self) {
+        // Never leave the waiters blocked, e.g. if resolving the symbols panicked.
+        if let Some(slot) = self.0.take() {
+            slot.complete(None);
+        }
+    }
+}
+
+/// The stack backtrace whose symbols are resolved by the background resolver.
+///
+/// This value is returned from [`Unwind::resolved_backtrace`].
+///
+/// [`Unwind::resolved_backtrace`]: ./struct.Unwind.html#method.resolved_backtrace
+#[derive(Clone)]
+pub struct ResolvedBacktrace {
+    inner: Arc<Slot>,
+}
+
+struct Slot {
+    state: Mutex<Option<Resolved>>,
+    cond: Condvar,
+}
+
+struct Resolved {
+    text: Option<String>,
+    thread_name: Option<String>,
+}
+
+impl fmt::Debug for ResolvedBacktrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResolvedBacktrace")
+            .field("resolved", &self.is_resolved())
+            .finish()
+    }
+}
+
+impl ResolvedBacktrace {
+    #[cfg(backtrace)]
+    fn pending() -> Self {
+        Self {
+            inner: Arc::new(Slot {
+                state: Mutex::new(None),
+                cond: Condvar::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Resolved>> {
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn complete(&self, text: Option<String>) {
+        *self.lock() = Some(Resolved {
+            text,
+            thread_name: thread::current().name().map(ToOwned::to_owned),
+        });
+        self.inner.cond.notify_all();
+    }
+
+    fn wait_resolved<R>(&self, f: impl FnOnce(&Resolved) -> R) -> R {
+        let mut state = self.lock();
+        loop {
+            if let Some(resolved) = state.as_ref() {
+                return f(resolved);
+            }
+            state = self
+                .inner
+                .cond
+                .wait(state)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+    }
+
+    /// Return whether the worker has finished resolving the backtrace.
+    #[inline]
+    pub fn is_resolved(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// Block the current thread until the backtrace is resolved, and return
+    /// its text representation.
+    ///
+    /// The text is truncated by [`CaptureOptions::max_backtrace_frames`] if
+    /// specified. This method returns `None` if the backtrace could not be
+    /// resolved.
+    ///
+    /// [`CaptureOptions::max_backtrace_frames`]: ./struct.CaptureOptions.html#method.max_backtrace_frames
+    pub fn wait(&self) -> Option<String> {
+        self.wait_resolved(|resolved| resolved.text.clone())
+    }
+
+    /// Block the current thread until the backtrace is resolved, and return
+    /// the name of the thread that resolved it.
+    pub fn resolver_thread_name(&self) -> Option<String> {
+        self.wait_resolved(|resolved| resolved.thread_name.clone())
+    }
+}
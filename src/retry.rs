@@ -0,0 +1,146 @@
+use crate::{options::CaptureOptions, unwind::maybe_unwind_mut_at, unwind_set::UnwindSet};
+use std::{error, fmt, panic::Location};
+
+/// The error returned when all attempts of [`maybe_unwind_retry`] panicked.
+///
+/// [`maybe_unwind_retry`]: ./fn.maybe_unwind_retry.html
+#[derive(Debug)]
+pub struct RetryUnwind {
+    unwinds: UnwindSet,
+}
+
+impl RetryUnwind {
+    /// Return the captured panics, one per attempt, in order.
+    ///
+    /// The panics are labeled as `attempt 1`, `attempt 2` and so on.
+    #[inline]
+    pub fn unwinds(&self) -> &UnwindSet {
+        &self.unwinds
+    }
+
+    /// Convert itself into the captured panics.
+    #[inline]
+    pub fn into_unwinds(self) -> UnwindSet {
+        self.unwinds
+    }
+
+    /// Return the number of attempts.
+    #[inline]
+    pub fn attempts(&self) -> usize {
+        self.unwinds.len()
+    }
+}
+
+impl fmt::Display for RetryUnwind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "all {} attempts panicked", self.unwinds.len())?;
+        for (label, unwind) in self.unwinds.iter() {
+            match unwind.location() {
+                Some(location) => write!(
+                    f,
+                    "\n  {}: panicked at {}: {}",
+                    label,
+                    location,
+                    unwind.message()
+                )?,
+                None => write!(f, "\n  {}: panicked: {}", label, unwind.message())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for RetryUnwind {}
+
+/// Invokes a closure repeatedly until it returns without panicking.
+///
+/// The closure is invoked up to `attempts` times, and the result of the first
+/// successful attempt is returned. If all attempts panicked, the captured panics
+/// are returned as a `RetryUnwind`.
+///
+/// Unlike [`maybe_unwind`], the closure is not required to be `UnwindSafe`
+/// since it is invoked multiple times through a mutable reference. The state
+/// captured by the closure may be observed in a broken state by the next
+/// attempt after a panic, so the closure must be written to tolerate it
+/// (e.g. by re-initializing the state at the beginning of each attempt).
+///
+/// # Panics
+///
+/// This function panics if `attempts` is zero.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_retry;
+///
+/// maybe_unwind::set_hook();
+///
+/// let mut count = 0;
+/// let res = maybe_unwind_retry(3, || {
+///     count += 1;
+///     assert!(count >= 2, "flaky");
+///     count
+/// });
+/// assert_eq!(res.unwrap(), 2);
+/// ```
+///
+/// [`maybe_unwind`]: ./fn.maybe_unwind.html
+#[inline]
+#[track_caller]
+pub fn maybe_unwind_retry<F, R>(attempts: usize, f: F) -> Result<R, RetryUnwind>
+where
+    F: FnMut() -> R,
+{
+    maybe_unwind_retry_with(attempts, |_| (), f)
+}
+
+/// Invokes a closure repeatedly with a backoff between the attempts.
+///
+/// This function is the same as [`maybe_unwind_retry`], except that `backoff`
+/// is called before each retry with the number of the failed attempts so far,
+/// e.g. for sleeping before the next attempt.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_retry_with;
+/// use std::{thread, time::Duration};
+///
+/// maybe_unwind::set_hook();
+///
+/// let err = maybe_unwind_retry_with(
+///     3,
+///     |failed| thread::sleep(Duration::from_millis(failed as u64)),
+///     || panic!("unavailable"),
+/// )
+/// .unwrap_err();
+/// assert_eq!(err.attempts(), 3);
+/// ```
+///
+/// [`maybe_unwind_retry`]: ./fn.maybe_unwind_retry.html
+#[track_caller]
+pub fn maybe_unwind_retry_with<F, B, R>(
+    attempts: usize,
+    mut backoff: B,
+    mut f: F,
+) -> Result<R, RetryUnwind>
+where
+    F: FnMut() -> R,
+    B: FnMut(usize),
+{
+    assert!(attempts > 0, "the number of attempts must be positive");
+
+    let caller = Location::caller();
+    let options = CaptureOptions::default();
+    let mut unwinds = UnwindSet::new();
+    for attempt in 0..attempts {
+        if attempt > 0 {
+            backoff(attempt);
+        }
+        match maybe_unwind_mut_at(caller, &options, &mut f) {
+            Ok(output) => return Ok(output),
+            Err(unwind) => unwinds.push(format!("attempt {}", attempt + 1), unwind),
+        }
+    }
+    Err(RetryUnwind { unwinds })
+}
@@ -0,0 +1,234 @@
+use crate::options::CaptureOptions;
+use crate::{
+    hook::{install_hook, HookOptions},
+    unwind::{maybe_unwind_at, Unwind},
+};
+use std::{
+    fmt,
+    panic::{Location, UnwindSafe},
+    process::{ExitCode, Termination},
+};
+
+/// The exit code of the process terminated by an unwinding panic.
+const PANIC_EXIT_CODE: u8 = 101;
+
+/// The options for [`run_with`].
+///
+/// [`run_with`]: ./fn.run_with.html
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    exit_code: u8,
+    capture: CaptureOptions,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            exit_code: PANIC_EXIT_CODE,
+            capture: CaptureOptions::default(),
+        }
+    }
+}
+
+impl RunOptions {
+    /// Create a new `RunOptions` with the default values.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the exit code returned when the function panics.
+    ///
+    /// The default value is `101`, which is the same as the exit
+    /// code of the process terminated by an unwinding panic.
+    #[inline]
+    pub fn exit_code(mut self, code: u8) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    /// Specify the options for capturing the panic information.
+    #[inline]
+    pub fn capture_options(mut self, options: CaptureOptions) -> Self {
+        self.capture = options;
+        self
+    }
+}
+
+/// Runs the main function of the program, reporting the panic if one occurs.
+///
+/// This function is equivalent to `run_with(RunOptions::default(), f)`.
+/// See [`run_with`] for details.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::process::ExitCode;
+///
+/// fn main() -> ExitCode {
+///     maybe_unwind::run(|| {
+///         // ...
+///     })
+/// }
+/// ```
+///
+/// [`run_with`]: ./fn.run_with.html
+#[track_caller]
+pub fn run<F, T>(f: F) -> ExitCode
+where
+    F: FnOnce() -> T + UnwindSafe,
+    T: Termination,
+{
+    run_at(Location::caller(), RunOptions::default(), f)
+}
+
+/// Runs the main function of the program with the specified options,
+/// reporting the panic if one occurs.
+///
+/// The capturing panic hook is installed while the function is running,
+/// and the previous hook is restored before returning. If the function
+/// panics, the report of the captured panic is printed to the standard
+/// error in the alternate `Display` format and the configured exit code
+/// is returned. Otherwise, the return value of the function is converted
+/// into the exit code via `Termination`.
+#[track_caller]
+pub fn run_with<F, T>(options: RunOptions, f: F) -> ExitCode
+where
+    F: FnOnce() -> T + UnwindSafe,
+    T: Termination,
+{
+    run_at(Location::caller(), options, f)
+}
+
+fn run_at<F, T>(caller: &'static Location<'static>, options: RunOptions, f: F) -> ExitCode
+where
+    F: FnOnce() -> T + UnwindSafe,
+    T: Termination,
+{
+    let res = {
+        let _guard = install_hook(HookOptions::default());
+        maybe_unwind_at(caller, &options.capture, f)
+    };
+
+    match res {
+        Ok(output) => output.report(),
+        Err(unwind) => {
+            eprint!("{:#}", unwind);
+            ExitCode::from(options.exit_code)
+        }
+    }
+}
+
+impl Unwind {
+    /// Return the exit code conventionally used for reporting this panic,
+    /// i.e. `101`, which is the same as the exit code of the process
+    /// terminated by an unwinding panic.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use maybe_unwind::maybe_unwind;
+    /// use std::process;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// if let Err(unwind) = maybe_unwind(|| panic!("oops")) {
+    ///     eprint!("{:#}", unwind);
+    ///     process::exit(i32::from(unwind.exit_code()));
+    /// }
+    /// ```
+    #[inline]
+    pub fn exit_code(&self) -> u8 {
+        PANIC_EXIT_CODE
+    }
+}
+
+/// A wrapper of `Unwind` for reporting the panic at the end of the program.
+///
+/// When returned from the main function as the error of `Result`, e.g.
+/// `fn main() -> Result<(), UnwindReport>`, the standard library prints
+/// `Error: ` followed by the `Debug` output, which is the same as the
+/// alternate `Display` output of `Unwind` rather than the raw fields.
+/// Note that the exit code is then always `1`, since it is fixed by the
+/// standard library.
+///
+/// The `Termination` implementation of `UnwindReport` itself prints the
+/// report to the standard error and returns the configured exit code,
+/// which is `101` by default. It can be used for returning the panic
+/// exit code from a main function returning `ExitCode`.
+///
+/// # Example
+///
+/// ```no_run
+/// use maybe_unwind::{maybe_unwind, UnwindReport};
+///
+/// fn main() -> Result<(), UnwindReport> {
+///     maybe_unwind::set_hook();
+///     maybe_unwind(|| {
+///         // ...
+///     })?;
+///     Ok(())
+/// }
+/// ```
+pub struct UnwindReport {
+    // Boxed so that `Result<(), UnwindReport>` stays small.
+    unwind: Box<Unwind>,
+    exit_code: u8,
+}
+
+impl UnwindReport {
+    /// Create a new `UnwindReport` with the default exit code.
+    #[inline]
+    pub fn new(unwind: Unwind) -> Self {
+        Self {
+            exit_code: unwind.exit_code(),
+            unwind: Box::new(unwind),
+        }
+    }
+
+    /// Specify the exit code returned by the `Termination` implementation.
+    #[inline]
+    pub fn with_exit_code(mut self, code: u8) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    /// Return the reference to the inner `Unwind`.
+    #[inline]
+    pub fn unwind(&self) -> &Unwind {
+        &self.unwind
+    }
+
+    /// Convert itself into the inner `Unwind`.
+    #[inline]
+    pub fn into_unwind(self) -> Unwind {
+        *self.unwind
+    }
+}
+
+impl From<Unwind> for UnwindReport {
+    #[inline]
+    fn from(unwind: Unwind) -> Self {
+        Self::new(unwind)
+    }
+}
+
+impl fmt::Debug for UnwindReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = format!("{:#}", self.unwind);
+        f.write_str(report.trim_end())
+    }
+}
+
+impl fmt::Display for UnwindReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.unwind, f)
+    }
+}
+
+impl Termination for UnwindReport {
+    fn report(self) -> ExitCode {
+        eprint!("{:#}", self.unwind);
+        ExitCode::from(self.exit_code)
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    abort::unwinding_supported,
+    context::{self, Caught},
+    hook::{self, hook_installed},
+    options::{BacktraceMode, CaptureOptions},
+};
+use std::{error, fmt, panic, thread};
+
+/// The error type returned from [`self_check`], describing why the panic
+/// information is not captured.
+///
+/// [`self_check`]: ./fn.self_check.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SelfCheckError {
+    /// The `capture` feature is disabled.
+    CaptureDisabled,
+
+    /// The panics cannot be caught, e.g. the crate is built with `panic = "abort"`.
+    UnwindingUnsupported,
+
+    /// The capturing panic hook is not installed.
+    HookNotInstalled,
+
+    /// The capturing panic hook was replaced by another hook.
+    HookReplaced,
+
+    /// The panic hook did not capture the panic information.
+    NotCaptured,
+
+    /// The self-check was run on a panicking thread, where the panic hook cannot be inspected.
+    Panicking,
+}
+
+impl fmt::Display for SelfCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SelfCheckError::CaptureDisabled => {
+                "the `capture` feature of maybe-unwind is disabled, so the panic locations will be missing"
+            }
+            SelfCheckError::UnwindingUnsupported => {
+                "panics cannot be caught since unwinding is not supported by this build"
+            }
+            SelfCheckError::HookNotInstalled => {
+                "the panic hook is not installed, so the panic locations will be missing (call `maybe_unwind::set_hook`)"
+            }
+            SelfCheckError::HookReplaced => {
+                "the panic hook installed by maybe-unwind was replaced by another hook, so the panic locations will be missing"
+            }
+            SelfCheckError::NotCaptured => {
+                "the panic hook did not capture the panic information"
+            }
+            SelfCheckError::Panicking => {
+                "the self-check cannot be run while the thread is panicking"
+            }
+        })
+    }
+}
+
+impl error::Error for SelfCheckError {}
+
+/// Verify that the panic information is captured by `maybe_unwind`.
+///
+/// This function checks the configuration of the capture, and then raises
+/// a panic inside of a capture scope for verifying that its location is
+/// actually captured by the panic hook. It is intended for the libraries
+/// built on top of this crate, which want to warn the users about the
+/// missing locations.
+///
+/// The self-check does not print anything, and leaves no trace of the panic:
+/// the observers, the panic collectors and the history are not notified.
+/// If the panic hook installed by this crate is not the current one, the
+/// error is returned without raising the panic, so that the panic is not
+/// printed by another hook. Note that the current hook is removed for a
+/// moment while inspecting it, so the panics on other threads at that
+/// moment are reported by the default hook.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::SelfCheckError;
+///
+/// let err = maybe_unwind::self_check().unwrap_err();
+/// assert_eq!(err, SelfCheckError::HookNotInstalled);
+/// eprintln!("warning: {}", err);
+///
+/// maybe_unwind::set_hook();
+/// assert!(maybe_unwind::self_check().is_ok());
+/// ```
+pub fn self_check() -> Result<(), SelfCheckError> {
+    if !cfg!(feature = "capture") {
+        return Err(SelfCheckError::CaptureDisabled);
+    }
+    if !unwinding_supported() {
+        return Err(SelfCheckError::UnwindingUnsupported);
+    }
+    if thread::panicking() {
+        return Err(SelfCheckError::Panicking);
+    }
+    if !hook_installed() {
+        return Err(SelfCheckError::HookNotInstalled);
+    }
+
+    if hook::is_replaced() == Some(true) {
+        return Err(SelfCheckError::HookReplaced);
+    }
+
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Never);
+    let res = hook::probe(|| {
+        context::scope(&options, || {
+            panic!("maybe_unwind self-check");
+        })
+    });
+    match res {
+        Err(Caught {
+            captured: Some(captured),
+            ..
+        }) if captured.location.is_some() => Ok(()),
+        _ => Err(SelfCheckError::NotCaptured),
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{frames, hook::PanicHookInfo, payload, unwind::Unwind};
+use ::sentry_core::protocol::{
+    Event, Exception, Frame as SentryFrame, Level, Mechanism, Stacktrace, Value,
+};
+
+/// The type of the mechanism of the events built by this crate.
+const MECHANISM: &str = "maybe_unwind";
+
+impl Unwind {
+    /// Build a Sentry event from the captured panic information.
+    ///
+    /// The event has a single exception of type `panic` whose value is the
+    /// panic message, with the mechanism `maybe_unwind` marked as handled.
+    /// The panic location is set as the culprit and as the last frame of the
+    /// stack trace, which is preceded by the frames of the captured backtrace
+    /// if available. The frames of the standard library and this crate are
+    /// marked as not in-app. The context frames are attached as the extra
+    /// data `context_frames`, and the result of [`classify`] as the tag
+    /// `panic.kind`.
+    ///
+    /// This is a pure data mapping that does not need a Sentry client, so
+    /// the event can be inspected or modified before it is passed to
+    /// `sentry::capture_event`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// let event = unwind.to_sentry_event();
+    /// assert_eq!(event.exception[0].value.as_deref(), Some("oops"));
+    /// assert_eq!(event.exception[0].mechanism.as_ref().unwrap().ty, "maybe_unwind");
+    /// ```
+    ///
+    /// [`classify`]: #method.classify
+    #[cfg_attr(docs, doc(cfg(feature = "sentry")))]
+    pub fn to_sentry_event(&self) -> Event<'static> {
+        let location = self
+            .location()
+            .map(|loc| (loc.file(), loc.line(), loc.column()));
+        let mut event = build_event(
+            self.message().into_owned(),
+            location,
+            self.backtrace_sentry_frames(),
+            true,
+        );
+        event
+            .tags
+            .insert("panic.kind".to_owned(), self.classify().as_str().to_owned());
+        if !self.context_frames().is_empty() {
+            event.extra.insert(
+                "context_frames".to_owned(),
+                Value::from(self.context_frames().to_vec()),
+            );
+        }
+        event
+    }
+
+    #[cfg(backtrace)]
+    fn backtrace_sentry_frames(&self) -> Vec<SentryFrame> {
+        match self.backtrace_frames() {
+            Some(frames) => frames
+                .map(|frame| {
+                    to_sentry_frame(
+                        frame.symbol_name(),
+                        frame.filename().map(|f| f.display().to_string()),
+                        frame.lineno(),
+                        !frame.is_internal(),
+                    )
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    #[cfg(not(backtrace))]
+    fn backtrace_sentry_frames(&self) -> Vec<SentryFrame> {
+        vec![]
+    }
+}
+
+/// Send the event of a panic that was not captured to the current Sentry hub.
+///
+/// The event is built in the same manner as `Unwind::to_sentry_event`,
+/// except that the mechanism is marked as not handled.
+pub(crate) fn report_uncaptured(info: &PanicHookInfo<'_>) {
+    let location = info
+        .location()
+        .map(|loc| (loc.file(), loc.line(), loc.column()));
+    let mut event = build_event(
+        payload::render(info.payload()).into_owned(),
+        location,
+        uncaptured_sentry_frames(),
+        false,
+    );
+    let context_frames = frames::snapshot();
+    if !context_frames.is_empty() {
+        event
+            .extra
+            .insert("context_frames".to_owned(), Value::from(context_frames));
+    }
+    ::sentry_core::capture_event(event);
+}
+
+#[cfg(backtrace)]
+fn uncaptured_sentry_frames() -> Vec<SentryFrame> {
+    use crate::backtrace::Frames;
+    use std::backtrace::{Backtrace, BacktraceStatus};
+
+    let backtrace = Backtrace::capture();
+    match backtrace.status() {
+        BacktraceStatus::Captured => Frames::new(&backtrace)
+            .map(|frame| {
+                to_sentry_frame(
+                    frame.symbol_name(),
+                    frame.filename().map(|f| f.display().to_string()),
+                    frame.lineno(),
+                    !frame.is_internal(),
+                )
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(not(backtrace))]
+fn uncaptured_sentry_frames() -> Vec<SentryFrame> {
+    vec![]
+}
+
+/// Build the event from the panic message, the location and the backtrace
+/// frames ordered from the innermost one.
+fn build_event(
+    message: String,
+    location: Option<(&str, u32, u32)>,
+    backtrace: Vec<SentryFrame>,
+    handled: bool,
+) -> Event<'static> {
+    // Sentry expects the frames ordered from the outermost one.
+    let mut frames: Vec<_> = backtrace.into_iter().rev().collect();
+    if let Some((file, line, column)) = location {
+        frames.push(SentryFrame {
+            filename: Some(file.to_owned()),
+            lineno: Some(line.into()),
+            colno: Some(column.into()),
+            in_app: Some(true),
+            ..Default::default()
+        });
+    }
+
+    Event {
+        level: if handled { Level::Error } else { Level::Fatal },
+        culprit: location.map(|(file, line, column)| format!("{}:{}:{}", file, line, column)),
+        exception: vec![Exception {
+            ty: "panic".to_owned(),
+            value: Some(message.clone()),
+            stacktrace: if frames.is_empty() {
+                None
+            } else {
+                Some(Stacktrace {
+                    frames,
+                    ..Default::default()
+                })
+            },
+            mechanism: Some(Mechanism {
+                ty: MECHANISM.to_owned(),
+                handled: Some(handled),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }]
+        .into(),
+        message: Some(message),
+        ..Default::default()
+    }
+}
+
+#[cfg(backtrace)]
+fn to_sentry_frame(
+    symbol_name: Option<&str>,
+    filename: Option<String>,
+    lineno: Option<u32>,
+    in_app: bool,
+) -> SentryFrame {
+    SentryFrame {
+        function: symbol_name.map(ToOwned::to_owned),
+        filename,
+        lineno: lineno.map(Into::into),
+        in_app: Some(in_app),
+        ..Default::default()
+    }
+}
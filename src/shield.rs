@@ -0,0 +1,214 @@
+use crate::{
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use std::{
+    panic::{AssertUnwindSafe, Location},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The options of the callbacks shielded from panics.
+///
+/// A shielded callback invokes the inner callback inside of a capture scope,
+/// hands the captured panic to `on_unwind` instead of propagating it, and
+/// returns `R::default()` for the panicked invocation. The methods named
+/// after the arity of the callbacks, e.g. [`fn_mut2`], create the shielded
+/// callbacks with these options. The shorthands [`shield_fn_mut`] and
+/// [`shield_fn`] cover the most common case of the callbacks with a single
+/// argument.
+///
+/// # Unwind safety
+///
+/// The callback is invoked again after a panic, so it is wrapped by
+/// `AssertUnwindSafe` internally, and the arguments of the panicked
+/// invocation are dropped inside of the scope. The state captured by
+/// the callback may be observed in a broken state by the next invocation,
+/// so it must be written to tolerate that, or stop being invoked by
+/// [`max_unwinds`] before the broken state matters.
+///
+/// # Re-entrancy
+///
+/// The callbacks created by the `fn*` methods can be re-entered, e.g. when
+/// the inner callback dispatches another event to the same event loop.
+/// Each invocation has its own capture scope, so a panic in the nested
+/// invocation is handed to `on_unwind` by the nested invocation, and the
+/// outer one continues normally. `on_unwind` is called outside of the scope,
+/// so a panic in it propagates to the caller.
+///
+/// [`fn_mut2`]: #method.fn_mut2
+/// [`shield_fn_mut`]: ./fn.shield_fn_mut.html
+/// [`shield_fn`]: ./fn.shield_fn.html
+/// [`max_unwinds`]: #method.max_unwinds
+#[derive(Debug, Clone, Default)]
+pub struct ShieldOptions {
+    max_unwinds: Option<usize>,
+    capture_options: CaptureOptions,
+}
+
+impl ShieldOptions {
+    /// Create a new `ShieldOptions` with the default values.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the number of the captured panics after which the inner
+    /// callback is no longer invoked.
+    ///
+    /// Once the limit is reached, the shielded callback returns `R::default()`
+    /// without invoking the inner callback. The inner callback is invoked
+    /// regardless of the number of panics by default.
+    #[inline]
+    pub fn max_unwinds(mut self, max: usize) -> Self {
+        self.max_unwinds = Some(max);
+        self
+    }
+
+    /// Specify the options for capturing the panic information of the callback.
+    #[inline]
+    pub fn capture_options(mut self, options: CaptureOptions) -> Self {
+        self.capture_options = options;
+        self
+    }
+
+    fn is_exhausted(&self, unwinds: usize) -> bool {
+        self.max_unwinds.map_or(false, |max| unwinds >= max)
+    }
+}
+
+macro_rules! shield_methods {
+    ($( ($fn_mut:ident, $fn:ident, $arity:literal; $($arg:ident: $ty:ident),*) )*) => {
+        impl ShieldOptions {$(
+            #[doc = concat!(
+                "Shield a `FnMut` callback with ", $arity, " from the panics.\n\n",
+                "See [`ShieldOptions`](./struct.ShieldOptions.html) for details."
+            )]
+            #[track_caller]
+            pub fn $fn_mut<$($ty,)* R, F, U>(self, mut callback: F, mut on_unwind: U) -> impl FnMut($($ty),*) -> R
+            where
+                F: FnMut($($ty),*) -> R,
+                U: FnMut(Unwind),
+                R: Default,
+            {
+                let caller = Location::caller();
+                let mut unwinds = 0;
+                move |$($arg),*| {
+                    if self.is_exhausted(unwinds) {
+                        return R::default();
+                    }
+                    let res = maybe_unwind_at(
+                        caller,
+                        &self.capture_options,
+                        AssertUnwindSafe(|| callback($($arg),*)),
+                    );
+                    match res {
+                        Ok(output) => output,
+                        Err(unwind) => {
+                            unwinds += 1;
+                            on_unwind(unwind);
+                            R::default()
+                        }
+                    }
+                }
+            }
+
+            #[doc = concat!(
+                "Shield a `Fn` callback with ", $arity, " from the panics.\n\n",
+                "See [`ShieldOptions`](./struct.ShieldOptions.html) for details."
+            )]
+            #[track_caller]
+            pub fn $fn<$($ty,)* R, F, U>(self, callback: F, on_unwind: U) -> impl Fn($($ty),*) -> R
+            where
+                F: Fn($($ty),*) -> R,
+                U: Fn(Unwind),
+                R: Default,
+            {
+                let caller = Location::caller();
+                let unwinds = AtomicUsize::new(0);
+                move |$($arg),*| {
+                    if self.is_exhausted(unwinds.load(Ordering::Acquire)) {
+                        return R::default();
+                    }
+                    let res = maybe_unwind_at(
+                        caller,
+                        &self.capture_options,
+                        AssertUnwindSafe(|| callback($($arg),*)),
+                    );
+                    match res {
+                        Ok(output) => output,
+                        Err(unwind) => {
+                            unwinds.fetch_add(1, Ordering::AcqRel);
+                            on_unwind(unwind);
+                            R::default()
+                        }
+                    }
+                }
+            }
+        )*}
+    };
+}
+
+shield_methods! {
+    (fn_mut0, fn0, "no arguments";)
+    (fn_mut1, fn1, "an argument"; a: A)
+    (fn_mut2, fn2, "two arguments"; a: A, b: B)
+    (fn_mut3, fn3, "three arguments"; a: A, b: B, c: C)
+}
+
+/// Shield a `FnMut` callback with an argument from the panics, e.g. an event
+/// handler passed to an event loop that cannot tolerate the panics.
+///
+/// Every invocation is run inside of a capture scope, and the captured panic
+/// is handed to `on_unwind` instead of propagating to the caller. The panicked
+/// invocation returns `R::default()`, and the next invocation calls the
+/// callback again. This is a shorthand of [`ShieldOptions::fn_mut1`], and
+/// the caveats about the unwind safety are described in [`ShieldOptions`].
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::shield_fn_mut;
+///
+/// maybe_unwind::set_hook();
+///
+/// let mut failures = vec![];
+/// let mut handler = shield_fn_mut(
+///     |event: u32| assert!(event % 3 != 0, "bad event {}", event),
+///     |unwind| failures.push(unwind.message().into_owned()),
+/// );
+/// for event in 1..=6 {
+///     handler(event);
+/// }
+/// drop(handler);
+/// assert_eq!(failures, ["bad event 3", "bad event 6"]);
+/// ```
+///
+/// [`ShieldOptions::fn_mut1`]: ./struct.ShieldOptions.html#method.fn_mut1
+/// [`ShieldOptions`]: ./struct.ShieldOptions.html
+#[track_caller]
+pub fn shield_fn_mut<A, R, F, U>(callback: F, on_unwind: U) -> impl FnMut(A) -> R
+where
+    F: FnMut(A) -> R,
+    U: FnMut(Unwind),
+    R: Default,
+{
+    ShieldOptions::new().fn_mut1(callback, on_unwind)
+}
+
+/// Shield a `Fn` callback with an argument from the panics.
+///
+/// This is the same as [`shield_fn_mut`], except that the shielded callback
+/// is `Fn`, and is `Send` and `Sync` if the callbacks are. This is a shorthand
+/// of [`ShieldOptions::fn1`].
+///
+/// [`shield_fn_mut`]: ./fn.shield_fn_mut.html
+/// [`ShieldOptions::fn1`]: ./struct.ShieldOptions.html#method.fn1
+#[track_caller]
+pub fn shield_fn<A, R, F, U>(callback: F, on_unwind: U) -> impl Fn(A) -> R
+where
+    F: Fn(A) -> R,
+    U: Fn(Unwind),
+    R: Default,
+{
+    ShieldOptions::new().fn1(callback, on_unwind)
+}
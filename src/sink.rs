@@ -0,0 +1,44 @@
+use std::{cell::RefCell, io};
+
+thread_local! {
+    /// The buffer receiving the output of the panic hook on this thread instead of stderr.
+    static BUFFER: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+}
+
+/// Write the output of the panic hook, into stderr unless it is being recorded.
+pub(crate) fn write_stderr<F>(f: F) -> io::Result<()>
+where
+    F: FnOnce(&mut dyn io::Write) -> io::Result<()>,
+{
+    let mut f = Some(f);
+    let recorded = BUFFER.try_with(|buffer| {
+        let mut buffer = buffer.try_borrow_mut().ok()?;
+        let buffer = buffer.as_mut()?;
+        f.take().map(|f| f(buffer))
+    });
+    match (recorded, f) {
+        (Ok(Some(res)), _) => res,
+        (_, Some(f)) => f(&mut io::stderr().lock()),
+        (_, None) => Ok(()),
+    }
+}
+
+/// Run `f`, recording the output of the panic hook on this thread instead of writing it into stderr.
+pub(crate) fn record<F, R>(f: F) -> (R, String)
+where
+    F: FnOnce() -> R,
+{
+    struct Guard(Option<Vec<u8>>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            let prev = self.0.take();
+            let _ = BUFFER.try_with(|buffer| *buffer.borrow_mut() = prev);
+        }
+    }
+
+    let _guard = Guard(BUFFER.with(|buffer| buffer.borrow_mut().replace(Vec::new())));
+    let res = f();
+    let output = BUFFER.with(|buffer| buffer.borrow_mut().take().unwrap_or_default());
+    (res, String::from_utf8_lossy(&output).into_owned())
+}
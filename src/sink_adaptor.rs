@@ -0,0 +1,175 @@
+use crate::{
+    error::MaybeUnwindError, futures::poll_maybe_unwind_at, options::CaptureOptions,
+    unwind::maybe_unwind_at,
+};
+use futures_core::task::{self, Poll};
+use futures_sink::Sink;
+use std::{
+    panic::{AssertUnwindSafe, Location, UnwindSafe},
+    pin::Pin,
+};
+
+/// A sink for the [`maybe_unwind`] method.
+///
+/// The errors of the inner sink are returned as [`MaybeUnwindError::Inner`].
+/// If the inner sink panics, the captured panic is returned as
+/// [`MaybeUnwindError::Unwind`] and the adaptor is terminated without
+/// touching the inner sink again, which may be left in a broken state by
+/// the panic: `poll_flush` and `poll_close` complete immediately, and
+/// `poll_ready` and `start_send` panic.
+///
+/// [`maybe_unwind`]: ./trait.SinkMaybeUnwindExt.html#method.maybe_unwind
+/// [`MaybeUnwindError::Inner`]: ./enum.MaybeUnwindError.html#variant.Inner
+/// [`MaybeUnwindError::Unwind`]: ./enum.MaybeUnwindError.html#variant.Unwind
+#[derive(Debug)]
+#[cfg_attr(docs, doc(cfg(feature = "sink")))]
+#[must_use = "sinks do nothing unless polled"]
+pub struct MaybeUnwindSink<S> {
+    inner: S,
+    caller: &'static Location<'static>,
+    panicked: bool,
+}
+
+impl<S> MaybeUnwindSink<S> {
+    /// Return the reference to the inner sink.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Return the mutable reference to the inner sink.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Return the pinned mutable reference to the inner sink.
+    #[inline]
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut S> {
+        // Safety: the inner sink is structurally pinned.
+        unsafe { self.map_unchecked_mut(|me| &mut me.inner) }
+    }
+
+    /// Poll the inner sink by `f`, unless it has already panicked.
+    fn poll_inner<F, E>(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        f: F,
+    ) -> Poll<Result<(), MaybeUnwindError<E>>>
+    where
+        F: FnOnce(Pin<&mut S>, &mut task::Context<'_>) -> Poll<Result<(), E>>,
+    {
+        let me = unsafe { self.get_unchecked_mut() };
+        if me.panicked {
+            return Poll::Ready(Ok(()));
+        }
+
+        let inner = unsafe { Pin::new_unchecked(&mut me.inner) };
+        match futures_core::ready!(poll_maybe_unwind_at(me.caller, cx, |cx| f(inner, cx))) {
+            Ok(res) => Poll::Ready(res.map_err(MaybeUnwindError::Inner)),
+            Err(unwind) => {
+                me.panicked = true;
+                Poll::Ready(Err(MaybeUnwindError::Unwind(unwind)))
+            }
+        }
+    }
+}
+
+impl<S, Item> Sink<Item> for MaybeUnwindSink<S>
+where
+    S: Sink<Item> + UnwindSafe,
+{
+    type Error = MaybeUnwindError<S::Error>;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        assert!(
+            !self.panicked,
+            "`MaybeUnwindSink` used after the inner sink panicked"
+        );
+        self.poll_inner(cx, |inner, cx| inner.poll_ready(cx))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
+        assert!(
+            !self.panicked,
+            "`MaybeUnwindSink` used after the inner sink panicked"
+        );
+        let me = unsafe { self.get_unchecked_mut() };
+        let inner = unsafe { Pin::new_unchecked(&mut me.inner) };
+        match maybe_unwind_at(
+            me.caller,
+            &CaptureOptions::default(),
+            AssertUnwindSafe(|| inner.start_send(item)),
+        ) {
+            Ok(res) => res.map_err(MaybeUnwindError::Inner),
+            Err(unwind) => {
+                me.panicked = true;
+                Err(MaybeUnwindError::Unwind(unwind))
+            }
+        }
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.poll_inner(cx, |inner, cx| inner.poll_flush(cx))
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.poll_inner(cx, |inner, cx| inner.poll_close(cx))
+    }
+}
+
+/// An extension trait for `Sink`s that provides an adaptor for capturing
+/// the unwinding panic information.
+#[cfg_attr(docs, doc(cfg(feature = "sink")))]
+pub trait SinkMaybeUnwindExt<Item>: Sink<Item> + Sized {
+    /// Catches unwinding panics while sending the items to the sink.
+    ///
+    /// The location where this method is called is recorded as
+    /// [`Unwind::caller`] of the captured panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_executor::block_on;
+    /// use futures_util::SinkExt as _;
+    /// use maybe_unwind::SinkMaybeUnwindExt as _;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let sink = futures_util::sink::drain().with(|n: u32| async move {
+    ///     assert!(n < 2, "too large: {}", n);
+    ///     Ok::<_, std::convert::Infallible>(n)
+    /// });
+    /// let mut sink = Box::pin(sink).maybe_unwind();
+    ///
+    /// block_on(async {
+    ///     sink.send(1).await.unwrap();
+    ///     let err = sink.send(2).await.unwrap_err();
+    ///     assert_eq!(err.unwind().unwrap().message(), "too large: 2");
+    /// });
+    /// ```
+    ///
+    /// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+    #[track_caller]
+    fn maybe_unwind(self) -> MaybeUnwindSink<Self>
+    where
+        Self: UnwindSafe,
+    {
+        MaybeUnwindSink {
+            inner: self,
+            caller: Location::caller(),
+            panicked: false,
+        }
+    }
+}
+
+impl<S: Sink<Item>, Item> SinkMaybeUnwindExt<Item> for S {}
@@ -0,0 +1,280 @@
+use crate::{
+    backtrace::BacktraceStatus,
+    display::DisplayOptions,
+    redact,
+    report::Report,
+    unwind::{Location, Unwind},
+};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+/// An owned summary of the captured panic.
+///
+/// Unlike `Unwind`, this type does not hold the panic payload itself,
+/// so it is `Send + Sync` and can be cloned freely, e.g. for sharing
+/// the test results across threads via `Arc`.
+/// The payload is rendered into a string when the snapshot is created,
+/// and the redactor registered by [`set_redactor`] is applied at that time.
+///
+/// [`set_redactor`]: ./fn.set_redactor.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnwindSnapshot {
+    pub(crate) message: String,
+    pub(crate) location: Option<Location>,
+    pub(crate) caller: Location,
+    pub(crate) context_frames: Vec<String>,
+    pub(crate) annotations: Vec<(String, String)>,
+    pub(crate) env: BTreeMap<String, Option<String>>,
+    pub(crate) thread_name: Option<String>,
+    pub(crate) timestamp: Option<SystemTime>,
+    pub(crate) elapsed: Option<Duration>,
+    pub(crate) notes: Vec<String>,
+    pub(crate) causes: Vec<String>,
+    pub(crate) panic_depth: u32,
+    pub(crate) scope_label: Option<String>,
+    pub(crate) backtrace: Option<String>,
+    pub(crate) backtrace_status: BacktraceStatus,
+    pub(crate) scope_backtrace: Option<String>,
+    pub(crate) max_message_len: Option<usize>,
+}
+
+impl UnwindSnapshot {
+    /// Create a snapshot with the specified message and caller location,
+    /// e.g. for testing the reporters that consume the snapshots.
+    ///
+    /// The other fields are left empty, and can be filled in with
+    /// [`with_location`] and [`with_backtrace`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{Location, UnwindSnapshot};
+    ///
+    /// let snapshot = UnwindSnapshot::new("oops", Location::new("src/main.rs", 10, 5))
+    ///     .with_location(Location::new("src/parser.rs", 88, 9));
+    /// assert_eq!(snapshot.message(), "oops");
+    /// assert_eq!(snapshot.location().unwrap().line(), 88);
+    /// ```
+    ///
+    /// [`with_location`]: #method.with_location
+    /// [`with_backtrace`]: #method.with_backtrace
+    pub fn new(message: impl Into<String>, caller: Location) -> Self {
+        Self {
+            message: message.into(),
+            location: None,
+            caller,
+            context_frames: vec![],
+            annotations: vec![],
+            env: BTreeMap::new(),
+            thread_name: None,
+            timestamp: None,
+            elapsed: None,
+            notes: vec![],
+            causes: vec![],
+            panic_depth: 1,
+            scope_label: None,
+            backtrace: None,
+            backtrace_status: BacktraceStatus::Disabled,
+            scope_backtrace: None,
+            max_message_len: None,
+        }
+    }
+
+    /// Set the location from which the panic originated.
+    #[inline]
+    pub fn with_location(mut self, location: Location) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// Set the text representation of the stack backtrace,
+    /// in the format of the `Display` of `std::backtrace::Backtrace`.
+    #[inline]
+    pub fn with_backtrace(mut self, backtrace: impl Into<String>) -> Self {
+        self.backtrace = Some(backtrace.into());
+        self.backtrace_status = BacktraceStatus::Captured;
+        self
+    }
+
+    /// Return the string representation of the panic payload.
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return the information about the location from which the panic originated.
+    #[inline]
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// Return the location where the capture scope was established.
+    #[inline]
+    pub fn caller(&self) -> &Location {
+        &self.caller
+    }
+
+    /// Return the annotations that were active when the panic occurred.
+    #[inline]
+    pub fn context_frames(&self) -> &[String] {
+        &self.context_frames
+    }
+
+    /// Return the key-value annotations attached at the moment of the capture.
+    #[inline]
+    pub fn annotations(&self) -> &[(String, String)] {
+        &self.annotations
+    }
+
+    /// Return the environment variables recorded at the moment of the capture.
+    ///
+    /// See [`Unwind::env`] for details.
+    ///
+    /// [`Unwind::env`]: ./struct.Unwind.html#method.env
+    #[inline]
+    pub fn env(&self) -> &BTreeMap<String, Option<String>> {
+        &self.env
+    }
+
+    /// Return the name of the thread on which the panic occurred.
+    #[inline]
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    /// Return the time when the panic information was captured.
+    #[inline]
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// Return the time spent inside the capture scope until the panic was caught.
+    #[inline]
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    /// Return the notes about the problems that occurred while capturing.
+    #[inline]
+    pub fn capture_notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    /// Return the source chain of the error carried by the panic payload.
+    ///
+    /// See [`Unwind::payload_as_error`] for details.
+    ///
+    /// [`Unwind::payload_as_error`]: ./struct.Unwind.html#method.payload_as_error
+    #[inline]
+    pub fn causes(&self) -> &[String] {
+        &self.causes
+    }
+
+    /// Return the status of the captured stack backtrace.
+    #[inline]
+    pub fn backtrace_status(&self) -> BacktraceStatus {
+        self.backtrace_status
+    }
+
+    /// Return whether the panic occurred while another panic was in flight.
+    #[inline]
+    pub fn is_secondary_panic(&self) -> bool {
+        self.panic_depth > 1
+    }
+
+    /// Return the number of panics in flight when the panic occurred, including itself.
+    #[inline]
+    pub fn panic_depth(&self) -> u32 {
+        self.panic_depth
+    }
+
+    /// Return the label attached to the scope by [`set_scope_label`]
+    /// or [`maybe_unwind_named`].
+    ///
+    /// [`set_scope_label`]: ./fn.set_scope_label.html
+    /// [`maybe_unwind_named`]: ./fn.maybe_unwind_named.html
+    #[inline]
+    pub fn scope_label(&self) -> Option<&str> {
+        self.scope_label.as_deref()
+    }
+
+    /// Return the text representation of the captured stack backtrace.
+    #[inline]
+    pub fn backtrace(&self) -> Option<&str> {
+        self.backtrace.as_deref()
+    }
+
+    /// Return the text representation of the stack backtrace captured at the entry of the scope.
+    ///
+    /// See [`Unwind::scope_backtrace`] for details.
+    ///
+    /// [`Unwind::scope_backtrace`]: ./struct.Unwind.html#method.scope_backtrace
+    #[inline]
+    pub fn scope_backtrace(&self) -> Option<&str> {
+        self.scope_backtrace.as_deref()
+    }
+
+    pub(crate) fn report(&self) -> Report<'_> {
+        Report {
+            message: Cow::Borrowed(&self.message),
+            location: self.location.as_ref(),
+            caller: &self.caller,
+            context_frames: Cow::Borrowed(&self.context_frames),
+            annotations: Cow::Borrowed(&self.annotations),
+            notes: Cow::Borrowed(&self.notes),
+            causes: Cow::Borrowed(&self.causes),
+            panic_depth: self.panic_depth,
+            scope_label: self.scope_label.as_deref(),
+            span_trace: None,
+            backtrace: self.backtrace.as_deref(),
+            backtrace_status: self.backtrace_status,
+            scope_backtrace: self.scope_backtrace.as_deref(),
+            max_message_len: self.max_message_len,
+        }
+    }
+}
+
+impl fmt::Display for UnwindSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return f.write_str(&self.message);
+        }
+        self.report().write(f, None, &DisplayOptions::default())
+    }
+}
+
+impl Unwind {
+    /// Create an owned summary of this panic.
+    pub fn to_snapshot(&self) -> UnwindSnapshot {
+        UnwindSnapshot {
+            message: self.message().into_owned(),
+            location: self.location().cloned(),
+            caller: self.caller().clone(),
+            context_frames: redact::redact_all(self.context_frames()).into_owned(),
+            annotations: redact::redact_annotations(self.annotations()).into_owned(),
+            env: self.env().into_owned(),
+            thread_name: self.thread_name().map(ToOwned::to_owned),
+            timestamp: self.timestamp(),
+            elapsed: self.elapsed(),
+            notes: self.report_notes().into_owned(),
+            causes: self.causes(),
+            panic_depth: self.panic_depth(),
+            scope_label: self.scope_label().map(ToOwned::to_owned),
+            backtrace: self.backtrace_text(),
+            backtrace_status: self.backtrace_status(),
+            scope_backtrace: self.scope_backtrace_text(),
+            max_message_len: self.max_message_len(),
+        }
+    }
+}
+
+impl From<Unwind> for UnwindSnapshot {
+    #[inline]
+    fn from(unwind: Unwind) -> Self {
+        unwind.to_snapshot()
+    }
+}
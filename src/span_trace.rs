@@ -0,0 +1,69 @@
+use crate::{captured::CapturedInfo, options::CaptureOptions, unwind::Unwind};
+use tracing_error::{SpanTrace, SpanTraceStatus};
+
+/// Capture the span trace of the current thread, unless disabled by the options.
+pub(crate) fn capture(options: &CaptureOptions) -> Option<SpanTrace> {
+    if options.skip_span_trace {
+        return None;
+    }
+    let span_trace = SpanTrace::capture();
+    // Without `ErrorLayer` or outside of the spans, nothing is worth keeping.
+    if span_trace.status() == SpanTraceStatus::CAPTURED {
+        Some(span_trace)
+    } else {
+        None
+    }
+}
+
+impl CapturedInfo {
+    /// Return the span trace captured by the panic hook.
+    ///
+    /// See [`Unwind::span_trace`] for details.
+    ///
+    /// [`Unwind::span_trace`]: ./struct.Unwind.html#method.span_trace
+    #[cfg_attr(docs, doc(cfg(feature = "tracing-error")))]
+    #[inline]
+    pub fn span_trace(&self) -> Option<&SpanTrace> {
+        self.span_trace.as_ref()
+    }
+}
+
+impl Unwind {
+    /// Return the `SpanTrace` of the `tracing` spans entered at the moment of the panic.
+    ///
+    /// The span trace is captured by the panic hook unless disabled by
+    /// [`CaptureOptions::capture_span_trace`], and is rendered under the
+    /// `span trace:` heading of the alternate `Display`. This method returns
+    /// `None` if the panic information is not captured, if the subscriber is
+    /// not configured with `tracing_error::ErrorLayer`, or if the panic
+    /// occurred outside of the spans.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    /// use tracing_error::ErrorLayer;
+    /// use tracing_subscriber::prelude::*;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let subscriber = tracing_subscriber::registry().with(ErrorLayer::default());
+    /// tracing::subscriber::with_default(subscriber, || {
+    ///     let unwind = maybe_unwind(|| {
+    ///         let _span = tracing::info_span!("handle_request").entered();
+    ///         panic!("oops");
+    ///     })
+    ///     .unwrap_err();
+    ///
+    ///     let span_trace = unwind.span_trace().unwrap();
+    ///     assert!(span_trace.to_string().contains("handle_request"));
+    /// });
+    /// ```
+    ///
+    /// [`CaptureOptions::capture_span_trace`]: ./struct.CaptureOptions.html#method.capture_span_trace
+    #[cfg_attr(docs, doc(cfg(feature = "tracing-error")))]
+    #[inline]
+    pub fn span_trace(&self) -> Option<&SpanTrace> {
+        self.captured().and_then(CapturedInfo::span_trace)
+    }
+}
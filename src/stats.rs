@@ -0,0 +1,265 @@
+use crate::{
+    captured::{CaptureError, CaptureStatus, CapturedInfo},
+    hook::PanicHookInfo,
+    json::{json_duration, json_location, json_option, json_str, json_strs},
+    payload, redact,
+    unwind::Location,
+};
+use std::{
+    ffi::OsString,
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The default size of the stats file at which it is rotated.
+const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+
+static PANICS: AtomicUsize = AtomicUsize::new(0);
+static CAPTURED: AtomicUsize = AtomicUsize::new(0);
+static SUPPRESSED: AtomicUsize = AtomicUsize::new(0);
+
+static STATS_FILE: Mutex<Option<StatsFile>> = Mutex::new(None);
+
+/// Whether the stats file is set, so that the hook does not lock `STATS_FILE` otherwise.
+static PERSISTED: AtomicBool = AtomicBool::new(false);
+
+struct StatsFile {
+    path: PathBuf,
+    max_bytes: u64,
+    /// The JSON object of the last captured panic.
+    last_captured: Option<String>,
+}
+
+fn lock_stats_file() -> MutexGuard<'static, Option<StatsFile>> {
+    STATS_FILE.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// The counters of the panics processed by the panic hook.
+///
+/// This value is returned from [`panic_stats`].
+///
+/// [`panic_stats`]: ./fn.panic_stats.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct PanicStats {
+    /// The number of the panics passed to [`capture_panic_info`].
+    ///
+    /// [`capture_panic_info`]: ./fn.capture_panic_info.html
+    pub panics: usize,
+
+    /// The number of the panics captured by the capture scopes.
+    pub captured: usize,
+
+    /// The number of the panics only counted by the capture scopes, since
+    /// they reached the limit of [`CaptureOptions::max_captures_per_scope`].
+    ///
+    /// [`CaptureOptions::max_captures_per_scope`]: ./struct.CaptureOptions.html#method.max_captures_per_scope
+    pub suppressed: usize,
+}
+
+impl PanicStats {
+    /// Return the number of the panics that were neither captured nor suppressed.
+    #[inline]
+    pub fn uncaptured(&self) -> usize {
+        self.panics
+            .saturating_sub(self.captured)
+            .saturating_sub(self.suppressed)
+    }
+}
+
+/// Return the counters of the panics processed by the panic hook in this process.
+///
+/// The panics are counted by [`capture_panic_info`], so the panic hook must
+/// be installed by [`set_hook`] or a custom hook calling `capture_panic_info`.
+/// Nothing is counted if the `capture` feature is disabled.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, panic_stats};
+///
+/// maybe_unwind::set_hook();
+///
+/// let before = panic_stats();
+/// let _ = maybe_unwind(|| panic!("oops"));
+/// assert_eq!(panic_stats().captured, before.captured + 1);
+/// ```
+///
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+/// [`set_hook`]: ./fn.set_hook.html
+pub fn panic_stats() -> PanicStats {
+    // `PANICS` is read last, so that every panic counted by the others is
+    // also counted by `PANICS`, see `record`.
+    let captured = CAPTURED.load(Ordering::Acquire);
+    let suppressed = SUPPRESSED.load(Ordering::Acquire);
+    let panics = PANICS.load(Ordering::Relaxed);
+    PanicStats {
+        panics,
+        captured,
+        suppressed,
+    }
+}
+
+/// Append the panic stats to the file at `path` every time a panic is processed.
+///
+/// The panic hook appends a line of JSON to the file for every panic, so that
+/// the trail of the panics survives an abort right after, e.g. by a double
+/// panic or by `std::process::abort` in a dependency, which loses everything
+/// kept in memory. The object has the following fields:
+///
+/// * `pid`: the process ID
+/// * `timestamp`: the time of the panic as `{"secs": .., "nanos": ..}`
+///   relative to the UNIX epoch, or `null` without the system clock
+/// * `panics`, `captured` and `suppressed`: the counters returned from
+///   [`panic_stats`], including this panic
+/// * `thread`: the name of the panicking thread, or `null`
+/// * `message`: the panic message with the redactor applied
+/// * `location`: the panic location as `{"file": .., "line": .., "column": ..}`,
+///   or `null`
+/// * `last_captured`: the last captured panic as an object of `message`,
+///   `location`, `thread`, `scope_label` and `context`, or `null`
+///
+/// The file is rotated once it reaches 1 MiB, by renaming it with the `.1`
+/// suffix and replacing the previously rotated one. Use
+/// [`persist_stats_on_panic_with`] to specify the size. The writes are best-effort,
+/// and the I/O errors are ignored since they are made inside of the panic hook.
+///
+/// The panics are processed by [`capture_panic_info`], so the panic hook
+/// must be installed by [`set_hook`] or a custom hook calling `capture_panic_info`.
+/// Calling this function again replaces the file.
+///
+/// # Example
+///
+/// ```no_run
+/// maybe_unwind::set_hook();
+/// maybe_unwind::persist_stats_on_panic("target/panic-stats.jsonl");
+/// ```
+///
+/// [`panic_stats`]: ./fn.panic_stats.html
+/// [`persist_stats_on_panic_with`]: ./fn.persist_stats_on_panic_with.html
+/// [`capture_panic_info`]: ./fn.capture_panic_info.html
+/// [`set_hook`]: ./fn.set_hook.html
+pub fn persist_stats_on_panic(path: impl Into<PathBuf>) {
+    persist_stats_on_panic_with(path, DEFAULT_MAX_BYTES);
+}
+
+/// Append the panic stats to the file at `path` every time a panic is processed,
+/// rotating the file once its size reaches `max_bytes`.
+///
+/// See [`persist_stats_on_panic`] for details.
+///
+/// [`persist_stats_on_panic`]: ./fn.persist_stats_on_panic.html
+pub fn persist_stats_on_panic_with(path: impl Into<PathBuf>, max_bytes: u64) {
+    *lock_stats_file() = Some(StatsFile {
+        path: path.into(),
+        max_bytes: max_bytes.max(1),
+        last_captured: None,
+    });
+    PERSISTED.store(true, Ordering::Release);
+}
+
+/// Remember the captured panic for the next line of the stats file, if enabled.
+pub(crate) fn remember_captured(captured: &CapturedInfo) {
+    if !PERSISTED.load(Ordering::Acquire) {
+        return;
+    }
+    if let Some(file) = &mut *lock_stats_file() {
+        file.last_captured = Some(captured_json(captured));
+    }
+}
+
+/// Count the panic processed by the panic hook, and append the line to the stats file if enabled.
+pub(crate) fn record(
+    info: &PanicHookInfo<'_>,
+    status: &Result<CaptureStatus, CaptureError>,
+    timestamp: Option<SystemTime>,
+) {
+    let panics = PANICS.fetch_add(1, Ordering::Relaxed) + 1;
+    let captured = match status {
+        // Released after `PANICS` is incremented, for `panic_stats`.
+        Ok(CaptureStatus::Captured) => CAPTURED.fetch_add(1, Ordering::Release) + 1,
+        _ => CAPTURED.load(Ordering::Relaxed),
+    };
+    let suppressed = match status {
+        Ok(CaptureStatus::Suppressed) => SUPPRESSED.fetch_add(1, Ordering::Release) + 1,
+        _ => SUPPRESSED.load(Ordering::Relaxed),
+    };
+
+    if !PERSISTED.load(Ordering::Acquire) {
+        return;
+    }
+    let guard = lock_stats_file();
+    let file = match &*guard {
+        Some(file) => file,
+        None => return,
+    };
+
+    let mut line = String::new();
+    let _ = write!(line, "{{\"pid\":{},\"timestamp\":", process::id());
+    json_option(
+        &mut line,
+        timestamp
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .as_ref(),
+        json_duration,
+    );
+    let _ = write!(
+        line,
+        ",\"panics\":{},\"captured\":{},\"suppressed\":{}",
+        panics, captured, suppressed
+    );
+    line.push_str(",\"thread\":");
+    json_option(&mut line, thread::current().name(), json_str);
+    line.push_str(",\"message\":");
+    json_str(&mut line, &redact::redact(&payload::render(info.payload())));
+    line.push_str(",\"location\":");
+    json_option(
+        &mut line,
+        info.location().map(Location::from_std).as_ref(),
+        json_location,
+    );
+    line.push_str(",\"last_captured\":");
+    json_option(&mut line, file.last_captured.as_deref(), |out, s| {
+        out.push_str(s)
+    });
+    line.push_str("}\n");
+
+    // The errors are ignored since this is called inside of the panic hook.
+    let _ = append(&file.path, file.max_bytes, &line);
+}
+
+fn captured_json(captured: &CapturedInfo) -> String {
+    let mut out = String::new();
+    out.push_str("{\"message\":");
+    json_str(&mut out, &captured.message());
+    out.push_str(",\"location\":");
+    json_option(&mut out, captured.location.as_ref(), json_location);
+    out.push_str(",\"thread\":");
+    json_option(&mut out, captured.thread_name(), json_str);
+    out.push_str(",\"scope_label\":");
+    json_option(&mut out, captured.scope_label.as_deref(), json_str);
+    out.push_str(",\"context\":");
+    json_strs(&mut out, &redact::redact_all(&captured.context_frames));
+    out.push('}');
+    out
+}
+
+/// Append the line to the file, after rotating the file if it reached `max_bytes`.
+fn append(path: &Path, max_bytes: u64, line: &str) -> io::Result<()> {
+    if fs::metadata(path).map_or(false, |meta| meta.len() >= max_bytes) {
+        let mut rotated = OsString::from(path);
+        rotated.push(".1");
+        fs::rename(path, rotated)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())
+}
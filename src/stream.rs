@@ -0,0 +1,142 @@
+use crate::{futures::poll_maybe_unwind_at, unwind::Unwind};
+use futures_core::{
+    stream::{FusedStream, Stream},
+    task::{self, Poll},
+};
+use std::{
+    panic::{Location, UnwindSafe},
+    pin::Pin,
+};
+
+/// A stream for the [`maybe_unwind`] method.
+///
+/// Each item of the inner stream is yielded as `Ok(item)`. If polling the
+/// inner stream panics, the captured panic is yielded as `Err(unwind)` and
+/// the adaptor is terminated: it yields `None` afterwards without touching
+/// the inner stream, which may be left in a broken state by the panic.
+///
+/// [`maybe_unwind`]: ./trait.StreamMaybeUnwindExt.html#method.maybe_unwind
+#[derive(Debug)]
+#[cfg_attr(docs, doc(cfg(feature = "stream")))]
+#[must_use = "streams do nothing unless polled"]
+pub struct MaybeUnwindStream<S> {
+    inner: S,
+    caller: &'static Location<'static>,
+    panicked: bool,
+}
+
+impl<S> MaybeUnwindStream<S> {
+    /// Return the reference to the inner stream.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Return the mutable reference to the inner stream.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Return the pinned mutable reference to the inner stream.
+    #[inline]
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut S> {
+        // Safety: the inner stream is structurally pinned.
+        unsafe { self.map_unchecked_mut(|me| &mut me.inner) }
+    }
+}
+
+impl<S> Stream for MaybeUnwindStream<S>
+where
+    S: Stream + UnwindSafe,
+{
+    type Item = Result<S::Item, Unwind>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = unsafe { self.get_unchecked_mut() };
+        if me.panicked {
+            return Poll::Ready(None);
+        }
+
+        let inner = unsafe { Pin::new_unchecked(&mut me.inner) };
+        match futures_core::ready!(poll_maybe_unwind_at(me.caller, cx, |cx| inner.poll_next(cx))) {
+            Ok(item) => Poll::Ready(item.map(Ok)),
+            Err(unwind) => {
+                me.panicked = true;
+                Poll::Ready(Some(Err(unwind)))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.panicked {
+            return (0, Some(0));
+        }
+        // A panic yields an error in place of the item being polled and ends
+        // the stream early, so only the first item is guaranteed.
+        let (lower, upper) = self.inner.size_hint();
+        (lower.min(1), upper)
+    }
+}
+
+impl<S> FusedStream for MaybeUnwindStream<S>
+where
+    S: FusedStream + UnwindSafe,
+{
+    fn is_terminated(&self) -> bool {
+        self.panicked || self.inner.is_terminated()
+    }
+}
+
+/// An extension trait for `Stream`s that provides an adaptor for capturing
+/// the unwinding panic information.
+#[cfg_attr(docs, doc(cfg(feature = "stream")))]
+pub trait StreamMaybeUnwindExt: Stream + Sized {
+    /// Catches unwinding panics while polling the stream.
+    ///
+    /// This is a variant of [`catch_unwind`] that also captures
+    /// the panic information. The location where this method is called
+    /// is recorded as [`Unwind::caller`] of the captured panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_executor::block_on_stream;
+    /// use maybe_unwind::StreamMaybeUnwindExt as _;
+    /// # use futures_core::{Stream, task::{Context, Poll}};
+    /// # use std::pin::Pin;
+    /// # struct Countdown(u32);
+    /// # impl Stream for Countdown {
+    /// #     type Item = u32;
+    /// #     fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<u32>> {
+    /// #         self.0 -= 1;
+    /// #         Poll::Ready(Some(10 / self.0))
+    /// #     }
+    /// # }
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// // yields 5, 10 and then panics with a division by zero.
+    /// let stream = Countdown(3).maybe_unwind();
+    /// let items: Vec<_> = block_on_stream(stream).collect();
+    /// assert_eq!(items.len(), 3);
+    /// assert_eq!(items[1].as_ref().ok(), Some(&10));
+    /// assert!(items[2].is_err());
+    /// ```
+    ///
+    /// [`catch_unwind`]: https://docs.rs/futures/0.3/futures/stream/trait.StreamExt.html#method.catch_unwind
+    /// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+    #[track_caller]
+    fn maybe_unwind(self) -> MaybeUnwindStream<Self>
+    where
+        Self: UnwindSafe,
+    {
+        MaybeUnwindStream {
+            inner: self,
+            caller: Location::caller(),
+            panicked: false,
+        }
+    }
+}
+
+impl<S: Stream> StreamMaybeUnwindExt for S {}
@@ -0,0 +1,559 @@
+use crate::{
+    context,
+    json::{json_duration, json_option, json_str},
+    options::CaptureOptions,
+    snapshot::UnwindSnapshot,
+    unwind::{maybe_unwind_timed_at, now},
+};
+use std::{
+    fmt::{self, Write as _},
+    panic::{AssertUnwindSafe, Location},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+type CaseFn<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+struct Case<'a> {
+    name: String,
+    caller: &'static Location<'static>,
+    f: CaseFn<'a>,
+}
+
+/// A runner of the named cases, each invoked in its own capture scope.
+///
+/// This is a convenience layer for the harnesses that run a batch of
+/// closures and report the outcomes, not a test framework: the cases are
+/// registered by [`case`] and run by [`finish`], which returns a
+/// [`SuiteSummary`] of the outcomes. Each case is invoked as if by
+/// [`maybe_unwind_named`] with the name of the case as the scope label.
+///
+/// As with [`maybe_unwind_each`], the closures are not required to be
+/// `UnwindSafe`. They are required to be `Send` so that the cases can be
+/// run on the worker threads in the [`parallel`] mode.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::SuiteRunner;
+///
+/// maybe_unwind::set_hook();
+///
+/// let mut runner = SuiteRunner::new("arith");
+/// runner
+///     .case("add", || assert_eq!(1 + 1, 2))
+///     .case("sub", || assert_eq!(1 - 1, 1));
+/// let summary = runner.finish();
+///
+/// assert_eq!((summary.passed(), summary.failed()), (1, 1));
+/// assert!(summary.to_junit_xml().contains("<testcase name=\"sub\""));
+/// ```
+///
+/// [`case`]: #method.case
+/// [`finish`]: #method.finish
+/// [`parallel`]: #method.parallel
+/// [`SuiteSummary`]: ./struct.SuiteSummary.html
+/// [`maybe_unwind_named`]: ./fn.maybe_unwind_named.html
+/// [`maybe_unwind_each`]: ./fn.maybe_unwind_each.html
+pub struct SuiteRunner<'a> {
+    name: String,
+    fail_fast: bool,
+    threads: usize,
+    capture: CaptureOptions,
+    cases: Vec<Case<'a>>,
+}
+
+impl fmt::Debug for SuiteRunner<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SuiteRunner")
+            .field("name", &self.name)
+            .field("fail_fast", &self.fail_fast)
+            .field("threads", &self.threads)
+            .field("capture", &self.capture)
+            .field(
+                "cases",
+                &self.cases.iter().map(|case| &case.name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<'a> SuiteRunner<'a> {
+    /// Create a new runner of the suite with the specified name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            fail_fast: false,
+            threads: 1,
+            capture: CaptureOptions::default(),
+            cases: vec![],
+        }
+    }
+
+    /// Specify whether to skip the remaining cases after the first failure.
+    ///
+    /// In the parallel mode, the cases already running on the other workers
+    /// are completed, and the cases not started yet are skipped.
+    #[inline]
+    pub fn fail_fast(mut self, enabled: bool) -> Self {
+        self.fail_fast = enabled;
+        self
+    }
+
+    /// Specify the number of the worker threads running the cases.
+    ///
+    /// The workers are spawned as scoped threads by [`finish`], so the cases
+    /// may borrow the data outside of the runner. The outcomes are reported
+    /// in the order the cases were registered regardless of the order they
+    /// completed. The default value is `1`, which runs the cases on the
+    /// current thread one by one.
+    ///
+    /// [`finish`]: #method.finish
+    #[inline]
+    pub fn parallel(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Specify the options for capturing the panic information of the cases.
+    #[inline]
+    pub fn capture_options(mut self, options: CaptureOptions) -> Self {
+        self.capture = options;
+        self
+    }
+
+    /// Register a case with the specified name.
+    ///
+    /// The location where this method is called is recorded as
+    /// [`Unwind::caller`] of the panic in the case.
+    ///
+    /// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+    #[track_caller]
+    pub fn case<F>(&mut self, name: impl Into<String>, f: F) -> &mut Self
+    where
+        F: FnOnce() + Send + 'a,
+    {
+        self.cases.push(Case {
+            name: name.into(),
+            caller: Location::caller(),
+            f: Box::new(f),
+        });
+        self
+    }
+
+    /// Run the registered cases and return the summary of the outcomes.
+    pub fn finish(self) -> SuiteSummary {
+        let Self {
+            name,
+            fail_fast,
+            threads,
+            capture,
+            cases,
+        } = self;
+        let start = now();
+        let cases = if threads > 1 && cases.len() > 1 {
+            run_parallel(cases, threads, fail_fast, &capture)
+        } else {
+            let mut failed = false;
+            cases
+                .into_iter()
+                .map(|case| {
+                    if failed && fail_fast {
+                        return CaseResult::skipped(case.name);
+                    }
+                    let res = run_case(case, &capture);
+                    failed |= res.is_failed();
+                    res
+                })
+                .collect()
+        };
+        SuiteSummary {
+            name,
+            cases,
+            duration: start.map(|start| start.elapsed()),
+        }
+    }
+}
+
+fn run_case(case: Case<'_>, options: &CaptureOptions) -> CaseResult {
+    let Case { name, caller, f } = case;
+    let label = name.clone();
+    let (duration, res) = maybe_unwind_timed_at(
+        caller,
+        options,
+        AssertUnwindSafe(move || {
+            context::set_scope_label(label);
+            f()
+        }),
+    );
+    CaseResult {
+        name,
+        outcome: match res {
+            Ok(()) => CaseOutcome::Passed,
+            Err(unwind) => CaseOutcome::Failed(Box::new(unwind.to_snapshot())),
+        },
+        duration,
+    }
+}
+
+fn run_parallel(
+    cases: Vec<Case<'_>>,
+    threads: usize,
+    fail_fast: bool,
+    options: &CaptureOptions,
+) -> Vec<CaseResult> {
+    let len = cases.len();
+    let queue: Vec<_> = cases
+        .into_iter()
+        .map(|case| Mutex::new(Some(case)))
+        .collect();
+    let results: Vec<Mutex<Option<CaseResult>>> = (0..len).map(|_| Mutex::new(None)).collect();
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        for _ in 0..threads.min(len) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= len {
+                    break;
+                }
+                let case = lock(&queue[i]).take().expect("the case has been taken");
+                let res = if fail_fast && failed.load(Ordering::SeqCst) {
+                    CaseResult::skipped(case.name)
+                } else {
+                    let res = run_case(case, options);
+                    if res.is_failed() {
+                        failed.store(true, Ordering::SeqCst);
+                    }
+                    res
+                };
+                *lock(&results[i]) = Some(res);
+            });
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|res| {
+            res.into_inner()
+                .unwrap_or_else(|e| e.into_inner())
+                .expect("the case has not been run")
+        })
+        .collect()
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The outcome of a case run by [`SuiteRunner`].
+///
+/// [`SuiteRunner`]: ./struct.SuiteRunner.html
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum CaseOutcome {
+    /// The case returned without panicking.
+    Passed,
+
+    /// The case panicked.
+    Failed(Box<UnwindSnapshot>),
+
+    /// The case was not run since a previous case failed in the fail-fast mode.
+    Skipped,
+}
+
+/// The result of a case run by [`SuiteRunner`].
+///
+/// [`SuiteRunner`]: ./struct.SuiteRunner.html
+#[derive(Debug, Clone)]
+pub struct CaseResult {
+    name: String,
+    outcome: CaseOutcome,
+    duration: Option<Duration>,
+}
+
+impl CaseResult {
+    fn skipped(name: String) -> Self {
+        Self {
+            name,
+            outcome: CaseOutcome::Skipped,
+            duration: None,
+        }
+    }
+
+    /// Return the name of the case.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the outcome of the case.
+    #[inline]
+    pub fn outcome(&self) -> &CaseOutcome {
+        &self.outcome
+    }
+
+    /// Return the time the case took to complete.
+    ///
+    /// This value is not available for the skipped cases, or on the platforms
+    /// where the time cannot be measured.
+    #[inline]
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Return the snapshot of the panic, if the case failed.
+    #[inline]
+    pub fn unwind(&self) -> Option<&UnwindSnapshot> {
+        match &self.outcome {
+            CaseOutcome::Failed(snapshot) => Some(snapshot),
+            _ => None,
+        }
+    }
+
+    /// Return whether the case returned without panicking.
+    #[inline]
+    pub fn is_passed(&self) -> bool {
+        matches!(self.outcome, CaseOutcome::Passed)
+    }
+
+    /// Return whether the case panicked.
+    #[inline]
+    pub fn is_failed(&self) -> bool {
+        matches!(self.outcome, CaseOutcome::Failed(..))
+    }
+
+    /// Return whether the case was skipped.
+    #[inline]
+    pub fn is_skipped(&self) -> bool {
+        matches!(self.outcome, CaseOutcome::Skipped)
+    }
+
+    fn outcome_name(&self) -> &'static str {
+        match self.outcome {
+            CaseOutcome::Passed => "passed",
+            CaseOutcome::Failed(..) => "failed",
+            CaseOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+/// The summary of the cases run by [`SuiteRunner::finish`].
+///
+/// The `Display` output is a one-line count of the outcomes followed by
+/// a line for each failed case, and the alternate `Display` output is
+/// followed by the full report of each failure.
+///
+/// [`SuiteRunner::finish`]: ./struct.SuiteRunner.html#method.finish
+#[derive(Debug, Clone)]
+pub struct SuiteSummary {
+    name: String,
+    cases: Vec<CaseResult>,
+    duration: Option<Duration>,
+}
+
+impl SuiteSummary {
+    /// Return the name of the suite.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the results of the cases, in the order they were registered.
+    #[inline]
+    pub fn cases(&self) -> &[CaseResult] {
+        &self.cases
+    }
+
+    /// Return an iterator over the results of the failed cases.
+    pub fn failures(&self) -> impl Iterator<Item = &CaseResult> + '_ {
+        self.cases.iter().filter(|case| case.is_failed())
+    }
+
+    /// Return the time the whole suite took to complete.
+    #[inline]
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Return the number of the cases.
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.cases.len()
+    }
+
+    /// Return the number of the passed cases.
+    pub fn passed(&self) -> usize {
+        self.cases.iter().filter(|case| case.is_passed()).count()
+    }
+
+    /// Return the number of the failed cases.
+    pub fn failed(&self) -> usize {
+        self.cases.iter().filter(|case| case.is_failed()).count()
+    }
+
+    /// Return the number of the skipped cases.
+    pub fn skipped(&self) -> usize {
+        self.cases.iter().filter(|case| case.is_skipped()).count()
+    }
+
+    /// Return whether no case failed.
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Render this summary as a JSON object, terminated by a newline.
+    ///
+    /// The object contains the name and the counts of the suite, and the
+    /// results of the cases. The panic of a failed case is written as the
+    /// object of [`UnwindSnapshot::to_json_line`].
+    ///
+    /// [`UnwindSnapshot::to_json_line`]: ./struct.UnwindSnapshot.html#method.to_json_line
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        out.push_str("{\"name\":");
+        json_str(&mut out, &self.name);
+        let _ = write!(
+            out,
+            ",\"total\":{},\"passed\":{},\"failed\":{},\"skipped\":{}",
+            self.total(),
+            self.passed(),
+            self.failed(),
+            self.skipped()
+        );
+        out.push_str(",\"duration\":");
+        json_option(&mut out, self.duration.as_ref(), json_duration);
+        out.push_str(",\"cases\":[");
+        for (i, case) in self.cases.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            json_str(&mut out, &case.name);
+            out.push_str(",\"outcome\":");
+            json_str(&mut out, case.outcome_name());
+            out.push_str(",\"duration\":");
+            json_option(&mut out, case.duration.as_ref(), json_duration);
+            out.push_str(",\"unwind\":");
+            match case.unwind() {
+                Some(snapshot) => out.push_str(snapshot.to_json_line().trim_end()),
+                None => out.push_str("null"),
+            }
+            out.push('}');
+        }
+        out.push_str("]}\n");
+        out
+    }
+
+    /// Render this summary as a JUnit XML report.
+    ///
+    /// The report consists of a `<testsuite>` element with a `<testcase>`
+    /// element for each case. The failed cases have the `file` and `line`
+    /// attributes of the panic location and a `<failure>` element containing
+    /// the full report of the panic, and the skipped cases have a `<skipped/>`
+    /// element.
+    pub fn to_junit_xml(&self) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<testsuite name=\"");
+        xml_escape(&mut out, &self.name);
+        let _ = write!(
+            out,
+            "\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\"",
+            self.total(),
+            self.failed(),
+            self.skipped()
+        );
+        xml_time(&mut out, self.duration);
+        out.push_str(">\n");
+        for case in &self.cases {
+            out.push_str("  <testcase name=\"");
+            xml_escape(&mut out, &case.name);
+            out.push_str("\" classname=\"");
+            xml_escape(&mut out, &self.name);
+            out.push('"');
+            xml_time(&mut out, case.duration);
+            match &case.outcome {
+                CaseOutcome::Passed => out.push_str("/>\n"),
+                CaseOutcome::Skipped => out.push_str(">\n    <skipped/>\n  </testcase>\n"),
+                CaseOutcome::Failed(snapshot) => {
+                    let map = snapshot.to_map();
+                    for key in ["file", "line"] {
+                        if !map[key].is_empty() {
+                            let _ = write!(out, " {}=\"", key);
+                            xml_escape(&mut out, &map[key]);
+                            out.push('"');
+                        }
+                    }
+                    out.push_str(">\n    <failure message=\"");
+                    xml_escape(&mut out, &map["message"]);
+                    out.push_str("\">");
+                    xml_escape(&mut out, format!("{:#}", snapshot).trim_end());
+                    out.push_str("</failure>\n  </testcase>\n");
+                }
+            }
+        }
+        out.push_str("</testsuite>\n");
+        out
+    }
+}
+
+impl fmt::Display for SuiteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} passed, {} failed, {} skipped",
+            self.name,
+            self.passed(),
+            self.failed(),
+            self.skipped()
+        )?;
+        for case in self.failures() {
+            write!(f, "\n  {}: panicked", case.name)?;
+            if let Some(snapshot) = case.unwind() {
+                if let Some(location) = snapshot.location() {
+                    write!(f, " at {}", location)?;
+                }
+                write!(f, ": {}", snapshot.message())?;
+            }
+        }
+
+        if f.alternate() {
+            for case in self.failures() {
+                write!(f, "\n\n--- {} ---\n", case.name)?;
+                if let Some(snapshot) = case.unwind() {
+                    let report = format!("{:#}", snapshot);
+                    f.write_str(report.trim_end())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn xml_time(out: &mut String, duration: Option<Duration>) {
+    if let Some(duration) = duration {
+        let _ = write!(out, " time=\"{:.3}\"", duration.as_secs_f64());
+    }
+}
+
+fn xml_escape(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' | '\r' | '\t' => out.push(c),
+            // The other control characters are not allowed in XML 1.0.
+            c if (c as u32) < 0x20 => out.push('\u{fffd}'),
+            c => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Utilities for testing the panic hooks built on top of this crate.
+
+use crate::{
+    hook::{capture_panic_info, install_hook_fn, PanicHookInfo},
+    sink,
+    unwind::payload_str,
+};
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    thread::{self, ThreadId},
+};
+
+static RECORDING: Mutex<()> = Mutex::new(());
+
+/// An invocation of the panic hook recorded by [`with_recorded_hook`].
+///
+/// [`with_recorded_hook`]: ./fn.with_recorded_hook.html
+#[derive(Debug, Clone)]
+pub struct RecordedHookEvent {
+    captured: bool,
+    output: String,
+    thread_id: ThreadId,
+    thread_name: Option<String>,
+}
+
+impl RecordedHookEvent {
+    /// Return whether the panic information was captured by `maybe_unwind`.
+    #[inline]
+    pub fn captured(&self) -> bool {
+        self.captured
+    }
+
+    /// Return the output that would have been written into the standard error.
+    ///
+    /// This is the message printed by the fallback for the panics that were
+    /// not captured, in the same format as the default panic hook but without
+    /// the note about the backtrace, and the report of a double panic written
+    /// by the reporter installed by [`install_abort_reporter`]. It is empty if
+    /// nothing would have been written.
+    ///
+    /// [`install_abort_reporter`]: ../fn.install_abort_reporter.html
+    #[inline]
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Return the identifier of the thread on which the panic occurred.
+    #[inline]
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// Return the name of the thread on which the panic occurred.
+    #[inline]
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+}
+
+/// Runs a closure with the capturing panic hook that records its invocations
+/// instead of writing into the standard error.
+///
+/// The installed hook behaves as the one installed by [`set_hook`], except
+/// that the output of the fallback for the panics that were not captured is
+/// recorded into the returned events rather than being printed by the
+/// previous hook. The previous hook is restored before returning, even if
+/// the closure panics.
+///
+/// Since the panic hook is process-global, the panics on the other threads
+/// while running the closure are also recorded, and the calls of this
+/// function on multiple threads are serialized. If this function is called
+/// while the current thread is panicking, the hook cannot be replaced and
+/// no events are recorded.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, testing::with_recorded_hook};
+/// use std::panic;
+///
+/// let (_, events) = with_recorded_hook(|| {
+///     let _ = maybe_unwind(|| panic!("captured"));
+///     let _ = panic::catch_unwind(|| panic!("not captured"));
+/// });
+///
+/// assert!(events[0].captured());
+/// assert_eq!(events[0].output(), "");
+/// assert!(!events[1].captured());
+/// assert!(events[1].output().ends_with(":\nnot captured\n"));
+/// ```
+///
+/// [`set_hook`]: ../fn.set_hook.html
+pub fn with_recorded_hook<F, R>(f: F) -> (R, Vec<RecordedHookEvent>)
+where
+    F: FnOnce() -> R,
+{
+    let _lock = RECORDING.lock().unwrap_or_else(|err| err.into_inner());
+
+    let events = Arc::new(Mutex::new(vec![]));
+    let guard = install_hook_fn({
+        let events = Arc::clone(&events);
+        move |info, _prev| {
+            let (captured, output) = sink::record(|| {
+                let captured = capture_panic_info(info);
+                if !captured {
+                    let _ = sink::write_stderr(|w| write_fallback(w, info));
+                }
+                captured
+            });
+            let thread = thread::current();
+            let event = RecordedHookEvent {
+                captured,
+                output,
+                thread_id: thread.id(),
+                thread_name: thread.name().map(ToOwned::to_owned),
+            };
+            events
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .push(event);
+        }
+    });
+    let res = f();
+    drop(guard);
+
+    let events = std::mem::take(&mut *events.lock().unwrap_or_else(|err| err.into_inner()));
+    (res, events)
+}
+
+fn write_fallback(w: &mut dyn io::Write, info: &PanicHookInfo<'_>) -> io::Result<()> {
+    let thread = thread::current();
+    let name = thread.name().unwrap_or("<unnamed>");
+    match info.location() {
+        Some(loc) => writeln!(w, "thread '{}' panicked at {}:", name, loc)?,
+        None => writeln!(w, "thread '{}' panicked:", name)?,
+    }
+    writeln!(w, "{}", payload_str(info.payload()))
+}
@@ -0,0 +1,18 @@
+//! The control of the capture on the current thread, and the helpers running
+//! the closures on the other threads.
+//!
+//! The items in this module are also re-exported from the crate root.
+
+pub use crate::{
+    context::{
+        current_scope_label, disable_for_current_thread, enable_for_current_thread, in_scope,
+        is_enabled, scope_depth, set_scope_label,
+    },
+    drop_guard::DropGuard,
+    frames::with_context,
+    shield::{shield_fn, shield_fn_mut, ShieldOptions},
+};
+
+#[cfg(feature = "rayon")]
+#[cfg_attr(docs, doc(cfg(feature = "rayon")))]
+pub use crate::rayon::join_maybe_unwind;
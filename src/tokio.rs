@@ -0,0 +1,73 @@
+use crate::{
+    futures::MaybeUnwind,
+    options::CaptureOptions,
+    unwind::{maybe_unwind_at, Unwind},
+};
+use ::tokio::task::{self, JoinHandle};
+use std::{
+    future::Future,
+    panic::{AssertUnwindSafe, Location},
+};
+
+/// Spawn a future onto the Tokio runtime, capturing the panic information if it panics.
+///
+/// The task is polled by [`MaybeUnwind`], so a panic inside of it is returned
+/// as `Ok(Err(unwind))` from the `JoinHandle`, rather than as the `JoinError`
+/// that carries only the payload. The `JoinError` is still returned if the
+/// task is cancelled. The location where this function is called is recorded
+/// as [`Unwind::caller`].
+///
+/// The future is wrapped by `AssertUnwindSafe`, since the task is not
+/// polled again after a panic.
+///
+/// This function must be called from the context of a Tokio runtime,
+/// as with `tokio::spawn`.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::spawn_maybe_unwind;
+///
+/// maybe_unwind::set_hook();
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// rt.block_on(async {
+///     let res = spawn_maybe_unwind(async { panic!("oops") }).await.unwrap();
+///     assert_eq!(res.unwrap_err().message(), "oops");
+/// });
+/// ```
+///
+/// [`MaybeUnwind`]: ./struct.MaybeUnwind.html
+/// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
+#[cfg_attr(docs, doc(cfg(feature = "tokio")))]
+#[track_caller]
+pub fn spawn_maybe_unwind<F>(future: F) -> JoinHandle<Result<F::Output, Unwind>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    task::spawn(MaybeUnwind::new_at(
+        AssertUnwindSafe(future),
+        Location::caller(),
+    ))
+}
+
+/// Run a blocking closure on the blocking thread pool of the Tokio runtime,
+/// capturing the panic information if it panics.
+///
+/// This is the blocking variant of [`spawn_maybe_unwind`], built on
+/// `tokio::task::spawn_blocking`.
+///
+/// [`spawn_maybe_unwind`]: ./fn.spawn_maybe_unwind.html
+#[cfg_attr(docs, doc(cfg(feature = "tokio")))]
+#[track_caller]
+pub fn spawn_blocking_maybe_unwind<F, R>(f: F) -> JoinHandle<Result<R, Unwind>>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let caller = Location::caller();
+    task::spawn_blocking(move || {
+        maybe_unwind_at(caller, &CaptureOptions::default(), AssertUnwindSafe(f))
+    })
+}
@@ -1,45 +1,508 @@
-use crate::{backtrace::Backtrace, context::Context};
+//! The capture scopes and the captured panics.
+//!
+//! The items in this module are also re-exported from the crate root.
+
+#[cfg(backtrace)]
+use crate::backtrace::{Backtrace, Frames};
+use crate::{
+    backtrace,
+    context::{self, Caught},
+    display::DisplayOptions,
+    hook, location,
+    normalize::NormalizeOptions,
+    observer, payload, poison, redact,
+    report::Report,
+};
 use std::{
     any::Any,
-    fmt,
-    panic::{self, UnwindSafe},
+    borrow::Cow,
+    collections::BTreeMap,
+    error::Error,
+    fmt, mem,
+    panic::{self, AssertUnwindSafe, UnwindSafe},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+pub use crate::{
+    backtrace::BacktraceStatus,
+    captured::{CaptureMiss, CapturedInfo},
+    classify::PanicKind,
+    expect::{expect_unwind, ExpectError, PayloadPattern},
+    location::{interner_stats, set_interner_capacity, InternerStats},
+    options::{BacktraceMode, CaptureOptions, LocationPolicy},
+    pattern::{ParsePatternError, UnwindPattern},
+    resolver::{spawn_backtrace_resolver, ResolvedBacktrace, ResolverHandle},
+    snapshot::UnwindSnapshot,
+    unwind_set::UnwindSet,
 };
 
+#[cfg(backtrace)]
+#[cfg_attr(docs, doc(cfg(feature = "backtrace")))]
+pub use crate::backtrace::Frame;
+
 /// Invokes a closure, capturing the cause of an unwinding panic if one occurs.
 ///
 /// In addition, this function also captures the panic information if the custom
 /// panic hook is set. If the panic hook is not set, only the cause of unwinding
 /// panic captured by `catch_unwind` is returned.
+///
+/// The location where this function is called is always recorded,
+/// and can be retrieved by [`Unwind::caller`].
+///
+/// [`Unwind::caller`]: ./struct.Unwind.html#method.caller
 #[inline]
+#[track_caller]
 pub fn maybe_unwind<F, R>(f: F) -> Result<R, Unwind>
 where
     F: FnOnce() -> R + UnwindSafe,
 {
-    let mut captured: Option<Captured> = None;
+    maybe_unwind_with(CaptureOptions::default(), f)
+}
+
+/// Invokes a closure with the specified capture options, capturing the cause
+/// of an unwinding panic if one occurs.
+///
+/// The options only affect the panics that occur in this scope.
+/// If another `maybe_unwind` call is nested inside the closure,
+/// the panics inside of it are captured according to its own options.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind_with, BacktraceMode, CaptureOptions};
+///
+/// std::panic::set_hook(Box::new(|info| {
+///     maybe_unwind::capture_panic_info(info);
+/// }));
+///
+/// let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+/// let res = maybe_unwind_with(options, || do_something());
+/// # drop(res);
+/// # fn do_something() {}
+/// ```
+#[inline]
+#[track_caller]
+pub fn maybe_unwind_with<F, R>(options: CaptureOptions, f: F) -> Result<R, Unwind>
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    maybe_unwind_at(panic::Location::caller(), &options, f)
+}
+
+/// Invokes a closure through a mutable reference, capturing the cause of an
+/// unwinding panic if one occurs.
+///
+/// This is the same as [`maybe_unwind`], except that the closure is borrowed
+/// rather than consumed, so the same closure can be invoked again after a
+/// captured panic, e.g. in a driver loop.
+///
+/// The closure is not required to be `UnwindSafe`, since it is wrapped by
+/// `AssertUnwindSafe` internally. It is the caller's responsibility to deal
+/// with the state observed after a panic: the variables captured by the
+/// closure may be left half-updated by the panicked invocation, and the next
+/// invocation sees them as they are.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_mut;
+///
+/// maybe_unwind::set_hook();
+///
+/// let mut count = 0;
+/// let mut poll = || {
+///     count += 1;
+///     assert!(count != 2, "flaky");
+///     count
+/// };
+/// assert_eq!(maybe_unwind_mut(&mut poll).unwrap(), 1);
+/// assert!(maybe_unwind_mut(&mut poll).is_err());
+/// assert_eq!(maybe_unwind_mut(&mut poll).unwrap(), 3);
+/// ```
+///
+/// [`maybe_unwind`]: ./fn.maybe_unwind.html
+#[inline]
+#[track_caller]
+pub fn maybe_unwind_mut<F, R>(f: &mut F) -> Result<R, Unwind>
+where
+    F: FnMut() -> R,
+{
+    maybe_unwind_mut_at(panic::Location::caller(), &CaptureOptions::default(), f)
+}
+
+/// Invokes a closure, capturing the cause of an unwinding panic and measuring
+/// the time spent in it.
+///
+/// This is the same as [`maybe_unwind`], except that the time from entering
+/// the capture scope until the closure returns or the panic is caught is
+/// returned together with the result, and is also available from
+/// [`Unwind::elapsed`]. The measurement is not performed by `maybe_unwind`
+/// since reading the clock is much more expensive than the scope itself.
+///
+/// The elapsed time is not available on the platforms without a monotonic
+/// clock, such as `wasm32-unknown-unknown`.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_timed;
+///
+/// maybe_unwind::set_hook();
+///
+/// let (elapsed, res) = maybe_unwind_timed(|| panic!("timeout"));
+/// assert!(res.is_err());
+/// println!("failed after {:?}", elapsed);
+/// ```
+///
+/// [`maybe_unwind`]: ./fn.maybe_unwind.html
+/// [`Unwind::elapsed`]: ./struct.Unwind.html#method.elapsed
+#[inline]
+#[track_caller]
+pub fn maybe_unwind_timed<F, R>(f: F) -> (Option<Duration>, Result<R, Unwind>)
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    maybe_unwind_timed_at(panic::Location::caller(), &CaptureOptions::default(), f)
+}
+
+/// Invokes a closure in a capture scope with the specified name, capturing
+/// the cause of an unwinding panic if one occurs.
+///
+/// The name is attached to the scope as if [`set_scope_label`] was called at
+/// the beginning of the closure, and is available from [`Unwind::scope_label`]
+/// and the header of the alternate `Display` output. The name of a nested
+/// scope overrides the outer one while the nested scope is active, and the
+/// outer name is restored when it exits. The nested scopes without a name
+/// inherit the name of the outer scope.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_named;
+///
+/// maybe_unwind::set_hook();
+///
+/// let unwind = maybe_unwind_named("suite::case", || panic!("oops")).unwrap_err();
+/// assert_eq!(unwind.scope_label(), Some("suite::case"));
+/// assert!(format!("{:#}", unwind).starts_with("panicked in suite::case at "));
+/// ```
+///
+/// [`set_scope_label`]: ./fn.set_scope_label.html
+/// [`Unwind::scope_label`]: ./struct.Unwind.html#method.scope_label
+#[inline]
+#[track_caller]
+pub fn maybe_unwind_named<F, R>(name: impl Into<Cow<'static, str>>, f: F) -> Result<R, Unwind>
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    let name = name.into();
+    maybe_unwind_at(
+        panic::Location::caller(),
+        &CaptureOptions::default(),
+        move || {
+            context::set_scope_label(name);
+            f()
+        },
+    )
+}
+
+/// Invokes a closure in a capture scope associated with the specified data,
+/// capturing the cause of an unwinding panic if one occurs.
+///
+/// The data is typed, unlike the name of [`maybe_unwind_named`], so that the
+/// consumers of the captured panics, e.g. a test harness, can tell which scope
+/// a panic belongs to without parsing a string. If the closure panics, the data
+/// is moved into the `Unwind` and can be retrieved by [`Unwind::scope_data`] or
+/// [`Unwind::take_scope_data`]. Otherwise, it is returned with the result of
+/// the closure.
+///
+/// The data belongs only to this scope: the `Unwind` captured by a nested
+/// scope carries the data of the nested scope, or nothing.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::maybe_unwind_with_data;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct TestId(u32);
+///
+/// maybe_unwind::set_hook();
+///
+/// let (output, id) = maybe_unwind_with_data(TestId(1), || 42).unwrap();
+/// assert_eq!((output, id), (42, TestId(1)));
+///
+/// let mut unwind = maybe_unwind_with_data(TestId(2), || panic!("oops")).unwrap_err();
+/// assert_eq!(unwind.scope_data::<TestId>(), Some(&TestId(2)));
+/// assert_eq!(unwind.take_scope_data::<TestId>(), Some(TestId(2)));
+/// assert_eq!(unwind.scope_data::<TestId>(), None);
+/// ```
+///
+/// [`maybe_unwind_named`]: ./fn.maybe_unwind_named.html
+/// [`Unwind::scope_data`]: ./struct.Unwind.html#method.scope_data
+/// [`Unwind::take_scope_data`]: ./struct.Unwind.html#method.take_scope_data
+#[inline]
+#[track_caller]
+pub fn maybe_unwind_with_data<D, F, R>(data: D, f: F) -> Result<(R, D), Unwind>
+where
+    D: Any + Send + 'static,
+    F: FnOnce() -> R + UnwindSafe,
+{
+    match maybe_unwind_at(panic::Location::caller(), &CaptureOptions::default(), f) {
+        Ok(output) => Ok((output, data)),
+        Err(mut unwind) => {
+            unwind.scope_data = Some(Box::new(Box::new(data)));
+            Err(unwind)
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn maybe_unwind_at<F, R>(
+    caller: &'static panic::Location<'static>,
+    options: &CaptureOptions,
+    f: F,
+) -> Result<R, Unwind>
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    maybe_unwind_impl(caller, options, None, f).1
+}
+
+#[inline]
+pub(crate) fn maybe_unwind_mut_at<F, R>(
+    caller: &'static panic::Location<'static>,
+    options: &CaptureOptions,
+    f: &mut F,
+) -> Result<R, Unwind>
+where
+    F: FnMut() -> R,
+{
+    maybe_unwind_impl(caller, options, None, AssertUnwindSafe(f)).1
+}
+
+#[inline]
+pub(crate) fn maybe_unwind_timed_at<F, R>(
+    caller: &'static panic::Location<'static>,
+    options: &CaptureOptions,
+    f: F,
+) -> (Option<Duration>, Result<R, Unwind>)
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    maybe_unwind_impl(caller, options, now(), f)
+}
 
-    let mut ctx = Context {
-        captured: &mut captured,
-    };
+#[inline]
+fn maybe_unwind_impl<F, R>(
+    caller: &'static panic::Location<'static>,
+    options: &CaptureOptions,
+    start: Option<Instant>,
+    f: F,
+) -> (Option<Duration>, Result<R, Unwind>)
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    let scope_backtrace = backtrace::capture_at_scope_entry(options);
+    let res = payload::with_scope_describer(options.describer.as_ref(), || {
+        context::scope(options, f).map_err(|caught| {
+            let captured_message = caught.captured.as_ref().and_then(|c| c.message.as_deref());
+            let description =
+                payload::describe_unknown(&*caught.payload, captured_message).map(Into::into);
+            (caught, description)
+        })
+    });
+    let elapsed = start.map(|start| start.elapsed());
+    let res = res.map_err(|(caught, description)| {
+        into_unwind(
+            caller,
+            options,
+            elapsed,
+            caught,
+            description,
+            scope_backtrace,
+        )
+    });
+    (elapsed, res)
+}
 
-    let res = with_set_ctx!(&mut ctx, { panic::catch_unwind(f) });
+/// Build the `Unwind` from the caught panic, which is out of the path of
+/// the calls that do not panic.
+#[cold]
+#[inline(never)]
+fn into_unwind(
+    caller: &'static panic::Location<'static>,
+    options: &CaptureOptions,
+    elapsed: Option<Duration>,
+    caught: Caught,
+    description: Option<Box<str>>,
+    scope_backtrace: Option<backtrace::Backtrace>,
+) -> Unwind {
+    let Caught {
+        mut payload,
+        mut captured,
+        failure,
+        entered,
+    } = caught;
 
-    res.map_err(|payload| Unwind {
+    let mut capture_miss = None;
+    match captured.as_mut() {
+        Some(captured) => {
+            if let Some(err) = failure {
+                // The failure belongs to another panic in the same scope.
+                captured.notes.push(format!(
+                    "another panic in the scope was not captured: {}",
+                    err
+                ));
+            }
+            captured.scope_backtrace = scope_backtrace;
+            observer::notify(captured);
+            #[cfg(feature = "history")]
+            crate::history::record(captured);
+        }
+        None => {
+            // The payload created by `into_resumable` has been rethrown
+            // without calling the hook, so restore the original information.
+            payload = match Unwind::from_resumed(payload) {
+                Ok(unwind) => return unwind,
+                Err(payload) => payload,
+            };
+            capture_miss = Some(match failure {
+                _ if !cfg!(feature = "capture") => CaptureMiss::FeatureDisabled,
+                _ if !entered => CaptureMiss::DisabledForThread,
+                Some(err) => CaptureMiss::Failed(err),
+                None => hook::diagnose_miss(),
+            });
+        }
+    }
+
+    Unwind {
         payload,
-        captured: captured.take(),
-    })
+        description,
+        caller: Location::from(caller),
+        captured,
+        capture_miss,
+        scope_data: None,
+        elapsed,
+        max_message_len: options.max_message_len,
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) fn now() -> Option<Instant> {
+    Some(Instant::now())
+}
+
+// `Instant::now` panics on this target.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) fn now() -> Option<Instant> {
+    None
 }
 
 /// The captured information about an unwinding panic.
+///
+/// As with the panic payload returned from `catch_unwind`, this type is
+/// `Send` but neither `Sync` nor `UnwindSafe`. Use [`to_snapshot`] to share
+/// the captured information across threads.
+///
+/// [`to_snapshot`]: #method.to_snapshot
 #[derive(Debug)]
 pub struct Unwind {
     payload: Box<dyn Any + Send + 'static>,
-    captured: Option<Captured>,
+    /// The description of the payload unknown to the crate, by the payload describer.
+    description: Option<Box<str>>,
+    caller: Location,
+    captured: Option<Box<CapturedInfo>>,
+    capture_miss: Option<CaptureMiss>,
+    /// The data attached by `maybe_unwind_with_data`, boxed twice to keep `Unwind` small.
+    scope_data: Option<Box<Box<dyn Any + Send + 'static>>>,
+    elapsed: Option<Duration>,
+    max_message_len: Option<usize>,
 }
 
+/// The metadata of an `Unwind` separated from the panic payload by [`Unwind::split`].
+///
+/// [`Unwind::split`]: ./struct.Unwind.html#method.split
 #[derive(Debug)]
-pub(crate) struct Captured {
-    pub(crate) location: Option<Location>,
-    pub(crate) backtrace: Option<Backtrace>,
+pub struct UnwindMeta {
+    caller: Location,
+    captured: Option<Box<CapturedInfo>>,
+    capture_miss: Option<CaptureMiss>,
+    scope_data: Option<Box<Box<dyn Any + Send + 'static>>>,
+    elapsed: Option<Duration>,
+    max_message_len: Option<usize>,
+}
+
+impl UnwindMeta {
+    /// Return the information about the location from which the panic originated.
+    #[inline]
+    pub fn location(&self) -> Option<&Location> {
+        self.captured.as_ref()?.location.as_ref()
+    }
+
+    /// Return the location where the capture scope was established.
+    #[inline]
+    pub fn caller(&self) -> &Location {
+        &self.caller
+    }
+
+    /// Return the panic information captured by the panic hook.
+    #[inline]
+    pub fn captured(&self) -> Option<&CapturedInfo> {
+        self.captured.as_deref()
+    }
+
+    /// Combine the metadata with the specified payload into an `Unwind`.
+    #[inline]
+    pub fn rewrap(self, payload: Box<dyn Any + Send + 'static>) -> Unwind {
+        Unwind {
+            description: payload::describe_unknown(
+                &*payload,
+                self.captured.as_ref().and_then(|c| c.message.as_deref()),
+            )
+            .map(Into::into),
+            payload,
+            caller: self.caller,
+            captured: self.captured,
+            capture_miss: self.capture_miss,
+            scope_data: self.scope_data,
+            elapsed: self.elapsed,
+            max_message_len: self.max_message_len,
+        }
+    }
+}
+
+/// The payload created by [`Unwind::into_resumable`].
+///
+/// [`Unwind::into_resumable`]: ./struct.Unwind.html#method.into_resumable
+pub(crate) struct Resumed(Unwind);
+
+impl Resumed {
+    pub(crate) fn unwind(&self) -> &Unwind {
+        &self.0
+    }
+}
+
+/// The string representation of the payloads of the unknown types.
+pub(crate) const UNKNOWN_PAYLOAD: &str = "Box<dyn Any>";
+
+pub(crate) fn payload_str(payload: &(dyn Any + Send)) -> &str {
+    if let Some(resumed) = payload.downcast_ref::<Resumed>() {
+        return resumed.unwind().payload_str();
+    }
+    downcast_str(payload).unwrap_or(UNKNOWN_PAYLOAD)
+}
+
+/// Downcast the payload of the string types, in the order documented in `Unwind::payload_str`.
+pub(crate) fn downcast_str(payload: &(dyn Any + Send)) -> Option<&str> {
+    (payload.downcast_ref::<&str>().copied())
+        .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
+        .or_else(|| payload.downcast_ref::<Cow<'static, str>>().map(|s| &**s))
+        .or_else(|| payload.downcast_ref::<Box<str>>().map(|s| &**s))
+        .or_else(|| payload.downcast_ref::<Arc<str>>().map(|s| &**s))
+        .or_else(|| payload.downcast_ref::<Arc<String>>().map(|s| s.as_str()))
 }
 
 impl Unwind {
@@ -50,12 +513,86 @@ impl Unwind {
     }
 
     /// Return the string representation of the panic payload.
+    ///
+    /// The returned value is the raw payload string, so the redactor
+    /// registered by [`set_redactor`] is not applied. Use [`message`]
+    /// for rendering the payload into reports.
+    ///
+    /// The string is looked up in the following order, which is kept stable:
+    ///
+    /// 1. The payload of the string types, namely `&'static str`, `String`,
+    ///    `Cow<'static, str>`, `Box<str>`, `Arc<str>` and `Arc<String>`
+    ///    (the payload must be `Send`, so `Rc` never appears).
+    /// 2. The description by the payload describer (see [`set_payload_describer`]).
+    /// 3. The message captured by the panic hook, e.g. after the payload was
+    ///    replaced by [`map_payload`]. This is skipped if the payload is
+    ///    rendered by the formatters (see [`register_payload_formatter`]),
+    ///    which never affect the value of this method.
+    /// 4. The placeholder `"Box<dyn Any>"`.
+    ///
+    /// [`set_redactor`]: ./fn.set_redactor.html
+    /// [`message`]: #method.message
+    /// [`set_payload_describer`]: ./fn.set_payload_describer.html
+    /// [`map_payload`]: #method.map_payload
+    /// [`register_payload_formatter`]: ./fn.register_payload_formatter.html
     #[inline]
     pub fn payload_str(&self) -> &str {
-        let payload = self.payload();
-        (payload.downcast_ref::<&str>().copied())
-            .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
-            .unwrap_or_else(|| "Box<dyn Any>")
+        match &self.description {
+            Some(description) => description,
+            None => payload_str(self.payload()),
+        }
+    }
+
+    /// Return the panic message to be rendered into reports.
+    ///
+    /// This is the string representation of the panic payload, rendered by
+    /// the formatter registered by [`register_payload_formatter`] if any,
+    /// with the redactor registered by [`set_redactor`] applied.
+    ///
+    /// [`register_payload_formatter`]: ./fn.register_payload_formatter.html
+    /// [`set_redactor`]: ./fn.set_redactor.html
+    #[inline]
+    pub fn message(&self) -> Cow<'_, str> {
+        match self.render_payload() {
+            Cow::Borrowed(message) => redact::redact(message),
+            Cow::Owned(message) => Cow::Owned(redact::redact(&message).into_owned()),
+        }
+    }
+
+    /// Render the payload without the redactor applied.
+    pub(crate) fn render_payload(&self) -> Cow<'_, str> {
+        payload::render_known(self.payload()).unwrap_or_else(|| Cow::Borrowed(self.payload_str()))
+    }
+
+    /// Return the error value carried by the panic payload.
+    ///
+    /// The payloads of the types `Box<dyn Error + Send + Sync>` and
+    /// `Box<dyn Error + Send>`, and of the types registered by
+    /// [`register_payload_error`] are recognized. The source chain of the
+    /// error is rendered as the `caused by:` lines in the alternate `Display`
+    /// output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    /// use std::{error::Error, panic::panic_any};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| {
+    ///     let err: Box<dyn Error + Send + Sync> = "oops".into();
+    ///     panic_any(err);
+    /// })
+    /// .unwrap_err();
+    /// assert_eq!(unwind.payload_as_error().unwrap().to_string(), "oops");
+    /// assert_eq!(unwind.message(), "oops");
+    /// ```
+    ///
+    /// [`register_payload_error`]: ./fn.register_payload_error.html
+    #[inline]
+    pub fn payload_as_error(&self) -> Option<&(dyn Error + 'static)> {
+        payload::as_error(self.payload())
     }
 
     /// Convert itself into a trait object of the panic payload.
@@ -64,64 +601,893 @@ impl Unwind {
         self.payload
     }
 
+    /// Convert itself into the string representation of the panic payload.
+    ///
+    /// This is the consuming variant of [`payload_str`], and returns the same
+    /// string. The payload of `String` or `Box<str>` is returned without
+    /// copying it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    /// use std::{borrow::Cow, panic::panic_any};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic_any(Cow::<str>::Borrowed("oops"))).unwrap_err();
+    /// assert_eq!(unwind.into_string(), "oops");
+    /// ```
+    ///
+    /// [`payload_str`]: #method.payload_str
+    pub fn into_string(mut self) -> String {
+        let payload = mem::replace(&mut self.payload, Box::new(()));
+        let payload = match payload.downcast::<String>() {
+            Ok(s) => return *s,
+            Err(payload) => payload,
+        };
+        let payload = match payload.downcast::<Box<str>>() {
+            Ok(s) => return s.into_string(),
+            Err(payload) => payload,
+        };
+        self.payload = payload;
+        self.payload_str().to_owned()
+    }
+
+    /// Replace the panic payload with the value returned from `f`, keeping the
+    /// captured information.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    ///
+    /// #[derive(Debug)]
+    /// enum Failure {
+    ///     Panicked(String),
+    /// }
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// let message = unwind.payload_str().to_owned();
+    /// let unwind = unwind.map_payload(|_| Failure::Panicked(message));
+    /// assert!(unwind.payload().is::<Failure>());
+    /// assert!(unwind.location().is_some());
+    /// ```
+    pub fn map_payload<T, F>(self, f: F) -> Unwind
+    where
+        T: Any + Send + 'static,
+        F: FnOnce(Box<dyn Any + Send + 'static>) -> T,
+    {
+        let (payload, meta) = self.split();
+        meta.rewrap(Box::new(f(payload)))
+    }
+
+    /// Separate the panic payload from the rest of the information.
+    ///
+    /// The metadata can be combined with a payload again by [`UnwindMeta::rewrap`].
+    ///
+    /// [`UnwindMeta::rewrap`]: ./struct.UnwindMeta.html#method.rewrap
+    #[inline]
+    pub fn split(self) -> (Box<dyn Any + Send + 'static>, UnwindMeta) {
+        let meta = UnwindMeta {
+            caller: self.caller,
+            captured: self.captured,
+            capture_miss: self.capture_miss,
+            scope_data: self.scope_data,
+            elapsed: self.elapsed,
+            max_message_len: self.max_message_len,
+        };
+        (self.payload, meta)
+    }
+
+    /// Convert itself into a panic payload that carries the captured information.
+    ///
+    /// The returned value can be passed to the APIs that only accept the
+    /// panic payload, such as `std::panic::resume_unwind`, and converted back
+    /// by [`from_resumed`] after being caught. The panic message rendered from
+    /// the returned payload is the same as the original one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, Unwind};
+    /// use std::panic::{self, AssertUnwindSafe};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// let line = unwind.location().unwrap().line();
+    ///
+    /// let payload = panic::catch_unwind(AssertUnwindSafe(|| {
+    ///     panic::resume_unwind(unwind.into_resumable());
+    /// }))
+    /// .unwrap_err();
+    /// let unwind = Unwind::from_resumed(payload).unwrap();
+    /// assert_eq!(unwind.payload_str(), "oops");
+    /// assert_eq!(unwind.location().unwrap().line(), line);
+    /// ```
+    ///
+    /// [`from_resumed`]: #method.from_resumed
+    #[inline]
+    pub fn into_resumable(self) -> Box<dyn Any + Send + 'static> {
+        Box::new(Resumed(self))
+    }
+
+    /// Restore the `Unwind` from the panic payload created by [`into_resumable`].
+    ///
+    /// If the payload was not created by [`into_resumable`], it is returned as
+    /// it is.
+    ///
+    /// [`into_resumable`]: #method.into_resumable
+    pub fn from_resumed(
+        payload: Box<dyn Any + Send + 'static>,
+    ) -> Result<Unwind, Box<dyn Any + Send + 'static>> {
+        payload.downcast::<Resumed>().map(|resumed| resumed.0)
+    }
+
+    /// Resume the panic with the original payload, discarding the captured information.
+    ///
+    /// The payload is rethrown by `std::panic::resume_unwind` exactly as it was
+    /// passed to the panic, without any wrapper, so the code catching it sees
+    /// the same value as if the panic had never been captured. This is the
+    /// one to use when the panic is deferred to the test harness, e.g. for
+    /// keeping `#[should_panic(expected = "..")]` matching against the
+    /// original message.
+    ///
+    /// Unlike [`into_resumable`], the location, the backtrace and the other
+    /// captured information are lost, and a capture scope catching the
+    /// resumed panic reports it as [`CaptureMiss::Resumed`]. Use
+    /// [`resume_with_report`] instead to keep them in the human-facing
+    /// contexts. The panic hook is not called for the resumed panic.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    /// use std::panic;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let payload = panic::catch_unwind(|| {
+    ///     if let Err(unwind) = maybe_unwind(|| panic!("bar")) {
+    ///         unwind.resume_with_original_payload();
+    ///     }
+    /// })
+    /// .unwrap_err();
+    /// assert_eq!(*payload.downcast::<&str>().unwrap(), "bar");
+    /// ```
+    ///
+    /// [`into_resumable`]: #method.into_resumable
+    /// [`resume_with_report`]: #method.resume_with_report
+    /// [`CaptureMiss::Resumed`]: ./enum.CaptureMiss.html#variant.Resumed
+    pub fn resume_with_original_payload(self) -> ! {
+        panic::resume_unwind(self.payload)
+    }
+
+    /// Resume the panic with the report of this panic as the payload.
+    ///
+    /// The payload is a `String` of the alternate `Display` output, i.e. the
+    /// report including the location, the context frames and the backtrace,
+    /// which is suitable for the human-facing contexts where the payload is
+    /// printed, e.g. the result of a joined thread. The original payload is
+    /// dropped, so the code catching the panic cannot downcast it, and the
+    /// `expected` message of `#[should_panic]` must match the report rather
+    /// than the original message. Use [`resume_with_original_payload`] when
+    /// the payload needs to be preserved.
+    ///
+    /// The panic hook is not called for the resumed panic.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    /// use std::panic;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let payload = panic::catch_unwind(|| {
+    ///     if let Err(unwind) = maybe_unwind(|| panic!("bar")) {
+    ///         unwind.resume_with_report();
+    ///     }
+    /// })
+    /// .unwrap_err();
+    /// let report = payload.downcast::<String>().unwrap();
+    /// assert!(report.starts_with("panicked at "));
+    /// ```
+    ///
+    /// [`resume_with_original_payload`]: #method.resume_with_original_payload
+    pub fn resume_with_report(self) -> ! {
+        panic::resume_unwind(Box::new(format!("{:#}", self)))
+    }
+
     /// Return the information about the location from which the panic originated.
+    ///
+    /// If the scope captured more than one panic, e.g. the panic was caught
+    /// and raised again inside of the scope, this is the location of the
+    /// panic captured last. See [`best_location`] for the location reported
+    /// by the alternate `Display` output.
+    ///
+    /// [`best_location`]: #method.best_location
     #[inline]
     pub fn location(&self) -> Option<&Location> {
         self.captured.as_ref()?.location.as_ref()
     }
 
+    /// Return the location of the panic that most likely caused this unwind.
+    ///
+    /// If the scope captured more than one panic, the location is picked
+    /// among them by [`CaptureOptions::location_policy`]. By default, the
+    /// earliest panic whose message is contained in the final payload is
+    /// preferred, so that the panic re-raised by a middleware with the
+    /// original message is blamed on the original panic site. Otherwise,
+    /// this is the same as [`location`]. The alternate `Display` output
+    /// reports this location.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    /// use std::panic;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// # if cfg!(feature = "capture") {
+    /// let line = line!() + 3;
+    /// let unwind = maybe_unwind(|| {
+    ///     // A middleware catching the panic and raising it again.
+    ///     let payload = panic::catch_unwind(|| panic!("original")).unwrap_err();
+    ///     let message = payload.downcast_ref::<&str>().unwrap();
+    ///     panic!("middleware: {}", message);
+    /// })
+    /// .unwrap_err();
+    /// assert_eq!(unwind.best_location().unwrap().line(), line);
+    /// assert_eq!(unwind.location().unwrap().line(), line + 2);
+    /// # }
+    /// ```
+    ///
+    /// [`CaptureOptions::location_policy`]: ./struct.CaptureOptions.html#method.location_policy
+    /// [`location`]: #method.location
+    pub fn best_location(&self) -> Option<&Location> {
+        self.captured
+            .as_ref()?
+            .best_location(&payload::render(&*self.payload))
+    }
+
+    /// Return whether the panic originated in a source file under `path_prefix`.
+    ///
+    /// The source file is determined in the following order:
+    ///
+    /// 1. The file of [`location`], if the panic information was captured.
+    /// 2. The file of the innermost frame of the backtrace that does not belong
+    ///    to the standard library or this crate, if the backtrace was captured
+    ///    (requires the backtrace support).
+    ///
+    /// The backtrace is consulted only if the location is missing, so a panic
+    /// raised via a `#[track_caller]` function is attributed to its caller.
+    /// This method returns `false` if neither is available.
+    ///
+    /// The prefix is compared by the path components, so `"src/plugin"` matches
+    /// `"src/plugin/mod.rs"` but not `"src/plugins.rs"`. A leading `./` of the
+    /// paths is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use maybe_unwind::maybe_unwind;
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// assert!(unwind.originates_from("src"));
+    /// assert!(!unwind.originates_from("src/plugin"));
+    /// ```
+    ///
+    /// [`location`]: #method.location
+    pub fn originates_from(&self, path_prefix: &str) -> bool {
+        let prefix = Path::new(path_prefix.trim_start_matches("./"));
+        self.origin_file().map_or(false, |file| {
+            Path::new(file.trim_start_matches("./")).starts_with(prefix)
+        })
+    }
+
+    /// Return whether the panic originated in the source tree of a crate
+    /// identified by `crate_src_marker`.
+    ///
+    /// The marker is a fragment of the path that is unique to the source tree
+    /// of the crate, e.g. `"/plugin-sdk-0.3.1/"` for a crate downloaded from a
+    /// registry or `"vendor/plugin-sdk/"` for a vendored one, and is matched
+    /// against any part of the source file path. The path separators of both
+    /// are compared as `/`. The source file is determined in the same order
+    /// as [`originates_from`].
+    ///
+    /// [`originates_from`]: #method.originates_from
+    pub fn originates_from_crate(&self, crate_src_marker: &str) -> bool {
+        let marker = crate_src_marker.replace('\\', "/");
+        self.origin_file()
+            .map_or(false, |file| file.replace('\\', "/").contains(&*marker))
+    }
+
+    fn origin_file(&self) -> Option<Cow<'_, str>> {
+        if let Some(location) = self.location() {
+            return Some(Cow::Borrowed(location.file()));
+        }
+
+        #[cfg(backtrace)]
+        {
+            self.backtrace_frames()?
+                .filter(|frame| !frame.is_internal())
+                .find_map(|frame| Some(frame.filename()?.to_string_lossy().into_owned()))
+                .map(Cow::Owned)
+        }
+
+        #[cfg(not(backtrace))]
+        None
+    }
+
+    /// Return the location where the capture scope was established.
+    ///
+    /// Unlike [`location`], this value is always available since it
+    /// is recorded by `maybe_unwind` itself rather than the panic hook.
+    ///
+    /// [`location`]: #method.location
+    #[inline]
+    pub fn caller(&self) -> &Location {
+        &self.caller
+    }
+
+    /// Return the annotations that were active when the panic occurred.
+    ///
+    /// The annotations are ordered from the outermost one.
+    /// See [`with_context`] for details.
+    ///
+    /// [`with_context`]: ./fn.with_context.html
+    #[inline]
+    pub fn context_frames(&self) -> &[String] {
+        self.captured
+            .as_ref()
+            .map_or(&[], |captured| &captured.context_frames[..])
+    }
+
+    /// Return the key-value annotations attached at the moment of the capture.
+    ///
+    /// See [`HookOptions::on_capture_mut`] for details.
+    ///
+    /// [`HookOptions::on_capture_mut`]: ./struct.HookOptions.html#method.on_capture_mut
+    #[inline]
+    pub fn annotations(&self) -> &[(String, String)] {
+        self.captured
+            .as_ref()
+            .map_or(&[], |captured| &captured.annotations[..])
+    }
+
+    /// Return the environment variables recorded at the moment of the capture,
+    /// with the redactor applied.
+    ///
+    /// The map contains the variables specified by [`CaptureOptions::snapshot_env`],
+    /// and the variables that were not set are mapped to `None`. It is empty
+    /// if the panic information was not captured.
+    ///
+    /// [`CaptureOptions::snapshot_env`]: ./struct.CaptureOptions.html#method.snapshot_env
+    pub fn env(&self) -> Cow<'_, BTreeMap<String, Option<String>>> {
+        match &self.captured {
+            Some(captured) => captured.env(),
+            None => Cow::Owned(BTreeMap::new()),
+        }
+    }
+
+    /// Return the name of the thread on which the panic occurred.
+    ///
+    /// This value is not available if the panic information was not captured
+    /// or the thread is unnamed.
+    #[inline]
+    pub fn thread_name(&self) -> Option<&str> {
+        self.captured.as_ref()?.thread_name()
+    }
+
+    /// Return the label attached to the scope by [`set_scope_label`]
+    /// or [`maybe_unwind_named`].
+    ///
+    /// This value is not available if the panic information was not captured
+    /// or no label was set.
+    ///
+    /// [`set_scope_label`]: ./fn.set_scope_label.html
+    /// [`maybe_unwind_named`]: ./fn.maybe_unwind_named.html
+    #[inline]
+    pub fn scope_label(&self) -> Option<&str> {
+        self.captured.as_ref()?.scope_label.as_deref()
+    }
+
+    /// Return the data attached to the scope by [`maybe_unwind_with_data`].
+    ///
+    /// This value is not available if the scope has no data or the data
+    /// is not of the type `D`.
+    ///
+    /// [`maybe_unwind_with_data`]: ./fn.maybe_unwind_with_data.html
+    #[inline]
+    pub fn scope_data<D: Any>(&self) -> Option<&D> {
+        self.scope_data.as_ref()?.downcast_ref()
+    }
+
+    /// Take the data attached to the scope by [`maybe_unwind_with_data`].
+    ///
+    /// The data is left as it is if it is not of the type `D`.
+    ///
+    /// [`maybe_unwind_with_data`]: ./fn.maybe_unwind_with_data.html
+    pub fn take_scope_data<D: Any>(&mut self) -> Option<D> {
+        let data = *self.scope_data.take()?;
+        match data.downcast() {
+            Ok(data) => Some(*data),
+            Err(data) => {
+                self.scope_data = Some(Box::new(data));
+                None
+            }
+        }
+    }
+
+    /// Return the panic information rendered by the panic hook as it is,
+    /// e.g. `panicked at src/main.rs:2:5:\noops`.
+    ///
+    /// The exact format depends on the version of the standard library.
+    /// This value is available only if the panic information was captured
+    /// with [`CaptureOptions::hook_message`] enabled.
+    ///
+    /// [`CaptureOptions::hook_message`]: ./struct.CaptureOptions.html#method.hook_message
+    #[inline]
+    pub fn hook_message(&self) -> Option<&str> {
+        self.captured.as_ref()?.raw_hook_message.as_deref()
+    }
+
+    /// Return the time when the panic information was captured.
+    #[inline]
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.captured.as_ref()?.timestamp
+    }
+
+    /// Return the process-global sequence number allocated to this panic by the panic hook.
+    ///
+    /// The numbers are allocated from a single counter in the order the
+    /// panics reach [`capture_panic_info`], so they are unique within the
+    /// process and increase in the order of the panics on each thread.
+    /// Unlike [`timestamp`], the order is not affected by the adjustments of
+    /// the system clock, which makes this value suitable for sorting the
+    /// panics captured on multiple threads. A number is allocated to every
+    /// panic processed by the hook, including the ones that were not
+    /// captured, so a gap in the sequence indicates the missing panics.
+    ///
+    /// This method returns `None` if the panic information is not captured.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::maybe_unwind;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let first = maybe_unwind(|| panic!("first")).unwrap_err();
+    /// let second = maybe_unwind(|| panic!("second")).unwrap_err();
+    /// assert!(first.sequence().unwrap() < second.sequence().unwrap());
+    /// ```
+    ///
+    /// [`capture_panic_info`]: ./fn.capture_panic_info.html
+    /// [`timestamp`]: #method.timestamp
+    #[inline]
+    pub fn sequence(&self) -> Option<u64> {
+        Some(self.captured.as_ref()?.sequence)
+    }
+
+    /// Return the notes about the problems that occurred while capturing.
+    ///
+    /// See [`CapturedInfo::notes`] for details.
+    ///
+    /// [`CapturedInfo::notes`]: ./struct.CapturedInfo.html#method.notes
+    #[inline]
+    pub fn capture_notes(&self) -> &[String] {
+        self.captured
+            .as_ref()
+            .map_or(&[], |captured| &captured.notes[..])
+    }
+
+    /// Return the reason why the panic information was not captured.
+    ///
+    /// This value is available only if the panic was caught by an active
+    /// capture scope but the panic hook did not capture anything, e.g. the
+    /// panic was rethrown by `std::panic::resume_unwind`. It is `None` if the
+    /// capture is disabled, by the `capture` feature or on the current thread;
+    /// use [`why_no_capture`] for these reasons as well.
+    ///
+    /// The payload created by [`into_resumable`] is an exception: when it is
+    /// rethrown by `resume_unwind` inside of the scope, the original `Unwind`
+    /// is restored instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind, CaptureMiss};
+    /// use std::panic;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let unwind = maybe_unwind(|| panic::resume_unwind(Box::new("oops"))).unwrap_err();
+    /// assert!(unwind.location().is_none());
+    /// assert_eq!(unwind.capture_miss(), Some(CaptureMiss::Resumed));
+    /// ```
+    ///
+    /// [`why_no_capture`]: #method.why_no_capture
+    /// [`into_resumable`]: #method.into_resumable
+    #[inline]
+    pub fn capture_miss(&self) -> Option<CaptureMiss> {
+        self.capture_miss.filter(|miss| !miss.is_disabled())
+    }
+
+    /// Return the reason why the panic information, such as [`location`], is missing.
+    ///
+    /// This is `None` if the panic information was captured. Unlike
+    /// [`capture_miss`], the reason is also available if the capture is
+    /// disabled. The reason is also written into the alternate `Display`
+    /// output as a note.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{disable_for_current_thread, maybe_unwind, CaptureMiss};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// # if cfg!(feature = "capture") {
+    /// disable_for_current_thread();
+    /// let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    /// assert!(unwind.location().is_none());
+    /// assert_eq!(unwind.why_no_capture(), Some(&CaptureMiss::DisabledForThread));
+    /// assert!(format!("{:#}", unwind).contains("the capture is disabled on the current thread"));
+    /// # }
+    /// ```
+    ///
+    /// [`location`]: #method.location
+    /// [`capture_miss`]: #method.capture_miss
+    #[inline]
+    pub fn why_no_capture(&self) -> Option<&CaptureMiss> {
+        self.capture_miss.as_ref()
+    }
+
+    /// Return the number of the panics in the scope that were only counted
+    /// instead of being captured, due to [`CaptureOptions::max_captures_per_scope`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{maybe_unwind_with, CaptureOptions};
+    /// use std::panic;
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// let options = CaptureOptions::new().max_captures_per_scope(1);
+    /// let unwind = maybe_unwind_with(options, || {
+    ///     for _ in 0..10 {
+    ///         let _ = panic::catch_unwind(|| panic!("caught internally"));
+    ///     }
+    ///     panic!("oops");
+    /// })
+    /// .unwrap_err();
+    /// assert_eq!(unwind.suppressed_capture_count(), 10);
+    /// ```
+    ///
+    /// [`CaptureOptions::max_captures_per_scope`]: ./struct.CaptureOptions.html#method.max_captures_per_scope
+    #[inline]
+    pub fn suppressed_capture_count(&self) -> usize {
+        self.captured
+            .as_ref()
+            .map_or(0, |captured| captured.suppressed_captures)
+    }
+
+    /// Return whether this panic occurred while another panic was in flight
+    /// on the same thread, e.g. in a destructor run during unwinding.
+    #[inline]
+    pub fn is_secondary_panic(&self) -> bool {
+        self.panic_depth() > 1
+    }
+
+    /// Return the number of panics in flight on the thread when this panic
+    /// occurred, including itself.
+    ///
+    /// The value is `1` for an ordinary panic, and `2` or more for the panics
+    /// raised inside a `maybe_unwind` scope entered while the thread was already
+    /// unwinding. It is always `1` if the panic information was not captured.
+    #[inline]
+    pub fn panic_depth(&self) -> u32 {
+        self.captured
+            .as_ref()
+            .map_or(1, |captured| captured.panic_depth)
+    }
+
+    /// Return the panic information captured by the panic hook.
+    #[inline]
+    pub fn captured(&self) -> Option<&CapturedInfo> {
+        self.captured.as_deref()
+    }
+
+    /// Return the time spent inside the capture scope until the panic was caught.
+    ///
+    /// This value is available only if the panic was captured by
+    /// [`maybe_unwind_timed`] or [`FutureMaybeUnwindExt::maybe_unwind_timed`].
+    /// For the latter, this is the total time spent in polling the inner
+    /// future, not including the time while it was pending.
+    ///
+    /// [`maybe_unwind_timed`]: ./fn.maybe_unwind_timed.html
+    /// [`FutureMaybeUnwindExt::maybe_unwind_timed`]: ./trait.FutureMaybeUnwindExt.html#method.maybe_unwind_timed
+    #[inline]
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    #[cfg(feature = "futures")]
+    pub(crate) fn set_elapsed(&mut self, elapsed: Option<Duration>) {
+        self.elapsed = elapsed;
+    }
+
+    /// Return the status of the stack backtrace captured by the panic hook.
+    ///
+    /// If the backtrace is not available, the alternate `Display` output
+    /// contains a note explaining the reason according to this value.
+    #[inline]
+    pub fn backtrace_status(&self) -> BacktraceStatus {
+        self.captured
+            .as_ref()
+            .map_or(BacktraceStatus::CaptureFailed, |captured| {
+                captured.backtrace_status()
+            })
+    }
+
     /// Get the stack backtrace captured by the panic hook.
     ///
-    /// Currently this method is enabled only if the backtrace is supported.
+    /// This method is available only if the backtrace is supported, that is,
+    /// the `backtrace` feature is enabled (requires Rust 1.65 or later) or
+    /// the crate is built by a nightly compiler.
+    ///
+    /// This method returns `None` if the backtrace was truncated by
+    /// [`CaptureOptions::max_backtrace_frames`].
+    ///
+    /// [`CaptureOptions::max_backtrace_frames`]: ./struct.CaptureOptions.html#method.max_backtrace_frames
     #[cfg(backtrace)]
+    #[cfg_attr(docs, doc(cfg(feature = "backtrace")))]
     #[inline]
     pub fn backtrace(&self) -> Option<&Backtrace> {
         self.captured.as_ref()?.backtrace.as_ref()
     }
-}
 
-impl fmt::Display for Unwind {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let msg = self.payload_str();
-        if !f.alternate() {
-            return f.write_str(msg);
+    /// Get the stack backtrace whose symbols are resolved by the background resolver.
+    ///
+    /// The backtrace is handed to the resolver if the capture scope is
+    /// configured by [`CaptureOptions::resolve_via`], and the text is
+    /// retrieved by [`ResolvedBacktrace::wait`] without resolving the
+    /// symbols on the current thread. This method returns `None` if the
+    /// backtrace was not captured or resolved by the current thread.
+    ///
+    /// [`CaptureOptions::resolve_via`]: ./struct.CaptureOptions.html#method.resolve_via
+    /// [`ResolvedBacktrace::wait`]: ./struct.ResolvedBacktrace.html#method.wait
+    #[inline]
+    pub fn resolved_backtrace(&self) -> Option<&ResolvedBacktrace> {
+        self.captured.as_ref()?.resolved_backtrace.as_ref()
+    }
+
+    /// Get the stack backtrace captured at the entry of the capture scope.
+    ///
+    /// The backtrace is captured only if enabled by
+    /// [`CaptureOptions::capture_scope_backtrace`] or
+    /// [`HookOptions::capture_scope_backtrace`], and tells how the scope
+    /// was reached, while [`backtrace`] tells where the panic occurred.
+    ///
+    /// This method is available only if the backtrace is supported.
+    ///
+    /// [`CaptureOptions::capture_scope_backtrace`]: ./struct.CaptureOptions.html#method.capture_scope_backtrace
+    /// [`HookOptions::capture_scope_backtrace`]: ./struct.HookOptions.html#method.capture_scope_backtrace
+    /// [`backtrace`]: #method.backtrace
+    #[cfg(backtrace)]
+    #[cfg_attr(docs, doc(cfg(feature = "backtrace")))]
+    #[inline]
+    pub fn scope_backtrace(&self) -> Option<&Backtrace> {
+        self.captured.as_ref()?.scope_backtrace.as_ref()
+    }
+
+    /// Return an iterator over the frames of the stack backtrace captured by the panic hook.
+    ///
+    /// The frames are ordered from the innermost one, and the symbols are
+    /// resolved the first time this method is called.
+    /// This method returns `None` if the backtrace was not captured,
+    /// e.g. it was disabled by [`BacktraceMode`] or `RUST_BACKTRACE`,
+    /// or it was truncated by [`CaptureOptions::max_backtrace_frames`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use maybe_unwind::{maybe_unwind_with, BacktraceMode, CaptureOptions};
+    /// maybe_unwind::set_hook();
+    ///
+    /// let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    /// let unwind = maybe_unwind_with(options, || panic!("oops")).unwrap_err();
+    /// if let Some(mut frames) = unwind.backtrace_frames() {
+    ///     if let Some(frame) = frames.find(|frame| !frame.is_internal()) {
+    ///         println!("blame: {:?}", frame.symbol_name());
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`BacktraceMode`]: ./enum.BacktraceMode.html
+    /// [`CaptureOptions::max_backtrace_frames`]: ./struct.CaptureOptions.html#method.max_backtrace_frames
+    #[cfg(backtrace)]
+    #[cfg_attr(docs, doc(cfg(feature = "backtrace")))]
+    pub fn backtrace_frames(&self) -> Option<impl Iterator<Item = Frame>> {
+        use std::backtrace::BacktraceStatus;
+
+        let backtrace = self.backtrace()?;
+        match backtrace.status() {
+            BacktraceStatus::Captured => Some(Frames::new(backtrace)),
+            _ => None,
         }
+    }
 
-        if let Some(location) = self.location() {
-            writeln!(f, "panicked at {}: {}", location, msg)?;
-        } else {
-            writeln!(f, "panicked: {}", msg)?;
+    /// Mark the shared state identified by `key` as poisoned by this panic.
+    ///
+    /// This is the same as [`poison`], except that the location and the
+    /// message of this panic are recorded as well, so that [`poison_report`]
+    /// can tell which panic poisoned the key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use maybe_unwind::{is_poisoned, maybe_unwind, poison_report};
+    ///
+    /// maybe_unwind::set_hook();
+    ///
+    /// if let Err(unwind) = maybe_unwind(|| panic!("fixture broken")) {
+    ///     unwind.poison("fixture");
+    /// }
+    /// assert!(is_poisoned("fixture"));
+    ///
+    /// let report = poison_report();
+    /// assert_eq!(report[0].key(), "fixture");
+    /// assert_eq!(report[0].message(), Some("fixture broken"));
+    /// # maybe_unwind::clear_poison("fixture");
+    /// ```
+    ///
+    /// [`poison`]: ./fn.poison.html
+    /// [`poison_report`]: ./fn.poison_report.html
+    pub fn poison(&self, key: &str) {
+        poison::poison_with(
+            key,
+            self.location().cloned(),
+            Some(self.message().into_owned()),
+        );
+    }
+}
+
+impl Unwind {
+    pub(crate) fn max_message_len(&self) -> Option<usize> {
+        self.max_message_len
+    }
+
+    /// Return the redacted source chain of the error carried by the payload.
+    pub(crate) fn causes(&self) -> Vec<String> {
+        payload::causes(self.payload())
+            .iter()
+            .map(|cause| redact::redact(cause).into_owned())
+            .collect()
+    }
+
+    pub(crate) fn backtrace_text(&self) -> Option<String> {
+        self.captured.as_ref()?.backtrace_text()
+    }
+
+    pub(crate) fn scope_backtrace_text(&self) -> Option<String> {
+        self.captured.as_ref()?.scope_backtrace_text()
+    }
+
+    /// Return the notes shown in the report, including the reason of the capture miss.
+    pub(crate) fn report_notes(&self) -> Cow<'_, [String]> {
+        let notes = redact::redact_all(self.capture_notes());
+        let suppressed = self.suppressed_capture_count();
+        if self.why_no_capture().is_none() && suppressed == 0 {
+            return notes;
+        }
+        let mut notes = notes.into_owned();
+        if let Some(miss) = self.capture_miss {
+            notes.push(format!("location unavailable: {}", miss));
+        }
+        match suppressed {
+            0 => (),
+            1 => notes.push("1 more panic in the scope was not captured".into()),
+            n => notes.push(format!("{} more panics in the scope were not captured", n)),
         }
+        Cow::Owned(notes)
+    }
 
-        #[cfg(backtrace)]
+    fn span_trace_text(&self) -> Option<String> {
+        #[cfg(feature = "tracing-error")]
         {
-            use std::backtrace::BacktraceStatus;
+            self.span_trace().map(ToString::to_string)
+        }
+        #[cfg(not(feature = "tracing-error"))]
+        {
+            None
+        }
+    }
 
-            if let Some(backtrace) = self.backtrace() {
-                if let BacktraceStatus::Captured = backtrace.status() {
-                    writeln!(f, "stack backtrace:")?;
-                    writeln!(f, "{}", backtrace)?;
-                }
-            }
+    pub(crate) fn write_report(
+        &self,
+        f: &mut dyn fmt::Write,
+        normalize: Option<&NormalizeOptions>,
+        display: &DisplayOptions,
+    ) -> fmt::Result {
+        let backtrace = self.backtrace_text();
+        let scope_backtrace = self.scope_backtrace_text();
+        Report {
+            message: self.message(),
+            location: self.best_location(),
+            caller: &self.caller,
+            context_frames: redact::redact_all(self.context_frames()),
+            annotations: redact::redact_annotations(self.annotations()),
+            notes: self.report_notes(),
+            causes: Cow::Owned(self.causes()),
+            panic_depth: self.panic_depth(),
+            scope_label: self.scope_label(),
+            span_trace: self.span_trace_text(),
+            backtrace: backtrace.as_deref(),
+            backtrace_status: self.backtrace_status(),
+            scope_backtrace: scope_backtrace.as_deref(),
+            max_message_len: self.max_message_len,
         }
+        .write(f, normalize, display)
+    }
+}
 
-        Ok(())
+impl fmt::Display for Unwind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return f.write_str(&self.message());
+        }
+        self.write_report(f, None, &DisplayOptions::default())
     }
 }
 
 /// The information about the location of an unwinding panic.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Location {
-    file: String,
+    file: Cow<'static, str>,
     line: u32,
     column: u32,
 }
 
 impl Location {
+    /// Create a new `Location` from the components.
+    #[inline]
+    pub fn new(file: impl Into<String>, line: u32, column: u32) -> Self {
+        Self {
+            file: Cow::Owned(file.into()),
+            line,
+            column,
+        }
+    }
+
+    /// Create a new `Location` from the components without allocating the file name.
     #[inline]
+    pub const fn from_static(file: &'static str, line: u32, column: u32) -> Self {
+        Self {
+            file: Cow::Borrowed(file),
+            line,
+            column,
+        }
+    }
+
+    /// Create a new `Location` from the location of the panic hook.
+    ///
+    /// The lifetime of the location passed to the panic hook is not `'static`,
+    /// so the file names are interned and the allocation occurs only the first
+    /// time each file name is seen (see [`interner_stats`]).
+    ///
+    /// [`interner_stats`]: ./fn.interner_stats.html
     pub(crate) fn from_std(loc: &panic::Location<'_>) -> Self {
         Self {
-            file: loc.file().to_string(),
+            file: location::intern(loc.file()),
             line: loc.line(),
             column: loc.column(),
         }
@@ -130,7 +1496,7 @@ impl Location {
     /// Return the name of the source file from which the panic originated.
     #[inline]
     pub fn file(&self) -> &str {
-        self.file.as_str()
+        &self.file
     }
 
     /// Return the line number from which the panic originated.
@@ -146,6 +1512,13 @@ impl Location {
     }
 }
 
+impl From<&'static panic::Location<'static>> for Location {
+    #[inline]
+    fn from(loc: &'static panic::Location<'static>) -> Self {
+        Self::from_static(loc.file(), loc.line(), loc.column())
+    }
+}
+
 impl fmt::Display for Location {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}:{}:{}", self.file, self.line, self.column)
@@ -0,0 +1,136 @@
+use crate::unwind::Unwind;
+use std::{error, fmt, vec};
+
+/// A collection of the captured panics, each labeled with the operation that panicked.
+///
+/// This is intended for reporting the failures of multiple sub-operations
+/// at once. The `Display` output is a numbered summary of the panics, and
+/// the alternate `Display` output is followed by the full report of each
+/// panic. The panics are listed in the order they were pushed, which is the
+/// same as the order of [`iter`]. Use [`sorted_by_sequence`] for the order
+/// the panics were captured.
+///
+/// # Example
+///
+/// ```
+/// use maybe_unwind::{maybe_unwind, UnwindSet};
+///
+/// maybe_unwind::set_hook();
+///
+/// let mut failures = UnwindSet::new();
+/// for name in ["foo", "bar", "baz"] {
+///     if let Err(unwind) = maybe_unwind(|| assert_ne!(name, "bar")) {
+///         failures.push(name, unwind);
+///     }
+/// }
+///
+/// let err = failures.into_result().unwrap_err();
+/// assert_eq!(err.len(), 1);
+/// assert!(err.to_string().starts_with("1 panic\n  1. bar: panicked at "));
+/// ```
+///
+/// [`iter`]: #method.iter
+/// [`sorted_by_sequence`]: #method.sorted_by_sequence
+#[derive(Debug, Default)]
+pub struct UnwindSet {
+    unwinds: Vec<(String, Unwind)>,
+}
+
+impl UnwindSet {
+    /// Create an empty `UnwindSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a captured panic with the label of the operation that panicked.
+    pub fn push(&mut self, label: impl Into<String>, unwind: Unwind) {
+        self.unwinds.push((label.into(), unwind));
+    }
+
+    /// Return the number of the captured panics.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.unwinds.len()
+    }
+
+    /// Return whether no panic has been captured.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.unwinds.is_empty()
+    }
+
+    /// Return an iterator over the labels and the captured panics, in the order they were pushed.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Unwind)> + '_ {
+        self.unwinds
+            .iter()
+            .map(|(label, unwind)| (label.as_str(), unwind))
+    }
+
+    /// Return an iterator over the labels and the captured panics, in the order they were captured.
+    ///
+    /// The panics are sorted by [`Unwind::sequence`], which orders them across
+    /// the threads, and the panics without the sequence number, i.e. those
+    /// that were not captured by the hook, follow in the order they were pushed.
+    ///
+    /// [`Unwind::sequence`]: ./struct.Unwind.html#method.sequence
+    pub fn sorted_by_sequence(&self) -> impl Iterator<Item = (&str, &Unwind)> + '_ {
+        let mut unwinds: Vec<_> = self.iter().collect();
+        // The sort is stable, so the order of the uncaptured panics is kept.
+        unwinds.sort_by_key(|(_, unwind)| unwind.sequence().unwrap_or(u64::MAX));
+        unwinds.into_iter()
+    }
+
+    /// Return `Err(self)` if any panic has been captured, or `Ok(())` otherwise.
+    #[inline]
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl IntoIterator for UnwindSet {
+    type Item = (String, Unwind);
+    type IntoIter = vec::IntoIter<(String, Unwind)>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.unwinds.into_iter()
+    }
+}
+
+impl Extend<(String, Unwind)> for UnwindSet {
+    fn extend<I: IntoIterator<Item = (String, Unwind)>>(&mut self, iter: I) {
+        self.unwinds.extend(iter);
+    }
+}
+
+impl fmt::Display for UnwindSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unwinds.len() {
+            1 => f.write_str("1 panic")?,
+            n => write!(f, "{} panics", n)?,
+        }
+        for (i, (label, unwind)) in self.iter().enumerate() {
+            write!(f, "\n  {}. {}: panicked", i + 1, label)?;
+            if let Some(location) = unwind.location() {
+                write!(f, " at {}", location)?;
+            }
+            write!(f, ": {}", unwind.message())?;
+        }
+
+        if f.alternate() {
+            for (i, (label, unwind)) in self.iter().enumerate() {
+                write!(f, "\n\n--- {}. {} ---\n", i + 1, label)?;
+                let report = format!("{:#}", unwind);
+                f.write_str(report.trim_end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for UnwindSet {}
@@ -0,0 +1,21 @@
+use crate::hook::{capture_panic_info, replace_hook};
+
+/// Install the panic hook that captures the panic information and logs it to
+/// the browser console.
+///
+/// Since panics cannot be caught on `wasm32-unknown-unknown`, the captured
+/// information is stored for post-mortem inspection and can be retrieved by
+/// [`take_aborted_panic`] after the trap. The panic is always forwarded to
+/// the hook of `console_error_panic_hook`.
+///
+/// [`take_aborted_panic`]: ./fn.take_aborted_panic.html
+#[cfg_attr(docs, doc(cfg(feature = "console_error_panic_hook")))]
+pub fn set_console_hook() {
+    replace_hook(
+        Box::new(|info| {
+            capture_panic_info(info);
+            console_error_panic_hook::hook(info);
+        }),
+        true,
+    );
+}
@@ -0,0 +1,23 @@
+// These tests spawn the example binary, which is not supported by Miri.
+#![cfg(all(feature = "capture", not(miri)))]
+
+mod support;
+
+use std::{path::Path, process::Command};
+use support::example_path;
+
+#[test]
+fn report_before_abort() {
+    let output = Command::new(example_path("double_panic")).output().unwrap();
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let file = Path::new("examples").join("double_panic.rs");
+    let prefix = format!(
+        "note: panicked at {}:7:9 while processing the panic below",
+        file.display()
+    );
+    assert!(stderr.starts_with(&prefix), "{}", stderr);
+    assert!(stderr.contains(&format!("\npanicked at {}:21:13: first\n", file.display())));
+    assert!(stderr.contains("\ncontext:\n    while running the example\n"));
+}
@@ -0,0 +1,110 @@
+mod support;
+
+use maybe_unwind::{maybe_unwind, AssertionKind};
+use std::panic::panic_any;
+use support::ensure_set_hook;
+
+#[test]
+fn assert_eq() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| assert_eq!(1 + 1, 3)).unwrap_err();
+    let info = unwind.assertion_info().unwrap();
+    assert_eq!(info.kind(), AssertionKind::Eq);
+    assert_eq!(info.left(), Some("2"));
+    assert_eq!(info.right(), Some("3"));
+    assert_eq!(info.message(), None);
+    assert_eq!(info.expression(), None);
+}
+
+#[test]
+fn assert_eq_with_message() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| assert_eq!("a\nb", "c", "values differ: {}", 42)).unwrap_err();
+    let info = unwind.assertion_info().unwrap();
+    assert_eq!(info.kind(), AssertionKind::Eq);
+    assert_eq!(info.left(), Some(r#""a\nb""#));
+    assert_eq!(info.right(), Some(r#""c""#));
+    assert_eq!(info.message(), Some("values differ: 42"));
+}
+
+#[test]
+fn assert_ne() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| assert_ne!(vec![1, 2], vec![1, 2])).unwrap_err();
+    let info = unwind.assertion_info().unwrap();
+    assert_eq!(info.kind(), AssertionKind::Ne);
+    assert_eq!(info.left(), Some("[1, 2]"));
+    assert_eq!(info.right(), Some("[1, 2]"));
+}
+
+#[test]
+fn multiline_values() {
+    #[derive(PartialEq)]
+    struct Multiline;
+
+    impl std::fmt::Debug for Multiline {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("first\n  left: second")
+        }
+    }
+
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| assert_ne!(Multiline, Multiline)).unwrap_err();
+    let info = unwind.assertion_info().unwrap();
+    assert_eq!(info.left(), Some("first\n  left: second"));
+    assert_eq!(info.right(), Some("first\n  left: second"));
+}
+
+#[test]
+fn assert_bool() {
+    ensure_set_hook();
+    let value = 1;
+    let unwind = maybe_unwind(|| assert!(value > 2)).unwrap_err();
+    let info = unwind.assertion_info().unwrap();
+    assert_eq!(info.kind(), AssertionKind::Bool);
+    assert_eq!(info.expression(), Some("value > 2"));
+    assert_eq!(info.left(), None);
+}
+
+#[test]
+fn legacy_format() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| {
+        panic_any(String::from(
+            "assertion failed: `(left == right)`\n  left: `1`,\n right: `2`: custom message",
+        ))
+    })
+    .unwrap_err();
+    let info = unwind.assertion_info().unwrap();
+    assert_eq!(info.kind(), AssertionKind::Eq);
+    assert_eq!(info.left(), Some("1"));
+    assert_eq!(info.right(), Some("2"));
+    assert_eq!(info.message(), Some("custom message"));
+
+    let unwind = maybe_unwind(|| {
+        panic_any(String::from(
+            "assertion failed: `(left != right)`\n  left: `\"a\"`,\n right: `\"a\"`",
+        ))
+    })
+    .unwrap_err();
+    let info = unwind.assertion_info().unwrap();
+    assert_eq!(info.kind(), AssertionKind::Ne);
+    assert_eq!(info.left(), Some(r#""a""#));
+    assert_eq!(info.right(), Some(r#""a""#));
+    assert_eq!(info.message(), None);
+}
+
+#[test]
+fn not_an_assertion() {
+    ensure_set_hook();
+    for payload in &[
+        "oops",
+        "assertion failed: ",
+        "assertion `left == right` failed",
+        "assertion `left <= right` failed\n  left: 1\n right: 2",
+        "assertion failed: `(left == right)`\n  left: `1`",
+    ] {
+        let unwind = maybe_unwind(|| panic_any(*payload)).unwrap_err();
+        assert_eq!(unwind.assertion_info(), None, "payload: {:?}", payload);
+    }
+}
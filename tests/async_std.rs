@@ -0,0 +1,45 @@
+#![cfg(all(feature = "capture", feature = "async-std"))]
+
+mod support;
+
+use async_std::task;
+use maybe_unwind::{
+    async_std::{spawn_blocking_maybe_unwind, spawn_maybe_unwind},
+    with_context_async,
+};
+use support::ensure_set_hook;
+
+#[test]
+fn spawned_task() {
+    ensure_set_hook();
+    task::block_on(async {
+        let line = line!() + 1;
+        let handle = spawn_maybe_unwind(with_context_async("in the task", async {
+            panic!("task failed");
+        }));
+        let unwind = handle.await.unwrap_err();
+        assert_eq!(unwind.message(), "task failed");
+        assert_eq!(unwind.location().unwrap().line(), line + 1);
+        assert_eq!(unwind.caller().line(), line);
+        assert_eq!(unwind.context_frames(), ["in the task"]);
+
+        let output = spawn_maybe_unwind(async { 42 }).await;
+        assert_eq!(output.unwrap(), 42);
+    });
+}
+
+#[test]
+fn blocking_task() {
+    ensure_set_hook();
+    task::block_on(async {
+        let line = line!() + 1;
+        let handle = spawn_blocking_maybe_unwind(|| -> u32 { panic!("blocking failed") });
+        let unwind = handle.await.unwrap_err();
+        assert_eq!(unwind.message(), "blocking failed");
+        assert_eq!(unwind.location().unwrap().line(), line);
+        assert_eq!(unwind.caller().line(), line);
+
+        let output = spawn_blocking_maybe_unwind(|| 42).await;
+        assert_eq!(output.unwrap(), 42);
+    });
+}
@@ -0,0 +1,127 @@
+//! Compile-time assertions of the auto traits implemented by the public types.
+//!
+//! A refactor that changes any of them fails to compile this file.
+
+#![allow(dead_code)]
+
+use maybe_unwind::{
+    CaptureOptions, CapturedInfo, CollectedPanic, HookOptions, Location, PanicCollector,
+    SuiteSummary, Unwind, UnwindMeta, UnwindSet, UnwindSnapshot,
+};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+fn assert_unpin<T: Unpin>() {}
+fn assert_unwind_safe<T: UnwindSafe + RefUnwindSafe>() {}
+
+/// Fails to compile if `$ty` implements `$trait`.
+macro_rules! assert_not_impl {
+    ($ty:ty: $trait:path) => {
+        const _: fn() = || {
+            trait AmbiguousIfImpl<A> {
+                fn some_item() {}
+            }
+            impl<T: ?Sized> AmbiguousIfImpl<()> for T {}
+            struct Invalid;
+            impl<T: ?Sized + $trait> AmbiguousIfImpl<Invalid> for T {}
+            let _ = <$ty as AmbiguousIfImpl<_>>::some_item;
+        };
+    };
+}
+
+fn unwind() {
+    // The payload is `Box<dyn Any + Send>`, as in `catch_unwind`.
+    assert_send::<Unwind>();
+    assert_send::<UnwindMeta>();
+    assert_send::<UnwindSet>();
+}
+assert_not_impl!(Unwind: Sync);
+assert_not_impl!(Unwind: UnwindSafe);
+
+fn snapshot() {
+    assert_send::<UnwindSnapshot>();
+    assert_sync::<UnwindSnapshot>();
+    assert_unwind_safe::<UnwindSnapshot>();
+    assert_send::<SuiteSummary>();
+    assert_sync::<SuiteSummary>();
+}
+
+fn location() {
+    assert_send::<Location>();
+    assert_sync::<Location>();
+    assert_unpin::<Location>();
+    assert_unwind_safe::<Location>();
+}
+
+fn captured_info() {
+    assert_send::<CapturedInfo>();
+    assert_sync::<CapturedInfo>();
+    assert_send::<CollectedPanic>();
+    assert_sync::<CollectedPanic>();
+}
+
+fn options() {
+    assert_send::<CaptureOptions>();
+    assert_sync::<CaptureOptions>();
+    assert_send::<HookOptions>();
+    assert_sync::<HookOptions>();
+    assert_send::<PanicCollector>();
+    assert_sync::<PanicCollector>();
+}
+
+#[cfg(feature = "futures")]
+mod futures {
+    use super::*;
+    use maybe_unwind::{MaybeUnwind, WithContext};
+    use std::{
+        future::Future,
+        marker::PhantomPinned,
+        pin::Pin,
+        rc::Rc,
+        task::{Context, Poll},
+    };
+
+    fn send<F: Send>() {
+        assert_send::<MaybeUnwind<F>>();
+        assert_send::<WithContext<F>>();
+    }
+
+    fn sync<F: Sync>() {
+        assert_sync::<MaybeUnwind<F>>();
+    }
+
+    fn unpin<F: Unpin>() {
+        assert_unpin::<MaybeUnwind<F>>();
+        assert_unpin::<WithContext<F>>();
+    }
+
+    /// A future that is neither `Send` nor `Unpin`.
+    struct Pinned(PhantomPinned, Rc<()>);
+
+    impl Future for Pinned {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+            Poll::Ready(())
+        }
+    }
+
+    assert_not_impl!(MaybeUnwind<Pinned>: Send);
+    assert_not_impl!(MaybeUnwind<Pinned>: Unpin);
+    assert_not_impl!(WithContext<Pinned>: Unpin);
+}
+
+#[cfg(feature = "stream")]
+fn stream<S: Send + Unpin>() {
+    use maybe_unwind::MaybeUnwindStream;
+    assert_send::<MaybeUnwindStream<S>>();
+    assert_unpin::<MaybeUnwindStream<S>>();
+}
+
+#[cfg(feature = "futures01")]
+fn futures01<F: Send + Unpin>() {
+    use maybe_unwind::MaybeUnwind01;
+    assert_send::<MaybeUnwind01<F>>();
+    assert_unpin::<MaybeUnwind01<F>>();
+}
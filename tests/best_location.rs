@@ -0,0 +1,75 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_with, CaptureOptions, LocationPolicy, Unwind};
+use std::panic;
+use support::{ensure_set_hook, panic_in_helper};
+
+/// A middleware that inspects the payload and panics again with the original message.
+fn middleware(f: fn()) {
+    if let Err(payload) = panic::catch_unwind(f) {
+        let message = payload.downcast_ref::<&str>().copied().unwrap_or("unknown");
+        panic!("{}", message);
+    }
+}
+
+fn run(options: CaptureOptions) -> Unwind {
+    maybe_unwind_with(options, || middleware(panic_in_helper)).unwrap_err()
+}
+
+#[test]
+fn original_panic_site() {
+    ensure_set_hook();
+    let unwind = run(CaptureOptions::new());
+    assert_eq!(unwind.payload_str(), "panicked in helper");
+
+    let best = unwind.best_location().unwrap();
+    assert!(best.file().ends_with("mod.rs"), "{}", best);
+    assert!(unwind
+        .location()
+        .unwrap()
+        .file()
+        .contains("best_location.rs"));
+
+    let report = format!("{:#}", unwind);
+    assert!(
+        report.starts_with(&format!("panicked at {}:", best)),
+        "{}",
+        report
+    );
+}
+
+#[test]
+fn policies() {
+    ensure_set_hook();
+    let unwind = run(CaptureOptions::new().location_policy(LocationPolicy::Last));
+    assert_eq!(unwind.best_location(), unwind.location());
+    assert!(format!("{:#}", unwind).contains("best_location.rs"));
+
+    let unwind = run(CaptureOptions::new().location_policy(LocationPolicy::Earliest));
+    assert!(unwind.best_location().unwrap().file().ends_with("mod.rs"));
+}
+
+#[test]
+fn unrelated_panic_is_not_blamed() {
+    ensure_set_hook();
+    let line = line!() + 3;
+    let unwind = maybe_unwind(|| {
+        let _ = panic::catch_unwind(|| panic!("unrelated"));
+        panic!("real");
+    })
+    .unwrap_err();
+    assert_eq!(unwind.best_location().unwrap().line(), line);
+    assert_eq!(unwind.best_location(), unwind.location());
+
+    // The earliest panic is used if none of the messages matches.
+    let line = line!() + 2;
+    let unwind = maybe_unwind(|| {
+        let _ = panic::catch_unwind(|| panic!("first"));
+        panic!("second")
+    })
+    .unwrap_err()
+    .map_payload(|_| "replaced");
+    assert_eq!(unwind.best_location().unwrap().line(), line);
+}
@@ -0,0 +1,159 @@
+#![cfg(feature = "capture")]
+
+#[path = "vendor/plugin/mod.rs"]
+mod plugin;
+mod support;
+
+use maybe_unwind::{
+    maybe_unwind, maybe_unwind_with, BacktraceMode, BacktraceStatus, BlameSource, CaptureOptions,
+    Location, UnwindSnapshot,
+};
+use std::path::Path;
+use support::ensure_set_hook;
+
+const REGISTRY_FILE: &str =
+    "/home/user/.cargo/registry/src/index.crates.io-6f17d22bba15001f/serde_json-1.0.108/src/de.rs";
+
+fn backtrace() -> String {
+    format!(
+        "   0: rust_begin_unwind
+             at /rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/std/src/panicking.rs:665:5
+   1: core::panicking::panic_fmt
+             at /rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/core/src/panicking.rs:74:14
+   2: serde_json::de::Deserializer<R>::parse_value
+             at {}:120:13
+   3: my_crate::parser::parse_expr
+             at ./src/parser.rs:88:9
+   4: my_crate::main
+             at ./src/main.rs:10:5
+",
+        REGISTRY_FILE
+    )
+}
+
+fn snapshot_at(location: &str, line: u32) -> UnwindSnapshot {
+    UnwindSnapshot::new("oops", Location::new("src/main.rs", 10, 5))
+        .with_location(Location::new(location, line, 13))
+}
+
+#[test]
+fn location_inside_of_workspace() {
+    let snapshot = snapshot_at("src/parser.rs", 88).with_backtrace(backtrace());
+    let blame = snapshot.blame().unwrap();
+    assert_eq!(blame.source(), BlameSource::Location);
+    assert_eq!(*blame.location(), Location::new("src/parser.rs", 88, 13));
+    // The function is looked up from the backtrace.
+    assert_eq!(blame.symbol_name(), Some("my_crate::parser::parse_expr"));
+    assert_eq!(
+        blame.to_string(),
+        "my_crate::parser::parse_expr at src/parser.rs:88"
+    );
+
+    let blame = snapshot_at("src/parser.rs", 88).blame().unwrap();
+    assert_eq!(blame.source(), BlameSource::Location);
+    assert_eq!(blame.symbol_name(), None);
+    assert_eq!(blame.to_string(), "src/parser.rs:88");
+}
+
+#[test]
+fn location_inside_of_dependency() {
+    let snapshot = snapshot_at(REGISTRY_FILE, 120).with_backtrace(backtrace());
+    let blame = snapshot.blame().unwrap();
+    assert_eq!(blame.source(), BlameSource::Backtrace);
+    assert_eq!(*blame.location(), Location::new("src/parser.rs", 88, 9));
+    assert_eq!(blame.symbol_name(), Some("my_crate::parser::parse_expr"));
+    assert_eq!(
+        blame.to_string(),
+        "my_crate::parser::parse_expr at src/parser.rs:88"
+    );
+}
+
+#[test]
+fn location_inside_of_dependency_without_backtrace() {
+    let blame = snapshot_at(REGISTRY_FILE, 120).blame().unwrap();
+    assert_eq!(blame.source(), BlameSource::Location);
+    assert_eq!(blame.location().file(), REGISTRY_FILE);
+    assert_eq!(blame.to_string(), format!("{}:120", REGISTRY_FILE));
+}
+
+#[test]
+fn explicit_workspace() {
+    let snapshot = snapshot_at("src/parser.rs", 88).with_backtrace(backtrace());
+    // The parser is outside of the specified workspace.
+    let blame = snapshot.blame_within("./src/main.rs").unwrap();
+    assert_eq!(blame.source(), BlameSource::Backtrace);
+    assert_eq!(blame.to_string(), "my_crate::main at src/main.rs:10");
+
+    let blame = snapshot.blame_within("src").unwrap();
+    assert_eq!(blame.source(), BlameSource::Location);
+
+    // Falls back to the location if no frame is inside of the workspace.
+    let blame = snapshot.blame_within("tests").unwrap();
+    assert_eq!(blame.source(), BlameSource::Location);
+    assert_eq!(blame.location().file(), "src/parser.rs");
+}
+
+#[test]
+fn without_location() {
+    let snapshot = UnwindSnapshot::new("oops", Location::new("src/main.rs", 10, 5));
+    assert_eq!(snapshot.blame(), None);
+
+    let blame = snapshot.with_backtrace(backtrace()).blame().unwrap();
+    assert_eq!(blame.source(), BlameSource::Backtrace);
+    assert_eq!(blame.symbol_name(), Some("my_crate::parser::parse_expr"));
+}
+
+#[test]
+fn panic_in_test_file() {
+    ensure_set_hook();
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    let blame = unwind.blame().unwrap();
+    assert_eq!(blame.source(), BlameSource::Location);
+    assert_eq!(blame.location().file(), file!());
+    assert_eq!(blame.location().line(), line);
+    assert_eq!(unwind.to_snapshot().blame(), Some(blame));
+}
+
+#[test]
+fn panic_in_vendored_module() {
+    ensure_set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    let line = line!() + 1;
+    let unwind = maybe_unwind_with(options, || plugin::run(0)).unwrap_err();
+    assert!(unwind.originates_from("tests/vendor/plugin"));
+
+    // The vendored module is a part of the workspace by default.
+    let blame = unwind.blame().unwrap();
+    assert_eq!(blame.source(), BlameSource::Location);
+    assert_eq!(
+        Path::new(blame.location().file()),
+        Path::new("tests/vendor/plugin/mod.rs")
+    );
+
+    let blame = unwind.blame_within(file!()).unwrap();
+    if unwind.backtrace_status() == BacktraceStatus::Captured {
+        assert_eq!(blame.source(), BlameSource::Backtrace);
+        assert_eq!(blame.location().file(), file!());
+        assert_eq!(blame.location().line(), line);
+        assert!(blame.symbol_name().unwrap().starts_with("blame::"));
+    } else {
+        assert_eq!(blame.source(), BlameSource::Location);
+        assert_eq!(
+            Path::new(blame.location().file()),
+            Path::new("tests/vendor/plugin/mod.rs")
+        );
+    }
+    assert_eq!(unwind.to_snapshot().blame_within(file!()), Some(blame));
+}
+
+#[test]
+fn panic_via_track_caller() {
+    ensure_set_hook();
+    // The location of a `#[track_caller]` function is already in the workspace.
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| plugin::check(0)).unwrap_err();
+    let blame = unwind.blame_within(file!()).unwrap();
+    assert_eq!(blame.source(), BlameSource::Location);
+    assert_eq!(blame.location().line(), line);
+}
@@ -0,0 +1,46 @@
+#!/bin/sh
+#
+# Check that every feature compiles on its own, without the default features,
+# together with the tests, examples and benchmarks that it enables.
+#
+# Usage: tests/build-matrix.sh [<additional args of cargo check>...]
+#
+# With cargo-hack installed, the same matrix is covered by:
+#
+#     cargo hack check --each-feature --no-dev-deps
+#     cargo hack check --each-feature --all-targets
+
+set -eu
+
+cd "$(dirname "$0")/.."
+
+# The features declared in the [features] section of the manifest,
+# and the implicit ones of the optional dependencies.
+features=$(
+    {
+        sed -n '/^\[features\]/,/^\[/s/^\([a-z0-9_-]*\) *=.*/\1/p' Cargo.toml
+        sed -n 's/^\([a-z0-9_-]*\) *=.*optional = true.*/\1/p' Cargo.toml
+    } | sort -u
+)
+
+check() {
+    echo "--- cargo check --no-default-features $*" >&2
+    cargo check --no-default-features --all-targets "$@"
+}
+
+check "$@"
+for feature in $features; do
+    case "$feature" in
+        default) check --features default "$@" ;;
+        # Only available on wasm32-unknown-unknown.
+        console_error_panic_hook) ;;
+        *) check --features "$feature" "$@" ;;
+    esac
+done
+check --all-features "$@"
+
+# The overrides of the backtrace detection, which skip the probes.
+for mode in on off; do
+    echo "--- MAYBE_UNWIND_BACKTRACE=$mode" >&2
+    MAYBE_UNWIND_BACKTRACE=$mode check --all-features "$@"
+done
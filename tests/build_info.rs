@@ -0,0 +1,56 @@
+//! The build script reads `MAYBE_UNWIND_BACKTRACE` from the environment of the
+//! same `cargo` invocation that builds this test, so the expectations follow it.
+//! Run with `MAYBE_UNWIND_BACKTRACE=on` or `off` to cover the overrides.
+
+use maybe_unwind::{build_info, BacktraceDetection};
+
+fn override_mode() -> Option<String> {
+    option_env!("MAYBE_UNWIND_BACKTRACE").map(|value| value.trim().to_ascii_lowercase())
+}
+
+#[test]
+fn detection_follows_the_override() {
+    let info = build_info();
+    match override_mode().as_deref() {
+        Some("on") => {
+            assert_eq!(info.backtrace_detection, BacktraceDetection::ForcedOn);
+            assert!(info.backtrace);
+            // Nothing else is probed.
+            assert!(!info.panic_hook_info);
+            assert!(!info.panic_update_hook);
+        }
+        Some("off") => {
+            assert_eq!(info.backtrace_detection, BacktraceDetection::ForcedOff);
+            assert!(!info.backtrace);
+            assert!(!info.panic_hook_info);
+            assert!(!info.panic_update_hook);
+        }
+        _ => assert_eq!(info.backtrace_detection, BacktraceDetection::Auto),
+    }
+}
+
+#[test]
+fn capabilities_match_the_features() {
+    let info = build_info();
+    assert_eq!(info.capture, cfg!(feature = "capture"));
+    assert_eq!(info.unwinding, maybe_unwind::unwinding_supported());
+    // The configuration of the build script is shared with the tests.
+    assert_eq!(info.backtrace, cfg!(backtrace));
+    assert_eq!(info.panic_hook_info, cfg!(panic_hook_info));
+    if cfg!(feature = "backtrace") && override_mode().as_deref() != Some("off") {
+        // Every compiler running the tests supports `std::backtrace`.
+        assert!(info.backtrace);
+    }
+}
+
+#[cfg(feature = "capture")]
+#[test]
+fn backtrace_is_captured_only_if_compiled_in() {
+    use maybe_unwind::{maybe_unwind_with, BacktraceMode, BacktraceStatus, CaptureOptions};
+
+    maybe_unwind::set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    let unwind = maybe_unwind_with(options, || panic!("oops")).unwrap_err();
+    let captured = matches!(unwind.backtrace_status(), BacktraceStatus::Captured);
+    assert_eq!(captured, build_info().backtrace);
+}
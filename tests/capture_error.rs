@@ -0,0 +1,114 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::{
+    add_observer, maybe_unwind, maybe_unwind_with, register_payload_formatter, remove_observer,
+    try_capture_panic_info, CaptureError, CaptureOptions, CaptureStatus, PanicHookInfo,
+};
+use std::{
+    cell::{Cell, RefCell},
+    mem,
+    panic::{self, panic_any},
+    ptr,
+    sync::{Arc, Mutex, Once},
+    thread::LocalKey,
+};
+
+type Results = RefCell<Vec<Result<CaptureStatus, CaptureError>>>;
+
+thread_local! {
+    /// The panic information being captured by `hook` on this thread.
+    static CAPTURING: Cell<*const ()> = const { Cell::new(ptr::null()) };
+    static STATUSES: Results = const { RefCell::new(Vec::new()) };
+    static NESTED: Results = const { RefCell::new(Vec::new()) };
+}
+
+/// The payload whose formatter calls the capture again while the panic is
+/// being captured, like a hook that is reentered during the capture.
+struct Reenter;
+
+fn hook(info: &PanicHookInfo<'_>) {
+    CAPTURING.with(|capturing| capturing.set(info as *const PanicHookInfo<'_> as *const ()));
+    let res = try_capture_panic_info(info);
+    CAPTURING.with(|capturing| capturing.set(ptr::null()));
+    STATUSES.with(|statuses| statuses.borrow_mut().push(res));
+}
+
+fn set_hook() {
+    static SET_HOOK: Once = Once::new();
+    SET_HOOK.call_once(|| {
+        panic::set_hook(Box::new(hook));
+        register_payload_formatter(|_: &Reenter| {
+            let info = CAPTURING.with(Cell::get);
+            if !info.is_null() {
+                // SAFETY: The pointer is only set while `hook` borrows the panic information.
+                let info = unsafe { &*(info as *const PanicHookInfo<'_>) };
+                let res = try_capture_panic_info(info);
+                NESTED.with(|nested| nested.borrow_mut().push(res));
+            }
+            "reentered".to_owned()
+        });
+    });
+}
+
+fn take(results: &'static LocalKey<Results>) -> Vec<Result<CaptureStatus, CaptureError>> {
+    results.with(|results| mem::take(&mut *results.borrow_mut()))
+}
+
+#[test]
+fn reentrant_capture_is_reported() {
+    set_hook();
+    let notes = Arc::new(Mutex::new(vec![]));
+    let id = add_observer({
+        let notes = notes.clone();
+        move |info| {
+            if info.message() == "reentered" {
+                notes.lock().unwrap().extend(info.notes().iter().cloned());
+            }
+        }
+    });
+
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| panic_any(Reenter)).unwrap_err();
+    assert!(remove_observer(id));
+
+    assert_eq!(take(&NESTED), [Err(CaptureError::Reentrant)]);
+    assert_eq!(take(&STATUSES), [Ok(CaptureStatus::Captured)]);
+
+    // The outer capture is not affected by the failure.
+    assert_eq!(unwind.location().unwrap().line(), line);
+    assert_eq!(unwind.capture_miss(), None);
+
+    let note = "another panic in the scope was not captured: \
+                the capture was reentered while capturing another panic";
+    assert_eq!(unwind.capture_notes(), [note]);
+    assert_eq!(*notes.lock().unwrap(), [note]);
+
+    // The failure is reported only to the scope in which it occurred.
+    let unwind = maybe_unwind(|| panic!("next")).unwrap_err();
+    assert!(unwind.capture_notes().is_empty());
+    assert_eq!(take(&STATUSES), [Ok(CaptureStatus::Captured)]);
+}
+
+#[test]
+fn capture_status() {
+    set_hook();
+
+    let _ = maybe_unwind(|| panic!("captured"));
+    let _ = panic::catch_unwind(|| panic!("outside"));
+    let options = CaptureOptions::new().max_captures_per_scope(1);
+    let _ = maybe_unwind_with(options, || {
+        let _ = panic::catch_unwind(|| panic!("first"));
+        panic!("suppressed");
+    });
+
+    assert_eq!(
+        take(&STATUSES),
+        [
+            Ok(CaptureStatus::Captured),
+            Ok(CaptureStatus::NotInScope),
+            Ok(CaptureStatus::Captured),
+            Ok(CaptureStatus::Suppressed),
+        ]
+    );
+    assert!(take(&NESTED).is_empty());
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::{
+    disable_for_current_thread, enable_for_current_thread, maybe_unwind, set_hook, CaptureMiss,
+};
+use std::panic;
+
+#[test]
+fn miss_reasons() {
+    // The steps run in order, since they depend on the process-global hook.
+    let unwind = maybe_unwind(|| panic!("no hook")).unwrap_err();
+    assert!(unwind.location().is_none());
+    assert_eq!(unwind.why_no_capture(), Some(&CaptureMiss::NoHook));
+    assert_eq!(unwind.capture_miss(), Some(CaptureMiss::NoHook));
+    assert!(format!("{:#}", unwind)
+        .contains("note: location unavailable: no panic hook capturing the panic information"));
+
+    set_hook();
+    let unwind = maybe_unwind(|| panic!("captured")).unwrap_err();
+    assert!(unwind.location().is_some());
+    assert_eq!(unwind.why_no_capture(), None);
+    assert!(!format!("{:#}", unwind).contains("location unavailable"));
+
+    let unwind = maybe_unwind(|| panic::resume_unwind(Box::new("resumed"))).unwrap_err();
+    assert_eq!(unwind.why_no_capture(), Some(&CaptureMiss::Resumed));
+
+    disable_for_current_thread();
+    let unwind = maybe_unwind(|| panic!("disabled")).unwrap_err();
+    enable_for_current_thread();
+    assert_eq!(
+        unwind.why_no_capture(),
+        Some(&CaptureMiss::DisabledForThread)
+    );
+    assert_eq!(unwind.capture_miss(), None);
+    assert!(format!("{:#}", unwind)
+        .contains("note: location unavailable: the capture is disabled on the current thread"));
+
+    let installed = panic::take_hook();
+    panic::set_hook(Box::new(|_| ()));
+    let unwind = maybe_unwind(|| panic!("replaced")).unwrap_err();
+    panic::set_hook(installed);
+    assert_eq!(unwind.why_no_capture(), Some(&CaptureMiss::HookReplaced));
+    assert_eq!(
+        unwind.why_no_capture().unwrap().to_string(),
+        "the panic hook installed by maybe-unwind was replaced by another hook"
+    );
+
+    let unwind = maybe_unwind(|| panic!("restored")).unwrap_err();
+    assert_eq!(unwind.why_no_capture(), None);
+}
@@ -0,0 +1,218 @@
+//! The rules are checked against the messages generated by the real panics,
+//! so that a change of the formats in the standard library is caught.
+
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, PanicKind};
+use std::{
+    collections::HashMap,
+    mem,
+    panic::{panic_any, UnwindSafe},
+    ptr,
+};
+use support::ensure_set_hook;
+
+// `std::hint::black_box` is not available on the MSRV.
+fn black_box<T>(x: T) -> T {
+    let y = unsafe { ptr::read_volatile(&x) };
+    mem::forget(x);
+    y
+}
+
+fn classify(f: impl FnOnce() + UnwindSafe) -> PanicKind {
+    ensure_set_hook();
+    let unwind = maybe_unwind(f).unwrap_err();
+    let kind = unwind.classify();
+    assert_eq!(unwind.captured().unwrap().classify(), kind);
+    assert_eq!(unwind.to_snapshot().classify(), kind);
+    assert_eq!(unwind.to_map()["kind"], kind.as_str());
+    kind
+}
+
+#[test]
+fn assertions() {
+    assert_eq!(classify(|| assert!(black_box(false))), PanicKind::Assertion);
+    assert_eq!(
+        classify(|| assert_eq!(black_box(1), 2)),
+        PanicKind::Assertion
+    );
+    assert_eq!(
+        classify(|| assert_ne!(black_box(1), 1, "with {}", "message")),
+        PanicKind::Assertion
+    );
+}
+
+#[test]
+fn explicit_panics() {
+    assert_eq!(classify(|| panic!("oops")), PanicKind::Explicit);
+    assert_eq!(classify(|| panic!()), PanicKind::Explicit);
+    assert_eq!(classify(|| unreachable!()), PanicKind::Explicit);
+    assert_eq!(classify(|| todo!()), PanicKind::Explicit);
+    assert_eq!(
+        classify(|| assert!(black_box(false), "custom message")),
+        PanicKind::Explicit
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(None::<i32>).expect("expected a value");
+        }),
+        PanicKind::Explicit
+    );
+}
+
+#[test]
+fn out_of_bounds() {
+    let v = vec![1, 2, 3];
+    assert_eq!(
+        classify(|| {
+            black_box(v[black_box(3)]);
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(&v[black_box(1)..black_box(5)]);
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(&v[black_box(4)..]);
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(&v[black_box(2)..black_box(1)]);
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(&"héllo"[..black_box(2)]);
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(&"hello"[..black_box(10)]);
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(&"hello"[black_box(3)..black_box(2)]);
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(v.clone()).remove(black_box(5));
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(v.clone()).insert(black_box(5), 0);
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(v.clone()).swap_remove(black_box(5));
+        }),
+        PanicKind::IndexOutOfBounds
+    );
+}
+
+#[test]
+fn unwraps() {
+    assert_eq!(
+        classify(|| {
+            black_box(None::<i32>).unwrap();
+        }),
+        PanicKind::Unwrap
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(Err::<i32, _>("error")).unwrap();
+        }),
+        PanicKind::Unwrap
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(Ok::<_, i32>(1)).unwrap_err();
+        }),
+        PanicKind::Unwrap
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(HashMap::<i32, i32>::new()).get(&0).unwrap();
+        }),
+        PanicKind::Unwrap
+    );
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(
+        classify(|| {
+            black_box(1 / black_box(0));
+        }),
+        PanicKind::Overflow
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(1 % black_box(0));
+        }),
+        PanicKind::Overflow
+    );
+    assert_eq!(
+        classify(|| {
+            black_box(i32::MIN / black_box(-1));
+        }),
+        PanicKind::Overflow
+    );
+
+    // The overflow checks are only enabled with the debug assertions.
+    if cfg!(debug_assertions) {
+        assert_eq!(
+            classify(|| {
+                black_box(u8::MAX + black_box(1));
+            }),
+            PanicKind::Overflow
+        );
+        assert_eq!(
+            classify(|| {
+                black_box(0u8 - black_box(1));
+            }),
+            PanicKind::Overflow
+        );
+        assert_eq!(
+            classify(|| {
+                black_box(u8::MAX * black_box(2));
+            }),
+            PanicKind::Overflow
+        );
+        assert_eq!(
+            classify(|| {
+                black_box(-black_box(i8::MIN));
+            }),
+            PanicKind::Overflow
+        );
+        assert_eq!(
+            classify(|| {
+                black_box(1u8 << black_box(8));
+            }),
+            PanicKind::Overflow
+        );
+    }
+}
+
+#[test]
+fn other_payloads() {
+    assert_eq!(classify(|| panic_any(42)), PanicKind::Other);
+    assert_eq!(classify(|| panic_any(())), PanicKind::Other);
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, set_scope_label, PanicCollector};
+use std::{thread, time::Duration};
+use support::ensure_set_hook;
+
+#[test]
+fn collect_from_threads() {
+    ensure_set_hook();
+    let (collector, receiver) = PanicCollector::new();
+
+    let handles: Vec<_> = (0..3)
+        .map(|i| {
+            thread::spawn(move || {
+                let unwind = maybe_unwind(|| {
+                    set_scope_label(format!("collect_from_threads_{}", i));
+                    panic!("worker {}", i);
+                })
+                .unwrap_err();
+                (thread::current().id(), unwind)
+            })
+        })
+        .collect();
+
+    let mut labels = vec![];
+    while labels.len() < 3 {
+        let panic = receiver
+            .recv_timeout(Duration::from_secs(10))
+            .expect("missing panic");
+        if let Some(label) = panic.scope_label() {
+            if let Some(i) = label.strip_prefix("collect_from_threads_") {
+                assert_eq!(panic.message(), format!("worker {}", i));
+                labels.push((label.to_owned(), panic.thread_id()));
+            }
+        }
+    }
+    drop(collector);
+
+    for handle in handles {
+        let (thread_id, unwind) = handle.join().unwrap();
+        let label = unwind.scope_label().unwrap();
+        assert!(labels.contains(&(label.to_owned(), thread_id)));
+    }
+    let mut labels: Vec<_> = labels.into_iter().map(|(label, _)| label).collect();
+    labels.sort();
+    assert_eq!(
+        labels,
+        [
+            "collect_from_threads_0",
+            "collect_from_threads_1",
+            "collect_from_threads_2"
+        ]
+    );
+
+    // The channel is disconnected after the collector is dropped.
+    let _ = receiver.drain();
+    assert!(receiver.recv().is_none());
+}
+
+#[test]
+fn label_is_scoped() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| {
+        set_scope_label("outer");
+        let inner = maybe_unwind(|| {
+            set_scope_label("inner");
+            panic!("inner");
+        })
+        .unwrap_err();
+        assert_eq!(inner.scope_label(), Some("inner"));
+
+        let unlabeled = maybe_unwind(|| panic!("nested")).unwrap_err();
+        assert_eq!(unlabeled.scope_label(), Some("outer"));
+
+        panic!("outer");
+    })
+    .unwrap_err();
+    assert_eq!(unwind.scope_label(), Some("outer"));
+
+    let unwind = maybe_unwind(|| panic!("unlabeled")).unwrap_err();
+    assert_eq!(unwind.scope_label(), None);
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::{
+    compose, compose_with_current_hook, hook_installed, maybe_unwind, maybe_unwind_quiet,
+    HookOptions, PanicHookFn,
+};
+use std::{
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+fn address(hook: &PanicHookFn) -> *const () {
+    hook as *const PanicHookFn as *const ()
+}
+
+#[test]
+fn compose_twice() {
+    let prev: Box<PanicHookFn> = Box::new(|_| {});
+    let prev_addr = address(&*prev);
+
+    let composed = compose(prev, HookOptions::new());
+    let composed_addr = address(&*composed);
+    assert_ne!(composed_addr, prev_addr);
+
+    let again = compose(composed, HookOptions::new().also_forward_captured(true));
+    assert_eq!(
+        address(&*again),
+        composed_addr,
+        "the composed hook is returned as it is"
+    );
+}
+
+// The hook is process-global, so the scenarios are run in a single test.
+#[test]
+fn compose_with_framework_hook() {
+    static CALLED: AtomicUsize = AtomicUsize::new(0);
+
+    // The hook composed by hand is still detected after a quiet scope restores it.
+    panic::set_hook(compose(panic::take_hook(), HookOptions::new()));
+    let _ = maybe_unwind_quiet(|| panic!("quiet"));
+    assert!(!compose_with_current_hook(HookOptions::new()));
+
+    // The hook installed by another framework.
+    panic::set_hook(Box::new(|_| {
+        CALLED.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    assert!(compose_with_current_hook(HookOptions::new()));
+    assert!(hook_installed());
+    assert!(!compose_with_current_hook(HookOptions::new()));
+    assert!(hook_installed());
+
+    let unwind = maybe_unwind(|| panic!("captured")).unwrap_err();
+    assert!(unwind.location().is_some());
+    assert_eq!(CALLED.load(Ordering::SeqCst), 0);
+
+    let res = panic::catch_unwind(|| panic!("not captured"));
+    assert!(res.is_err());
+    assert_eq!(
+        CALLED.load(Ordering::SeqCst),
+        1,
+        "the framework hook is called exactly once"
+    );
+
+    // The hook composed by hand is also detected.
+    let hook = compose(panic::take_hook(), HookOptions::new());
+    panic::set_hook(hook);
+    let res = panic::catch_unwind(|| panic!("not captured"));
+    assert!(res.is_err());
+    assert_eq!(CALLED.load(Ordering::SeqCst), 2);
+}
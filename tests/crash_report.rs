@@ -0,0 +1,140 @@
+#![cfg(all(feature = "capture", not(miri)))]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, with_context, HookOptions};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use support::example_path;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!(
+        "maybe-unwind-crash-report-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn crash_files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.starts_with("crash-"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn uncaptured_panic() {
+    let dir = temp_dir("uncaptured");
+    let output = Command::new(example_path("crash_report"))
+        .arg(&dir)
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    // The previous hook is still called.
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("soak failed"), "{}", stderr);
+
+    let names = crash_files(&dir);
+    assert_eq!(names.len(), 1, "{:?}", names);
+    assert!(names[0].ends_with(".txt"));
+    let report = fs::read_to_string(dir.join(&names[0])).unwrap();
+    assert_eq!(
+        report,
+        format!(
+            "thread 'main' panicked at {}:13:9:\n\
+             soak failed\n\
+             context:\n    while soaking\n\
+             note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n",
+            Path::new("examples").join("crash_report.rs").display()
+        )
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rotate_oldest_files() {
+    let dir = temp_dir("rotate");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("crash-0000000001.000000000-1-0.txt"), "oldest").unwrap();
+    fs::write(dir.join("crash-0000000002.000000000-1-0.txt"), "older").unwrap();
+    fs::write(dir.join("unrelated.txt"), "kept").unwrap();
+
+    for _ in 0..2 {
+        let output = Command::new(example_path("crash_report"))
+            .arg(&dir)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+    }
+
+    // At most two reports are kept, and the oldest ones are removed first.
+    let names = crash_files(&dir);
+    assert_eq!(names.len(), 2, "{:?}", names);
+    assert!(names
+        .iter()
+        .all(|name| !name.starts_with("crash-000000000")));
+    assert!(dir.join("unrelated.txt").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn captured_panic_and_fs_errors() {
+    let dir = temp_dir("captured");
+    // The path of the directory is occupied by a file, so every write fails.
+    let blocked = temp_dir("blocked");
+    fs::write(&blocked, "").unwrap();
+
+    maybe_unwind::set_hook_with(
+        HookOptions::new()
+            .write_crash_report_to(&dir)
+            .crash_report_captured(true),
+    );
+    let line = line!() + 2;
+    let unwind = maybe_unwind(|| {
+        with_context("inside the scope", || panic!("captured oops"));
+    })
+    .unwrap_err();
+    assert_eq!(unwind.location().unwrap().line(), line);
+
+    let names = crash_files(&dir);
+    assert_eq!(names.len(), 1, "{:?}", names);
+    let report = fs::read_to_string(dir.join(&names[0])).unwrap();
+    assert!(
+        report.starts_with(&format!(
+            "thread 'captured_panic_and_fs_errors' panicked at {}:{}:",
+            file!(),
+            line
+        )),
+        "{}",
+        report
+    );
+    assert!(report.contains(
+        ":\ncaptured oops\n\
+         note: the panic was captured by a capture scope\n\
+         context:\n    inside the scope\n"
+    ));
+    fs::remove_dir_all(&dir).unwrap();
+
+    maybe_unwind::set_hook_with(
+        HookOptions::new()
+            .write_crash_report_to(&blocked)
+            .crash_report_captured(true),
+    );
+    let unwind = maybe_unwind(|| panic!("still captured")).unwrap_err();
+    assert_eq!(unwind.message(), "still captured");
+    assert!(unwind.location().is_some());
+
+    fs::remove_file(&blocked).unwrap();
+}
@@ -0,0 +1,66 @@
+#![cfg(not(feature = "capture"))]
+
+mod support;
+
+use maybe_unwind::{
+    maybe_unwind, maybe_unwind_with, panic_stats, with_context, BacktraceMode, BacktraceStatus,
+    CaptureMiss, CaptureOptions,
+};
+use std::panic;
+use support::ensure_set_hook;
+
+#[test]
+fn never_unwind() {
+    ensure_set_hook();
+    assert_eq!(maybe_unwind(|| "foo").unwrap(), "foo");
+}
+
+#[test]
+fn no_captured_information() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| with_context("frame", || panic!("bar"))).unwrap_err();
+    assert_eq!(unwind.payload_str(), "bar");
+    assert!(unwind.location().is_none());
+    assert!(unwind.context_frames().is_empty());
+    assert!(unwind.thread_name().is_none());
+    assert!(unwind.timestamp().is_none());
+    assert!(unwind.caller().file().contains(file!()));
+    assert_eq!(unwind.to_string(), "bar");
+}
+
+#[test]
+fn options_are_ignored() {
+    ensure_set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    let unwind = maybe_unwind_with(options, || panic!("bar")).unwrap_err();
+    assert!(unwind.location().is_none());
+    assert_eq!(unwind.backtrace_status(), BacktraceStatus::CaptureFailed);
+    #[cfg(backtrace)]
+    assert!(unwind.backtrace().is_none());
+}
+
+#[test]
+fn resumed_panic() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| panic::resume_unwind(Box::new("bar"))).unwrap_err();
+    assert_eq!(unwind.capture_miss(), None);
+    assert_eq!(unwind.why_no_capture(), Some(&CaptureMiss::FeatureDisabled));
+}
+
+#[test]
+fn hook_captures_nothing() {
+    let before = panic_stats();
+    let captured = maybe_unwind(|| {
+        let prev = panic::take_hook();
+        panic::set_hook(Box::new(|info| {
+            assert!(!maybe_unwind::capture_panic_info(info));
+        }));
+        let res = panic::catch_unwind(|| panic!("bar"));
+        panic::set_hook(prev);
+        res.is_err()
+    });
+    assert!(captured.unwrap());
+
+    // The hook returns before recording anything.
+    assert_eq!(panic_stats(), before);
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, take_orphaned_unwinds, DropGuard, Unwind};
+use std::{cell::RefCell, rc::Rc};
+use support::ensure_set_hook;
+
+/// Take the orphaned panics reported by the guards with `label`.
+fn take_reported(label: &str) -> Vec<Unwind> {
+    take_orphaned_unwinds()
+        .into_iter()
+        .filter(|unwind| unwind.scope_label() == Some(label))
+        .collect()
+}
+
+#[test]
+fn fixture_teardown_panics() {
+    ensure_set_hook();
+    let label = "drop_guard::fixture";
+
+    let line = line!() + 3;
+    let res = maybe_unwind(|| {
+        let _fixture = DropGuard::with_teardown(label, || {
+            panic!("failed to remove the temporary directory");
+        });
+        // The test body itself succeeds.
+    });
+    assert!(res.is_ok());
+
+    let reported = take_reported(label);
+    assert_eq!(reported.len(), 1);
+    let unwind = &reported[0];
+    assert_eq!(unwind.message(), "failed to remove the temporary directory");
+    assert_eq!(
+        (unwind.caller().file(), unwind.caller().line()),
+        (file!(), line - 1)
+    );
+    let location = unwind.location().unwrap();
+    assert_eq!((location.file(), location.line()), (file!(), line));
+}
+
+#[test]
+fn teardowns_in_reverse_order() {
+    ensure_set_hook();
+    let label = "drop_guard::order";
+    let order = Rc::new(RefCell::new(Vec::new()));
+
+    {
+        let mut guard = DropGuard::new(label);
+        assert_eq!(guard.label(), label);
+        for i in 0..3 {
+            let order = Rc::clone(&order);
+            guard.add_teardown(move || {
+                order.borrow_mut().push(i);
+                if i == 1 {
+                    panic!("teardown {}", i);
+                }
+            });
+        }
+    }
+
+    // The panic in a teardown does not prevent the others from running.
+    assert_eq!(*order.borrow(), [2, 1, 0]);
+    let reported = take_reported(label);
+    assert_eq!(reported.len(), 1);
+    assert_eq!(reported[0].message(), "teardown 1");
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind_each, FailureSummary, IterMaybeUnwindExt as _};
+use support::ensure_set_hook;
+
+#[test]
+fn collect_failures() {
+    ensure_set_hook();
+    let mut visited = vec![];
+    let line = line!() + 4;
+    let results = maybe_unwind_each(vec!["a", "b", "c", "d", "e"], |&case| {
+        visited.push(case);
+        match case {
+            "b" => panic!("case b failed"),
+            "d" => panic!("case d failed with {}", 42),
+            _ => case.to_uppercase(),
+        }
+    });
+    assert_eq!(visited, ["a", "b", "c", "d", "e"]);
+    assert_eq!(results.len(), 5);
+
+    let items: Vec<_> = results.iter().map(|(item, _)| *item).collect();
+    assert_eq!(items, ["a", "b", "c", "d", "e"]);
+    assert_eq!(*results[0].1.as_ref().unwrap(), "A");
+    assert_eq!(*results[2].1.as_ref().unwrap(), "C");
+    assert_eq!(*results[4].1.as_ref().unwrap(), "E");
+
+    let b = results[1].1.as_ref().unwrap_err();
+    assert_eq!(b.payload_str(), "case b failed");
+    assert_eq!(b.location().unwrap().line(), line);
+    let d = results[3].1.as_ref().unwrap_err();
+    assert_eq!(d.payload_str(), "case d failed with 42");
+    assert_eq!(d.location().unwrap().line(), line + 1);
+
+    let summary = FailureSummary::new(&results);
+    assert!(!summary.is_empty());
+    assert_eq!(summary.len(), 2);
+    let indices: Vec<_> = summary.iter().map(|(i, _, _)| i).collect();
+    assert_eq!(indices, [1, 3]);
+    assert_eq!(
+        summary.to_string(),
+        format!(
+            "2 of 5 items panicked\n  \
+             item 1 (\"b\"): panicked at {file}:{b}:20: case b failed\n  \
+             item 3 (\"d\"): panicked at {file}:{d}:20: case d failed with 42",
+            file = file!(),
+            b = line,
+            d = line + 1,
+        )
+    );
+}
+
+#[test]
+fn no_failures() {
+    ensure_set_hook();
+    let results = maybe_unwind_each(0..3, |&n| n * 2);
+    let summary = FailureSummary::new(&results);
+    assert!(summary.is_empty());
+    assert_eq!(summary.to_string(), "0 of 3 items panicked");
+}
+
+#[test]
+fn partition_unwinds() {
+    ensure_set_hook();
+    let (values, unwinds) = (0..10)
+        .map_maybe_unwind(|i| {
+            if i % 3 == 1 {
+                panic!("item {} failed", i);
+            }
+            i * 2
+        })
+        .partition_unwinds();
+
+    assert_eq!(values, [0, 4, 6, 10, 12, 16, 18]);
+    let indices: Vec<_> = unwinds.iter().map(|(i, _)| *i).collect();
+    assert_eq!(indices, [1, 4, 7]);
+    for (i, unwind) in &unwinds {
+        assert_eq!(unwind.payload_str(), format!("item {} failed", i));
+        assert_eq!(unwind.caller().file(), file!());
+    }
+}
+
+/// An iterator running the discovery code in `next()`.
+struct Discovery {
+    calls: usize,
+    panic_at: usize,
+}
+
+impl Iterator for Discovery {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.calls += 1;
+        if self.calls == self.panic_at {
+            panic!("discovery failed");
+        }
+        Some(format!("case{}", self.calls))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+#[test]
+fn maybe_unwind_iter_fuses_after_panic() {
+    ensure_set_hook();
+    let mut iter = Discovery {
+        calls: 0,
+        panic_at: 3,
+    }
+    .maybe_unwind_iter();
+    let caller = line!() - 1;
+    assert_eq!(iter.size_hint(), (0, None));
+
+    assert_eq!(iter.next().unwrap().unwrap(), "case1");
+    assert_eq!(iter.next().unwrap().unwrap(), "case2");
+
+    let line = line!() - 24;
+    let unwind = iter.next().unwrap().unwrap_err();
+    assert_eq!(unwind.payload_str(), "discovery failed");
+    let location = unwind.location().unwrap();
+    assert_eq!((location.file(), location.line()), (file!(), line));
+    assert_eq!(unwind.caller().line(), caller);
+
+    assert!(iter.next().is_none());
+    assert!(iter.next().is_none());
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+}
+
+#[test]
+fn maybe_unwind_iter_without_panic() {
+    ensure_set_hook();
+    let iter = vec![1, 2, 3].into_iter().maybe_unwind_iter();
+    assert_eq!(iter.size_hint(), (0, Some(3)));
+    let items: Vec<_> = iter.map(Result::unwrap).collect();
+    assert_eq!(items, [1, 2, 3]);
+}
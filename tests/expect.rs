@@ -0,0 +1,53 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{expect_unwind, ExpectError, PayloadPattern};
+use support::ensure_set_hook;
+
+#[test]
+fn matched() {
+    ensure_set_hook();
+    let unwind = expect_unwind("bar", || panic!("foo bar baz")).unwrap();
+    assert_eq!(unwind.payload_str(), "foo bar baz");
+    assert!(unwind
+        .location()
+        .map_or(false, |loc| loc.file().contains(file!())));
+
+    let unwind = expect_unwind(PayloadPattern::exact("foo"), || panic!("foo")).unwrap();
+    assert_eq!(unwind.payload_str(), "foo");
+}
+
+#[test]
+fn did_not_panic() {
+    ensure_set_hook();
+    let err = expect_unwind("bar", || "foo").unwrap_err();
+    assert!(matches!(err, ExpectError::DidNotPanic));
+    assert_eq!(err.to_string(), "the closure did not panic");
+}
+
+#[test]
+fn wrong_payload() {
+    ensure_set_hook();
+    let err = expect_unwind(PayloadPattern::exact("foo"), || panic!("foo bar")).unwrap_err();
+    match &err {
+        ExpectError::WrongPayload { unwind, .. } => assert_eq!(unwind.payload_str(), "foo bar"),
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    let msg = err.to_string();
+    assert!(msg.contains(r#"panic message: "foo bar""#));
+    assert!(msg.contains(r#"expected pattern: equal to "foo""#));
+    assert!(msg.contains(file!()));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_pattern() {
+    ensure_set_hook();
+    let re = regex::Regex::new(r"^index \d+ out of range$").unwrap();
+    assert!(expect_unwind(re.clone(), || panic!("index 42 out of range")).is_ok());
+
+    let err = expect_unwind(re, || panic!("index out of range")).unwrap_err();
+    assert!(matches!(err, ExpectError::WrongPayload { .. }));
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::ffi;
+use std::thread;
+use support::ensure_set_hook;
+
+extern "C" fn checked_div(a: i32, b: i32, out: *mut i32) -> i32 {
+    match ffi::guard(|| a / b) {
+        Ok(value) => {
+            unsafe { *out = value };
+            0
+        }
+        Err(..) => -1,
+    }
+}
+
+#[test]
+fn error_code_and_last_unwind() {
+    ensure_set_hook();
+    ffi::clear_last_ffi_unwind();
+
+    let mut out = 0;
+    assert_eq!(checked_div(6, 3, &mut out), 0);
+    assert_eq!(out, 2);
+    assert!(ffi::last_ffi_unwind().is_none());
+
+    assert_eq!(checked_div(1, 0, &mut out), -1);
+    let last = ffi::last_ffi_unwind().unwrap();
+    assert_eq!(last.message(), "attempt to divide by zero");
+    assert!(last
+        .location()
+        .map_or(false, |loc| loc.file().contains(file!())));
+
+    // the stored report is kept until it is cleared or replaced.
+    assert!(ffi::last_ffi_unwind().is_some());
+    ffi::clear_last_ffi_unwind();
+    assert!(ffi::last_ffi_unwind().is_none());
+}
+
+#[test]
+fn per_thread_storage() {
+    ensure_set_hook();
+    let _ = ffi::guard(|| panic!("main thread"));
+
+    thread::spawn(|| {
+        assert!(ffi::last_ffi_unwind().is_none());
+        let _ = ffi::guard(|| panic!("worker thread"));
+        assert_eq!(ffi::last_ffi_unwind().unwrap().message(), "worker thread");
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(ffi::last_ffi_unwind().unwrap().message(), "main thread");
+}
@@ -0,0 +1,49 @@
+// These tests spawn the example binary, which is not supported by Miri.
+#![cfg(all(feature = "capture", not(miri)))]
+
+mod support;
+
+use std::{path::Path, process::Command};
+use support::example_path;
+
+fn run_example(mode: &str) -> String {
+    let output = Command::new(example_path("forward_captured"))
+        .arg(mode)
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn capture_only() {
+    let stderr = run_example("capture-only");
+    assert!(!stderr.contains("panicked at"), "{}", stderr);
+    assert!(stderr.contains("harness: captured\n"), "{}", stderr);
+}
+
+#[test]
+fn also_forward_captured() {
+    let stderr = run_example("forward");
+    // The default hook prints the thread ID after the name on newer compilers.
+    assert!(stderr.contains("thread 'main'"), "{}", stderr);
+    let location = format!(
+        " panicked at {}:",
+        Path::new("examples").join("forward_captured.rs").display()
+    );
+    assert!(stderr.contains(&location), "{}", stderr);
+    assert!(stderr.contains(":\ncaptured\n"), "{}", stderr);
+    assert!(stderr.contains("harness: captured\n"), "{}", stderr);
+}
+
+#[test]
+fn quiet_wins() {
+    let stderr = run_example("quiet");
+    assert!(!stderr.contains("panicked at"), "{}", stderr);
+    assert!(
+        stderr.contains("harness: captured in the quiet scope\n"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,67 @@
+#![cfg(all(feature = "capture", feature = "futures01"))]
+
+mod support;
+
+use futures01::{
+    executor,
+    future::{self, Future as _},
+    task, Async,
+};
+use maybe_unwind::{Future01MaybeUnwindExt as _, MaybeUnwindError};
+use support::ensure_set_hook;
+
+#[test]
+fn capture_panic_in_lazy() {
+    ensure_set_hook();
+    let line = line!() + 1;
+    let fut = future::lazy(|| -> Result<(), ()> { panic!("lazy") });
+    let caller_line = line!() + 1;
+    let err = fut.maybe_unwind().wait().unwrap_err();
+
+    let unwind = err.unwind().expect("the panic should be captured");
+    assert_eq!(unwind.payload_str(), "lazy");
+    let loc = unwind.location().expect("the location should be captured");
+    assert_eq!(loc.file(), file!());
+    assert_eq!(loc.line(), line);
+    assert_eq!(unwind.caller().line(), caller_line);
+}
+
+#[test]
+fn pass_through_item_and_error() {
+    ensure_set_hook();
+    let res = future::ok::<_, ()>(42).maybe_unwind().wait();
+    assert_eq!(res.ok(), Some(42));
+
+    let err = future::err::<(), _>("failed")
+        .maybe_unwind()
+        .wait()
+        .unwrap_err();
+    assert_eq!(err.inner(), Some(&"failed"));
+    assert!(err.unwind().is_none());
+    assert_eq!(err.to_string(), "failed");
+}
+
+#[test]
+fn panic_after_not_ready() {
+    ensure_set_hook();
+    let mut polled = 0;
+    let line = line!() + 4;
+    let fut = future::poll_fn(move || -> Result<Async<()>, ()> {
+        polled += 1;
+        if polled == 3 {
+            panic!("polled {} times", polled);
+        }
+        task::current().notify();
+        Ok(Async::NotReady)
+    });
+
+    // Driven by the current-thread task harness of futures 0.1.
+    let res = executor::spawn(fut.maybe_unwind()).wait_future();
+    match res {
+        Err(MaybeUnwindError::Unwind(unwind)) => {
+            assert_eq!(unwind.payload_str(), "polled 3 times");
+            assert_eq!(unwind.location().unwrap().line(), line);
+        }
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
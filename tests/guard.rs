@@ -0,0 +1,210 @@
+#![cfg(all(feature = "capture", feature = "macros"))]
+
+mod support;
+
+use maybe_unwind::{guard, Unwind};
+use std::{fmt, num::ParseIntError};
+use support::ensure_set_hook;
+
+#[derive(Debug)]
+enum PluginError {
+    Parse(ParseIntError),
+    Panicked(Unwind),
+}
+
+impl From<ParseIntError> for PluginError {
+    fn from(err: ParseIntError) -> Self {
+        PluginError::Parse(err)
+    }
+}
+
+impl PluginError {
+    fn parse(&self) -> &ParseIntError {
+        match self {
+            PluginError::Parse(err) => err,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn unwind(&self) -> &Unwind {
+        match self {
+            PluginError::Panicked(unwind) => unwind,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Plugin {
+    name: String,
+    loaded: usize,
+}
+
+#[guard(error = "PluginError::Panicked")]
+impl Plugin {
+    fn parse(&self, input: &str) -> Result<u32, PluginError> {
+        let n: u32 = input.parse()?;
+        assert!(n > 0, "zero is not allowed");
+        Ok(n)
+    }
+
+    fn load(&mut self, fail: bool) -> Result<(), PluginError> {
+        self.loaded += 1;
+        if fail {
+            panic!("failed to load {}", self.name);
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> Result<&str, PluginError> {
+        if self.name.is_empty() {
+            panic!("unnamed");
+        }
+        Ok(&self.name)
+    }
+
+    fn into_name(self) -> Result<String, PluginError> {
+        Ok(self.name)
+    }
+}
+
+#[test]
+fn sync_methods() {
+    ensure_set_hook();
+    let mut plugin = Plugin {
+        name: "foo".into(),
+        ..Default::default()
+    };
+
+    assert_eq!(plugin.parse("42").unwrap(), 42);
+    let err = plugin.parse("x").unwrap_err();
+    assert_eq!(err.parse().to_string(), "invalid digit found in string");
+
+    let err = plugin.parse("0").unwrap_err();
+    let unwind = err.unwind();
+    assert_eq!(unwind.message(), "zero is not allowed");
+    assert_eq!(unwind.location().unwrap().line(), 47);
+
+    plugin.load(false).unwrap();
+    let err = plugin.load(true).unwrap_err();
+    assert_eq!(err.unwind().message(), "failed to load foo");
+    assert_eq!(plugin.loaded, 2);
+
+    assert_eq!(plugin.name().unwrap(), "foo");
+    assert_eq!(
+        Plugin::default().name().unwrap_err().unwind().message(),
+        "unnamed"
+    );
+    assert_eq!(plugin.into_name().unwrap(), "foo");
+}
+
+/// The error converted from `Unwind` by `From`.
+#[derive(Debug)]
+struct Panicked(String);
+
+impl From<Unwind> for Panicked {
+    fn from(unwind: Unwind) -> Self {
+        Panicked(unwind.message().into_owned())
+    }
+}
+
+impl fmt::Display for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[guard]
+fn free_function(n: u32) -> Result<u32, Panicked> {
+    Ok(10 / n)
+}
+
+trait Callback {
+    fn call(&self, n: u32) -> Result<u32, Panicked>;
+}
+
+struct Divider;
+
+#[guard]
+impl Callback for Divider {
+    fn call(&self, n: u32) -> Result<u32, Panicked> {
+        Ok(100 / n)
+    }
+}
+
+#[test]
+fn from_conversion() {
+    ensure_set_hook();
+    assert_eq!(free_function(2).unwrap(), 5);
+    assert_eq!(
+        free_function(0).unwrap_err().to_string(),
+        "attempt to divide by zero"
+    );
+
+    let callback: &dyn Callback = &Divider;
+    assert_eq!(callback.call(4).unwrap(), 25);
+    assert_eq!(
+        callback.call(0).unwrap_err().to_string(),
+        "attempt to divide by zero"
+    );
+}
+
+#[cfg(feature = "futures")]
+mod async_methods {
+    use super::*;
+    use futures_executor::block_on;
+    use std::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    struct Service {
+        prefix: &'static str,
+    }
+
+    #[guard(error = "PluginError::Panicked")]
+    impl Service {
+        async fn handle(&self, input: &str) -> Result<String, PluginError> {
+            let n: u32 = input.parse()?;
+            yield_now().await;
+            assert!(n > 0, "zero is not allowed");
+            Ok(format!("{}{}", self.prefix, n))
+        }
+    }
+
+    /// A future that is pending once.
+    async fn yield_now() {
+        struct YieldNow(bool);
+
+        impl Future for YieldNow {
+            type Output = ();
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                if self.0 {
+                    return Poll::Ready(());
+                }
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+
+        YieldNow(false).await
+    }
+
+    #[test]
+    fn async_methods() {
+        ensure_set_hook();
+        let service = Service { prefix: "#" };
+        block_on(async {
+            assert_eq!(service.handle("1").await.unwrap(), "#1");
+            assert!(matches!(
+                service.handle("x").await,
+                Err(PluginError::Parse(..))
+            ));
+            let err = service.handle("0").await.unwrap_err();
+            assert_eq!(err.unwind().message(), "zero is not allowed");
+        });
+    }
+}
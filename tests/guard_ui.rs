@@ -0,0 +1,7 @@
+#![cfg(all(feature = "macros", not(miri)))]
+
+#[test]
+fn compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/guard/*.rs");
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "history")]
+
+mod support;
+
+use maybe_unwind::{
+    clear_history, history, maybe_unwind, maybe_unwind_named, set_history_capacity,
+};
+use support::ensure_set_hook;
+
+// The history is process-global, so the scenarios are run in a single test.
+#[test]
+fn bounded_history() {
+    ensure_set_hook();
+    clear_history();
+    set_history_capacity(4);
+
+    for i in 0..10 {
+        let _ = maybe_unwind_named(format!("case {}", i), || panic!("panic {}", i));
+    }
+    // The panics are recorded even if nobody inspects the `Unwind`s.
+    let _ = maybe_unwind(|| maybe_unwind(|| panic!("nested")));
+
+    let recent = history();
+    let messages: Vec<_> = recent.iter().map(|info| info.message()).collect();
+    assert_eq!(messages, ["panic 7", "panic 8", "panic 9", "nested"]);
+    let labels: Vec<_> = recent.iter().map(|info| info.scope_label()).collect();
+    assert_eq!(
+        labels,
+        [Some("case 7"), Some("case 8"), Some("case 9"), None]
+    );
+    assert!(recent.iter().all(|info| info.timestamp().is_some()));
+    assert!(recent
+        .iter()
+        .all(|info| info.location().unwrap().file() == file!()));
+    assert!(recent
+        .iter()
+        .all(|info| info.thread_name() == Some("bounded_history")));
+
+    // Shrinking the capacity discards the oldest ones.
+    set_history_capacity(2);
+    let messages: Vec<_> = history()
+        .iter()
+        .map(|info| info.message().into_owned())
+        .collect();
+    assert_eq!(messages, ["panic 9", "nested"]);
+
+    // Zero capacity disables the recording.
+    set_history_capacity(0);
+    let _ = maybe_unwind(|| panic!("ignored"));
+    assert!(history().is_empty());
+
+    set_history_capacity(4);
+    let _ = maybe_unwind(|| panic!("after clear"));
+    clear_history();
+    assert!(history().is_empty());
+}
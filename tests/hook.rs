@@ -0,0 +1,26 @@
+#![cfg(feature = "capture")]
+#![deny(deprecated)]
+
+use maybe_unwind::{capture_panic_info, maybe_unwind, PanicHookInfo};
+use std::panic;
+
+fn custom_hook(info: &PanicHookInfo<'_>) {
+    if !capture_panic_info(info) {
+        eprintln!("{}", info);
+    }
+}
+
+#[test]
+fn custom_hook_without_deprecation() {
+    panic::set_hook(Box::new(custom_hook));
+    let unwind = maybe_unwind(|| panic!("foo")).unwrap_err();
+    assert_eq!(unwind.payload_str(), "foo");
+    assert!(unwind.location().is_some());
+
+    panic::set_hook(Box::new(|info| {
+        capture_panic_info(info);
+    }));
+    let unwind = maybe_unwind(|| panic!("bar")).unwrap_err();
+    assert_eq!(unwind.payload_str(), "bar");
+    assert!(unwind.location().is_some());
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::{
+    capture_panic_info, last_hook_outcome, maybe_unwind, maybe_unwind_named, CaptureStatus,
+    HookOutcome,
+};
+use std::{
+    cell::RefCell,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+
+thread_local! {
+    /// The outcomes seen by the second stage of the hook.
+    static SEEN: RefCell<Vec<HookOutcome>> = const { RefCell::new(Vec::new()) };
+}
+
+fn set_two_stage_hook() {
+    static SET_HOOK: Once = Once::new();
+    SET_HOOK.call_once(|| {
+        panic::set_hook(Box::new(|info| {
+            let captured = capture_panic_info(info);
+
+            let outcome = last_hook_outcome();
+            assert_eq!(outcome.is_handled(), captured);
+            SEEN.with(|seen| seen.borrow_mut().push(outcome));
+        }));
+    });
+}
+
+fn take_seen() -> Vec<HookOutcome> {
+    SEEN.with(|seen| seen.borrow_mut().drain(..).collect())
+}
+
+#[test]
+fn captured() {
+    set_two_stage_hook();
+    take_seen();
+
+    let _ = maybe_unwind_named("suite::case", || {
+        let _ = maybe_unwind(|| panic!("inner"));
+        panic!("outer");
+    });
+    let seen = take_seen();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[0].status(), Ok(CaptureStatus::Captured));
+    assert!(seen[0].is_handled());
+    assert_eq!(seen[0].scope_label(), Some("suite::case"));
+    assert_eq!(seen[0].scope_depth(), 2);
+    assert_eq!(seen[1].status(), Ok(CaptureStatus::Captured));
+    assert_eq!(seen[1].scope_label(), Some("suite::case"));
+    assert_eq!(seen[1].scope_depth(), 1);
+
+    assert_eq!(
+        last_hook_outcome(),
+        HookOutcome::default(),
+        "the outcome is reset once the panic is caught"
+    );
+}
+
+#[test]
+fn uncaptured() {
+    set_two_stage_hook();
+    take_seen();
+
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| panic!("outside")));
+    let seen = take_seen();
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen[0].status(), Ok(CaptureStatus::NotInScope));
+    assert!(!seen[0].is_handled());
+    assert_eq!(seen[0].scope_label(), None);
+    assert_eq!(seen[0].scope_depth(), 0);
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{
+    current_scope_label, in_scope, maybe_unwind, maybe_unwind_named, scope_depth, set_scope_label,
+};
+use std::thread;
+use support::ensure_set_hook;
+
+#[test]
+fn outside_of_scope() {
+    assert!(!in_scope());
+    assert_eq!(scope_depth(), 0);
+    assert_eq!(current_scope_label(), None);
+
+    // The label is ignored outside of the scopes.
+    set_scope_label("ignored");
+    assert_eq!(current_scope_label(), None);
+}
+
+#[test]
+fn nested_scopes() {
+    ensure_set_hook();
+    maybe_unwind(|| {
+        assert!(in_scope());
+        assert_eq!(scope_depth(), 1);
+        assert_eq!(current_scope_label(), None);
+
+        maybe_unwind_named("outer", || {
+            assert_eq!(scope_depth(), 2);
+            let unwind = maybe_unwind(|| {
+                assert_eq!(scope_depth(), 3);
+                assert_eq!(current_scope_label().as_deref(), Some("outer"));
+                set_scope_label("inner");
+                assert_eq!(current_scope_label().as_deref(), Some("inner"));
+                panic!("nested");
+            })
+            .unwrap_err();
+            assert_eq!(unwind.payload_str(), "nested");
+            assert_eq!(scope_depth(), 2);
+            assert_eq!(current_scope_label().as_deref(), Some("outer"));
+        })
+        .unwrap();
+
+        assert_eq!(scope_depth(), 1);
+        assert_eq!(current_scope_label(), None);
+    })
+    .unwrap();
+    assert!(!in_scope());
+    assert_eq!(scope_depth(), 0);
+}
+
+#[test]
+fn thread_scoped() {
+    maybe_unwind(|| {
+        assert!(in_scope());
+        thread::spawn(|| {
+            assert!(!in_scope());
+            assert_eq!(scope_depth(), 0);
+        })
+        .join()
+        .unwrap();
+    })
+    .unwrap();
+}
+
+#[test]
+fn disabled_for_current_thread() {
+    maybe_unwind::disable_for_current_thread();
+    let res = maybe_unwind(|| {
+        assert!(!in_scope());
+        assert_eq!(scope_depth(), 0);
+    });
+    maybe_unwind::enable_for_current_thread();
+    res.unwrap();
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn inside_of_poll() {
+    use futures_executor::block_on;
+    use futures_task::noop_waker_ref;
+    use maybe_unwind::FutureMaybeUnwindExt as _;
+    use std::{
+        future::Future,
+        panic::AssertUnwindSafe,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    /// Records the scope depth at every poll.
+    struct Probe(Vec<usize>);
+
+    impl Future for Probe {
+        type Output = Vec<usize>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            self.0.push(scope_depth());
+            if self.0.len() < 2 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            Poll::Ready(std::mem::take(&mut self.0))
+        }
+    }
+
+    ensure_set_hook();
+    let mut cx = Context::from_waker(noop_waker_ref());
+    let mut fut = Probe(vec![]).maybe_unwind();
+
+    // The scope is entered only while polling the inner future.
+    assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+    assert!(!in_scope());
+    let depths = maybe_unwind(AssertUnwindSafe(|| Pin::new(&mut fut).poll(&mut cx))).unwrap();
+    match depths {
+        Poll::Ready(Ok(depths)) => assert_eq!(depths, [1, 2]),
+        _ => panic!("unexpected poll result"),
+    }
+
+    block_on(async {
+        let label = async { current_scope_label() }
+            .maybe_unwind_named("suite::async_case")
+            .await
+            .unwrap();
+        assert_eq!(label.as_deref(), Some("suite::async_case"));
+        assert_eq!(current_scope_label(), None);
+    });
+}
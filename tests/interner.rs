@@ -0,0 +1,46 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{interner_stats, maybe_unwind, set_hook, set_interner_capacity};
+use support::panic_in_helper;
+
+#[test]
+fn interned_file_names() {
+    // The steps run in order, since the interner is process-global.
+    set_hook();
+    let initial = interner_stats();
+    assert_eq!(initial.entries, 0);
+    assert_eq!(initial.capacity, 256);
+
+    let unwinds: Vec<_> = (0..1000)
+        .map(|_| maybe_unwind(|| panic!("same site")).unwrap_err())
+        .collect();
+    assert!(unwinds
+        .iter()
+        .all(|unwind| unwind.location().unwrap().file() == file!()));
+    let stats = interner_stats();
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.bytes, file!().len());
+    assert_eq!(stats.rejected, 0);
+
+    // The file names beyond the capacity are not interned.
+    set_interner_capacity(1);
+    for _ in 0..10 {
+        let unwind = maybe_unwind(panic_in_helper).unwrap_err();
+        assert!(unwind.location().unwrap().file().ends_with("mod.rs"));
+    }
+    let stats = interner_stats();
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.capacity, 1);
+    assert_eq!(stats.rejected, 10);
+
+    // The interned file names are still shared.
+    let unwind = maybe_unwind(|| panic!("same site")).unwrap_err();
+    assert_eq!(unwind.location().unwrap().file(), file!());
+    assert_eq!(interner_stats().rejected, 10);
+
+    set_interner_capacity(256);
+    let _ = maybe_unwind(panic_in_helper);
+    assert_eq!(interner_stats().entries, 2);
+}
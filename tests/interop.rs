@@ -0,0 +1,59 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, with_context, UnwindError};
+use std::io;
+use support::ensure_set_hook;
+
+#[test]
+fn io_error_round_trip() {
+    ensure_set_hook();
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| with_context("while decoding", || panic!("oops"))).unwrap_err();
+
+    let err = io::Error::from(unwind);
+    assert_eq!(err.kind(), io::ErrorKind::Other);
+
+    let message = err.to_string();
+    assert!(
+        message.starts_with(&format!("panicked at {}:{}:", file!(), line)),
+        "{}",
+        message
+    );
+    assert!(message.contains(": oops\ncaptured at "));
+    assert!(
+        message.ends_with("context:\n    while decoding"),
+        "{}",
+        message
+    );
+    assert!(!message.contains("stack backtrace"));
+    assert!(!message.contains("RUST_BACKTRACE"));
+
+    let inner = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<UnwindError>())
+        .expect("the inner error should be UnwindError");
+    assert_eq!(inner.location().unwrap().line(), line);
+    assert_eq!(inner.caller().line(), line);
+    assert_eq!(inner.snapshot().message(), "oops");
+
+    let payload = inner.take_payload().unwrap();
+    assert_eq!(*payload.downcast::<&str>().unwrap(), "oops");
+    assert!(inner.take_payload().is_none());
+}
+
+#[test]
+fn boxed_error() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    ensure_set_hook();
+    let err = maybe_unwind(|| panic!("oops"))
+        .unwrap_err()
+        .into_boxed_error();
+    assert_send_sync(&err);
+    assert!(err.source().is_none());
+
+    let err = err.downcast::<UnwindError>().unwrap();
+    assert!(err.location().unwrap().file().contains(file!()));
+}
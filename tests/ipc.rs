@@ -0,0 +1,116 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_named, with_context, UnwindSnapshot};
+use std::io::{self, Cursor};
+use support::ensure_set_hook;
+
+fn snapshot(msg: &'static str) -> UnwindSnapshot {
+    ensure_set_hook();
+    maybe_unwind_named("ipc::case", || {
+        with_context("while encoding", || panic!("{}", msg));
+    })
+    .unwrap_err()
+    .to_snapshot()
+}
+
+#[test]
+fn round_trip_in_memory() {
+    let first = snapshot("first \"quoted\"\nline");
+    let second = snapshot("second");
+
+    // Multiple records written to a stream are read one by one.
+    let mut pipe = vec![];
+    first.write_to(&mut pipe).unwrap();
+    second.write_to(&mut pipe).unwrap();
+    let mut reader = Cursor::new(pipe);
+    assert_eq!(UnwindSnapshot::read_from(&mut reader).unwrap(), first);
+    assert_eq!(UnwindSnapshot::read_from(&mut reader).unwrap(), second);
+    let err = UnwindSnapshot::read_from(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn reject_other_versions() {
+    let mut buf = vec![];
+    snapshot("versioned").write_to(&mut buf).unwrap();
+    assert_eq!(&buf[..4], b"MUWS");
+
+    let mut other = buf.clone();
+    other[4] = 255;
+    let err = UnwindSnapshot::read_from(&other[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(
+        err.to_string()
+            .contains("unsupported snapshot encoding version 255"),
+        "{}",
+        err
+    );
+
+    let err = UnwindSnapshot::read_from(&buf[..buf.len() - 1]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    let err = UnwindSnapshot::read_from(&b"not a snapshot"[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn json_line() {
+    ensure_set_hook();
+    let line = line!() + 1;
+    let snapshot = maybe_unwind(|| panic!("tab\there \"quoted\""))
+        .unwrap_err()
+        .to_snapshot();
+    let json = snapshot.to_json_line();
+    assert!(json.ends_with("}\n"));
+    assert_eq!(json.matches('\n').count(), 1, "{}", json);
+    assert!(json.starts_with("{\"version\":1,"), "{}", json);
+    assert!(
+        json.contains(r#""message":"tab\there \"quoted\"""#),
+        "{}",
+        json
+    );
+    assert!(json.contains(r#""kind":"explicit","#), "{}", json);
+    assert!(
+        json.contains(&format!(
+            r#""location":{{"file":"{}","line":{},"#,
+            // the backslashes of the Windows paths are escaped.
+            file!().replace('\\', "\\\\"),
+            line
+        )),
+        "{}",
+        json
+    );
+    assert!(json.contains(r#""scope_label":null"#), "{}", json);
+}
+
+// This test spawns the example binary, which is not supported by Miri.
+#[cfg(not(miri))]
+#[test]
+fn round_trip_through_child_process() {
+    use std::{
+        path::Path,
+        process::{Command, Stdio},
+    };
+
+    let mut child = Command::new(support::example_path("ipc_child"))
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let snapshot = UnwindSnapshot::read_from(child.stdout.take().unwrap()).unwrap();
+    assert!(child.wait().unwrap().success());
+
+    assert_eq!(snapshot.message(), "panicked in the child");
+    assert_eq!(snapshot.scope_label(), Some("ipc::child"));
+    assert_eq!(
+        snapshot.context_frames(),
+        ["while running in the child process"]
+    );
+    let loc = snapshot.location().unwrap();
+    assert_eq!(
+        Path::new(loc.file()),
+        Path::new("examples").join("ipc_child.rs")
+    );
+    assert_eq!(loc.line(), 9);
+}
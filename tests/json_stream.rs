@@ -0,0 +1,138 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_named, HookOptions};
+use std::{
+    io::{self, Write},
+    panic,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+#[derive(Default)]
+struct Buffer {
+    data: Vec<u8>,
+    flushed: usize,
+}
+
+/// An in-memory sink that records the data at every flush.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Buffer>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut buffer = self.0.lock().unwrap();
+        buffer.flushed = buffer.data.len();
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    /// Return the lines flushed so far.
+    fn lines(&self) -> Vec<String> {
+        let buffer = self.0.lock().unwrap();
+        let flushed = String::from_utf8(buffer.data[..buffer.flushed].to_vec()).unwrap();
+        assert!(flushed.is_empty() || flushed.ends_with('\n'));
+        flushed.lines().map(ToOwned::to_owned).collect()
+    }
+}
+
+/// Extract the raw JSON value of the field from a line.
+fn field<'a>(line: &'a str, name: &str) -> &'a str {
+    let key = format!("\"{}\":", name);
+    let start = line
+        .find(&key)
+        .unwrap_or_else(|| panic!("{}: {}", name, line))
+        + key.len();
+    let rest = &line[start..];
+    let end = if let Some(s) = rest.strip_prefix('"') {
+        s.find('"').unwrap() + 2
+    } else if rest.starts_with('{') {
+        rest.find('}').unwrap() + 1
+    } else {
+        rest.find([',', '}']).unwrap()
+    };
+    &rest[..end]
+}
+
+// The hook is process-global, so the scenarios are run in a single test.
+#[test]
+fn stream_captured_panics() {
+    let buffer = SharedBuffer::default();
+    maybe_unwind::set_hook_with(HookOptions::new().stream_json_to(Box::new(buffer.clone())));
+
+    let line = line!() + 1;
+    let _ = maybe_unwind_named("json::first", || panic!("first \"quoted\""));
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 1, "the panic is streamed immediately");
+
+    let first = &lines[0];
+    assert!(first.starts_with('{') && first.ends_with('}'), "{}", first);
+    assert!(field(first, "timestamp").contains("\"secs\":"), "{}", first);
+    assert_eq!(field(first, "thread"), "\"stream_captured_panics\"");
+    assert_eq!(field(first, "scope_label"), "\"json::first\"");
+    assert!(
+        first.contains(r#""message":"first \"quoted\"""#),
+        "{}",
+        first
+    );
+    assert_eq!(field(first, "file"), format!("\"{}\"", file!()));
+    assert_eq!(field(first, "line"), line.to_string());
+    assert!(field(first, "column").parse::<u32>().is_ok(), "{}", first);
+    let fingerprint = field(first, "fingerprint");
+    assert_eq!(fingerprint.len(), 18, "{}", fingerprint);
+    assert_eq!(field(first, "kind"), "\"explicit\"");
+
+    // The same location has the same fingerprint.
+    for _ in 0..2 {
+        let _ = maybe_unwind(support_panic);
+    }
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 3);
+    let sequences: Vec<u64> = lines
+        .iter()
+        .map(|line| field(line, "sequence").parse().unwrap())
+        .collect();
+    assert!(sequences.windows(2).all(|w| w[0] < w[1]), "{:?}", sequences);
+    assert_eq!(field(&lines[1], "scope_label"), "null");
+    assert_eq!(
+        field(&lines[1], "fingerprint"),
+        field(&lines[2], "fingerprint")
+    );
+    assert_ne!(field(&lines[1], "fingerprint"), fingerprint);
+
+    // The panics outside of the capture scopes and the self-check are not streamed.
+    let _ = panic::catch_unwind(|| panic!("not captured"));
+    maybe_unwind::self_check().unwrap();
+    assert_eq!(buffer.lines().len(), 3);
+
+    // The lines written by multiple threads are not interleaved.
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            thread::spawn(move || {
+                for j in 0..8 {
+                    let _ = maybe_unwind(|| panic!("thread {} panic {}", i, j));
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 3 + 32);
+    for line in &lines[3..] {
+        assert!(line.starts_with("{\"timestamp\":"), "{}", line);
+        assert!(line.ends_with("\"}"), "{}", line);
+        assert_eq!(field(line, "thread"), "null");
+        assert!(field(line, "message").starts_with("\"thread "), "{}", line);
+    }
+}
+
+fn support_panic() {
+    panic!("same location");
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "libtest-mimic")]
+
+mod support;
+
+use libtest_mimic::{Arguments, Trial};
+use maybe_unwind::{expect_unwind, maybe_unwind, trial};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+use support::ensure_set_hook;
+
+fn logfile(name: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "maybe-unwind-libtest-mimic-{}-{}.log",
+        std::process::id(),
+        name
+    ))
+}
+
+fn arguments(logfile: &Path) -> Arguments {
+    Arguments {
+        test_threads: Some(1),
+        logfile: Some(logfile.to_str().unwrap().to_owned()),
+        ..Arguments::default()
+    }
+}
+
+fn trials() -> Vec<Trial> {
+    vec![
+        trial("passes", || Ok(())),
+        trial("panics", || panic!("oops")),
+        trial("returns_error", || Err("failed".into())),
+        trial("should_panic", || {
+            expect_unwind("oops", || panic!("oops"))
+                .map(drop)
+                .map_err(Into::into)
+        }),
+        trial("should_panic_but_not", || {
+            expect_unwind("oops", || ()).map(drop).map_err(Into::into)
+        }),
+    ]
+}
+
+#[test]
+fn run_trials() {
+    let logfile = logfile("run_trials");
+    let conclusion = libtest_mimic::run(&arguments(&logfile), trials());
+    let log = fs::read_to_string(&logfile).unwrap();
+    let _ = fs::remove_file(&logfile);
+
+    assert_eq!(conclusion.num_passed, 2);
+    assert_eq!(conclusion.num_failed, 3);
+
+    let panics = log.split("---- panics ----").nth(1).unwrap();
+    assert!(
+        panics.contains(&format!("panicked at {}:", file!())),
+        "{}",
+        log
+    );
+    assert!(panics.contains(": oops"), "{}", log);
+    assert!(panics.contains("captured at "), "{}", log);
+
+    let should_panic = log.split("---- should_panic_but_not ----").nth(1).unwrap();
+    assert!(
+        should_panic.contains("the closure did not panic"),
+        "{}",
+        log
+    );
+}
+
+#[test]
+fn filtered_trial() {
+    let logfile = logfile("filtered_trial");
+    let args = Arguments {
+        filter: Some("should_panic".into()),
+        exact: true,
+        ..arguments(&logfile)
+    };
+    let conclusion = libtest_mimic::run(&args, trials());
+    let _ = fs::remove_file(&logfile);
+    assert_eq!(conclusion.num_passed, 1);
+    assert_eq!(conclusion.num_failed, 0);
+    assert_eq!(conclusion.num_filtered_out, 4);
+}
+
+#[test]
+fn failure_message_contains_report() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    let failed = unwind.into_failed();
+    let msg = failed.message().unwrap();
+    assert!(msg.starts_with("panicked at "), "{}", msg);
+    assert!(msg.contains(file!()), "{}", msg);
+    assert!(msg.contains(": oops"), "{}", msg);
+    assert!(msg.contains("captured at "), "{}", msg);
+    assert!(!msg.ends_with('\n'), "{:?}", msg);
+}
@@ -0,0 +1,114 @@
+#![cfg(all(feature = "capture", feature = "futures-executor"))]
+
+mod support;
+
+use futures_executor::LocalPool;
+use futures_util::task::LocalSpawnExt as _;
+use maybe_unwind::{spawn_local_maybe_unwind, LocalPoolExt as _};
+use std::{cell::Cell, rc::Rc, task::Poll};
+use support::ensure_set_hook;
+
+#[test]
+fn spawned_local_task() {
+    ensure_set_hook();
+    let mut pool = LocalPool::new();
+    let state = Rc::new(Cell::new(0));
+
+    let line = line!() + 1;
+    let handle = spawn_local_maybe_unwind(&pool.spawner(), {
+        let state = state.clone();
+        async move {
+            state.set(1);
+            panic!("local task failed");
+        }
+    })
+    .unwrap();
+    let unwind = pool.run_until(handle).unwrap_err();
+    assert_eq!(unwind.message(), "local task failed");
+    assert_eq!(unwind.location().unwrap().line(), line + 4);
+    assert_eq!(unwind.caller().line(), line);
+    assert_eq!(state.get(), 1);
+
+    let handle = spawn_local_maybe_unwind(&pool.spawner(), async { 42 }).unwrap();
+    assert_eq!(pool.run_until(handle).unwrap(), 42);
+}
+
+#[test]
+fn run_until_collects_other_tasks() {
+    ensure_set_hook();
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+    let done = Rc::new(Cell::new(false));
+
+    let line = line!() + 1;
+    spawner
+        .spawn_local(async { panic!("raw task failed") })
+        .unwrap();
+    spawner
+        .spawn_local({
+            let done = done.clone();
+            async move { done.set(true) }
+        })
+        .unwrap();
+
+    let (output, unwinds) = pool.run_until_maybe_unwind({
+        let done = done.clone();
+        // wait for the other tasks to be run.
+        futures_util::future::poll_fn(move |cx| {
+            if done.get() {
+                Poll::Ready(())
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+    });
+    assert!(output.is_ok());
+    assert_eq!(unwinds.len(), 1);
+    let (label, unwind) = unwinds.iter().next().unwrap();
+    assert_eq!(label, "spawned task");
+    assert_eq!(unwind.message(), "raw task failed");
+    assert_eq!(unwind.location().unwrap().line(), line + 1);
+}
+
+#[test]
+fn run_until_main_future_panics() {
+    ensure_set_hook();
+    let mut pool = LocalPool::new();
+
+    let line = line!() + 1;
+    let (output, unwinds) = pool.run_until_maybe_unwind(async { panic!("main failed") });
+    let unwind = output.unwrap_err();
+    assert_eq!(unwind.message(), "main failed");
+    assert_eq!(unwind.caller().line(), line);
+    assert!(unwinds.is_empty());
+}
+
+#[test]
+fn run_to_completion() {
+    ensure_set_hook();
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+    let count = Rc::new(Cell::new(0));
+    for i in 0..4 {
+        let count = count.clone();
+        spawner
+            .spawn_local(async move {
+                if i % 2 == 0 {
+                    panic!("task {} failed", i);
+                }
+                count.set(count.get() + 1);
+            })
+            .unwrap();
+    }
+
+    let unwinds = pool.run_maybe_unwind();
+    assert_eq!(unwinds.len(), 2);
+    let mut messages: Vec<_> = unwinds
+        .iter()
+        .map(|(_, u)| u.message().into_owned())
+        .collect();
+    messages.sort();
+    assert_eq!(messages, ["task 0 failed", "task 2 failed"]);
+    assert_eq!(count.get(), 2);
+}
@@ -0,0 +1,128 @@
+use maybe_unwind::{Location, LocationStyle};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    panic,
+};
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get().map(|n| n + 1)));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+/// Count the heap allocations performed by `f` on the current thread.
+fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    ALLOCATIONS.with(|count| count.set(Some(0)));
+    let res = f();
+    let count = ALLOCATIONS.with(|count| count.take()).unwrap();
+    (res, count)
+}
+
+fn hash(loc: &Location) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    loc.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn owned_and_borrowed() {
+    let owned = Location::new(String::from("src/lib.rs"), 10, 5);
+    let borrowed = Location::from_static("src/lib.rs", 10, 5);
+    assert_eq!(owned, borrowed);
+    assert_eq!(hash(&owned), hash(&borrowed));
+    assert_eq!(owned.file(), borrowed.file());
+    assert_eq!(owned.to_string(), "src/lib.rs:10:5");
+    assert_eq!(borrowed.to_string(), "src/lib.rs:10:5");
+}
+
+#[test]
+fn display_styles() {
+    let loc = Location::new(r"src\bin\main.rs", 12, 34);
+    assert_eq!(loc.path(), std::path::Path::new(r"src\bin\main.rs"));
+
+    let render = |style, normalize| {
+        loc.display_style(style)
+            .normalize_separators(normalize)
+            .to_string()
+    };
+    assert_eq!(
+        render(LocationStyle::Colon, false),
+        r"src\bin\main.rs:12:34"
+    );
+    assert_eq!(
+        render(LocationStyle::Msvc, false),
+        r"src\bin\main.rs(12,34)"
+    );
+    assert_eq!(
+        render(LocationStyle::FileLineOnly, false),
+        r"src\bin\main.rs:12"
+    );
+    assert_eq!(render(LocationStyle::Colon, true), "src/bin/main.rs:12:34");
+    assert_eq!(render(LocationStyle::Msvc, true), "src/bin/main.rs(12,34)");
+    assert_eq!(
+        render(LocationStyle::FileLineOnly, true),
+        "src/bin/main.rs:12"
+    );
+    assert_eq!(render(LocationStyle::default(), false), loc.to_string());
+}
+
+#[test]
+fn static_location_does_not_allocate() {
+    let (loc, count) = count_allocations(|| {
+        let loc = Location::from(panic::Location::caller());
+        let _ = loc.clone();
+        loc
+    });
+    assert_eq!(count, 0);
+    assert_eq!(loc.file(), file!());
+}
+
+#[cfg(feature = "capture")]
+#[test]
+fn hook_does_not_allocate() {
+    use maybe_unwind::{maybe_unwind_with, BacktraceMode, CaptureOptions};
+    use std::{sync::mpsc, thread};
+
+    let (tx, rx) = mpsc::channel();
+    let tx = std::sync::Mutex::new(tx);
+    panic::set_hook(Box::new(move |info| {
+        let (captured, count) = count_allocations(|| maybe_unwind::capture_panic_info(info));
+        if captured {
+            tx.lock().unwrap().send(count).unwrap();
+        }
+    }));
+
+    // The test threads are named, so spawn an unnamed thread.
+    thread::spawn(|| {
+        let options = CaptureOptions::new().backtrace(BacktraceMode::Never);
+        for _ in 0..2 {
+            let unwind = maybe_unwind_with(options.clone(), || panic!("msg")).unwrap_err();
+            assert_eq!(unwind.location().unwrap().file(), file!());
+        }
+    })
+    .join()
+    .unwrap();
+    let _ = panic::take_hook();
+
+    // The first capture interns the file name.
+    let counts: Vec<usize> = rx.try_iter().collect();
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts[1], 0);
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_named};
+use std::{panic, thread};
+use support::ensure_set_hook;
+
+const KEYS: [&str; 9] = [
+    "backtrace",
+    "column",
+    "file",
+    "fingerprint",
+    "kind",
+    "line",
+    "message",
+    "scope",
+    "thread",
+];
+
+#[test]
+fn captured_fields() {
+    ensure_set_hook();
+    let unwind = thread::Builder::new()
+        .name("map-worker".into())
+        .spawn(|| {
+            maybe_unwind_named("map::case", || panic!("oops: {}", 42))
+                .unwrap_err()
+                .to_snapshot()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let map = unwind.to_map();
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), KEYS);
+    assert_eq!(map["message"], "oops: 42");
+    assert_eq!(map["file"], file!());
+    assert_eq!(map["thread"], "map-worker");
+    assert_eq!(map["scope"], "map::case");
+    assert_eq!(map["fingerprint"].len(), 16);
+    assert!(map["fingerprint"].chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(map["kind"], "explicit");
+
+    // The location is stringified in the same way as its `Display`.
+    assert_eq!(
+        format!("{}:{}:{}", map["file"], map["line"], map["column"]),
+        unwind.location().unwrap().to_string()
+    );
+}
+
+#[test]
+fn same_map_for_every_form() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| panic!("same")).unwrap_err();
+    let map = unwind.to_map();
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), KEYS);
+    assert_eq!(unwind.captured().unwrap().to_map(), map);
+    assert_eq!(unwind.to_snapshot().to_map(), map);
+}
+
+#[test]
+fn without_captured_information() {
+    ensure_set_hook();
+    maybe_unwind::disable_for_current_thread();
+    let unwind = maybe_unwind(|| panic!("not captured"));
+    maybe_unwind::enable_for_current_thread();
+    let map = unwind.unwrap_err().to_map();
+
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), KEYS);
+    assert_eq!(map["message"], "not captured");
+    for key in ["file", "line", "column", "thread", "scope", "backtrace"] {
+        assert_eq!(map[key], "", "{}", key);
+    }
+    assert_eq!(map["fingerprint"].len(), 16);
+}
+
+#[test]
+fn same_fingerprint_without_location() {
+    ensure_set_hook();
+    maybe_unwind::disable_for_current_thread();
+    let unwind = maybe_unwind(|| panic::panic_any(42)).unwrap_err();
+    maybe_unwind::enable_for_current_thread();
+
+    // The fingerprint falls back to the message, which is shared by the forms.
+    let fingerprint = unwind.to_map()["fingerprint"].clone();
+    assert_eq!(unwind.to_snapshot().to_map()["fingerprint"], fingerprint);
+}
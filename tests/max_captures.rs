@@ -0,0 +1,92 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{
+    maybe_unwind_with, panic_stats, BacktraceMode, BacktraceStatus, CaptureOptions,
+};
+use std::panic;
+use support::ensure_set_hook;
+
+/// Panic `n` times, each caught inside of the scope like a misbehaving dependency.
+fn panic_storm(n: usize) {
+    for i in 0..n {
+        let _ = panic::catch_unwind(|| panic!("internal {}", i));
+    }
+}
+
+#[test]
+fn suppress_after_limit() {
+    ensure_set_hook();
+
+    let options = CaptureOptions::new()
+        .backtrace(BacktraceMode::Always)
+        .max_captures_per_scope(1);
+    let before = panic_stats();
+    let line = line!() + 3;
+    let unwind = maybe_unwind_with(options, || {
+        for _ in 0..10 {
+            let _ = panic::catch_unwind(|| panic!("first"));
+            panic_storm(99);
+        }
+        panic!("escaped");
+    })
+    .unwrap_err();
+
+    // The first capture in the scope keeps the full information.
+    assert_eq!(unwind.suppressed_capture_count(), 10 * 100);
+    // The other tests may panic concurrently.
+    let after = panic_stats();
+    assert!(after.suppressed - before.suppressed >= 10 * 100);
+    assert!(after.uncaptured() - before.uncaptured() < 10 * 100);
+    let location = unwind.location().unwrap();
+    assert_eq!((location.file(), location.line()), (file!(), line));
+    assert_eq!(unwind.captured().unwrap().message(), "first");
+    if cfg!(backtrace) {
+        assert_eq!(unwind.backtrace_status(), BacktraceStatus::Captured);
+    }
+    assert!(
+        format!("{:#}", unwind).contains("note: 1000 more panics in the scope were not captured\n")
+    );
+}
+
+#[test]
+fn limit_per_scope() {
+    ensure_set_hook();
+
+    let options = CaptureOptions::new().max_captures_per_scope(3);
+    let unwind = maybe_unwind_with(options.clone(), || {
+        panic_storm(2);
+
+        // The nested scope has its own counter.
+        let inner = maybe_unwind_with(options.clone(), || {
+            panic_storm(5);
+            panic!("inner");
+        })
+        .unwrap_err();
+        assert_eq!(inner.suppressed_capture_count(), 3);
+        assert_eq!(inner.message(), "inner");
+
+        panic_storm(1);
+        panic!("outer");
+    })
+    .unwrap_err();
+
+    // 2 + 1 captured, and the last one is not.
+    assert_eq!(unwind.suppressed_capture_count(), 1);
+    assert_eq!(unwind.captured().unwrap().message(), "internal 0");
+    assert!(format!("{:#}", unwind).contains("note: 1 more panic in the scope was not captured\n"));
+}
+
+#[test]
+fn unlimited_by_default() {
+    ensure_set_hook();
+
+    let unwind = maybe_unwind_with(CaptureOptions::new(), || {
+        panic_storm(10);
+        panic!("escaped");
+    })
+    .unwrap_err();
+    assert_eq!(unwind.suppressed_capture_count(), 0);
+    assert_eq!(unwind.captured().unwrap().message(), "escaped");
+}
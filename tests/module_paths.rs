@@ -0,0 +1,80 @@
+//! Compile-time assertions that the items grouped into the public modules
+//! are the same as the ones re-exported from the crate root.
+//!
+//! Moving an item between the modules must keep both paths compiling.
+
+#![allow(dead_code)]
+
+use std::marker::PhantomData;
+
+fn assert_same_type<T: ?Sized>(_: PhantomData<T>, _: PhantomData<T>) {}
+
+macro_rules! assert_same_types {
+    ($($root:ty => $module:ty,)*) => {
+        const _: fn() = || {
+            $( assert_same_type(PhantomData::<$root>, PhantomData::<$module>); )*
+        };
+    };
+}
+
+assert_same_types! {
+    maybe_unwind::HookOptions => maybe_unwind::hook::HookOptions,
+    maybe_unwind::CaptureStatus => maybe_unwind::hook::CaptureStatus,
+    maybe_unwind::CaptureError => maybe_unwind::hook::CaptureError,
+    maybe_unwind::HookOutcome => maybe_unwind::hook::HookOutcome,
+    maybe_unwind::PanicHookFn => maybe_unwind::hook::PanicHookFn,
+    maybe_unwind::Unwind => maybe_unwind::unwind::Unwind,
+    maybe_unwind::UnwindMeta => maybe_unwind::unwind::UnwindMeta,
+    maybe_unwind::Location => maybe_unwind::unwind::Location,
+    maybe_unwind::CapturedInfo => maybe_unwind::unwind::CapturedInfo,
+    maybe_unwind::CaptureOptions => maybe_unwind::unwind::CaptureOptions,
+    maybe_unwind::UnwindSnapshot => maybe_unwind::unwind::UnwindSnapshot,
+    maybe_unwind::UnwindSet => maybe_unwind::unwind::UnwindSet,
+    maybe_unwind::DropGuard => maybe_unwind::thread::DropGuard,
+    maybe_unwind::ShieldOptions => maybe_unwind::thread::ShieldOptions,
+    maybe_unwind::DisplayOptions => maybe_unwind::report::DisplayOptions,
+    maybe_unwind::NormalizeOptions => maybe_unwind::report::NormalizeOptions,
+    maybe_unwind::UnwindReport => maybe_unwind::report::UnwindReport,
+    maybe_unwind::Blame => maybe_unwind::report::Blame,
+}
+
+#[cfg(feature = "futures")]
+assert_same_types! {
+    maybe_unwind::MaybeUnwind<()> => maybe_unwind::futures::MaybeUnwind<()>,
+    maybe_unwind::UnwindOrTimeout => maybe_unwind::futures::UnwindOrTimeout,
+}
+
+#[cfg(feature = "stream")]
+assert_same_types! {
+    maybe_unwind::MaybeUnwindStream<()> => maybe_unwind::futures::MaybeUnwindStream<()>,
+}
+
+#[test]
+fn functions() {
+    let _: [fn(); 2] = [maybe_unwind::set_hook, maybe_unwind::hook::set_hook];
+    let _: [fn() -> bool; 2] = [maybe_unwind::is_enabled, maybe_unwind::thread::is_enabled];
+    let _: [fn() -> usize; 2] = [maybe_unwind::scope_depth, maybe_unwind::thread::scope_depth];
+    let _: [fn() -> maybe_unwind::HookOutcome; 2] = [
+        maybe_unwind::last_hook_outcome,
+        maybe_unwind::hook::last_hook_outcome,
+    ];
+}
+
+#[test]
+fn prelude() {
+    use maybe_unwind::prelude::*;
+
+    set_hook();
+
+    let unwind: Unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    assert_eq!(unwind.message(), "oops");
+
+    let (ok, failed) = (1..=2)
+        .map_maybe_unwind(|n| {
+            assert!(n != 2, "two");
+            n
+        })
+        .partition_unwinds();
+    assert_eq!(ok, [1]);
+    assert_eq!(failed.len(), 1);
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, with_context, Location, NormalizeOptions};
+use support::ensure_set_hook;
+
+#[test]
+fn normalized_report() {
+    ensure_set_hook();
+    let render = || {
+        let unwind = maybe_unwind(|| with_context("while testing", || panic!("oops"))).unwrap_err();
+        unwind.normalized_with(&NormalizeOptions::new().trim_backtrace(true))
+    };
+
+    let first = render();
+    let second = render();
+    assert_eq!(first, second);
+    assert!(first.starts_with(concat!(
+        "panicked at tests/normalize.rs:LL:CC: oops\n",
+        "captured at tests/normalize.rs:LL:CC\n",
+        "context:\n",
+        "    while testing\n",
+    )));
+}
+
+#[test]
+fn keep_line_column() {
+    ensure_set_hook();
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    let report = unwind.normalized_with(&NormalizeOptions::new().redact_line_column(false));
+    assert!(report.starts_with(&format!("panicked at tests/normalize.rs:{}:", line)));
+}
+
+#[test]
+fn path_prefix() {
+    let alice = Location::new("/home/alice/workspace/src/lib.rs", 10, 5);
+    let bob = Location::new("C:\\Users\\bob\\workspace\\src\\lib.rs", 12, 9);
+
+    let normalized_alice =
+        alice.normalized_with(&NormalizeOptions::new().path_prefix("/home/alice/workspace"));
+    let normalized_bob =
+        bob.normalized_with(&NormalizeOptions::new().path_prefix("C:\\Users\\bob\\workspace"));
+    assert_eq!(normalized_alice, "src/lib.rs:LL:CC");
+    assert_eq!(normalized_alice, normalized_bob);
+
+    let options = NormalizeOptions::new()
+        .clear_path_prefixes()
+        .redact_line_column(false);
+    assert_eq!(
+        alice.normalized_with(&options),
+        "/home/alice/workspace/src/lib.rs:10:5"
+    );
+}
+
+#[cfg(backtrace)]
+#[test]
+fn normalized_backtrace() {
+    use maybe_unwind::{maybe_unwind_with, BacktraceMode, CaptureOptions};
+
+    ensure_set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    let unwind = maybe_unwind_with(options, || panic!("oops")).unwrap_err();
+    let report = unwind.normalized();
+    assert!(report.contains("stack backtrace:\n"));
+    assert!(!report.contains("__rust_begin_short_backtrace"));
+    assert!(!report.contains("0x"));
+    assert!(!report.contains(&format!(":{}:", line!() - 5)));
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{add_observer, maybe_unwind, remove_observer};
+use std::sync::{Arc, Mutex};
+use support::ensure_set_hook;
+
+#[test]
+fn observe_captured_info() {
+    ensure_set_hook();
+    let observed = Arc::new(Mutex::new(vec![]));
+    let id = add_observer({
+        let observed = observed.clone();
+        move |info| {
+            if info.message().starts_with("observe_captured_info") {
+                let line = info.location().map(|loc| loc.line());
+                observed
+                    .lock()
+                    .unwrap()
+                    .push((info.message().into_owned(), line));
+            }
+        }
+    });
+
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| panic!("observe_captured_info")).unwrap_err();
+    assert!(unwind.capture_notes().is_empty());
+    assert!(remove_observer(id));
+    assert!(!remove_observer(id));
+
+    let _ = maybe_unwind(|| panic!("observe_captured_info"));
+    assert_eq!(
+        *observed.lock().unwrap(),
+        [("observe_captured_info".to_owned(), Some(line))]
+    );
+}
+
+#[test]
+fn panicking_observer() {
+    ensure_set_hook();
+    let id = add_observer(|info| {
+        if info.message() == "panicking_observer" {
+            panic!("observer failure");
+        }
+    });
+
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| panic!("panicking_observer")).unwrap_err();
+    assert!(remove_observer(id));
+
+    assert_eq!(unwind.payload_str(), "panicking_observer");
+    assert_eq!(unwind.location().unwrap().line(), line);
+
+    let notes = unwind.capture_notes();
+    assert_eq!(notes.len(), 1);
+    assert!(
+        notes[0].starts_with("an observer panicked at "),
+        "{}",
+        notes[0]
+    );
+    assert!(notes[0].contains(file!()), "{}", notes[0]);
+    assert!(notes[0].ends_with(": observer failure"), "{}", notes[0]);
+    assert!(format!("{:#}", unwind).contains("\nnote: an observer panicked at "));
+}
+
+#[test]
+fn reentrant_observer() {
+    ensure_set_hook();
+    let count = Arc::new(Mutex::new(0));
+    let id = add_observer({
+        let count = count.clone();
+        move |info| {
+            if info.message().starts_with("reentrant_observer") {
+                *count.lock().unwrap() += 1;
+                let nested = maybe_unwind(|| panic!("reentrant_observer (nested)")).unwrap_err();
+                assert!(nested.location().is_some());
+            }
+        }
+    });
+
+    let unwind = maybe_unwind(|| panic!("reentrant_observer")).unwrap_err();
+    assert!(remove_observer(id));
+
+    assert!(unwind.capture_notes().is_empty());
+    assert_eq!(*count.lock().unwrap(), 1);
+}
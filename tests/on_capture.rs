@@ -0,0 +1,93 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_with, CaptureOptions, HookOptions, UnwindSnapshot};
+use std::{
+    cell::{Cell, RefCell},
+    panic,
+    sync::Once,
+};
+
+thread_local! {
+    /// The test case running on the current thread, like a task-local of a test harness.
+    static TEST_CASE: RefCell<Option<String>> = const { RefCell::new(None) };
+    static CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn set_hook() {
+    static SET_HOOK: Once = Once::new();
+    SET_HOOK.call_once(|| {
+        maybe_unwind::set_hook_with(HookOptions::new().on_capture_mut(|info| {
+            CALLS.with(|calls| calls.set(calls.get() + 1));
+            TEST_CASE.with(|case| {
+                if let Some(case) = &*case.borrow() {
+                    info.annotate("test_case", case.clone());
+                    info.annotate("message_len", info.message().len().to_string());
+                }
+            });
+        }));
+    });
+}
+
+fn run_case<R>(name: &str, f: impl FnOnce() -> R) -> R {
+    TEST_CASE.with(|case| *case.borrow_mut() = Some(name.to_owned()));
+    let res = f();
+    TEST_CASE.with(|case| *case.borrow_mut() = None);
+    res
+}
+
+#[test]
+fn annotate_at_capture() {
+    set_hook();
+    let unwind = run_case("suite::case", || {
+        maybe_unwind(|| panic!("oops")).unwrap_err()
+    });
+
+    let annotations = [
+        ("test_case".to_owned(), "suite::case".to_owned()),
+        ("message_len".to_owned(), "4".to_owned()),
+    ];
+    assert_eq!(unwind.annotations(), annotations);
+    assert_eq!(unwind.captured().unwrap().annotations(), annotations);
+
+    let report = format!("{:#}", unwind);
+    assert!(
+        report.contains("annotations:\n    test_case = suite::case\n    message_len = 4\n"),
+        "{}",
+        report
+    );
+
+    // The annotations survive the round trip of the snapshot.
+    let snapshot = unwind.to_snapshot();
+    assert_eq!(snapshot.annotations(), annotations);
+    let mut buf = vec![];
+    snapshot.write_to(&mut buf).unwrap();
+    assert_eq!(UnwindSnapshot::read_from(&buf[..]).unwrap(), snapshot);
+    assert!(snapshot.to_json_line().contains(
+        "\"annotations\":[{\"key\":\"test_case\",\"value\":\"suite::case\"},\
+         {\"key\":\"message_len\",\"value\":\"4\"}]"
+    ));
+}
+
+#[test]
+fn called_only_for_captured_panics() {
+    set_hook();
+
+    // Outside of the test case, nothing is attached.
+    let unwind = maybe_unwind(|| panic!("no case")).unwrap_err();
+    assert_eq!(CALLS.with(Cell::get), 1);
+    assert!(unwind.annotations().is_empty());
+    assert!(!format!("{:#}", unwind).contains("annotations:"));
+
+    // The panics only counted by the scope are not passed to the callback.
+    let options = CaptureOptions::new().max_captures_per_scope(1);
+    let unwind = run_case("suite::limited", || {
+        maybe_unwind_with(options, || {
+            let _ = panic::catch_unwind(|| panic!("first"));
+            panic!("second");
+        })
+        .unwrap_err()
+    });
+    assert_eq!(CALLS.with(Cell::get), 2);
+    assert_eq!(unwind.suppressed_capture_count(), 1);
+    assert_eq!(unwind.annotations()[0].1, "suite::limited");
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "capture")]
+
+#[path = "vendor/plugin/mod.rs"]
+mod plugin;
+mod support;
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_with, BacktraceMode, CaptureOptions};
+use support::ensure_set_hook;
+
+#[test]
+fn panic_in_vendored_module() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| plugin::run(0)).unwrap_err();
+    assert_eq!(unwind.message(), "plugin rejected the input");
+
+    assert!(unwind.originates_from("tests/vendor"));
+    assert!(unwind.originates_from("./tests/vendor/plugin"));
+    assert!(unwind.originates_from("tests/vendor/plugin/mod.rs"));
+    assert!(!unwind.originates_from("tests/vendor/plug"));
+    assert!(!unwind.originates_from("tests/origin.rs"));
+
+    assert!(unwind.originates_from_crate("vendor/plugin/"));
+    assert!(!unwind.originates_from_crate("vendor/other/"));
+}
+
+#[test]
+fn panic_in_test_file() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| {
+        // The plugin itself is fine.
+        assert_eq!(plugin::run(1), 2);
+        panic!("shim failed");
+    })
+    .unwrap_err();
+
+    assert!(unwind.originates_from("tests/origin.rs"));
+    assert!(unwind.originates_from("tests"));
+    assert!(!unwind.originates_from("tests/vendor"));
+    assert!(!unwind.originates_from_crate("vendor/plugin/"));
+}
+
+#[test]
+fn location_takes_precedence_over_backtrace() {
+    ensure_set_hook();
+    // The location of a `#[track_caller]` function is the caller in this file,
+    // even though the innermost user frame of the backtrace is in the plugin.
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    let unwind = maybe_unwind_with(options, || plugin::check(0)).unwrap_err();
+    assert_eq!(unwind.message(), "plugin check failed");
+    assert!(unwind.originates_from("tests/origin.rs"));
+    assert!(!unwind.originates_from_crate("vendor/plugin/"));
+}
+
+#[test]
+fn without_captured_information() {
+    ensure_set_hook();
+    maybe_unwind::disable_for_current_thread();
+    let unwind = maybe_unwind(|| plugin::run(0));
+    maybe_unwind::enable_for_current_thread();
+    let unwind = unwind.unwrap_err();
+
+    assert!(unwind.location().is_none());
+    assert!(!unwind.originates_from("tests"));
+    assert!(!unwind.originates_from_crate("vendor/plugin/"));
+}
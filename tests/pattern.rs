@@ -0,0 +1,173 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, with_context, PanicKind, PayloadPattern, Unwind, UnwindPattern};
+use support::ensure_set_hook;
+
+fn sample() -> (Unwind, u32) {
+    ensure_set_hook();
+    let line = line!() + 2;
+    let unwind =
+        maybe_unwind(|| with_context("loading config", || panic!("missing key: {}", "port")))
+            .unwrap_err();
+    (unwind, line)
+}
+
+#[test]
+fn empty_pattern_matches_any() {
+    let (unwind, _) = sample();
+    assert!(unwind.matches(&UnwindPattern::new()));
+    assert_eq!(unwind.explain_mismatch(&UnwindPattern::new()), "");
+}
+
+#[test]
+fn message() {
+    let (unwind, _) = sample();
+    assert!(unwind.matches(&UnwindPattern::new().message("key")));
+    assert!(
+        unwind.matches(&UnwindPattern::new().message(PayloadPattern::exact("missing key: port")))
+    );
+
+    let pattern = UnwindPattern::new().message(PayloadPattern::exact("missing key"));
+    assert!(!unwind.matches(&pattern));
+    assert_eq!(
+        unwind.explain_mismatch(&pattern),
+        "message: expected equal to \"missing key\", found \"missing key: port\""
+    );
+}
+
+#[test]
+fn file_suffix() {
+    let (unwind, _) = sample();
+    for suffix in ["pattern.rs", "tests/pattern.rs", file!()] {
+        assert!(
+            unwind.matches(&UnwindPattern::new().file_suffix(suffix)),
+            "{}",
+            suffix
+        );
+    }
+    // only the whole components are compared.
+    let pattern = UnwindPattern::new().file_suffix("tern.rs");
+    assert!(!unwind.matches(&pattern));
+    assert_eq!(
+        unwind.explain_mismatch(&pattern),
+        format!(
+            "file: expected a path ending with \"tern.rs\", found {:?}",
+            file!()
+        )
+    );
+}
+
+#[test]
+fn line() {
+    let (unwind, line) = sample();
+    assert!(unwind.matches(&UnwindPattern::new().line(line)));
+    assert!(unwind.matches(&UnwindPattern::new().line_range(line - 1..=line + 1)));
+
+    let pattern = UnwindPattern::new().line(line + 1);
+    assert_eq!(
+        unwind.explain_mismatch(&pattern),
+        format!("line: expected {}, found {}", line + 1, line)
+    );
+    let pattern = UnwindPattern::new().line_range(1..=2);
+    assert_eq!(
+        unwind.explain_mismatch(&pattern),
+        format!("line: expected a line in 1..=2, found {}", line)
+    );
+}
+
+#[test]
+fn kind() {
+    let (unwind, _) = sample();
+    assert!(unwind.matches(&UnwindPattern::new().kind(PanicKind::Explicit)));
+
+    let pattern = UnwindPattern::new().kind(PanicKind::Unwrap);
+    assert_eq!(
+        unwind.explain_mismatch(&pattern),
+        "kind: expected unwrap, found explicit"
+    );
+}
+
+#[test]
+fn context_frames() {
+    let (unwind, _) = sample();
+    assert!(unwind.matches(&UnwindPattern::new().context_frame("config")));
+
+    let pattern = UnwindPattern::new()
+        .context_frame("loading")
+        .context_frame("saving");
+    assert_eq!(
+        unwind.explain_mismatch(&pattern),
+        "context frame: expected a frame containing \"saving\", found [\"loading config\"]"
+    );
+}
+
+#[test]
+fn multiple_mismatches() {
+    let (unwind, line) = sample();
+    let pattern = UnwindPattern::new()
+        .message("timeout")
+        .file_suffix("pattern.rs")
+        .line(line)
+        .kind(PanicKind::Assertion);
+    assert!(!unwind.matches(&pattern));
+    let explanation = unwind.explain_mismatch(&pattern);
+    let lines: Vec<_> = explanation.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "message: expected containing \"timeout\", found \"missing key: port\"",
+            "kind: expected assertion, found explicit",
+        ]
+    );
+}
+
+#[test]
+fn parse() {
+    let (unwind, line) = sample();
+    let matching = [
+        String::new(),
+        format!("pattern.rs:{}", line),
+        format!("tests/pattern.rs:{}-{} #explicit", line - 1, line),
+        "pattern.rs ~key: port".to_owned(),
+        "#explicit @config =missing key: port".to_owned(),
+        "  @loading   ~missing key  ".to_owned(),
+    ];
+    for s in &matching {
+        let pattern: UnwindPattern = s.parse().unwrap();
+        assert!(
+            unwind.matches(&pattern),
+            "{:?}: {}",
+            s,
+            unwind.explain_mismatch(&pattern)
+        );
+    }
+
+    let pattern: UnwindPattern = format!("lib.rs:{} #unwrap ~host", line + 1)
+        .parse()
+        .unwrap();
+    assert_eq!(unwind.explain_mismatch(&pattern).lines().count(), 4);
+}
+
+#[test]
+fn parse_errors() {
+    let errors = [
+        ("pattern.rs:x", "invalid unwind pattern: invalid line \"x\""),
+        ("pattern.rs:3-", "invalid unwind pattern: invalid line \"\""),
+        (
+            "pattern.rs:5-3",
+            "invalid unwind pattern: empty line range \"5-3\"",
+        ),
+        (":12", "invalid unwind pattern: missing file in \":12\""),
+        ("#nope", "invalid unwind pattern: unknown kind \"nope\""),
+        (
+            "#unwrap pattern.rs",
+            "invalid unwind pattern: unexpected token \"pattern.rs\"",
+        ),
+    ];
+    for (s, expected) in errors {
+        let err = s.parse::<UnwindPattern>().unwrap_err();
+        assert_eq!(err.to_string(), expected, "{:?}", s);
+    }
+}
@@ -0,0 +1,290 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{
+    clear_payload_describer, clear_payload_formatter, maybe_unwind, maybe_unwind_with,
+    register_payload_error, register_payload_formatter, set_payload_describer, CaptureOptions,
+};
+use std::{error::Error, fmt, io, panic::panic_any};
+use support::ensure_set_hook;
+
+struct AssertionFailed {
+    expected: i32,
+    actual: i32,
+}
+
+#[test]
+fn custom_payload() {
+    ensure_set_hook();
+    register_payload_formatter(|payload: &AssertionFailed| {
+        format!("expected {}, got {}", payload.expected, payload.actual)
+    });
+
+    let line = line!() + 2;
+    let unwind = maybe_unwind(|| {
+        panic_any(AssertionFailed {
+            expected: 1,
+            actual: 2,
+        })
+    })
+    .unwrap_err();
+    assert_eq!(unwind.payload_str(), "Box<dyn Any>");
+    assert_eq!(unwind.message(), "expected 1, got 2");
+    assert_eq!(unwind.to_string(), "expected 1, got 2");
+    assert!(format!("{:#}", unwind).starts_with(&format!(
+        "panicked at {}:{}:9: expected 1, got 2\n",
+        file!(),
+        line
+    )));
+    assert_eq!(
+        unwind.captured().unwrap().message(),
+        "expected 1, got 2",
+        "the message captured by the hook is rendered by the formatter as well"
+    );
+}
+
+#[test]
+fn replace_and_clear() {
+    struct Replaced;
+
+    ensure_set_hook();
+    register_payload_formatter(|_: &Replaced| "first".to_owned());
+    register_payload_formatter(|_: &Replaced| "second".to_owned());
+    let unwind = maybe_unwind(|| panic_any(Replaced)).unwrap_err();
+    assert_eq!(unwind.message(), "second");
+
+    assert!(clear_payload_formatter::<Replaced>());
+    assert!(!clear_payload_formatter::<Replaced>());
+    let unwind = maybe_unwind(|| panic_any(Replaced)).unwrap_err();
+    assert_eq!(unwind.message(), "Box<dyn Any>");
+}
+
+#[test]
+fn builtin_payloads_take_precedence() {
+    ensure_set_hook();
+    register_payload_formatter(|_: &String| "formatted".to_owned());
+    register_payload_formatter(|_: &&'static str| "formatted".to_owned());
+
+    let unwind = maybe_unwind(|| panic!("foo {}", 42)).unwrap_err();
+    assert_eq!(unwind.message(), "foo 42");
+    assert_eq!(unwind.captured().unwrap().message(), "foo 42");
+
+    let unwind = maybe_unwind(|| panic!("bar")).unwrap_err();
+    assert_eq!(unwind.message(), "bar");
+
+    clear_payload_formatter::<String>();
+    clear_payload_formatter::<&'static str>();
+}
+
+/// An error with a source, nested twice.
+#[derive(Debug)]
+struct RequestFailed(Layer);
+
+#[derive(Debug)]
+struct Layer(io::Error);
+
+impl fmt::Display for RequestFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("request failed")
+    }
+}
+
+impl Error for RequestFailed {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("connection lost")
+    }
+}
+
+impl Error for Layer {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+fn request_failed() -> RequestFailed {
+    RequestFailed(Layer(io::Error::new(io::ErrorKind::Other, "broken pipe")))
+}
+
+#[test]
+fn boxed_error_payload() {
+    ensure_set_hook();
+    let line = line!() + 3;
+    let unwind = maybe_unwind(|| {
+        let err: Box<dyn Error + Send + Sync> = Box::new(request_failed());
+        panic_any(err)
+    })
+    .unwrap_err();
+
+    let err = unwind.payload_as_error().unwrap();
+    assert_eq!(err.to_string(), "request failed");
+    assert_eq!(unwind.message(), "request failed");
+    assert_eq!(unwind.captured().unwrap().message(), "request failed");
+
+    let report = format!("{:#}", unwind);
+    assert!(
+        report.starts_with(&format!(
+            "panicked at {}:{}:9: request failed\n\
+             caused by: connection lost\n\
+             caused by: broken pipe\n\
+             captured at ",
+            file!(),
+            line
+        )),
+        "{}",
+        report
+    );
+
+    let snapshot = unwind.to_snapshot();
+    assert_eq!(snapshot.causes(), ["connection lost", "broken pipe"]);
+    assert_eq!(format!("{:#}", snapshot), report);
+}
+
+#[test]
+fn registered_error_payload() {
+    ensure_set_hook();
+    register_payload_error(|payload: &RequestFailed| payload);
+
+    let unwind = maybe_unwind(|| panic_any(request_failed())).unwrap_err();
+    let err = unwind.payload_as_error().unwrap();
+    assert!(err.is::<RequestFailed>());
+    assert_eq!(unwind.message(), "request failed");
+    assert_eq!(
+        unwind.to_snapshot().causes(),
+        ["connection lost", "broken pipe"]
+    );
+
+    // The carriers of the error value can be registered as well.
+    struct Carrier(Layer);
+    register_payload_error(|payload: &Carrier| &payload.0);
+    let unwind = maybe_unwind(|| {
+        panic_any(Carrier(Layer(io::Error::new(
+            io::ErrorKind::Other,
+            "timed out",
+        ))))
+    })
+    .unwrap_err();
+    assert_eq!(unwind.message(), "connection lost");
+    assert!(format!("{:#}", unwind).contains("\ncaused by: timed out\n"));
+
+    // Replaced by a formatter, which does not extract the error.
+    register_payload_formatter(|_: &RequestFailed| "formatted".to_owned());
+    let unwind = maybe_unwind(|| panic_any(request_failed())).unwrap_err();
+    assert!(unwind.payload_as_error().is_none());
+    assert_eq!(unwind.message(), "formatted");
+    assert!(!format!("{:#}", unwind).contains("caused by:"));
+
+    assert!(clear_payload_formatter::<RequestFailed>());
+    assert!(clear_payload_formatter::<Carrier>());
+}
+
+#[test]
+fn non_error_payloads() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| panic!("plain")).unwrap_err();
+    assert!(unwind.payload_as_error().is_none());
+    assert!(!format!("{:#}", unwind).contains("caused by:"));
+}
+
+/// A payload whose type is not visible to the describer, e.g. raised by foreign glue code.
+struct Opaque(u32);
+
+struct Known;
+
+#[test]
+fn payload_describer() {
+    ensure_set_hook();
+    register_payload_formatter(|_: &Known| "known".to_owned());
+    set_payload_describer(|payload| {
+        payload
+            .downcast_ref::<Opaque>()
+            .map(|opaque| format!("opaque({})", opaque.0))
+            .or_else(|| {
+                payload
+                    .downcast_ref::<Known>()
+                    .map(|_| "described".to_owned())
+            })
+    });
+
+    let unwind = maybe_unwind(|| panic_any(Opaque(1))).unwrap_err();
+    assert_eq!(unwind.payload_str(), "opaque(1)");
+    assert_eq!(unwind.message(), "opaque(1)");
+    assert_eq!(unwind.captured().unwrap().message(), "opaque(1)");
+
+    let unwind = maybe_unwind(|| panic_any(Known)).unwrap_err();
+    assert_eq!(
+        unwind.message(),
+        "known",
+        "the registered formatters take precedence"
+    );
+
+    let options = CaptureOptions::new().payload_describer(|payload| {
+        payload
+            .downcast_ref::<Opaque>()
+            .map(|opaque| format!("scoped({})", opaque.0))
+    });
+    let unwind = maybe_unwind_with(options, || {
+        let inner = maybe_unwind(|| panic_any(Opaque(2))).unwrap_err();
+        assert_eq!(inner.message(), "scoped(2)", "nested scopes inherit it");
+        panic_any(Opaque(3))
+    })
+    .unwrap_err();
+    assert_eq!(unwind.message(), "scoped(3)");
+
+    let unwind = maybe_unwind(|| panic_any(Opaque(4))).unwrap_err();
+    assert_eq!(unwind.message(), "opaque(4)");
+
+    clear_payload_describer();
+    clear_payload_formatter::<Known>();
+    let unwind = maybe_unwind(|| panic_any(Opaque(5))).unwrap_err();
+    assert_eq!(unwind.payload_str(), "Box<dyn Any>");
+    assert_eq!(unwind.message(), "Box<dyn Any>");
+}
+
+#[test]
+fn smart_pointer_string_payloads() {
+    use std::{borrow::Cow, sync::Arc};
+
+    ensure_set_hook();
+    let unwinds = vec![
+        maybe_unwind(|| panic_any(Cow::<'static, str>::Borrowed("borrowed"))).unwrap_err(),
+        maybe_unwind(|| panic_any(Cow::<'static, str>::Owned("owned".into()))).unwrap_err(),
+        maybe_unwind(|| panic_any(Box::<str>::from("boxed"))).unwrap_err(),
+        maybe_unwind(|| panic_any(Arc::<str>::from("arc str"))).unwrap_err(),
+        maybe_unwind(|| panic_any(Arc::new(String::from("arc string")))).unwrap_err(),
+    ];
+    let expected = ["borrowed", "owned", "boxed", "arc str", "arc string"];
+    for (unwind, expected) in unwinds.into_iter().zip(&expected) {
+        assert_eq!(unwind.payload_str(), *expected);
+        assert_eq!(unwind.message(), *expected);
+        assert_eq!(unwind.captured().unwrap().message(), *expected);
+        assert_eq!(unwind.into_string(), *expected);
+    }
+
+    let unwind = maybe_unwind(|| panic!("foo {}", 42)).unwrap_err();
+    assert_eq!(unwind.into_string(), "foo 42");
+}
+
+#[test]
+fn falls_back_to_captured_message() {
+    struct Replaced;
+
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| panic!("original"))
+        .unwrap_err()
+        .map_payload(|_| Replaced);
+    assert!(unwind.payload().is::<Replaced>());
+    assert_eq!(unwind.payload_str(), "original");
+    assert_eq!(unwind.message(), "original");
+    assert_eq!(unwind.into_string(), "original");
+
+    let unwind = maybe_unwind(|| panic_any(Replaced)).unwrap_err();
+    assert_eq!(unwind.payload_str(), "Box<dyn Any>");
+    assert_eq!(unwind.into_string(), "Box<dyn Any>");
+}
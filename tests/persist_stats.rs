@@ -0,0 +1,94 @@
+// The tests spawn the example binary, which is not supported by Miri.
+#![cfg(all(feature = "capture", not(miri)))]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, panic_stats, persist_stats_on_panic_with};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use support::{ensure_set_hook, example_path};
+
+fn temp_file(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!(
+        "maybe-unwind-stats-{}-{}.jsonl",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn trail_before_abort() {
+    let path = temp_file("abort");
+    let output = Command::new(example_path("double_panic"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    // The backslashes of the Windows paths are escaped in the JSON.
+    let file = Path::new("examples")
+        .join("double_panic.rs")
+        .display()
+        .to_string()
+        .replace('\\', "\\\\");
+    let stats = fs::read_to_string(&path).unwrap();
+    let lines: Vec<_> = stats.lines().collect();
+    // Newer toolchains report one more panic before the abort.
+    assert!(lines.len() >= 2, "{}", stats);
+
+    assert!(lines[0].starts_with("{\"pid\":"), "{}", lines[0]);
+    assert!(lines[0].contains(
+        ",\"panics\":1,\"captured\":1,\"suppressed\":0,\"thread\":\"main\",\"message\":\"first\","
+    ));
+    assert!(lines[0].contains(&format!(
+        ",\"location\":{{\"file\":\"{}\",\"line\":21,\"column\":13}},",
+        file
+    )));
+
+    // The second panic aborts the process, but the line is already written.
+    assert!(lines[1].contains(",\"panics\":2,"), "{}", lines[1]);
+    assert!(lines[1].contains(",\"message\":\"second\","));
+    assert!(lines[1].contains(&format!(
+        ",\"location\":{{\"file\":\"{}\",\"line\":7,\"column\":9}},",
+        file
+    )));
+    assert!(lines[1].contains(",\"last_captured\":{\"message\":\""));
+    assert!(lines[1].ends_with(",\"context\":[\"while running the example\"]}}"));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rotation() {
+    ensure_set_hook();
+    let path = temp_file("rotation");
+    let mut rotated = path.clone().into_os_string();
+    rotated.push(".1");
+    let _ = fs::remove_file(&rotated);
+
+    // Every line exceeds the limit, so the file is rotated before every append.
+    persist_stats_on_panic_with(&path, 1);
+    let before = panic_stats();
+    for msg in ["one", "two", "three"] {
+        let _ = maybe_unwind(|| panic!("{}", msg));
+    }
+    let after = panic_stats();
+    assert_eq!(after.panics - before.panics, 3);
+    assert_eq!(after.captured - before.captured, 3);
+    assert_eq!(after.uncaptured(), before.uncaptured());
+
+    let stats = fs::read_to_string(&path).unwrap();
+    assert_eq!(stats.lines().count(), 1);
+    assert!(stats.contains(",\"message\":\"three\","));
+    let stats = fs::read_to_string(&rotated).unwrap();
+    assert_eq!(stats.lines().count(), 1);
+    assert!(stats.contains(",\"message\":\"two\","));
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&rotated).unwrap();
+}
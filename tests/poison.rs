@@ -0,0 +1,89 @@
+mod support;
+
+use maybe_unwind::{clear_poison, is_poisoned, maybe_unwind, poison, poison_report};
+use std::sync::Mutex;
+use support::ensure_set_hook;
+
+/// A fixture shared by the simulated tests.
+static ACCOUNTS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+const FIXTURE: &str = "poison::accounts";
+
+/// Run a simulated test mutating the shared fixture, poisoning it on panic.
+fn run_test(f: fn(&mut Vec<u32>)) -> bool {
+    if is_poisoned(FIXTURE) {
+        return false;
+    }
+    let res = maybe_unwind(|| f(&mut ACCOUNTS.lock().unwrap_or_else(|err| err.into_inner())));
+    if let Err(unwind) = res {
+        unwind.poison(FIXTURE);
+        return false;
+    }
+    true
+}
+
+#[test]
+fn shared_fixture() {
+    ensure_set_hook();
+
+    let line = line!() + 3;
+    let first = run_test(|accounts| {
+        accounts.push(100);
+        panic!("transfer failed halfway");
+    });
+    assert!(!first);
+    assert!(is_poisoned(FIXTURE));
+
+    let second = run_test(|accounts| assert_eq!(accounts.as_slice(), [0u32; 0]));
+    assert!(!second, "the second test must be skipped");
+
+    let record = poison_report()
+        .into_iter()
+        .find(|record| record.key() == FIXTURE)
+        .unwrap();
+    assert_eq!(record.message(), Some("transfer failed halfway"));
+    if cfg!(feature = "capture") {
+        let location = record.location().unwrap();
+        assert_eq!((location.file(), location.line()), (file!(), line));
+        assert_eq!(
+            record.to_string(),
+            format!(
+                "`{}` is poisoned by the panic at {}: transfer failed halfway",
+                FIXTURE, location
+            )
+        );
+    }
+
+    // Rebuild the fixture, and then the second test passes.
+    ACCOUNTS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clear();
+    assert!(clear_poison(FIXTURE));
+    assert!(run_test(|accounts| assert_eq!(
+        accounts.as_slice(),
+        [0u32; 0]
+    )));
+}
+
+#[test]
+fn first_poison_is_kept() {
+    ensure_set_hook();
+    let key = "poison::first";
+
+    poison(key);
+    let unwind = maybe_unwind(|| panic!("later")).unwrap_err();
+    unwind.poison(key);
+
+    let record = poison_report()
+        .into_iter()
+        .find(|record| record.key() == key)
+        .unwrap();
+    assert_eq!(record.location(), None);
+    assert_eq!(record.message(), None);
+    assert_eq!(record.to_string(), "`poison::first` is poisoned");
+
+    assert!(clear_poison(key));
+    assert!(!clear_poison(key));
+    assert!(!is_poisoned(key));
+}
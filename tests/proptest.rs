@@ -0,0 +1,46 @@
+#![cfg(feature = "proptest")]
+
+mod support;
+
+use maybe_unwind::maybe_unwind_prop;
+use proptest::test_runner::{Config, TestError, TestRunner};
+use support::ensure_set_hook;
+
+#[test]
+fn failure_reason_contains_location() {
+    ensure_set_hook();
+    let mut runner = TestRunner::new(Config {
+        cases: 256,
+        failure_persistence: None,
+        ..Config::default()
+    });
+    let res = runner.run(&(0..100i32), |x| {
+        maybe_unwind_prop(|| assert!(x < 50, "too large: {}", x))?;
+        Ok(())
+    });
+
+    match res {
+        Err(TestError::Fail(reason, value)) => {
+            assert_eq!(value, 50);
+            let reason = reason.message();
+            assert!(reason.contains("too large: 50"), "{}", reason);
+            assert!(reason.contains(file!()), "{}", reason);
+        }
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn passing_property() {
+    ensure_set_hook();
+    let mut runner = TestRunner::new(Config {
+        failure_persistence: None,
+        ..Config::default()
+    });
+    let res = runner.run(&(0..100i32), |x| {
+        let y = maybe_unwind_prop(|| x * 2)?;
+        assert_eq!(y % 2, 0);
+        Ok(())
+    });
+    assert!(res.is_ok());
+}
@@ -0,0 +1,76 @@
+#![cfg(all(feature = "capture", feature = "quickcheck"))]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind_qc, maybe_unwind_qc_property};
+use quickcheck::{QuickCheck, TestResult};
+use support::ensure_set_hook;
+
+fn below_50(x: u8) -> TestResult {
+    maybe_unwind_qc(|| {
+        assert!(x < 50, "too large: {}", x);
+        true
+    })
+}
+
+#[test]
+fn failure_contains_location() {
+    ensure_set_hook();
+    let res = QuickCheck::new()
+        .tests(1000)
+        .quicktest(below_50 as fn(u8) -> TestResult);
+
+    let failure = res.unwrap_err();
+    assert!(failure.is_error());
+    // The arguments are shrunk to the smallest failing input.
+    let failure = format!("{:?}", failure);
+    assert!(failure.contains("too large: 50"), "{}", failure);
+    assert!(failure.contains("quickcheck.rs"), "{}", failure);
+    assert!(failure.contains("[\"50\"]"), "{}", failure);
+}
+
+#[test]
+fn property_of_closure() {
+    ensure_set_hook();
+    let limit = 50;
+    let res = QuickCheck::new()
+        .tests(1000)
+        .quicktest(maybe_unwind_qc_property(move |x: u8| {
+            assert!(x < limit, "too large: {}", x);
+            true
+        }));
+
+    let failure = format!("{:?}", res.unwrap_err());
+    assert!(
+        failure.contains("the property panicked for 50: panicked at "),
+        "{}",
+        failure
+    );
+    assert!(failure.contains("too large: 50"), "{}", failure);
+    assert!(failure.contains("quickcheck.rs"), "{}", failure);
+
+    let res = QuickCheck::new()
+        .tests(1000)
+        .quicktest(maybe_unwind_qc_property(|(a, b): (u8, u8)| a <= b));
+    let failure = format!("{:?}", res.unwrap_err());
+    assert!(
+        failure.contains("the property does not hold for (1, 0)"),
+        "{}",
+        failure
+    );
+}
+
+#[test]
+fn passing_property() {
+    ensure_set_hook();
+    let res = QuickCheck::new().quicktest(maybe_unwind_qc_property(|x: u32| {
+        x.wrapping_mul(2) % 2 == 0
+    }));
+    assert!(res.is_ok());
+
+    fn even(x: u32) -> TestResult {
+        maybe_unwind_qc(|| x.wrapping_mul(2) % 2 == 0)
+    }
+    let res = QuickCheck::new().quicktest(even as fn(u32) -> TestResult);
+    assert!(res.is_ok());
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::maybe_unwind_quiet;
+use std::{cell::Cell, panic, sync::Once};
+
+thread_local! {
+    static HOOK_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn hook_calls() -> usize {
+    HOOK_CALLS.with(|calls| calls.get())
+}
+
+fn install_counting_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        panic::set_hook(Box::new(|_| {
+            HOOK_CALLS.with(|calls| calls.set(calls.get() + 1));
+        }));
+    });
+}
+
+#[test]
+fn nested_scopes() {
+    install_counting_hook();
+    let before = hook_calls();
+
+    let res = maybe_unwind_quiet(|| {
+        let inner = maybe_unwind_quiet(|| panic!("inner")).unwrap_err();
+        assert_eq!(inner.message(), "inner");
+        panic!("outer");
+    });
+    let unwind = res.unwrap_err();
+    assert_eq!(unwind.message(), "outer");
+    assert!(unwind.location().is_some());
+    assert_eq!(hook_calls(), before);
+}
+
+#[test]
+fn restore_after_panic() {
+    install_counting_hook();
+    let before = hook_calls();
+
+    let unwind = maybe_unwind_quiet(|| panic!("silenced")).unwrap_err();
+    assert_eq!(unwind.message(), "silenced");
+    assert_eq!(hook_calls(), before);
+
+    let res = panic::catch_unwind(|| panic!("not silenced"));
+    assert!(res.is_err());
+    assert_eq!(hook_calls(), before + 1);
+}
+
+#[test]
+fn no_panic() {
+    install_counting_hook();
+    assert_eq!(maybe_unwind_quiet(|| 42).unwrap(), 42);
+}
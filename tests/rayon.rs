@@ -0,0 +1,28 @@
+#![cfg(all(feature = "capture", feature = "rayon"))]
+
+mod support;
+
+use maybe_unwind::join_maybe_unwind;
+use support::ensure_set_hook;
+
+#[test]
+fn both_panic() {
+    ensure_set_hook();
+    let line = line!() + 1;
+    let (a, b) = join_maybe_unwind(|| -> u32 { panic!("left") }, || -> u32 { panic!("right") });
+    let (a, b) = (a.unwrap_err(), b.unwrap_err());
+    assert_eq!(a.message(), "left");
+    assert_eq!(b.message(), "right");
+    for unwind in [&a, &b] {
+        assert_eq!(unwind.location().unwrap().line(), line);
+        assert_eq!(unwind.caller().line(), line);
+    }
+}
+
+#[test]
+fn result_of_other_side_is_kept() {
+    ensure_set_hook();
+    let (a, b) = join_maybe_unwind(|| (1..=10).sum::<u32>(), || -> u32 { panic!("right") });
+    assert_eq!(a.unwrap(), 55);
+    assert_eq!(b.unwrap_err().message(), "right");
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{expect_unwind, maybe_unwind, set_redactor, with_context, PayloadPattern};
+use support::ensure_set_hook;
+
+#[test]
+fn redacted_reports() {
+    ensure_set_hook();
+    set_redactor(|s| s.replace("s3cr3t", "[REDACTED]"));
+
+    let unwind = maybe_unwind(|| {
+        with_context("while logging in with token s3cr3t", || {
+            panic!("invalid token: s3cr3t");
+        })
+    })
+    .unwrap_err();
+
+    // the raw payload is untouched.
+    assert_eq!(unwind.payload_str(), "invalid token: s3cr3t");
+    assert_eq!(
+        unwind.payload().downcast_ref::<&str>().copied(),
+        Some("invalid token: s3cr3t")
+    );
+    assert_eq!(
+        unwind.context_frames(),
+        ["while logging in with token s3cr3t"]
+    );
+
+    // every rendered form is masked.
+    assert_eq!(unwind.message(), "invalid token: [REDACTED]");
+    assert_eq!(unwind.to_string(), "invalid token: [REDACTED]");
+
+    let rendered = vec![
+        format!("{:#}", unwind),
+        unwind.normalized(),
+        format!("{:#}", unwind.to_snapshot()),
+    ];
+    for report in &rendered {
+        assert!(!report.contains("s3cr3t"), "report: {}", report);
+        assert!(report.contains("invalid token: [REDACTED]"));
+        assert!(report.contains("while logging in with token [REDACTED]"));
+    }
+
+    let snapshot = unwind.to_snapshot();
+    assert_eq!(snapshot.message(), "invalid token: [REDACTED]");
+    assert_eq!(
+        snapshot.context_frames(),
+        ["while logging in with token [REDACTED]"]
+    );
+
+    let err =
+        expect_unwind(PayloadPattern::exact("s3cr3t"), || panic!("token: s3cr3t")).unwrap_err();
+    assert!(!err.to_string().contains("token: s3cr3t"));
+}
@@ -0,0 +1,69 @@
+#![cfg(all(feature = "capture", backtrace))]
+
+mod support;
+
+use maybe_unwind::{
+    maybe_unwind_with, spawn_backtrace_resolver, BacktraceMode, BacktraceStatus, CaptureOptions,
+    Unwind,
+};
+use support::ensure_set_hook;
+
+#[inline(never)]
+fn resolve_me() {
+    panic!("deep");
+}
+
+fn capture(options: CaptureOptions) -> Unwind {
+    maybe_unwind_with(options.backtrace(BacktraceMode::Always), resolve_me).unwrap_err()
+}
+
+#[test]
+fn resolved_on_worker() {
+    ensure_set_hook();
+    let resolver = spawn_backtrace_resolver();
+    let unwind = capture(CaptureOptions::new().resolve_via(&resolver));
+
+    assert!(unwind.backtrace().is_none(), "handed to the worker");
+    assert_eq!(unwind.backtrace_status(), BacktraceStatus::Captured);
+    let resolved = unwind.resolved_backtrace().expect("resolved backtrace");
+    let text = resolved.wait().expect("backtrace text");
+    assert!(text.contains("resolver::resolve_me"), "{}", text);
+    assert!(resolved.is_resolved());
+    assert_eq!(
+        resolved.resolver_thread_name().as_deref(),
+        Some("maybe-unwind-resolver")
+    );
+    assert!(format!("{:#}", unwind).contains("resolver::resolve_me"));
+}
+
+#[test]
+fn truncated_on_worker() {
+    ensure_set_hook();
+    let resolver = spawn_backtrace_resolver();
+    let unwind = capture(
+        CaptureOptions::new()
+            .max_backtrace_frames(2)
+            .resolve_via(&resolver),
+    );
+    let text = unwind.resolved_backtrace().unwrap().wait().unwrap();
+    assert!(text.contains("frames omitted"), "{}", text);
+}
+
+#[test]
+fn shutdown() {
+    ensure_set_hook();
+    let resolver = spawn_backtrace_resolver();
+    let options = CaptureOptions::new().resolve_via(&resolver);
+
+    // The submitted backtrace is resolved before the worker exits.
+    let pending = capture(options.clone());
+    drop(resolver);
+    let resolved = pending.resolved_backtrace().unwrap();
+    assert!(resolved.is_resolved());
+    assert!(resolved.wait().is_some());
+
+    // The backtrace is kept as usual afterwards.
+    let unwind = capture(options);
+    assert!(unwind.resolved_backtrace().is_none());
+    assert!(unwind.backtrace().is_some());
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, CaptureMiss, Unwind};
+use std::panic::{self, AssertUnwindSafe, UnwindSafe};
+use support::ensure_set_hook;
+
+/// Run the test body like a custom harness that defers the failures to libtest.
+fn harness(f: impl FnOnce() + UnwindSafe, resume: fn(Unwind) -> !) {
+    ensure_set_hook();
+    if let Err(unwind) = maybe_unwind(f) {
+        assert!(unwind.location().is_some());
+        resume(unwind);
+    }
+}
+
+#[test]
+#[should_panic(expected = "bar")]
+fn should_panic_with_original_payload() {
+    harness(|| panic!("bar"), Unwind::resume_with_original_payload);
+}
+
+#[test]
+#[should_panic(expected = "bar 42")]
+fn should_panic_with_formatted_payload() {
+    harness(
+        || panic!("bar {}", 42),
+        Unwind::resume_with_original_payload,
+    );
+}
+
+#[test]
+fn original_payload_is_not_wrapped() {
+    #[derive(Debug, PartialEq)]
+    struct Failure(u32);
+
+    let payload = panic::catch_unwind(|| {
+        harness(
+            || panic::panic_any(Failure(7)),
+            Unwind::resume_with_original_payload,
+        )
+    })
+    .unwrap_err();
+    assert_eq!(*payload.downcast::<Failure>().unwrap(), Failure(7));
+
+    // The captured information is not carried over to the outer scope.
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| harness(|| panic!("bar"), Unwind::resume_with_original_payload))
+        .unwrap_err();
+    assert_eq!(unwind.payload_str(), "bar");
+    assert_eq!(unwind.location(), None);
+    assert_eq!(unwind.capture_miss(), Some(CaptureMiss::Resumed));
+    assert_eq!(unwind.caller().line(), line);
+}
+
+#[test]
+fn report_payload() {
+    let line = line!() + 2;
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| {
+        harness(|| panic!("bar"), Unwind::resume_with_report)
+    }))
+    .unwrap_err();
+    let report = payload.downcast::<String>().unwrap();
+    assert!(
+        report.starts_with(&format!("panicked at {}:{}:", file!(), line)),
+        "{}",
+        report
+    );
+    assert!(report.contains(&format!(": bar\ncaptured at {}:", file!())));
+}
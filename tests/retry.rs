@@ -0,0 +1,94 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind_mut, maybe_unwind_retry, maybe_unwind_retry_with};
+use support::ensure_set_hook;
+
+#[test]
+fn succeed_after_panics() {
+    ensure_set_hook();
+    let mut count = 0;
+    let res = maybe_unwind_retry(5, || {
+        count += 1;
+        if count <= 2 {
+            panic!("attempt {}", count);
+        }
+        count
+    });
+    assert_eq!(res.unwrap(), 3);
+    assert_eq!(count, 3);
+}
+
+#[test]
+fn always_panic() {
+    ensure_set_hook();
+    let mut count = 0;
+    let mut backoffs = vec![];
+    let line = line!() + 6;
+    let err = maybe_unwind_retry_with(
+        3,
+        |failed| backoffs.push(failed),
+        || {
+            count += 1;
+            panic!("attempt {}", count)
+        },
+    )
+    .unwrap_err();
+    assert_eq!(count, 3);
+    assert_eq!(backoffs, [1, 2]);
+
+    assert_eq!(err.attempts(), 3);
+    for (i, (label, unwind)) in err.unwinds().iter().enumerate() {
+        assert_eq!(label, format!("attempt {}", i + 1));
+        assert_eq!(unwind.payload_str(), format!("attempt {}", i + 1));
+        let location = unwind.location().unwrap();
+        assert_eq!(location.file(), file!());
+        assert_eq!(location.line(), line);
+        assert_eq!(unwind.caller().line(), line - 5);
+    }
+
+    let msg = err.to_string();
+    assert!(msg.starts_with("all 3 attempts panicked\n"), "{}", msg);
+    assert!(
+        msg.ends_with(&format!(
+            "  attempt 3: panicked at {}:{}:13: attempt 3",
+            file!(),
+            line
+        )),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+#[should_panic(expected = "the number of attempts must be positive")]
+fn zero_attempts() {
+    let _ = maybe_unwind_retry(0, || ());
+}
+
+#[test]
+fn same_closure_repeatedly() {
+    ensure_set_hook();
+    let mut calls = vec![];
+    let step = |n: &mut u32| {
+        *n += 1;
+        assert!(*n != 2, "call {} panicked", n);
+        *n
+    };
+    let mut n = 0;
+    let mut f = || {
+        calls.push(n);
+        step(&mut n)
+    };
+
+    assert_eq!(maybe_unwind_mut(&mut f).unwrap(), 1);
+    let line = line!() + 1;
+    let unwind = maybe_unwind_mut(&mut f).unwrap_err();
+    assert_eq!(unwind.message(), "call 2 panicked");
+    assert_eq!(unwind.caller().line(), line);
+    assert_eq!(maybe_unwind_mut(&mut f).unwrap(), 3);
+
+    // The state updated before the panic is observed by the next call.
+    assert_eq!(calls, [0, 1, 2]);
+}
@@ -0,0 +1,122 @@
+// These tests spawn the example binary, which is not supported by Miri.
+#![cfg(all(feature = "capture", not(miri)))]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, UnwindReport};
+use std::{
+    path::Path,
+    process::{Command, ExitCode, Termination},
+};
+use support::example_path;
+
+#[test]
+fn exit_successfully() {
+    let output = Command::new(example_path("run")).output().unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn report_panic() {
+    let output = Command::new(example_path("run"))
+        .arg("panic")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(101));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let path = Path::new("examples").join("run.rs");
+    let prefix = format!("panicked at {}:", path.display());
+    assert!(stderr.starts_with(&prefix), "{}", stderr);
+    assert!(stderr.contains(": oops\n"));
+    assert!(stderr.contains(&format!("captured at {}:", path.display())));
+    assert!(stderr.contains("    while running the example\n"));
+    assert!(!stderr.contains("thread 'main' panicked"));
+}
+
+#[test]
+fn custom_exit_code() {
+    let output = Command::new(example_path("run"))
+        .args(["panic", "42"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(42));
+}
+
+#[test]
+fn termination_passthrough() {
+    let output = Command::new(example_path("run"))
+        .arg("fail")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Error: \"failed\""), "{}", stderr);
+}
+
+#[test]
+fn report_from_main() {
+    let output = Command::new(example_path("report")).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let prefix = format!(
+        "Error: panicked at {}:",
+        Path::new("examples").join("report.rs").display()
+    );
+    assert!(stderr.starts_with(&prefix), "{}", stderr);
+    assert!(stderr.contains(": oops\n"));
+    assert!(stderr.contains("    while running the example"));
+    assert!(!stderr.contains("Unwind {"), "{}", stderr);
+    assert!(!stderr.contains("thread 'main' panicked"));
+
+    let output = Command::new(example_path("report"))
+        .arg("ok")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn report_with_exit_code() {
+    let output = Command::new(example_path("report"))
+        .arg("exit")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(101));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let prefix = format!(
+        "panicked at {}:",
+        Path::new("examples").join("report.rs").display()
+    );
+    assert!(stderr.starts_with(&prefix), "{}", stderr);
+}
+
+#[test]
+fn report_termination() {
+    support::ensure_set_hook();
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    assert_eq!(unwind.exit_code(), 101);
+
+    let report = UnwindReport::from(unwind);
+    assert_eq!(report.to_string(), "oops");
+    let prefix = format!("panicked at {}:", file!());
+    assert!(format!("{:?}", report).starts_with(&prefix));
+    // `ExitCode` does not implement `PartialEq` on the MSRV.
+    assert_eq!(
+        format!("{:?}", report.report()),
+        format!("{:?}", ExitCode::from(101))
+    );
+
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    let report = UnwindReport::new(unwind).with_exit_code(42);
+    assert_eq!(report.unwind().message(), "oops");
+    assert_eq!(
+        format!("{:?}", report.report()),
+        format!("{:?}", ExitCode::from(42))
+    );
+}
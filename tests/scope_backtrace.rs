@@ -0,0 +1,87 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{
+    maybe_unwind, maybe_unwind_with, BacktraceMode, CaptureOptions, Unwind, UnwindSnapshot,
+};
+use support::ensure_set_hook;
+
+/// Return the innermost frame of this test crate in the text of a backtrace,
+/// skipping the panic hook installed by the support module.
+fn top_user_frame(backtrace: &str) -> Option<&str> {
+    backtrace.lines().find_map(|line| {
+        let (_, symbol) = line.trim_start().split_once(": ")?;
+        if symbol.starts_with("scope_backtrace::") && !symbol.contains("::support::") {
+            Some(symbol)
+        } else {
+            None
+        }
+    })
+}
+
+#[inline(never)]
+fn deep_helper() {
+    panic!("deep");
+}
+
+#[inline(never)]
+fn enter_scope(options: CaptureOptions) -> Unwind {
+    maybe_unwind_with(options, deep_helper).unwrap_err()
+}
+
+#[test]
+fn top_user_frames() {
+    ensure_set_hook();
+    let options = CaptureOptions::new()
+        .backtrace(BacktraceMode::Always)
+        .capture_scope_backtrace(true);
+    let snapshot = enter_scope(options).to_snapshot();
+
+    // The backtrace is not supported on this toolchain.
+    let scope_backtrace = match snapshot.scope_backtrace() {
+        Some(backtrace) => backtrace,
+        None => return,
+    };
+    assert_eq!(
+        top_user_frame(scope_backtrace),
+        Some("scope_backtrace::enter_scope")
+    );
+    if let Some(backtrace) = snapshot.backtrace() {
+        assert_eq!(
+            top_user_frame(backtrace),
+            Some("scope_backtrace::deep_helper")
+        );
+    }
+
+    let report = format!("{:#}", snapshot);
+    let section = report.find("scope established here:\n").unwrap();
+    assert!(report[section..].contains("scope_backtrace::enter_scope"));
+}
+
+#[test]
+fn not_captured_by_default() {
+    ensure_set_hook();
+    let unwind = enter_scope(CaptureOptions::new().backtrace(BacktraceMode::Always));
+    let snapshot = unwind.to_snapshot();
+    assert_eq!(snapshot.scope_backtrace(), None);
+    assert!(!format!("{:#}", unwind).contains("scope established here"));
+    assert!(snapshot
+        .to_json_line()
+        .contains(",\"scope_backtrace\":null}"));
+
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    assert_eq!(unwind.to_snapshot().scope_backtrace(), None);
+}
+
+#[test]
+fn survives_encoding() {
+    ensure_set_hook();
+    let options = CaptureOptions::new().capture_scope_backtrace(true);
+    let snapshot = enter_scope(options).to_snapshot();
+
+    let mut buf = vec![];
+    snapshot.write_to(&mut buf).unwrap();
+    let decoded = UnwindSnapshot::read_from(&buf[..]).unwrap();
+    assert_eq!(decoded.scope_backtrace(), snapshot.scope_backtrace());
+}
@@ -0,0 +1,18 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::{maybe_unwind, HookOptions};
+
+#[test]
+fn enabled_by_hook() {
+    maybe_unwind::set_hook_with(HookOptions::new().capture_scope_backtrace(true));
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    // The backtrace is not supported on this toolchain.
+    if let Some(backtrace) = unwind.to_snapshot().scope_backtrace() {
+        assert!(backtrace.contains("scope_backtrace_hook::enabled_by_hook"));
+    }
+
+    // The setting is replaced by installing the hook again.
+    maybe_unwind::set_hook();
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    assert_eq!(unwind.to_snapshot().scope_backtrace(), None);
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_with_data};
+use support::ensure_set_hook;
+
+#[derive(Debug, PartialEq)]
+struct TestId {
+    suite: &'static str,
+    index: usize,
+}
+
+#[test]
+fn read_back_typed() {
+    ensure_set_hook();
+    let line = line!() + 6;
+    let mut unwind = maybe_unwind_with_data(
+        TestId {
+            suite: "suite",
+            index: 3,
+        },
+        || panic!("oops"),
+    )
+    .unwrap_err();
+    assert_eq!(unwind.payload_str(), "oops");
+    assert_eq!(unwind.location().unwrap().line(), line);
+
+    let id = unwind.scope_data::<TestId>().unwrap();
+    assert_eq!(id.suite, "suite");
+    assert_eq!(id.index, 3);
+
+    // The data of other types is not available.
+    assert_eq!(unwind.scope_data::<usize>(), None);
+    assert_eq!(unwind.take_scope_data::<usize>(), None);
+
+    assert_eq!(
+        unwind.take_scope_data::<TestId>(),
+        Some(TestId {
+            suite: "suite",
+            index: 3
+        })
+    );
+    assert_eq!(unwind.scope_data::<TestId>(), None);
+    assert_eq!(unwind.take_scope_data::<TestId>(), None);
+}
+
+#[test]
+fn returned_on_success() {
+    let (output, data) = maybe_unwind_with_data(vec![1, 2], || "ok").unwrap();
+    assert_eq!(output, "ok");
+    assert_eq!(data, [1, 2]);
+}
+
+#[test]
+fn plain_scope_has_no_data() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    assert_eq!(unwind.scope_data::<TestId>(), None);
+}
+
+#[test]
+fn nested_scopes() {
+    ensure_set_hook();
+    let mut outer = maybe_unwind_with_data("outer", || {
+        let inner = maybe_unwind_with_data(1_u32, || panic!("inner")).unwrap_err();
+        assert_eq!(inner.scope_data::<u32>(), Some(&1));
+        assert_eq!(inner.scope_data::<&str>(), None);
+
+        let (_, data) = maybe_unwind_with_data(2_u32, || ()).unwrap();
+        assert_eq!(data, 2);
+
+        // A nested scope without data does not see the data of the outer scope.
+        let unwind = maybe_unwind(|| panic!("plain")).unwrap_err();
+        assert_eq!(unwind.scope_data::<&str>(), None);
+
+        panic!("outer");
+    })
+    .unwrap_err();
+    assert_eq!(outer.payload_str(), "outer");
+    assert_eq!(outer.scope_data::<u32>(), None);
+    assert_eq!(outer.take_scope_data::<&str>(), Some("outer"));
+}
@@ -0,0 +1,81 @@
+use maybe_unwind::{hook_installed, self_check, SelfCheckError};
+
+#[cfg(feature = "capture")]
+#[test]
+fn self_check_scenarios() {
+    use maybe_unwind::{add_observer, maybe_unwind_quiet, remove_observer, run, HookOptions};
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    fn counting_hook() -> Arc<AtomicUsize> {
+        let count = Arc::new(AtomicUsize::new(0));
+        let hook_count = Arc::clone(&count);
+        panic::set_hook(Box::new(move |_| {
+            hook_count.fetch_add(1, Ordering::SeqCst);
+        }));
+        count
+    }
+
+    // Not installed.
+    let outer = counting_hook();
+    assert!(!hook_installed());
+    assert_eq!(self_check(), Err(SelfCheckError::HookNotInstalled));
+
+    // Installed only while `run` is running.
+    let mut res = None;
+    run(AssertUnwindSafe(|| {
+        assert!(hook_installed());
+        res = Some(self_check());
+    }));
+    assert_eq!(res, Some(Ok(())));
+    assert!(!hook_installed());
+    assert_eq!(self_check(), Err(SelfCheckError::HookNotInstalled));
+
+    // Installed only while the quiet scope is active.
+    let res = maybe_unwind_quiet(self_check);
+    assert_eq!(res.unwrap(), Ok(()));
+    assert!(!hook_installed());
+
+    // The probe is neither forwarded to the previous hook nor observed.
+    let observed = Arc::new(AtomicUsize::new(0));
+    let id = add_observer({
+        let observed = Arc::clone(&observed);
+        move |_| {
+            observed.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    maybe_unwind::set_hook_with(HookOptions::new().also_forward_captured(true));
+    assert!(hook_installed());
+    assert_eq!(self_check(), Ok(()));
+    let mut res = None;
+    run(AssertUnwindSafe(|| res = Some(self_check())));
+    assert_eq!(res, Some(Ok(())));
+    assert_eq!(outer.load(Ordering::SeqCst), 0);
+    assert_eq!(observed.load(Ordering::SeqCst), 0);
+    assert!(remove_observer(id));
+
+    // Replaced by another hook.
+    let other = counting_hook();
+    assert_eq!(self_check(), Err(SelfCheckError::HookReplaced));
+    assert_eq!(other.load(Ordering::SeqCst), 0);
+
+    let err = SelfCheckError::HookNotInstalled;
+    assert!(
+        err.to_string().contains("maybe_unwind::set_hook"),
+        "{}",
+        err
+    );
+}
+
+#[cfg(not(feature = "capture"))]
+#[test]
+fn capture_disabled() {
+    maybe_unwind::set_hook();
+    assert_eq!(self_check(), Err(SelfCheckError::CaptureDisabled));
+    let _ = hook_installed();
+}
@@ -0,0 +1,111 @@
+#![cfg(all(feature = "capture", feature = "sentry"))]
+
+use maybe_unwind::{maybe_unwind, with_context, HookOptions};
+use sentry_core::protocol::{Level, Value};
+use std::{panic, sync::Once};
+
+fn ensure_set_hook() {
+    static SET_HOOK: Once = Once::new();
+    SET_HOOK.call_once(|| {
+        maybe_unwind::set_hook_with(HookOptions::new().report_uncaptured_to_sentry(true));
+    });
+}
+
+#[test]
+fn event_fields() {
+    ensure_set_hook();
+    let keys: Vec<&str> = vec![];
+    let line = line!() + 2;
+    let unwind = maybe_unwind(|| {
+        with_context("loading config", || keys.first().unwrap());
+    })
+    .unwrap_err();
+    let event = unwind.to_sentry_event();
+
+    assert_eq!(event.level, Level::Error);
+    assert_eq!(
+        event.message.as_deref(),
+        Some("called `Option::unwrap()` on a `None` value")
+    );
+    let loc = unwind.location().unwrap();
+    assert_eq!(
+        event.culprit,
+        Some(format!("{}:{}:{}", file!(), line, loc.column()))
+    );
+    assert_eq!(event.tags["panic.kind"], "unwrap");
+    assert_eq!(
+        event.extra["context_frames"],
+        Value::from(vec!["loading config"])
+    );
+
+    assert_eq!(event.exception.len(), 1);
+    let exception = &event.exception[0];
+    assert_eq!(exception.ty, "panic");
+    assert_eq!(
+        exception.value.as_deref(),
+        Some("called `Option::unwrap()` on a `None` value")
+    );
+    let mechanism = exception.mechanism.as_ref().unwrap();
+    assert_eq!(mechanism.ty, "maybe_unwind");
+    assert_eq!(mechanism.handled, Some(true));
+
+    // The panic location is the last frame.
+    let frame = exception
+        .stacktrace
+        .as_ref()
+        .unwrap()
+        .frames
+        .last()
+        .unwrap();
+    assert_eq!(frame.filename.as_deref(), Some(file!()));
+    assert_eq!(frame.lineno, Some(line.into()));
+    assert_eq!(frame.colno, Some(loc.column().into()));
+    assert_eq!(frame.in_app, Some(true));
+}
+
+#[test]
+fn event_without_context_frames() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    let event = unwind.to_sentry_event();
+    assert_eq!(event.tags["panic.kind"], "explicit");
+    assert!(!event.extra.contains_key("context_frames"));
+}
+
+#[cfg(backtrace)]
+#[test]
+fn backtrace_frames() {
+    use maybe_unwind::{maybe_unwind_with, BacktraceMode, CaptureOptions};
+
+    ensure_set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    let unwind = maybe_unwind_with(options, || panic!("oops")).unwrap_err();
+    let event = unwind.to_sentry_event();
+    let frames = &event.exception[0].stacktrace.as_ref().unwrap().frames;
+    assert!(frames.len() > 1);
+    assert!(frames.iter().any(|frame| frame.in_app == Some(false)));
+}
+
+#[test]
+fn report_uncaptured() {
+    ensure_set_hook();
+    let line = line!() + 3;
+    let events = sentry_core::test::with_captured_events(|| {
+        let _ = maybe_unwind(|| panic!("captured"));
+        let _ = panic::catch_unwind(|| with_context("serving", || panic!("uncaptured")));
+    });
+
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.level, Level::Fatal);
+    assert_eq!(event.message.as_deref(), Some("uncaptured"));
+    assert!(event
+        .culprit
+        .as_deref()
+        .unwrap()
+        .starts_with(&format!("{}:{}:", file!(), line)));
+    assert_eq!(event.extra["context_frames"], Value::from(vec!["serving"]));
+    let mechanism = event.exception[0].mechanism.as_ref().unwrap();
+    assert_eq!(mechanism.ty, "maybe_unwind");
+    assert_eq!(mechanism.handled, Some(false));
+}
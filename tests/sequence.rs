@@ -0,0 +1,77 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, PanicCollector, UnwindSet};
+use std::{collections::HashSet, panic, thread};
+use support::ensure_set_hook;
+
+#[test]
+fn unique_and_increasing_across_threads() {
+    ensure_set_hook();
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            thread::spawn(move || {
+                (0..16)
+                    .map(|j| {
+                        maybe_unwind(|| panic!("worker {} #{}", i, j))
+                            .unwrap_err()
+                            .sequence()
+                            .unwrap()
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+    for handle in handles {
+        let sequences = handle.join().unwrap();
+        assert!(sequences.windows(2).all(|w| w[0] < w[1]), "{:?}", sequences);
+        for sequence in sequences {
+            assert!(seen.insert(sequence), "duplicated: {}", sequence);
+        }
+    }
+    assert_eq!(seen.len(), 64);
+}
+
+#[test]
+fn uncaptured_panic_leaves_gap() {
+    ensure_set_hook();
+    let first = maybe_unwind(|| panic!("first")).unwrap_err();
+    // Not in a capture scope, but the panic is processed by the hook.
+    let _ = panic::catch_unwind(|| panic!("uncaptured"));
+    let second = maybe_unwind(|| panic!("second")).unwrap_err();
+    assert!(second.sequence().unwrap() >= first.sequence().unwrap() + 2);
+}
+
+#[test]
+fn sorted_by_sequence() {
+    ensure_set_hook();
+    let (collector, receiver) = PanicCollector::new();
+    let first = maybe_unwind(|| panic!("sorted first")).unwrap_err();
+    let second = maybe_unwind(|| panic!("sorted second")).unwrap_err();
+    drop(collector);
+
+    let panics: Vec<_> = receiver
+        .drain()
+        .into_iter()
+        .filter(|p| p.message().starts_with("sorted "))
+        .collect();
+    assert_eq!(panics.len(), 2);
+    assert_eq!(panics[0].sequence(), first.sequence().unwrap());
+    assert_eq!(panics[1].sequence(), second.sequence().unwrap());
+
+    let mut set = UnwindSet::new();
+    set.push("second", second);
+    set.push("first", first);
+    let labels: Vec<_> = set.iter().map(|(label, _)| label).collect();
+    assert_eq!(labels, ["second", "first"]);
+    let labels: Vec<_> = set.sorted_by_sequence().map(|(label, _)| label).collect();
+    assert_eq!(labels, ["first", "second"]);
+    // The summary lists the panics in the same order as `iter`.
+    let summary = set.to_string();
+    let lines: Vec<_> = summary.lines().skip(1).collect();
+    assert!(lines[0].starts_with("  1. second: "), "{}", summary);
+    assert!(lines[1].starts_with("  2. first: "), "{}", summary);
+}
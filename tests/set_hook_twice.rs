@@ -0,0 +1,48 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::{
+    compose_with_current_hook, maybe_unwind, maybe_unwind_quiet, panic_stats, HookOptions,
+};
+use std::{
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static OTHER_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+// The hook is process-global, so the scenarios are run in a single test.
+#[test]
+fn set_hook_twice() {
+    panic::set_hook(Box::new(|_| {
+        OTHER_HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+    }));
+    maybe_unwind::set_hook();
+    maybe_unwind::set_hook();
+    maybe_unwind::set_hook();
+
+    // The panic is processed by a single capturing hook.
+    let before = panic_stats();
+    let res = panic::catch_unwind(|| panic!("not captured"));
+    assert!(res.is_err());
+    assert_eq!(panic_stats().panics, before.panics + 1);
+    assert_eq!(OTHER_HOOK_CALLS.load(Ordering::SeqCst), 1);
+
+    // The options of the installed hook are replaced.
+    maybe_unwind::set_hook_with(HookOptions::new().also_forward_captured(true));
+    let before = panic_stats();
+    let first = maybe_unwind(|| panic!("captured")).unwrap_err();
+    let second = maybe_unwind(|| panic!("captured")).unwrap_err();
+    assert_eq!(panic_stats().panics, before.panics + 2);
+    assert_eq!(panic_stats().captured, before.captured + 2);
+    assert_eq!(second.sequence(), first.sequence().map(|n| n + 1));
+    assert_eq!(OTHER_HOOK_CALLS.load(Ordering::SeqCst), 3);
+
+    // The hook is still recognized after the quiet scope restores it.
+    let _ = maybe_unwind_quiet(|| panic!("quiet"));
+    assert!(!compose_with_current_hook(HookOptions::new()));
+    maybe_unwind::set_hook();
+    let before = panic_stats();
+    let _ = maybe_unwind(|| panic!("captured"));
+    assert_eq!(panic_stats().panics, before.panics + 1);
+    assert_eq!(OTHER_HOOK_CALLS.load(Ordering::SeqCst), 3);
+}
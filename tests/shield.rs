@@ -0,0 +1,163 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{shield_fn, shield_fn_mut, ShieldOptions, Unwind};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    thread,
+};
+use support::ensure_set_hook;
+
+#[derive(Debug)]
+struct Event(u32);
+
+/// A stand-in for the event loop of a foreign library.
+fn run_event_loop(events: u32, mut callback: Box<dyn FnMut(Event) + '_>) {
+    for i in 1..=events {
+        callback(Event(i));
+    }
+}
+
+#[test]
+fn every_third_event_panics() {
+    ensure_set_hook();
+    let mut handled = vec![];
+    let mut unwinds = vec![];
+    let line = line!() + 1;
+    let callback = shield_fn_mut(
+        |event: Event| {
+            assert!(event.0 % 3 != 0, "event {} failed", event.0);
+            handled.push(event.0);
+        },
+        |unwind: Unwind| unwinds.push(unwind),
+    );
+    run_event_loop(10, Box::new(callback));
+
+    assert_eq!(handled, [1, 2, 4, 5, 7, 8, 10]);
+    let messages: Vec<_> = unwinds.iter().map(|unwind| unwind.message()).collect();
+    assert_eq!(
+        messages,
+        ["event 3 failed", "event 6 failed", "event 9 failed"]
+    );
+    for unwind in &unwinds {
+        assert_eq!(unwind.location().unwrap().file(), file!());
+        // The location where the callback was shielded.
+        assert_eq!(unwind.caller().line(), line);
+    }
+}
+
+#[test]
+fn stop_after_max_unwinds() {
+    ensure_set_hook();
+    let mut handled = vec![];
+    let mut unwinds = 0;
+    let callback = ShieldOptions::new().max_unwinds(2).fn_mut1(
+        |event: Event| {
+            assert!(event.0 % 3 != 0, "event {} failed", event.0);
+            handled.push(event.0);
+        },
+        |_| unwinds += 1,
+    );
+    run_event_loop(10, Box::new(callback));
+
+    // The callback is no longer invoked after the second panic.
+    assert_eq!(handled, [1, 2, 4, 5]);
+    assert_eq!(unwinds, 2);
+}
+
+#[test]
+fn default_return_value() {
+    ensure_set_hook();
+    let mut unwinds = 0;
+    let mut add = ShieldOptions::new().fn_mut2(
+        |a: u32, b: u32| a.checked_add(b).expect("overflow"),
+        |_| unwinds += 1,
+    );
+    assert_eq!(add(1, 2), 3);
+    assert_eq!(add(u32::MAX, 1), 0);
+    drop(add);
+    assert_eq!(unwinds, 1);
+
+    let tick = ShieldOptions::new().fn0(|| -> bool { panic!("tick") }, |_| ());
+    assert!(!tick());
+
+    let sum = ShieldOptions::new().fn3(|a: u8, b: u8, c: u8| a + b + c, |_| ());
+    assert_eq!(sum(1, 2, 3), 6);
+}
+
+#[test]
+fn reentrant_invocation() {
+    ensure_set_hook();
+    type Handler = Rc<dyn Fn(u32)>;
+
+    let slot: Rc<RefCell<Option<Handler>>> = Rc::default();
+    let handled = Rc::new(RefCell::new(vec![]));
+    let unwinds = Rc::new(RefCell::new(vec![]));
+    let handler: Handler = Rc::new(ShieldOptions::new().fn1(
+        {
+            let slot = slot.clone();
+            let handled = handled.clone();
+            move |event: u32| {
+                assert!(event > 0, "nested event failed");
+                // Dispatch another event to the same handler.
+                let nested = slot.borrow().clone().unwrap();
+                nested(event - 1);
+                handled.borrow_mut().push(event);
+            }
+        },
+        {
+            let unwinds = unwinds.clone();
+            move |unwind: Unwind| unwinds.borrow_mut().push(unwind.message().into_owned())
+        },
+    ));
+    *slot.borrow_mut() = Some(handler.clone());
+
+    handler(2);
+    // Only the innermost invocation panicked, and the outer ones continued.
+    assert_eq!(*handled.borrow(), [1, 2]);
+    assert_eq!(*unwinds.borrow(), ["nested event failed"]);
+
+    slot.borrow_mut().take();
+}
+
+#[test]
+fn shared_across_threads() {
+    ensure_set_hook();
+    let unwinds = Arc::new(Mutex::new(vec![]));
+    let callback = Arc::new(shield_fn(
+        |event: u32| assert!(event % 3 != 0, "event {} failed", event),
+        {
+            let unwinds = unwinds.clone();
+            move |unwind: Unwind| unwinds.lock().unwrap().push(unwind.message().into_owned())
+        },
+    ));
+
+    let handles: Vec<_> = (0..3)
+        .map(|i| {
+            let callback = callback.clone();
+            thread::spawn(move || {
+                for event in (i * 4 + 1)..=(i * 4 + 4) {
+                    callback(event);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let mut unwinds = unwinds.lock().unwrap().clone();
+    unwinds.sort();
+    assert_eq!(
+        unwinds,
+        [
+            "event 12 failed",
+            "event 3 failed",
+            "event 6 failed",
+            "event 9 failed"
+        ]
+    );
+}
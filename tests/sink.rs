@@ -0,0 +1,101 @@
+#![cfg(all(feature = "capture", feature = "sink"))]
+
+mod support;
+
+use futures_executor::block_on;
+use futures_sink::Sink;
+use futures_util::SinkExt as _;
+use maybe_unwind::{MaybeUnwindError, SinkMaybeUnwindExt as _};
+use std::{
+    panic,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use support::ensure_set_hook;
+
+/// A sink that collects the items, failing on zero and panicking on a negative item.
+#[derive(Default)]
+struct Checked {
+    items: Vec<i32>,
+    flushed: usize,
+}
+
+impl Sink<i32> for Checked {
+    type Error = &'static str;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: i32) -> Result<(), Self::Error> {
+        match item {
+            0 => Err("zero"),
+            n if n < 0 => panic!("negative item: {}", n),
+            n => {
+                self.items.push(n);
+                Ok(())
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.flushed += 1;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[test]
+fn pass_through_items_and_errors() {
+    ensure_set_hook();
+    let mut sink = Checked::default().maybe_unwind();
+    block_on(async {
+        sink.send(1).await.unwrap();
+        let err = sink.send(0).await.unwrap_err();
+        assert_eq!(err.inner(), Some(&"zero"));
+        assert!(err.unwind().is_none());
+        sink.send(2).await.unwrap();
+    });
+    assert_eq!(sink.get_ref().items, [1, 2]);
+}
+
+#[test]
+fn capture_panic_in_start_send() {
+    ensure_set_hook();
+    let caller_line = line!() + 1;
+    let mut sink = Checked::default().maybe_unwind();
+    block_on(async {
+        sink.send(1).await.unwrap();
+        match sink.send(-1).await {
+            Err(MaybeUnwindError::Unwind(unwind)) => {
+                assert_eq!(unwind.message(), "negative item: -1");
+                assert_eq!(unwind.location().unwrap().file(), file!());
+                assert_eq!(unwind.caller().line(), caller_line);
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        // The inner sink is no longer touched.
+        let flushed = sink.get_ref().flushed;
+        sink.close().await.unwrap();
+        assert_eq!(sink.get_ref().flushed, flushed);
+    });
+}
+
+#[test]
+fn send_after_panic() {
+    ensure_set_hook();
+    let mut sink = Checked::default().maybe_unwind();
+    assert!(block_on(sink.send(-1)).is_err());
+
+    let err = panic::catch_unwind(panic::AssertUnwindSafe(|| block_on(sink.send(1))))
+        .map(drop)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<&str>(),
+        Some(&"`MaybeUnwindSink` used after the inner sink panicked")
+    );
+}
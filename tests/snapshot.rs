@@ -0,0 +1,48 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, with_context, UnwindSnapshot};
+use std::{sync::Arc, thread};
+use support::ensure_set_hook;
+
+#[test]
+fn snapshot_fields() {
+    ensure_set_hook();
+    let unwind = thread::Builder::new()
+        .name("snapshot-worker".into())
+        .spawn(|| maybe_unwind(|| with_context("while testing", || panic!("oops"))).unwrap_err())
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let snapshot = unwind.to_snapshot();
+    assert_eq!(snapshot.message(), "oops");
+    assert_eq!(snapshot.location(), unwind.location());
+    assert_eq!(snapshot.caller(), unwind.caller());
+    assert_eq!(snapshot.context_frames(), ["while testing"]);
+    assert_eq!(snapshot.thread_name(), Some("snapshot-worker"));
+    assert!(snapshot.timestamp().is_some());
+    assert_eq!(format!("{:#}", snapshot), format!("{:#}", unwind));
+    assert_eq!(snapshot.to_string(), "oops");
+
+    assert_eq!(snapshot, unwind.to_snapshot());
+    assert_eq!(snapshot, UnwindSnapshot::from(unwind));
+}
+
+#[test]
+fn share_across_threads() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| panic!("shared")).unwrap_err();
+    let snapshot = Arc::new(unwind.to_snapshot());
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let snapshot = Arc::clone(&snapshot);
+            thread::spawn(move || (*snapshot).clone())
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), *snapshot);
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_with, set_redactor, CaptureOptions, UnwindSnapshot};
+use std::env;
+use support::ensure_set_hook;
+
+#[test]
+fn captured_variables() {
+    ensure_set_hook();
+    env::set_var("MAYBE_UNWIND_TEST_LOG", "debug");
+    env::set_var("MAYBE_UNWIND_TEST_EMPTY", "");
+    env::remove_var("MAYBE_UNWIND_TEST_UNSET");
+
+    let options = CaptureOptions::new().snapshot_env(&[
+        "MAYBE_UNWIND_TEST_LOG",
+        "MAYBE_UNWIND_TEST_EMPTY",
+        "MAYBE_UNWIND_TEST_UNSET",
+    ]);
+    let unwind = maybe_unwind_with(options, || {
+        // The values are recorded at the moment of the capture.
+        env::set_var("MAYBE_UNWIND_TEST_LOG", "trace");
+        panic!("flaky");
+    })
+    .unwrap_err();
+
+    let captured = unwind.env();
+    assert_eq!(captured.len(), 3);
+    assert_eq!(captured["MAYBE_UNWIND_TEST_LOG"].as_deref(), Some("trace"));
+    assert_eq!(captured["MAYBE_UNWIND_TEST_EMPTY"].as_deref(), Some(""));
+    assert_eq!(captured["MAYBE_UNWIND_TEST_UNSET"], None);
+    assert_eq!(unwind.captured().unwrap().env(), captured);
+
+    let snapshot = unwind.to_snapshot();
+    assert_eq!(*snapshot.env(), *captured);
+    assert!(snapshot.to_json_line().contains(
+        "\"env\":{\"MAYBE_UNWIND_TEST_EMPTY\":\"\",\
+         \"MAYBE_UNWIND_TEST_LOG\":\"trace\",\
+         \"MAYBE_UNWIND_TEST_UNSET\":null}"
+    ));
+
+    let mut buf = vec![];
+    snapshot.write_to(&mut buf).unwrap();
+    assert_eq!(UnwindSnapshot::read_from(&buf[..]).unwrap(), snapshot);
+}
+
+#[test]
+fn not_recorded_by_default() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    assert!(unwind.env().is_empty());
+    assert!(unwind.to_snapshot().to_json_line().contains(",\"env\":{},"));
+}
+
+#[test]
+fn redacted_values() {
+    ensure_set_hook();
+    set_redactor(|s| s.replace("t0k3n", "[REDACTED]"));
+    env::set_var("MAYBE_UNWIND_TEST_TOKEN", "Bearer t0k3n");
+
+    let options = CaptureOptions::new().snapshot_env(&["MAYBE_UNWIND_TEST_TOKEN"]);
+    let unwind = maybe_unwind_with(options, || panic!("unauthorized")).unwrap_err();
+    assert_eq!(
+        unwind.env()["MAYBE_UNWIND_TEST_TOKEN"].as_deref(),
+        Some("Bearer [REDACTED]")
+    );
+    assert!(!unwind.to_snapshot().to_json_line().contains("t0k3n"));
+}
@@ -0,0 +1,78 @@
+#![cfg(all(feature = "capture", feature = "tracing-error"))]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_with, CaptureOptions};
+use support::ensure_set_hook;
+use tracing_error::ErrorLayer;
+use tracing_subscriber::prelude::*;
+
+fn with_error_layer<R>(f: impl FnOnce() -> R) -> R {
+    let subscriber = tracing_subscriber::registry().with(ErrorLayer::default());
+    tracing::subscriber::with_default(subscriber, f)
+}
+
+fn handle_request(id: u32) {
+    let _span = tracing::info_span!("handle_request", id).entered();
+    load_user();
+}
+
+fn load_user() {
+    let _span = tracing::info_span!("load_user").entered();
+    panic!("user not found");
+}
+
+#[test]
+fn captures_entered_spans() {
+    ensure_set_hook();
+    let unwind = with_error_layer(|| maybe_unwind(|| handle_request(42)).unwrap_err());
+
+    let span_trace = unwind.span_trace().expect("the span trace is captured");
+    let rendered = span_trace.to_string();
+    let inner = rendered.find("load_user").expect(&rendered);
+    let outer = rendered.find("handle_request").expect(&rendered);
+    assert!(
+        inner < outer,
+        "the innermost span comes first: {}",
+        rendered
+    );
+    assert!(rendered.contains("id=42"), "{}", rendered);
+
+    let report = format!("{:#}", unwind);
+    let section = report.find("span trace:\n").expect(&report);
+    assert!(report[section..].contains("load_user"), "{}", report);
+    assert!(!format!("{}", unwind).contains("span trace:"));
+
+    // The captured information carries the same trace.
+    assert_eq!(
+        unwind
+            .captured()
+            .unwrap()
+            .span_trace()
+            .map(ToString::to_string),
+        Some(rendered)
+    );
+}
+
+#[test]
+fn skipped_by_options() {
+    ensure_set_hook();
+    let options = CaptureOptions::new().capture_span_trace(false);
+    let unwind = with_error_layer(|| maybe_unwind_with(options, || handle_request(1)).unwrap_err());
+    assert!(unwind.captured().is_some());
+    assert!(unwind.span_trace().is_none());
+    assert!(!format!("{:#}", unwind).contains("span trace:"));
+}
+
+#[test]
+fn not_captured_without_spans_or_layer() {
+    ensure_set_hook();
+
+    // No spans are entered.
+    let unwind = with_error_layer(|| maybe_unwind(|| panic!("no spans")).unwrap_err());
+    assert!(unwind.span_trace().is_none());
+
+    // The subscriber does not have `ErrorLayer`.
+    let unwind = maybe_unwind(|| handle_request(2)).unwrap_err();
+    assert!(unwind.span_trace().is_none());
+}
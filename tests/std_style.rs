@@ -0,0 +1,105 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind, maybe_unwind_with, CaptureOptions, DisplayOptions};
+use support::ensure_set_hook;
+
+#[test]
+fn hook_message_is_disabled_by_default() {
+    ensure_set_hook();
+
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    assert_eq!(unwind.hook_message(), None);
+
+    let location = unwind.location().unwrap();
+    assert_eq!(location.line(), line);
+    let report = unwind
+        .display_with(DisplayOptions::new().std_style(true))
+        .to_string();
+    assert!(
+        report.starts_with(&format!(
+            "thread 'hook_message_is_disabled_by_default' panicked at {}:\noops\n",
+            location
+        )),
+        "{}",
+        report
+    );
+}
+
+#[test]
+fn hook_message_verbatim() {
+    ensure_set_hook();
+
+    let options = CaptureOptions::new().hook_message(true);
+    let unwind = maybe_unwind_with(options, || panic!("multi\nline")).unwrap_err();
+    let hook_message = unwind.hook_message().unwrap();
+    assert!(hook_message.starts_with("panicked at "), "{}", hook_message);
+    assert!(hook_message.ends_with("multi\nline"), "{}", hook_message);
+    assert_eq!(
+        unwind.captured().unwrap().hook_message(),
+        Some(hook_message)
+    );
+
+    let report = unwind
+        .display_with(DisplayOptions::new().std_style(true))
+        .to_string();
+    assert!(
+        report.starts_with(&format!(
+            "thread 'hook_message_verbatim' {}\n",
+            hook_message
+        )),
+        "{}",
+        report
+    );
+}
+
+/// Remove the parts of the output of the default hook which are not
+/// reproduced, i.e. the blank lines, the thread IDs and the hints.
+#[cfg(not(miri))]
+fn normalize(output: &[u8]) -> String {
+    let output = String::from_utf8(output.to_vec()).unwrap();
+    let mut normalized = String::new();
+    for line in output.lines() {
+        if line.is_empty() || line.starts_with("note: ") {
+            continue;
+        }
+        let line = match (line.find("' ("), line.find(") panicked at ")) {
+            (Some(start), Some(end)) if line.starts_with("thread '") && start < end => {
+                format!("{}'{}", &line[..start], &line[end + 1..])
+            }
+            _ => line.to_owned(),
+        };
+        normalized.push_str(&line);
+        normalized.push('\n');
+    }
+    normalized
+}
+
+// This test spawns the example binary, which is not supported by Miri.
+#[cfg(not(miri))]
+#[test]
+fn same_as_default_hook() {
+    use std::{path::Path, process::Command};
+
+    let output = Command::new(support::example_path("std_style"))
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = normalize(&output.stdout);
+    let stderr = normalize(&output.stderr);
+    let prefix = format!(
+        "thread 'main' panicked at {}:",
+        Path::new("examples").join("std_style.rs").display()
+    );
+    assert!(stdout.starts_with(&prefix), "{}", stdout);
+    assert!(
+        stdout.contains("thread 'worker' panicked at "),
+        "{}",
+        stdout
+    );
+    assert_eq!(stdout, stderr);
+}
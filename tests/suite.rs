@@ -0,0 +1,236 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{CaseOutcome, SuiteRunner, SuiteSummary};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use support::ensure_set_hook;
+
+fn outcomes(summary: &SuiteSummary) -> Vec<(&str, &'static str)> {
+    summary
+        .cases()
+        .iter()
+        .map(|case| {
+            let outcome = match case.outcome() {
+                CaseOutcome::Passed => "passed",
+                CaseOutcome::Failed(..) => "failed",
+                CaseOutcome::Skipped => "skipped",
+                _ => unreachable!(),
+            };
+            (case.name(), outcome)
+        })
+        .collect()
+}
+
+#[test]
+fn sequential() {
+    ensure_set_hook();
+    let mut runs = vec![];
+    let mut runner = SuiteRunner::new("seq");
+    let line = line!() + 3;
+    runner
+        .case("ok", || runs.push("ok"))
+        .case("fail", || panic!("oops"))
+        .case("after", || ());
+    let summary = runner.finish();
+    assert_eq!(runs, ["ok"]);
+
+    assert_eq!(summary.name(), "seq");
+    assert_eq!(
+        outcomes(&summary),
+        [("ok", "passed"), ("fail", "failed"), ("after", "passed")]
+    );
+    assert_eq!(
+        (summary.total(), summary.passed(), summary.failed()),
+        (3, 2, 1)
+    );
+    assert_eq!(summary.skipped(), 0);
+    assert!(!summary.is_success());
+    assert!(summary.duration().is_some());
+
+    let failure = summary.failures().next().unwrap();
+    assert_eq!(failure.name(), "fail");
+    assert!(failure.duration().is_some());
+    let snapshot = failure.unwind().unwrap();
+    assert_eq!(snapshot.message(), "oops");
+    assert_eq!(snapshot.scope_label(), Some("fail"));
+    assert_eq!(snapshot.location().unwrap().line(), line);
+    assert_eq!(snapshot.caller().line(), line);
+
+    assert_eq!(
+        summary.to_string().lines().collect::<Vec<_>>(),
+        [
+            "seq: 2 passed, 1 failed, 0 skipped".to_owned(),
+            format!("  fail: panicked at {}: oops", snapshot.location().unwrap()),
+        ]
+    );
+    assert!(format!("{:#}", summary).contains("\n\n--- fail ---\npanicked in fail at "));
+}
+
+#[test]
+fn all_passed() {
+    let mut runner = SuiteRunner::new("empty");
+    runner.case("a", || ()).case("b", || ());
+    let summary = runner.finish();
+    assert!(summary.is_success());
+    assert_eq!(summary.to_string(), "empty: 2 passed, 0 failed, 0 skipped");
+}
+
+#[test]
+fn fail_fast() {
+    ensure_set_hook();
+    let runs = AtomicUsize::new(0);
+    let mut runner = SuiteRunner::new("ff").fail_fast(true);
+    runner
+        .case("first", || {
+            runs.fetch_add(1, Ordering::SeqCst);
+        })
+        .case("fail", || panic!("stop"))
+        .case("skipped1", || {
+            runs.fetch_add(1, Ordering::SeqCst);
+        })
+        .case("skipped2", || {
+            runs.fetch_add(1, Ordering::SeqCst);
+        });
+    let summary = runner.finish();
+
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        outcomes(&summary),
+        [
+            ("first", "passed"),
+            ("fail", "failed"),
+            ("skipped1", "skipped"),
+            ("skipped2", "skipped"),
+        ]
+    );
+    assert_eq!(summary.skipped(), 2);
+    assert!(summary.cases()[2].duration().is_none());
+    assert!(summary.cases()[2].unwind().is_none());
+}
+
+#[test]
+fn parallel() {
+    ensure_set_hook();
+    let threads = Mutex::new(vec![]);
+    let mut runner = SuiteRunner::new("par").parallel(4);
+    for i in 0..16 {
+        let threads = &threads;
+        runner.case(format!("case{}", i), move || {
+            threads.lock().unwrap().push(thread::current().id());
+            thread::sleep(Duration::from_millis(10));
+            assert!(i % 3 != 0, "case {} failed", i);
+        });
+    }
+    let summary = runner.finish();
+
+    // The workers are distinct from the current thread.
+    let threads = threads.into_inner().unwrap();
+    assert_eq!(threads.len(), 16);
+    assert!(threads.iter().all(|&id| id != thread::current().id()));
+
+    // The results are in the order of registration.
+    for (i, case) in summary.cases().iter().enumerate() {
+        assert_eq!(case.name(), format!("case{}", i));
+        assert_eq!(case.is_failed(), i % 3 == 0);
+        if let Some(snapshot) = case.unwind() {
+            assert_eq!(snapshot.message(), format!("case {} failed", i));
+            assert_eq!(snapshot.scope_label(), Some(case.name()));
+        }
+    }
+    assert_eq!((summary.passed(), summary.failed()), (10, 6));
+}
+
+#[test]
+fn parallel_fail_fast() {
+    ensure_set_hook();
+    let runs = AtomicUsize::new(0);
+    let started = AtomicBool::new(false);
+    let mut runner = SuiteRunner::new("par-ff").parallel(2).fail_fast(true);
+    runner
+        .case("fail", || {
+            while !started.load(Ordering::SeqCst) {
+                thread::yield_now();
+            }
+            panic!("stop");
+        })
+        .case("slow", || {
+            started.store(true, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(200));
+        });
+    for i in 0..8 {
+        runner.case(format!("skipped{}", i), || {
+            runs.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    let summary = runner.finish();
+
+    // The case already running on the other worker is completed.
+    assert_eq!(runs.load(Ordering::SeqCst), 0);
+    assert!(summary.cases()[0].is_failed());
+    assert!(summary.cases()[1].is_passed());
+    assert!(summary.cases()[2..].iter().all(|case| case.is_skipped()));
+    assert_eq!(
+        (summary.passed(), summary.failed(), summary.skipped()),
+        (1, 1, 8)
+    );
+}
+
+#[test]
+fn emitters() {
+    ensure_set_hook();
+    let line = line!() + 4;
+    let mut runner = SuiteRunner::new("emit<&>");
+    runner
+        .case("pass", || ())
+        .case("fail \"quoted\"", || panic!("a < b"));
+    let summary = runner.finish();
+
+    let json = summary.to_json();
+    assert!(json.ends_with("]}\n"));
+    assert!(json.starts_with(
+        "{\"name\":\"emit<&>\",\"total\":2,\"passed\":1,\"failed\":1,\"skipped\":0,\"duration\":{"
+    ));
+    assert!(json
+        .contains("{\"name\":\"pass\",\"outcome\":\"passed\",\"duration\":{\"secs\":0,\"nanos\":"));
+    assert!(json.contains(
+        "\"name\":\"fail \\\"quoted\\\"\",\"outcome\":\"failed\",\"duration\":{\"secs\":0,\"nanos\":"
+    ));
+    assert!(json.contains("\"unwind\":null}"));
+    assert!(json.contains("\"unwind\":{\"version\":1,\"message\":\"a < b\","));
+
+    let xml = summary.to_junit_xml();
+    assert!(xml.starts_with(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuite name=\"emit&lt;&amp;&gt;\" tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"0\" time=\""
+    ));
+    assert!(xml.contains("\n  <testcase name=\"pass\" classname=\"emit&lt;&amp;&gt;\" time=\""));
+    assert!(xml.contains(
+        "\n  <testcase name=\"fail &quot;quoted&quot;\" classname=\"emit&lt;&amp;&gt;\" time=\""
+    ));
+    assert!(xml.contains(&format!(
+        " file=\"{}\" line=\"{}\">\n    <failure message=\"a &lt; b\">panicked in fail &quot;quoted&quot; at ",
+        file!(),
+        line
+    )));
+    assert!(xml.ends_with("</failure>\n  </testcase>\n</testsuite>\n"));
+}
+
+#[test]
+fn junit_skipped() {
+    ensure_set_hook();
+    let mut runner = SuiteRunner::new("s").fail_fast(true);
+    runner.case("fail", || panic!("x")).case("next", || ());
+    let xml = runner.finish().to_junit_xml();
+    assert!(xml.contains("skipped=\"1\""));
+    assert!(
+        xml.contains("<testcase name=\"next\" classname=\"s\">\n    <skipped/>\n  </testcase>\n")
+    );
+}
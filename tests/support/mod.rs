@@ -0,0 +1,35 @@
+#![allow(dead_code)]
+
+use std::{env, path::PathBuf, sync::Once};
+
+pub fn ensure_set_hook() {
+    static SET_HOOK: Once = Once::new();
+    SET_HOOK.call_once(|| {
+        let prev_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !maybe_unwind::capture_panic_info(info) {
+                prev_hook(info);
+            }
+        }));
+    });
+}
+
+pub fn panic_in_helper() {
+    panic!("panicked in helper");
+}
+
+pub fn example_path(name: &str) -> PathBuf {
+    let mut path = env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.push("examples");
+    path.push(format!("{}{}", name, env::consts::EXE_SUFFIX));
+    assert!(
+        path.exists(),
+        "the example `{}` is not built; run `cargo build --examples` first",
+        name
+    );
+    path
+}
@@ -1,18 +1,15 @@
+#![cfg(feature = "capture")]
 #![deny(deprecated)]
 
-use maybe_unwind::maybe_unwind;
-use std::{panic::PanicInfo, sync::Once};
+mod support;
 
-fn ensure_set_hook() {
-    fn test_hook(info: &PanicInfo) {
-        maybe_unwind::capture_panic_info(info);
-    }
-
-    static SET_HOOK: Once = Once::new();
-    SET_HOOK.call_once(|| {
-        std::panic::set_hook(Box::new(test_hook));
-    });
-}
+use maybe_unwind::{
+    maybe_unwind, maybe_unwind_named, maybe_unwind_timed, maybe_unwind_with, with_context,
+    BacktraceMode, BacktraceStatus, CaptureMiss, CaptureOptions, DisplayOptions, LocationStyle,
+    Unwind,
+};
+use std::panic::AssertUnwindSafe;
+use support::ensure_set_hook;
 
 #[test]
 fn never_unwind() {
@@ -20,6 +17,12 @@ fn never_unwind() {
     assert!(maybe_unwind(|| "foo").is_ok());
 }
 
+#[test]
+fn unwinding_supported() {
+    assert!(maybe_unwind::unwinding_supported());
+    assert!(maybe_unwind::take_aborted_panic().is_none());
+}
+
 #[allow(unreachable_code)]
 #[test]
 fn has_unwind() {
@@ -72,11 +75,642 @@ fn nested2() {
     assert_eq!(unwind.payload_str(), "foo");
 }
 
+#[test]
+fn nested_locations() {
+    ensure_set_hook();
+    let inner_line = line!() + 3;
+    let outer_line = line!() + 4;
+    let res = maybe_unwind(|| {
+        let inner = maybe_unwind(|| panic!("bar")).unwrap_err();
+        assert_eq!(inner.location().unwrap().line(), inner_line);
+        panic!("foo");
+    });
+    let outer = res.unwrap_err();
+    assert_eq!(outer.payload_str(), "foo");
+    assert_eq!(outer.location().unwrap().line(), outer_line);
+}
+
+#[test]
+fn nested_inner_catches_outer_panics() {
+    ensure_set_hook();
+    let inner_line = line!() + 3;
+    let outer_line = line!() + 7;
+    let outer = maybe_unwind(|| {
+        let inner = maybe_unwind(|| panic!("inner")).unwrap_err();
+        assert_eq!(inner.payload_str(), "inner");
+        assert_eq!(inner.location().unwrap().line(), inner_line);
+        // The panic not caught by any scope must not be observed by the outer scope.
+        let _ = std::panic::catch_unwind(|| panic!("uncaught"));
+        panic!("outer");
+    })
+    .unwrap_err();
+    assert_eq!(outer.payload_str(), "outer");
+    assert_eq!(outer.location().unwrap().line(), outer_line);
+}
+
+#[test]
+fn nested_inner_panics_outer_succeeds() {
+    ensure_set_hook();
+    let inner = maybe_unwind(|| maybe_unwind(|| panic!("inner")).unwrap_err()).unwrap();
+    assert_eq!(inner.payload_str(), "inner");
+    assert!(inner.location().is_some());
+
+    // The capture of the previous inner scope is not observed by the outer
+    // scope that panics without the panic hook being called.
+    let outer = maybe_unwind(|| {
+        let _ = maybe_unwind(|| panic!("inner"));
+        std::panic::resume_unwind(Box::new("outer"));
+    })
+    .unwrap_err();
+    assert_eq!(outer.payload_str(), "outer");
+    assert!(outer.location().is_none());
+}
+
+#[test]
+fn nested_sibling_scopes() {
+    ensure_set_hook();
+    let outer = maybe_unwind(|| {
+        let first = maybe_unwind(|| {
+            let _ = std::panic::catch_unwind(|| panic!("first"));
+        });
+        assert!(first.is_ok());
+        let second = maybe_unwind(|| std::panic::resume_unwind(Box::new("second"))).unwrap_err();
+        assert_eq!(second.payload_str(), "second");
+        assert!(second.location().is_none());
+        std::panic::resume_unwind(Box::new("outer"));
+    })
+    .unwrap_err();
+    assert!(outer.location().is_none());
+}
+
+#[test]
+fn nested_double() {
+    ensure_set_hook();
+    let line = line!();
+    let outer = maybe_unwind(|| {
+        let middle = maybe_unwind(|| {
+            let inner = maybe_unwind(|| panic!("inner")).unwrap_err();
+            assert_eq!(inner.location().unwrap().line(), line + 3);
+            panic!("middle");
+        })
+        .unwrap_err();
+        assert_eq!(middle.payload_str(), "middle");
+        assert_eq!(middle.location().unwrap().line(), line + 5);
+
+        let innermost = maybe_unwind(|| maybe_unwind(|| maybe_unwind(|| panic!("innermost"))))
+            .unwrap()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(innermost.location().unwrap().line(), line + 11);
+        panic!("outer");
+    })
+    .unwrap_err();
+    assert_eq!(outer.payload_str(), "outer");
+    assert_eq!(outer.location().unwrap().line(), line + 16);
+}
+
+#[test]
+fn nested_in_destructor_during_unwinding() {
+    type Lines = std::cell::Cell<Option<(u32, u32)>>;
+
+    struct CatchOnDrop(&'static std::thread::LocalKey<Lines>);
+
+    impl Drop for CatchOnDrop {
+        fn drop(&mut self) {
+            // The scope entered during unwinding does not observe the panic
+            // captured by the outer scope.
+            let res = maybe_unwind(|| ());
+            assert!(res.is_ok());
+            let expected = line!() + 1;
+            let inner = maybe_unwind(|| panic!("inner")).unwrap_err();
+            let actual = inner.location().map_or(0, |loc| loc.line());
+            self.0.with(|lines| lines.set(Some((actual, expected))));
+        }
+    }
+
+    thread_local!(static INNER_LINES: Lines = const { std::cell::Cell::new(None) });
+
+    ensure_set_hook();
+    let line = line!();
+    let outer = maybe_unwind(|| {
+        let _guard = CatchOnDrop(&INNER_LINES);
+        panic!("outer");
+    })
+    .unwrap_err();
+    assert_eq!(outer.payload_str(), "outer");
+    assert_eq!(outer.location().unwrap().line(), line + 3);
+    let (actual, expected) = INNER_LINES.with(|lines| lines.get()).unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn map_payload_and_split() {
+    #[derive(Debug, PartialEq)]
+    enum Failure {
+        Panicked(String),
+    }
+
+    ensure_set_hook();
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| panic!("oops")).unwrap_err();
+    let unwind = unwind
+        .map_payload(|payload| Failure::Panicked(payload.downcast::<&str>().unwrap().to_string()));
+    assert_eq!(
+        unwind.payload().downcast_ref::<Failure>(),
+        Some(&Failure::Panicked("oops".into()))
+    );
+    assert_eq!(unwind.location().unwrap().line(), line);
+
+    let (payload, meta) = unwind.split();
+    assert!(payload.is::<Failure>());
+    assert_eq!(meta.location().unwrap().line(), line);
+    assert_eq!(meta.caller().line(), line);
+    assert_eq!(meta.captured().unwrap().message(), "oops");
+
+    let unwind = meta.rewrap(Box::new("rewrapped"));
+    assert_eq!(unwind.payload_str(), "rewrapped");
+    assert_eq!(unwind.location().unwrap().line(), line);
+    assert_eq!(unwind.caller().line(), line);
+}
+
+#[test]
+fn resume_with_metadata() {
+    ensure_set_hook();
+    let line = line!() + 2;
+    let unwind = maybe_unwind(|| {
+        with_context("while resuming", || panic!("oops"));
+    })
+    .unwrap_err();
+
+    // Through `catch_unwind`.
+    let payload = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        std::panic::resume_unwind(unwind.into_resumable());
+    }))
+    .unwrap_err();
+    let unwind = Unwind::from_resumed(payload).unwrap();
+    assert_eq!(unwind.payload_str(), "oops");
+    assert_eq!(unwind.location().unwrap().line(), line);
+    assert_eq!(unwind.context_frames(), ["while resuming"]);
+
+    // Through an outer scope, which restores the original information.
+    let outer = maybe_unwind(AssertUnwindSafe(|| {
+        std::panic::resume_unwind(unwind.into_resumable())
+    }))
+    .unwrap_err();
+    assert_eq!(outer.payload_str(), "oops");
+    assert_eq!(outer.location().unwrap().line(), line);
+    assert_eq!(outer.context_frames(), ["while resuming"]);
+    assert_eq!(outer.capture_miss(), None);
+    assert!(Unwind::from_resumed(outer.into_payload()).is_err());
+
+    // Other payloads are returned as they are.
+    let payload = Unwind::from_resumed(Box::new("foo")).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"foo"));
+}
+
+#[test]
+fn resumed_unwind_is_not_misattributed() {
+    ensure_set_hook();
+    let res = maybe_unwind(|| {
+        let res = std::panic::catch_unwind(|| panic!("foo"));
+        assert!(res.is_err());
+    });
+    assert!(res.is_ok());
+
+    let unwind = maybe_unwind(|| std::panic::resume_unwind(Box::new("bar"))).unwrap_err();
+    assert_eq!(unwind.payload_str(), "bar");
+    assert!(unwind.location().is_none());
+    assert_eq!(unwind.capture_miss(), Some(CaptureMiss::Resumed));
+    let note = "note: location unavailable: the panic hook was not called, \
+                e.g. the panic was resumed by `resume_unwind`\n";
+    assert!(format!("{:#}", unwind).contains(note));
+    assert!(format!("{:#}", unwind.to_snapshot()).contains(note));
+}
+
+#[test]
+fn caller_location() {
+    ensure_set_hook();
+    let line = line!() + 1;
+    let unwind = maybe_unwind(support::panic_in_helper).unwrap_err();
+    assert_eq!(unwind.payload_str(), "panicked in helper");
+    assert!(unwind
+        .location()
+        .map_or(false, |loc| loc.file().contains("support")));
+    assert!(unwind.caller().file().contains(file!()));
+    assert_eq!(unwind.caller().line(), line);
+}
+
+#[test]
+fn context_frames() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| {
+        with_context("first", || {
+            with_context("second", || {
+                with_context("third", || panic!("bar"));
+            });
+        });
+    })
+    .unwrap_err();
+    assert_eq!(unwind.context_frames(), ["first", "second", "third"]);
+
+    let report = format!("{:#}", unwind);
+    assert!(report.contains("context:\n    first\n    second\n    third\n"));
+}
+
+#[test]
+fn context_frames_are_popped() {
+    ensure_set_hook();
+    let unwind = maybe_unwind(|| {
+        with_context("first", || {
+            let _ = maybe_unwind(|| with_context("second", || panic!("baz")));
+            with_context("third", || ());
+            panic!("bar");
+        });
+    })
+    .unwrap_err();
+    assert_eq!(unwind.payload_str(), "bar");
+    assert_eq!(unwind.context_frames(), ["first"]);
+
+    let unwind = maybe_unwind(|| panic!("foo")).unwrap_err();
+    assert!(unwind.context_frames().is_empty());
+}
+
+#[allow(unreachable_code)]
+#[test]
+fn with_options() {
+    ensure_set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Never);
+    let unwind = maybe_unwind_with(options, || {
+        panic!("bar");
+        "foo"
+    })
+    .unwrap_err();
+    assert_eq!(unwind.payload_str(), "bar");
+    assert!(unwind
+        .location()
+        .map_or(false, |loc| loc.file().contains(file!())));
+}
+
+#[test]
+fn elapsed() {
+    use std::{thread, time::Duration};
+
+    ensure_set_hook();
+    let (elapsed, res) = maybe_unwind_timed(|| {
+        thread::sleep(Duration::from_millis(20));
+        panic!("timeout");
+    });
+    let unwind = res.unwrap_err();
+    let elapsed = elapsed.unwrap();
+    assert!(elapsed >= Duration::from_millis(20), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(10), "{:?}", elapsed);
+    assert_eq!(unwind.elapsed(), Some(elapsed));
+    assert_eq!(unwind.to_snapshot().elapsed(), Some(elapsed));
+
+    let (elapsed, res) = maybe_unwind_timed(|| "ok");
+    assert_eq!(res.unwrap(), "ok");
+    assert!(elapsed.unwrap() < Duration::from_secs(10));
+
+    let unwind = maybe_unwind(|| panic!("untimed")).unwrap_err();
+    assert!(unwind.elapsed().is_none());
+}
+
+#[test]
+fn secondary_panic() {
+    use std::sync::{Arc, Mutex};
+
+    struct PanicOnDrop {
+        depth: u32,
+        unwinds: Arc<Mutex<Vec<maybe_unwind::Unwind>>>,
+    }
+
+    impl Drop for PanicOnDrop {
+        fn drop(&mut self) {
+            let unwinds = self.unwinds.clone();
+            let depth = self.depth;
+            let unwind = maybe_unwind(move || {
+                let _guard = (depth < 3).then(|| PanicOnDrop {
+                    depth: depth + 1,
+                    unwinds: unwinds.clone(),
+                });
+                panic!("secondary {}", depth);
+            })
+            .unwrap_err();
+            self.unwinds.lock().unwrap().push(unwind);
+        }
+    }
+
+    ensure_set_hook();
+    let unwinds = Arc::new(Mutex::new(vec![]));
+    let primary = maybe_unwind(|| {
+        let _guard = PanicOnDrop {
+            depth: 2,
+            unwinds: unwinds.clone(),
+        };
+        panic!("primary");
+    })
+    .unwrap_err();
+    assert!(!primary.is_secondary_panic());
+    assert_eq!(primary.panic_depth(), 1);
+    assert!(!format!("{:#}", primary).contains("panicked while processing panic"));
+
+    let unwinds = unwinds.lock().unwrap();
+    let depths: Vec<_> = unwinds
+        .iter()
+        .map(|unwind| (unwind.payload_str(), unwind.panic_depth()))
+        .collect();
+    assert_eq!(depths, [("secondary 3", 3), ("secondary 2", 2)]);
+    for unwind in unwinds.iter() {
+        assert!(unwind.is_secondary_panic());
+        let report = format!("{:#}", unwind);
+        assert_eq!(
+            report
+                .matches("note: panicked while processing panic\n")
+                .count(),
+            1,
+            "{}",
+            report
+        );
+    }
+
+    let unwind = maybe_unwind(|| panic!("after")).unwrap_err();
+    assert_eq!(unwind.panic_depth(), 1);
+}
+
+#[test]
+fn backtrace_note() {
+    ensure_set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Never);
+    let unwind = maybe_unwind_with(options, || panic!("bar")).unwrap_err();
+    let report = format!("{:#}", unwind);
+    assert!(!report.contains("stack backtrace:"));
+
+    let (status, note) = if cfg!(backtrace) {
+        (
+            BacktraceStatus::Disabled,
+            "note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n",
+        )
+    } else {
+        (
+            BacktraceStatus::Unsupported,
+            "note: stack backtraces are not supported in this build\n",
+        )
+    };
+    assert_eq!(unwind.backtrace_status(), status);
+    assert_eq!(unwind.to_snapshot().backtrace_status(), status);
+    assert_eq!(report.matches(note).count(), 1, "{}", report);
+    assert_eq!(report.matches("note: ").count(), 1, "{}", report);
+}
+
+#[cfg(backtrace)]
+#[test]
+fn backtrace_is_captured() {
+    use std::backtrace::BacktraceStatus;
+
+    ensure_set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    let unwind = maybe_unwind_with(options, || panic!("bar")).unwrap_err();
+    let backtrace = unwind
+        .backtrace()
+        .expect("the backtrace should be captured");
+    assert_eq!(backtrace.status(), BacktraceStatus::Captured);
+    assert!(!backtrace.to_string().is_empty());
+    assert_eq!(
+        unwind.backtrace_status(),
+        maybe_unwind::BacktraceStatus::Captured
+    );
+
+    let report = format!("{:#}", unwind);
+    assert_eq!(report.matches("stack backtrace:\n").count(), 1);
+    assert!(!report.contains("RUST_BACKTRACE"));
+}
+
+#[cfg(backtrace)]
+#[inline(never)]
+fn blame_me() {
+    panic!("blame");
+}
+
+#[cfg(backtrace)]
+#[test]
+fn backtrace_frames() {
+    ensure_set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    let unwind = maybe_unwind_with(options, blame_me).unwrap_err();
+    let frames: Vec<_> = unwind
+        .backtrace_frames()
+        .expect("the backtrace should be captured")
+        .collect();
+
+    let frame = frames
+        .iter()
+        .find(|frame| {
+            frame
+                .symbol_name()
+                .map_or(false, |s| s.contains("blame_me"))
+        })
+        .expect("the frame of the helper function");
+    assert!(!frame.is_internal());
+    assert!(frame.filename().unwrap().ends_with("tests/test.rs"));
+    assert_eq!(frame.lineno(), unwind.location().map(|loc| loc.line()));
+
+    let std_frame = frames
+        .iter()
+        .find(|frame| {
+            frame
+                .symbol_name()
+                .map_or(false, |s| s.starts_with("std::panicking::"))
+        })
+        .expect("the frame of std::panicking");
+    assert!(std_frame.is_internal());
+
+    let unwind = maybe_unwind(|| panic!("no backtrace")).unwrap_err();
+    if std::env::var_os("RUST_BACKTRACE").is_none() {
+        assert!(unwind.backtrace_frames().is_none());
+    }
+}
+
+#[test]
+fn display_with_location_style() {
+    ensure_set_hook();
+    let line = line!() + 1;
+    let unwind = maybe_unwind(|| panic!("styled")).unwrap_err();
+    let caller = unwind.caller().line();
+
+    // The separators are normalized so that the expected paths hold on Windows.
+    let options = DisplayOptions::new()
+        .location_style(LocationStyle::Msvc)
+        .normalize_separators(true);
+    let report = unwind.display_with(options).to_string();
+    assert!(
+        report.starts_with(&format!("panicked at tests/test.rs({},", line)),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains(&format!("captured at tests/test.rs({},", caller)),
+        "{}",
+        report
+    );
+
+    let options = DisplayOptions::new()
+        .location_style(LocationStyle::FileLineOnly)
+        .normalize_separators(true);
+    let report = unwind.to_snapshot().display_with(options).to_string();
+    assert!(
+        report.starts_with(&format!("panicked at tests/test.rs:{}: styled\n", line)),
+        "{}",
+        report
+    );
+
+    let report = unwind.display_with(DisplayOptions::new()).to_string();
+    assert_eq!(report, format!("{:#}", unwind));
+}
+
+#[test]
+fn max_message_len() {
+    ensure_set_hook();
+    let huge = "x".repeat(1 << 20);
+    let options = CaptureOptions::new()
+        .backtrace(BacktraceMode::Never)
+        .max_message_len(16);
+    let unwind = maybe_unwind_with(options, || panic!("{}", huge)).unwrap_err();
+
+    // The payload and the message are kept intact.
+    assert_eq!(unwind.payload_str(), huge);
+    assert_eq!(unwind.message(), huge);
+
+    let expected = format!("{}\u{2026}\n", "x".repeat(16));
+    let report = format!("{:#}", unwind);
+    let line = report.lines().next().unwrap();
+    assert!(line.starts_with("panicked at "), "{}", line);
+    assert!(line.ends_with(&expected[..expected.len() - 1]), "{}", line);
+    assert!(report.len() < 1024);
+
+    let report = format!("{:#}", unwind.to_snapshot());
+    assert!(report.len() < 1024);
+}
+
+#[cfg(backtrace)]
+fn recurse(depth: usize) -> usize {
+    if depth == 0 {
+        panic!("deep");
+    }
+    recurse(depth - 1) + 1
+}
+
+#[cfg(backtrace)]
+#[test]
+fn max_backtrace_frames() {
+    ensure_set_hook();
+    let options = CaptureOptions::new()
+        .backtrace(BacktraceMode::Always)
+        .max_backtrace_frames(8);
+    let unwind = maybe_unwind_with(options, || recurse(200)).unwrap_err();
+    assert_eq!(unwind.backtrace_status(), BacktraceStatus::Captured);
+    assert!(unwind.backtrace().is_none());
+    assert!(unwind.backtrace_frames().is_none());
+
+    let report = format!("{:#}", unwind);
+    let backtrace = report
+        .split("stack backtrace:\n")
+        .nth(1)
+        .expect("the truncated backtrace should be rendered");
+    let frames = backtrace
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            line.split(':').next().map_or(false, |n| {
+                !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())
+            })
+        })
+        .count();
+    assert_eq!(frames, 8, "{}", report);
+    assert!(backtrace.contains(" frames omitted\n"), "{}", report);
+
+    let snapshot = unwind.to_snapshot();
+    assert_eq!(
+        snapshot.backtrace().map(str::trim_end),
+        Some(backtrace.trim_end())
+    );
+
+    // The backtrace within the limit is kept as is.
+    let options = CaptureOptions::new()
+        .backtrace(BacktraceMode::Always)
+        .max_backtrace_frames(usize::MAX);
+    let unwind = maybe_unwind_with(options, || recurse(1)).unwrap_err();
+    assert!(unwind.backtrace().is_some());
+}
+
+#[cfg(backtrace)]
+#[allow(unreachable_code)]
+#[test]
+fn nested_backtrace_modes() {
+    ensure_set_hook();
+    let outer = CaptureOptions::new().backtrace(BacktraceMode::Never);
+    let inner = CaptureOptions::new().backtrace(BacktraceMode::Always);
+    let res = maybe_unwind_with(outer, || {
+        let inner = maybe_unwind_with(inner, || {
+            panic!("bar");
+            "baz"
+        })
+        .unwrap_err();
+        assert!(inner.backtrace().is_some());
+        panic!("foo");
+    });
+    let outer = res.unwrap_err();
+    assert_eq!(outer.payload_str(), "foo");
+    assert!(outer.backtrace().is_none());
+}
+
+#[test]
+fn named_scopes() {
+    ensure_set_hook();
+    let outer = maybe_unwind_named("suite::outer", || {
+        // The name of a nested scope overrides the outer one.
+        let inner = maybe_unwind_named("suite::inner", || panic!("inner")).unwrap_err();
+        assert_eq!(inner.scope_label(), Some("suite::inner"));
+
+        // The unnamed scopes inherit the name of the outer scope.
+        let unnamed = maybe_unwind(|| panic!("unnamed")).unwrap_err();
+        assert_eq!(unnamed.scope_label(), Some("suite::outer"));
+
+        panic!("outer");
+    })
+    .unwrap_err();
+    assert_eq!(outer.scope_label(), Some("suite::outer"));
+    assert_eq!(outer.to_snapshot().scope_label(), Some("suite::outer"));
+
+    let report = format!("{:#}", outer);
+    assert!(
+        report.starts_with(&format!("panicked in suite::outer at {}:", file!())),
+        "{}",
+        report
+    );
+
+    let unwind = maybe_unwind(|| panic!("after")).unwrap_err();
+    assert_eq!(unwind.scope_label(), None);
+}
+
 #[cfg(feature = "futures")]
 mod futures {
-    use super::ensure_set_hook;
-    use futures_executor::block_on;
-    use maybe_unwind::FutureMaybeUnwindExt as _;
+    use super::{ensure_set_hook, support};
+    use futures_core::future::FusedFuture;
+    use futures_executor::{block_on, ThreadPool};
+    use futures_task::noop_waker_ref;
+    use maybe_unwind::{FutureMaybeUnwindExt as _, MaybeUnwind, UnwindOrTimeout};
+    use std::{
+        future::Future,
+        panic::{self, AssertUnwindSafe},
+        pin::Pin,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc, Mutex,
+        },
+        task::{Context, Poll, Waker},
+        thread,
+        time::Duration,
+    };
 
     #[test]
     fn never_unwind() {
@@ -146,4 +780,621 @@ mod futures {
             assert_eq!(unwind.payload_str(), "foo");
         })
     }
+
+    #[test]
+    fn drop_panic_is_orphaned() {
+        struct PanicOnDrop;
+
+        impl Future for PanicOnDrop {
+            type Output = ();
+
+            fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+                Poll::Ready(())
+            }
+        }
+
+        impl Drop for PanicOnDrop {
+            fn drop(&mut self) {
+                panic!("panic on drop");
+            }
+        }
+
+        ensure_set_hook();
+        block_on(async {
+            let res = PanicOnDrop.maybe_unwind().await;
+            assert!(res.is_ok());
+        });
+
+        let orphans = maybe_unwind::take_orphaned_unwinds();
+        let unwind = orphans
+            .iter()
+            .find(|unwind| unwind.payload_str() == "panic on drop")
+            .expect("the drop panic should be stored");
+        assert!(unwind
+            .location()
+            .map_or(false, |loc| loc.file().contains(file!())));
+    }
+
+    #[test]
+    fn accessors_and_into_inner() {
+        /// A future that is ready at every poll, returning the number of polls.
+        #[derive(Debug, Default)]
+        struct Polls(u32);
+
+        impl Future for Polls {
+            type Output = u32;
+
+            fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+                self.0 += 1;
+                Poll::Ready(self.0)
+            }
+        }
+
+        ensure_set_hook();
+        let mut fut = MaybeUnwind::new(Polls::default());
+        assert_eq!(fut.get_ref().0, 0);
+        fut.get_mut().0 = 10;
+        assert_eq!(block_on(&mut fut).unwrap(), 11);
+        assert!(fut.is_terminated());
+
+        // The inner future is reusable after the adaptor has completed.
+        let inner = fut.into_inner();
+        assert_eq!(inner.0, 11);
+        let mut fut = Box::pin(MaybeUnwind::new(inner));
+        assert_eq!(fut.as_mut().get_pin_mut().0, 11);
+        assert_eq!(block_on(fut.as_mut()).unwrap(), 12);
+        assert_eq!(fut.get_ref().0, 12);
+
+        // The caller of the constructor is recorded.
+        let line = line!() + 1;
+        let unwind = block_on(MaybeUnwind::new(async { panic!("foo") })).unwrap_err();
+        assert_eq!(unwind.caller().file(), file!());
+        assert_eq!(unwind.caller().line(), line);
+    }
+
+    #[test]
+    fn into_inner_does_not_drop() {
+        struct DropFlag(Arc<AtomicBool>);
+
+        impl Future for DropFlag {
+            type Output = ();
+
+            fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+                Poll::Ready(())
+            }
+        }
+
+        impl Drop for DropFlag {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let inner = MaybeUnwind::new(DropFlag(dropped.clone())).into_inner();
+        assert!(!dropped.load(Ordering::SeqCst));
+        drop(inner);
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[allow(unreachable_code)]
+    #[test]
+    fn terminated_after_panic() {
+        ensure_set_hook();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut fut = Box::pin(
+            async {
+                panic!("bar");
+                "foo"
+            }
+            .maybe_unwind(),
+        );
+        assert!(!fut.is_terminated());
+
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(Err(unwind)) => assert_eq!(unwind.payload_str(), "bar"),
+            _ => panic!("unexpected poll result"),
+        }
+        assert!(fut.is_terminated());
+
+        let err = panic::catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(&mut cx))).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<&str>().copied(),
+            Some("MaybeUnwind polled after completion")
+        );
+    }
+
+    #[test]
+    fn terminated_after_completion() {
+        ensure_set_hook();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut fut = Box::pin(async { "foo" }.maybe_unwind());
+
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(output)) => assert_eq!(output, "foo"),
+            _ => panic!("unexpected poll result"),
+        }
+        assert!(fut.is_terminated());
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(&mut cx)));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn poll_fn_panics_on_second_poll() {
+        struct PanicOnSecondPoll {
+            polled: bool,
+        }
+
+        impl Future for PanicOnSecondPoll {
+            type Output = Result<&'static str, maybe_unwind::Unwind>;
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                maybe_unwind::poll_maybe_unwind(cx, |cx| {
+                    if self.polled {
+                        panic!("second poll");
+                    }
+                    self.polled = true;
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                })
+            }
+        }
+
+        ensure_set_hook();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut fut = PanicOnSecondPoll { polled: false };
+
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+        match Pin::new(&mut fut).poll(&mut cx) {
+            Poll::Ready(Err(unwind)) => {
+                assert_eq!(unwind.payload_str(), "second poll");
+                assert!(unwind
+                    .location()
+                    .map_or(false, |loc| loc.file().contains(file!())));
+            }
+            _ => panic!("unexpected poll result"),
+        }
+    }
+
+    #[test]
+    fn named_future() {
+        struct PanicOnSecondPoll {
+            polled: bool,
+        }
+
+        impl Future for PanicOnSecondPoll {
+            type Output = ();
+
+            fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+                if self.polled {
+                    panic!("named");
+                }
+                self.polled = true;
+                Poll::Pending
+            }
+        }
+
+        ensure_set_hook();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut fut = PanicOnSecondPoll { polled: false }.maybe_unwind_named("suite::async_case");
+
+        // The name is attached at every poll, not only at the first one.
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+        let unwind = maybe_unwind::maybe_unwind(|| panic!("between polls")).unwrap_err();
+        assert_eq!(unwind.scope_label(), None);
+        match Pin::new(&mut fut).poll(&mut cx) {
+            Poll::Ready(Err(unwind)) => {
+                assert_eq!(unwind.scope_label(), Some("suite::async_case"));
+            }
+            _ => panic!("unexpected poll result"),
+        }
+
+        block_on(async {
+            let unwind = async { panic!("named") }
+                .maybe_unwind_named(String::from("suite::block_on"))
+                .await
+                .unwrap_err();
+            assert_eq!(unwind.scope_label(), Some("suite::block_on"));
+        });
+    }
+
+    #[test]
+    fn elapsed_excludes_pending_time() {
+        struct SleepThenPanic {
+            polled: usize,
+        }
+
+        impl Future for SleepThenPanic {
+            type Output = ();
+
+            fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+                thread::sleep(Duration::from_millis(20));
+                self.polled += 1;
+                if self.polled == 2 {
+                    panic!("timeout");
+                }
+                Poll::Pending
+            }
+        }
+
+        ensure_set_hook();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut fut = SleepThenPanic { polled: 0 }.maybe_unwind_timed();
+        assert_eq!(fut.elapsed(), Some(Duration::from_secs(0)));
+
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+        assert!(fut.elapsed().unwrap() >= Duration::from_millis(20));
+        thread::sleep(Duration::from_millis(500));
+        match Pin::new(&mut fut).poll(&mut cx) {
+            Poll::Ready(Err(unwind)) => {
+                let elapsed = unwind.elapsed().unwrap();
+                assert!(elapsed >= Duration::from_millis(40), "{:?}", elapsed);
+                assert!(elapsed < Duration::from_millis(500), "{:?}", elapsed);
+            }
+            _ => panic!("unexpected poll result"),
+        }
+    }
+
+    #[test]
+    fn caller_location() {
+        ensure_set_hook();
+        block_on(async {
+            let fut = async { support::panic_in_helper() };
+            let line = line!() + 1;
+            let unwind = fut.maybe_unwind().await.unwrap_err();
+            assert!(unwind
+                .location()
+                .map_or(false, |loc| loc.file().contains("support")));
+            assert!(unwind.caller().file().contains(file!()));
+            assert_eq!(unwind.caller().line(), line);
+        })
+    }
+
+    #[test]
+    fn context_frames() {
+        ensure_set_hook();
+        block_on(async {
+            let unwind = maybe_unwind::with_context_async("first", async {
+                maybe_unwind::with_context_async("second", async {
+                    panic!("bar");
+                })
+                .await
+            })
+            .maybe_unwind()
+            .await
+            .unwrap_err();
+            assert_eq!(unwind.context_frames(), ["first", "second"]);
+        })
+    }
+
+    #[test]
+    fn context_frames_across_await_points() {
+        /// Returns `Pending` once, so that the task may be resumed on another worker.
+        struct YieldNow(bool);
+
+        impl Future for YieldNow {
+            type Output = ();
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                if self.0 {
+                    return Poll::Ready(());
+                }
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+
+        ensure_set_hook();
+        let pool = ThreadPool::builder().pool_size(4).create().unwrap();
+        let (tx, rx) = mpsc::channel();
+        for i in 0..16 {
+            let tx = tx.clone();
+            pool.spawn_ok(async move {
+                let res = async move {
+                    YieldNow(false).await;
+                    async move {
+                        YieldNow(false).await;
+                        panic!("task {}", i);
+                    }
+                    .context("inner")
+                    .await
+                }
+                .context(format!("task {}", i))
+                .context("outer")
+                .maybe_unwind()
+                .await;
+                tx.send((i, res)).unwrap();
+            });
+        }
+        drop(tx);
+
+        let mut count = 0;
+        for (i, res) in rx {
+            let unwind = res.unwrap_err();
+            assert_eq!(
+                unwind.context_frames(),
+                [
+                    "outer".to_owned(),
+                    format!("task {}", i),
+                    "inner".to_owned()
+                ]
+            );
+            count += 1;
+        }
+        assert_eq!(count, 16);
+    }
+
+    fn assert_location(unwind: &maybe_unwind::Unwind, line: u32) {
+        let loc = unwind.location().expect("the location should be captured");
+        assert_eq!(loc.file(), file!());
+        assert_eq!(loc.line(), line);
+    }
+
+    #[allow(unreachable_code)]
+    #[test]
+    fn location_under_block_on() {
+        ensure_set_hook();
+        block_on(async {
+            let line = line!() + 2;
+            let unwind = async {
+                panic!("bar");
+                "foo"
+            }
+            .maybe_unwind()
+            .await
+            .unwrap_err();
+            assert_location(&unwind, line);
+        })
+    }
+
+    #[allow(unreachable_code)]
+    #[test]
+    fn location_under_thread_pool() {
+        ensure_set_hook();
+        let pool = ThreadPool::builder().pool_size(4).create().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let line = line!() + 5;
+        for i in 0..16 {
+            let tx = tx.clone();
+            pool.spawn_ok(async move {
+                let res = async {
+                    panic!("bar {}", i);
+                    "foo"
+                }
+                .maybe_unwind()
+                .await;
+                tx.send((i, res)).unwrap();
+            });
+        }
+        drop(tx);
+
+        let mut count = 0;
+        for (i, res) in rx {
+            let unwind = res.unwrap_err();
+            assert_eq!(unwind.payload_str(), format!("bar {}", i));
+            assert_location(&unwind, line);
+            count += 1;
+        }
+        assert_eq!(count, 16);
+    }
+
+    #[allow(unreachable_code)]
+    #[test]
+    fn location_nested_in_sync_maybe_unwind() {
+        ensure_set_hook();
+        let line = line!() + 4;
+        let res = maybe_unwind::maybe_unwind(|| {
+            block_on(async {
+                async {
+                    panic!("bar");
+                    "foo"
+                }
+                .maybe_unwind()
+                .await
+            })
+        });
+        let unwind = res.unwrap().unwrap_err();
+        assert_location(&unwind, line);
+    }
+    /// A delay driven by a timer thread, which wakes the task when elapsed.
+    struct Delay {
+        state: Arc<Mutex<(bool, Option<Waker>)>>,
+    }
+
+    impl Delay {
+        fn new(dur: Duration) -> Self {
+            let state = Arc::new(Mutex::new((false, None::<Waker>)));
+            let timer = Arc::clone(&state);
+            thread::spawn(move || {
+                thread::sleep(dur);
+                let mut state = timer.lock().unwrap();
+                state.0 = true;
+                if let Some(waker) = state.1.take() {
+                    waker.wake();
+                }
+            });
+            Self { state }
+        }
+    }
+
+    impl Future for Delay {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let mut state = self.state.lock().unwrap();
+            if state.0 {
+                return Poll::Ready(());
+            }
+            state.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn timeout_of_hung_future() {
+        ensure_set_hook();
+        let res = maybe_unwind::with_context("while waiting for the server", || {
+            block_on(
+                async {
+                    maybe_unwind::set_scope_label("hung");
+                    std::future::pending::<()>().await
+                }
+                .maybe_unwind_timeout(Duration::from_millis(50), Delay::new),
+            )
+        });
+
+        let err = res.unwrap_err();
+        assert!(err.unwind().is_none());
+        let timeout = err.timeout().unwrap();
+        assert!(timeout.waited() >= Duration::from_millis(50));
+        assert_eq!(timeout.scope_label(), Some("hung"));
+        assert_eq!(timeout.context_frames(), ["while waiting for the server"]);
+        assert!(err.to_string().starts_with("timed out after "), "{}", err);
+        assert!(err
+            .to_string()
+            .ends_with(" in hung\n    while waiting for the server"));
+    }
+
+    #[test]
+    fn timeout_passes_panics_and_outputs() {
+        ensure_set_hook();
+        let err = block_on(
+            async {
+                panic!("boom");
+            }
+            .maybe_unwind_timeout(Duration::from_secs(60), Delay::new),
+        )
+        .unwrap_err();
+        assert!(matches!(err, UnwindOrTimeout::Unwind(..)));
+        assert_eq!(err.unwind().unwrap().payload_str(), "boom");
+        assert_eq!(err.to_string(), "boom");
+
+        let output =
+            block_on(async { 42 }.maybe_unwind_timeout(Duration::from_secs(60), Delay::new));
+        assert_eq!(output.unwrap(), 42);
+    }
+
+    #[test]
+    fn timeout_terminated() {
+        ensure_set_hook();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut fut =
+            Box::pin(async { "foo" }.maybe_unwind_timeout(Duration::from_secs(60), |_| async {}));
+        assert!(!fut.is_terminated());
+        assert!(matches!(fut.as_mut().poll(&mut cx), Poll::Ready(Ok("foo"))));
+        assert!(fut.is_terminated());
+
+        let err = panic::catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(&mut cx))).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<&str>().copied(),
+            Some("MaybeUnwindTimeout polled after completion")
+        );
+    }
+}
+
+#[cfg(feature = "stream")]
+mod stream {
+    use super::ensure_set_hook;
+    use futures_core::stream::{FusedStream, Stream};
+    use futures_task::noop_waker_ref;
+    use maybe_unwind::StreamMaybeUnwindExt as _;
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    /// A fused stream yielding `0..len`, which panics at `panic_at`.
+    struct Counter {
+        next: usize,
+        len: usize,
+        panic_at: Option<usize>,
+    }
+
+    impl Stream for Counter {
+        type Item = usize;
+
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<usize>> {
+            if self.next == self.len {
+                return Poll::Ready(None);
+            }
+            if Some(self.next) == self.panic_at {
+                panic!("counter panicked");
+            }
+            self.next += 1;
+            Poll::Ready(Some(self.next - 1))
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let remaining = self.len - self.next;
+            (remaining, Some(remaining))
+        }
+    }
+
+    impl FusedStream for Counter {
+        fn is_terminated(&self) -> bool {
+            self.next == self.len
+        }
+    }
+
+    #[test]
+    fn stream_size_hint() {
+        ensure_set_hook();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut stream = Box::pin(
+            Counter {
+                next: 0,
+                len: 3,
+                panic_at: None,
+            }
+            .maybe_unwind(),
+        );
+        assert_eq!(stream.size_hint(), (1, Some(3)));
+
+        let mut items = vec![];
+        while let Poll::Ready(Some(item)) = stream.as_mut().poll_next(&mut cx) {
+            items.push(item.unwrap());
+            let remaining = 3 - items.len();
+            assert_eq!(stream.size_hint(), (remaining.min(1), Some(remaining)));
+        }
+        assert_eq!(items, [0, 1, 2]);
+        assert!(stream.is_terminated());
+    }
+
+    #[test]
+    fn stream_terminated_after_panic() {
+        ensure_set_hook();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let counter = Counter {
+            next: 0,
+            len: 5,
+            panic_at: Some(2),
+        };
+        let (mut stream, line) = (Box::pin(counter.maybe_unwind()), line!());
+
+        for expected in 0..2 {
+            match stream.as_mut().poll_next(&mut cx) {
+                Poll::Ready(Some(Ok(item))) => assert_eq!(item, expected),
+                _ => panic!("unexpected poll result"),
+            }
+        }
+        assert!(!stream.is_terminated());
+
+        match stream.as_mut().poll_next(&mut cx) {
+            Poll::Ready(Some(Err(unwind))) => {
+                assert_eq!(unwind.payload_str(), "counter panicked");
+                assert_eq!(unwind.caller().line(), line);
+            }
+            _ => panic!("unexpected poll result"),
+        }
+        assert!(stream.is_terminated());
+        assert_eq!(stream.size_hint(), (0, Some(0)));
+
+        assert!(matches!(
+            stream.as_mut().poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+        assert_eq!(stream.get_ref().next, 2, "the inner stream is not polled");
+    }
 }
@@ -0,0 +1,73 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{hook_installed, maybe_unwind, testing::with_recorded_hook};
+use std::{panic, thread};
+
+#[test]
+fn recorded_events() {
+    let line = line!() + 2;
+    let (res, events) = with_recorded_hook(|| {
+        let unwind = maybe_unwind(|| panic!("captured")).unwrap_err();
+        let _ = panic::catch_unwind(|| panic!("not captured"));
+        assert!(hook_installed());
+        unwind
+    });
+    assert!(!hook_installed());
+    assert_eq!(res.location().unwrap().line(), line);
+
+    assert_eq!(events.len(), 2);
+    assert!(events[0].captured());
+    assert_eq!(events[0].output(), "");
+    assert_eq!(events[0].thread_id(), thread::current().id());
+    assert!(!events[1].captured());
+    let output = events[1].output();
+    let prefix = format!(
+        "thread '{}' panicked at {}:{}:",
+        events[1].thread_name().unwrap_or("<unnamed>"),
+        file!(),
+        line + 1,
+    );
+    assert!(output.starts_with(&prefix), "{}", output);
+    assert!(output.ends_with(":\nnot captured\n"), "{}", output);
+}
+
+// This test spawns the example binary, which is not supported by Miri.
+#[cfg(not(miri))]
+#[test]
+fn nothing_written_into_stderr() {
+    use std::{path::Path, process::Command};
+
+    let output = Command::new(support::example_path("recorded_hook"))
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr, "");
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let events: Vec<_> = stdout.split_terminator("--\n").collect();
+    assert_eq!(events.len(), 5, "{}", stdout);
+    assert_eq!(events[0], "captured=true thread=main\n");
+    let prefix = format!(
+        "captured=false thread=main\nthread 'main' panicked at {}:",
+        Path::new("examples").join("recorded_hook.rs").display()
+    );
+    assert!(events[1].starts_with(&prefix), "{}", events[1]);
+    assert!(events[1].ends_with(":\nnot captured\n"), "{}", events[1]);
+    assert!(
+        events[2].starts_with("captured=false thread=worker\nthread 'worker' panicked at "),
+        "{}",
+        events[2]
+    );
+    assert!(events[2].ends_with(":\non the worker\n"), "{}", events[2]);
+    assert_eq!(events[3], "captured=true thread=main\n");
+    assert!(
+        events[4].contains("while processing the panic below"),
+        "{}",
+        events[4]
+    );
+    assert!(events[4].contains(": first\n"), "{}", events[4]);
+}
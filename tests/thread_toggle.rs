@@ -0,0 +1,51 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{
+    disable_for_current_thread, enable_for_current_thread, is_enabled, maybe_unwind,
+};
+use std::thread;
+use support::ensure_set_hook;
+
+#[test]
+fn disable_on_spawned_thread() {
+    ensure_set_hook();
+
+    let handle = thread::spawn(|| {
+        assert!(is_enabled());
+        disable_for_current_thread();
+        assert!(!is_enabled());
+        (0..100)
+            .map(|i| maybe_unwind(|| panic!("intentional {}", i)).unwrap_err())
+            .map(|unwind| (unwind.location().is_none(), unwind.captured().is_none()))
+            .all(|(no_location, not_captured)| no_location && not_captured)
+    });
+
+    let unwind = maybe_unwind(|| panic!("main")).unwrap_err();
+    assert!(is_enabled());
+    assert!(unwind.location().is_some());
+    assert!(unwind.captured().is_some());
+
+    assert!(
+        handle.join().unwrap(),
+        "the panics on the disabled thread are not captured"
+    );
+}
+
+#[test]
+fn toggle_inside_scope() {
+    ensure_set_hook();
+
+    let outer = maybe_unwind(|| {
+        disable_for_current_thread();
+        let inner = maybe_unwind(|| panic!("inner")).unwrap_err();
+        enable_for_current_thread();
+        assert!(inner.location().is_none());
+        assert_eq!(inner.message(), "inner");
+        panic!("outer");
+    })
+    .unwrap_err();
+    assert!(outer.location().is_some());
+    assert_eq!(outer.captured().unwrap().message(), "outer");
+}
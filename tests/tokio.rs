@@ -0,0 +1,46 @@
+#![cfg(all(feature = "capture", feature = "tokio"))]
+
+mod support;
+
+use maybe_unwind::{spawn_blocking_maybe_unwind, spawn_maybe_unwind, with_context_async};
+use support::ensure_set_hook;
+use tokio::runtime::{Builder, Runtime};
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread().build().unwrap()
+}
+
+#[test]
+fn spawned_task() {
+    ensure_set_hook();
+    runtime().block_on(async {
+        let line = line!() + 1;
+        let handle = spawn_maybe_unwind(with_context_async("in the task", async {
+            panic!("task failed");
+        }));
+        let unwind = handle.await.unwrap().unwrap_err();
+        assert_eq!(unwind.message(), "task failed");
+        assert_eq!(unwind.location().unwrap().line(), line + 1);
+        assert_eq!(unwind.caller().line(), line);
+        assert_eq!(unwind.context_frames(), ["in the task"]);
+
+        let output = spawn_maybe_unwind(async { 42 }).await.unwrap();
+        assert_eq!(output.unwrap(), 42);
+    });
+}
+
+#[test]
+fn blocking_task() {
+    ensure_set_hook();
+    runtime().block_on(async {
+        let line = line!() + 1;
+        let handle = spawn_blocking_maybe_unwind(|| -> u32 { panic!("blocking failed") });
+        let unwind = handle.await.unwrap().unwrap_err();
+        assert_eq!(unwind.message(), "blocking failed");
+        assert_eq!(unwind.location().unwrap().line(), line);
+        assert_eq!(unwind.caller().line(), line);
+
+        let output = spawn_blocking_maybe_unwind(|| 42).await.unwrap();
+        assert_eq!(output.unwrap(), 42);
+    });
+}
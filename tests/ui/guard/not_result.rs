@@ -0,0 +1,23 @@
+use maybe_unwind::{guard, Unwind};
+
+struct Plugin;
+
+#[guard]
+impl Plugin {
+    fn ok(&self) -> Result<(), Unwind> {
+        Ok(())
+    }
+
+    fn count(&self) -> usize {
+        0
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[guard]
+fn option() -> Option<()> {
+    None
+}
+
+fn main() {}
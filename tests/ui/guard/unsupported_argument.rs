@@ -0,0 +1,13 @@
+use maybe_unwind::{guard, Unwind};
+
+#[guard(err = "From::from")]
+fn unknown() -> Result<(), Unwind> {
+    Ok(())
+}
+
+#[guard(error = "not a path")]
+fn invalid_path() -> Result<(), Unwind> {
+    Ok(())
+}
+
+fn main() {}
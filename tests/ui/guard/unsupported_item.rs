@@ -0,0 +1,6 @@
+use maybe_unwind::guard;
+
+#[guard]
+struct Plugin;
+
+fn main() {}
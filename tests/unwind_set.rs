@@ -0,0 +1,75 @@
+#![cfg(feature = "capture")]
+
+mod support;
+
+use maybe_unwind::{maybe_unwind_with, BacktraceMode, CaptureOptions, UnwindSet};
+use std::error::Error;
+use support::ensure_set_hook;
+
+#[test]
+fn empty_set() {
+    let set = UnwindSet::new();
+    assert!(set.is_empty());
+    assert_eq!(set.len(), 0);
+    assert!(set.into_result().is_ok());
+}
+
+#[test]
+fn combined_output() {
+    ensure_set_hook();
+    let options = CaptureOptions::new().backtrace(BacktraceMode::Never);
+
+    let mut set = UnwindSet::new();
+    let (unwind, line1) = (
+        maybe_unwind_with(options.clone(), || panic!("first")).unwrap_err(),
+        line!(),
+    );
+    set.push("fetch", unwind);
+    let (unwind, line2) = (
+        maybe_unwind_with(options.clone(), || panic!("second")).unwrap_err(),
+        line!(),
+    );
+    set.push(String::from("store"), unwind);
+
+    assert_eq!(set.len(), 2);
+    let labels: Vec<_> = set.iter().map(|(label, _)| label).collect();
+    assert_eq!(labels, ["fetch", "store"]);
+
+    let err = set.into_result().unwrap_err();
+    let file = file!();
+    let summary = format!(
+        "2 panics\n  \
+         1. fetch: panicked at {file}:{line1}:47: first\n  \
+         2. store: panicked at {file}:{line2}:47: second",
+        file = file,
+        line1 = line1 - 1,
+        line2 = line2 - 1,
+    );
+    assert_eq!(err.to_string(), summary);
+
+    let report = format!(
+        "{summary}\n\
+         \n\
+         --- 1. fetch ---\n\
+         panicked at {file}:{line1}:47: first\n\
+         captured at {file}:{line1}:9\n\
+         \n\
+         --- 2. store ---\n\
+         panicked at {file}:{line2}:47: second\n\
+         captured at {file}:{line2}:9",
+        summary = summary,
+        file = file,
+        line1 = line1 - 1,
+        line2 = line2 - 1,
+    );
+    // The note about `RUST_BACKTRACE` depends on the environment.
+    let actual: Vec<_> = format!("{:#}", err)
+        .lines()
+        .filter(|line| !line.starts_with("note: "))
+        .map(ToOwned::to_owned)
+        .collect();
+    assert_eq!(actual.join("\n"), report);
+
+    let err: Box<dyn Error> = Box::new(err);
+    assert!(err.source().is_none());
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "capture")]
+
+use maybe_unwind::maybe_unwind;
+use std::{
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static OTHER_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn compose_with_other_hook() {
+    // The hook installed by another library before ours.
+    panic::set_hook(Box::new(|_| {
+        OTHER_HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+    }));
+    maybe_unwind::set_hook();
+
+    let unwind = maybe_unwind(|| panic!("captured")).unwrap_err();
+    assert!(unwind.location().is_some());
+    assert_eq!(OTHER_HOOK_CALLS.load(Ordering::SeqCst), 0);
+
+    let res = panic::catch_unwind(|| panic!("not captured"));
+    assert!(res.is_err());
+    assert_eq!(OTHER_HOOK_CALLS.load(Ordering::SeqCst), 1);
+}
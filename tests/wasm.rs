@@ -0,0 +1,30 @@
+//! Run with `wasm-pack test --node -- --features console_error_panic_hook`.
+
+#![cfg(target_arch = "wasm32")]
+
+use maybe_unwind::{maybe_unwind, take_aborted_panic, unwinding_supported};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn unwinding_is_not_supported() {
+    assert!(!unwinding_supported());
+}
+
+#[cfg(feature = "console_error_panic_hook")]
+#[wasm_bindgen_test]
+fn post_mortem() {
+    maybe_unwind::set_console_hook();
+
+    // The panic traps the WebAssembly instance, and the trap is thrown
+    // to the JavaScript side as an exception.
+    let f = Closure::<dyn FnMut()>::new(|| {
+        let _ = maybe_unwind(|| panic!("oops"));
+    });
+    let func: &js_sys::Function = f.as_ref().unchecked_ref();
+    assert!(func.call0(&JsValue::NULL).is_err());
+
+    let captured = take_aborted_panic().expect("the panic should be recorded");
+    assert_eq!(captured.message(), "oops");
+    assert!(captured.location().is_some());
+}
@@ -0,0 +1,112 @@
+//! The panic hook must not allocate when only the location is captured.
+
+#![cfg(feature = "capture")]
+
+use maybe_unwind::{
+    capture_panic_info, maybe_unwind_with, BacktraceMode, CaptureOptions, HookOptions,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Install the hook counting the allocations made by `capture_panic_info`.
+fn set_counting_hook() {
+    panic::set_hook(Box::new(|info| {
+        COUNTING.with(|counting| counting.set(true));
+        capture_panic_info(info);
+        COUNTING.with(|counting| counting.set(false));
+    }));
+}
+
+/// Return the number of the allocations made by the hook for the second
+/// panic, after the first one warmed up the interner and the thread handle.
+fn allocations_in_hook(options: impl Fn() -> CaptureOptions, f: fn()) -> usize {
+    let mut count = 0;
+    for _ in 0..2 {
+        ALLOCATIONS.store(0, Ordering::Relaxed);
+        let unwind = maybe_unwind_with(options(), f).unwrap_err();
+        count = ALLOCATIONS.load(Ordering::Relaxed);
+        assert!(unwind.location().is_some());
+    }
+    count
+}
+
+fn literal() {
+    panic!("static message");
+}
+
+// Read at runtime, so that the message is not folded into a literal.
+static ANSWER: AtomicUsize = AtomicUsize::new(42);
+
+fn formatted() {
+    panic!("formatted message: {}", ANSWER.load(Ordering::Relaxed));
+}
+
+// The hook and the allocator are process-global, so the scenarios are run in a single test.
+#[test]
+fn location_only_capture_does_not_allocate() {
+    set_counting_hook();
+    let location_only = || {
+        CaptureOptions::new()
+            .capture_message(false)
+            .backtrace(BacktraceMode::Never)
+    };
+    assert_eq!(allocations_in_hook(location_only, literal), 0);
+    assert_eq!(allocations_in_hook(location_only, formatted), 0);
+
+    // The message is still available from the payload.
+    let unwind = maybe_unwind_with(location_only(), formatted).unwrap_err();
+    assert_eq!(unwind.message(), "formatted message: 42");
+    assert_eq!(unwind.captured().unwrap().message(), "");
+
+    // By default, the formatted message is copied.
+    let with_message = || CaptureOptions::new().backtrace(BacktraceMode::Never);
+    assert_ne!(allocations_in_hook(with_message, formatted), 0);
+    let unwind = maybe_unwind_with(with_message(), formatted).unwrap_err();
+    assert_eq!(
+        unwind.captured().unwrap().message(),
+        "formatted message: 42"
+    );
+
+    // The global setting overrides the scopes.
+    maybe_unwind::set_hook_with(HookOptions::new().capture_message(false));
+    set_counting_hook();
+    assert_eq!(allocations_in_hook(with_message, formatted), 0);
+    maybe_unwind::set_hook_with(HookOptions::new());
+    set_counting_hook();
+    assert_ne!(allocations_in_hook(with_message, formatted), 0);
+}